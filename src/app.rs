@@ -0,0 +1,3302 @@
+use crate::assertion::{self, Assertion};
+use crate::auth::{Auth, AuthKind};
+use crate::bench::{self, Bench, Stats};
+use crate::body::{self, BodyMode};
+use crate::clipboard::Clipboard;
+use crate::collections::{Collection, Collections, SavedRequest};
+use crate::command::Command;
+use crate::config::Config;
+use crate::cookies::CookieJar;
+use crate::curl;
+use crate::dataset;
+use crate::digest;
+use crate::download::Download;
+use crate::editor::Editor;
+use crate::environment::{Environment, Environments};
+use crate::error::AppError;
+use crate::finder::{Finder, Target};
+use crate::focus::Focus;
+use crate::har::{self, Har};
+use crate::history::{History, HistoryEntry};
+use crate::httpfile::{self, HttpFile};
+use crate::input;
+use crate::jsontable::JsonTable;
+use crate::jsontree::JsonTree;
+use crate::keymap::{self, Mode};
+use crate::kv::{KeyValue, KeyValueTable};
+use crate::layout::{self, Towards};
+use crate::logs::{Level, Logs};
+use crate::mock::{self, Mock};
+use crate::monitor::{self, Monitor};
+use crate::notify;
+use crate::oauth::{self, Pkce, Token, TokenStore};
+use crate::palette::Palette;
+use crate::prompt::{Prompt, PromptAction};
+use crate::proxy;
+use crate::request::{self, Request};
+use crate::response::{BodyText, ResponseData};
+use crate::retry::Retry;
+use crate::runner::{Outcome, Run, Step};
+use crate::script::{self, Effects};
+use crate::search::Search;
+use crate::settings::{self, Settings};
+use crate::snippet::{self, Language};
+use crate::sse::EventStream;
+use crate::state::State;
+use crate::storage;
+use crate::tabs::{self, Tab};
+use crate::theme::Theme;
+use crate::throttle::Throttle;
+use crate::undo::Undo;
+use crate::{openapi, postman};
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+use tui::layout::Rect;
+
+/// Methods the Method pane cycles through, in order.
+pub const METHODS: [Method; 7] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::HEAD,
+    Method::OPTIONS,
+];
+
+/// Most history URLs offered under the URL field at once.
+const MAX_COMPLETIONS: usize = 8;
+
+pub enum InputMode {
+    Normal,
+    Editing,
+    EditingBody,
+    EditingTable,
+    /// Typing a `/` search query for the Response pane.
+    Search,
+    /// Typing into the GraphQL variables editor of the Body pane.
+    EditingVariables,
+    /// Typing into the popup `App::prompt`.
+    Prompt,
+    /// Browsing the request history popup.
+    History,
+    /// Browsing the connection settings popup.
+    Settings,
+    /// Browsing the cookie jar popup.
+    Cookies,
+    /// Browsing the request's assertions popup.
+    Assertions,
+    /// Selecting lines of the Response pane to copy, from `visual_anchor`
+    /// to `visual_cursor`.
+    Visual,
+    /// Browsing the JSON response as a tree, `App::tree`.
+    Tree,
+    /// Browsing a JSON array of objects as a table, `App::table`.
+    JsonTable,
+    /// Reading the keybindings popup.
+    Help,
+    /// Picking an action from the command palette, `App::palette`.
+    Palette,
+    /// Picking a saved or past request from the Ctrl+F finder, `App::finder`.
+    Finder,
+    /// Watching a collection run, `App::run`.
+    Runner,
+    /// Reading the request as code, `App::snippets`.
+    Snippets,
+    /// Watching the request being sent again and again, `App::monitor`.
+    Monitor,
+    /// Watching a benchmark of the request, `App::bench`.
+    Bench,
+    /// Reading a chart of the request's response times from the history.
+    Trend,
+}
+
+impl InputMode {
+    /// For the status bar.
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputMode::Normal => "Normal",
+            InputMode::Editing | InputMode::EditingBody | InputMode::EditingVariables => "Editing",
+            InputMode::EditingTable => "Table",
+            InputMode::Search => "Search",
+            InputMode::Prompt => "Prompt",
+            InputMode::History
+            | InputMode::Settings
+            | InputMode::Cookies
+            | InputMode::Assertions
+            | InputMode::Runner
+            | InputMode::Snippets
+            | InputMode::Monitor
+            | InputMode::Bench
+            | InputMode::Trend => "Popups",
+            InputMode::Visual => "Visual",
+            InputMode::Tree => "Tree",
+            InputMode::JsonTable => "JSON table",
+            InputMode::Help => "Help",
+            InputMode::Palette => "Palette",
+            InputMode::Finder => "Finder",
+        }
+    }
+
+    /// This mode's bindings, or `None` for Normal mode's, which are `BINDINGS`.
+    pub fn keys(&self) -> Option<&'static Mode> {
+        Some(match self {
+            InputMode::Normal => return None,
+            InputMode::Editing | InputMode::EditingBody | InputMode::EditingVariables => {
+                &keymap::EDITING
+            }
+            InputMode::EditingTable => &keymap::TABLE,
+            InputMode::Search => &keymap::SEARCH,
+            InputMode::Visual => &keymap::VISUAL,
+            InputMode::Tree => &keymap::TREE,
+            InputMode::JsonTable => &keymap::JSON_TABLE,
+            InputMode::Prompt => &keymap::PROMPT,
+            InputMode::History => &keymap::HISTORY,
+            InputMode::Snippets => &keymap::SNIPPETS,
+            InputMode::Cookies => &keymap::COOKIES,
+            InputMode::Assertions => &keymap::ASSERTIONS,
+            InputMode::Runner => &keymap::RUNNER,
+            InputMode::Monitor => &keymap::MONITOR,
+            InputMode::Bench => &keymap::BENCH,
+            InputMode::Trend => &keymap::TREND,
+            InputMode::Settings => &keymap::SETTINGS,
+            InputMode::Palette => &keymap::PALETTE,
+            InputMode::Finder => &keymap::FINDER,
+            InputMode::Help => &keymap::HELP,
+        })
+    }
+}
+
+pub struct App {
+    pub config: Config,
+    pub response: Option<ResponseData>,
+    /// Set when the last request failed; shown in place of the response.
+    pub error: Option<AppError>,
+    /// Whether the status and headers section above the response body is expanded.
+    pub show_metadata: bool,
+    /// Show the body as received instead of formatted.
+    pub show_raw: bool,
+    /// First body line shown in the Response pane.
+    pub response_scroll: usize,
+    /// Body lines that fit in the Response pane, as of the last draw.
+    pub response_height: usize,
+    /// First line shown in the Logs pane.
+    pub logs_scroll: usize,
+    /// Lines that fit in the Logs pane, as of the last draw.
+    pub logs_height: usize,
+    /// Keeps the newest log lines in view. Cleared by scrolling up.
+    pub follow_logs: bool,
+    /// Where each pane was drawn, as of the last draw, to find the one clicked.
+    pub pane_areas: Vec<(Focus, Rect)>,
+    /// Keeps the newest events in view as they arrive on an event stream.
+    /// Cleared by scrolling up, and set again by reaching the bottom.
+    pub follow_events: bool,
+    /// Set after a first `g`, so a second one jumps to the top.
+    pub pending_g: bool,
+    /// The response line a visual selection was started on.
+    pub visual_anchor: usize,
+    /// The response line a visual selection has been moved to.
+    pub visual_cursor: usize,
+    /// The response body as a tree, while browsing it so.
+    pub tree: Option<JsonTree>,
+    /// A JSON array of objects as a table, while browsing it so.
+    pub table: Option<JsonTable>,
+    /// Set by `q` or `:q`; the main loop exits once it sees it.
+    pub quit: bool,
+    /// Set to have the main loop hand the body or a script to `$EDITOR`,
+    /// which needs the terminal.
+    pub external_edit: Option<ExternalEdit>,
+    /// What was piped to stdin when the TUI started, for the body.
+    pub stdin: Option<String>,
+    /// First line shown in the keybindings popup.
+    pub help_scroll: usize,
+    pub palette: Palette,
+    pub finder: Finder,
+    pub search: Search,
+    pub prompt: Option<Prompt>,
+    clipboard: Clipboard,
+    pub history: History,
+    /// Selected row of the history popup, counted from the newest entry.
+    pub history_selected: usize,
+    /// Selected row of the settings popup.
+    pub settings_selected: usize,
+    pub collections: Collections,
+    pub environments: Environments,
+    pub tokens: TokenStore,
+    /// Shared with the clients sending requests, which add cookies as they arrive.
+    pub cookies: Arc<CookieJar>,
+    /// Selected row of the cookie jar popup.
+    pub cookies_selected: usize,
+    /// The request as code in each language, for the snippets popup.
+    pub snippets: Vec<(Language, String)>,
+    /// Language shown in the snippets popup, and how far it's scrolled.
+    pub snippet_selected: usize,
+    pub snippet_scroll: u16,
+    /// The latest collection run, kept after it finishes to look over.
+    pub run: Option<Run>,
+    /// The request being sent every so often, kept after it's stopped to
+    /// look over.
+    pub monitor: Option<Monitor>,
+    /// The latest benchmark, kept after it finishes to look over.
+    pub bench: Option<Bench>,
+    /// The OAuth2 flow waiting for its browser redirect, if any.
+    authorizing: Option<JoinHandle<()>>,
+    /// The mock server started with `:mock`, if it's running.
+    mocking: Option<JoinHandle<()>>,
+    /// The recording proxy started with `:proxy`, if it's running.
+    proxying: Option<JoinHandle<()>>,
+    /// Every exchange completed since the app started, for HAR export.
+    pub session: Har,
+    pub input_mode: InputMode,
+    pub method: Method,
+    pub url: String,
+    /// Byte offset of the cursor in `url` while it's being edited.
+    pub url_cursor: usize,
+    /// Earlier URLs and their cursors, for Ctrl+Z.
+    pub url_undo: Undo<(String, usize)>,
+    /// URLs from the history matching what's typed, shown under the URL field.
+    pub url_completions: Vec<String>,
+    /// The completion picked with Up/Down, if any.
+    pub completion_selected: Option<usize>,
+    /// Whether the completions are the recent URLs, opened from Normal mode,
+    /// so picking one goes back to it.
+    pub showing_recent_urls: bool,
+    pub params: KeyValueTable,
+    pub headers: KeyValueTable,
+    pub auth: Auth,
+    /// This request's own settings; the rest come from the environment and config.
+    pub settings: Settings,
+    pub body: String,
+    /// The body editor's cursor and scroll.
+    pub body_editor: Editor,
+    pub body_mode: BodyMode,
+    /// Form fields, edited in the Body pane in place of `body` by the modes that use them.
+    pub fields: KeyValueTable,
+    /// The GraphQL variables editor's text.
+    pub variables: String,
+    pub variables_editor: Editor,
+    /// Checks on each response, e.g. `status == 200`, saved with the request.
+    pub assertions: Vec<String>,
+    /// Selected row of the assertions popup.
+    pub assertions_selected: usize,
+    /// Script run before each send, saved with the request.
+    pub pre_request: String,
+    /// Script run on each response, saved with the request.
+    pub post_response: String,
+    /// Saved requests sent before this one, saved with it.
+    pub requires: Vec<String>,
+    pub logs: Logs,
+    pub focus: Focus,
+    /// The background task sending the current request, if one is running.
+    pub in_flight: Option<JoinHandle<()>>,
+    /// When the request in flight was sent, for the timer shown while it runs.
+    pub request_started: Option<Instant>,
+    /// Where the request in flight is streaming its body, if it's a download.
+    pub download: Option<Arc<Download>>,
+    /// Events of the request in flight, if its response is an event stream.
+    pub events: Option<Arc<EventStream>>,
+    /// The id of this tab's latest request, so results of cancelled
+    /// requests can be told apart.
+    request_id: u64,
+    /// The last id given to a request in any tab.
+    next_request_id: u64,
+    /// The request in flight as written and as sent, kept to be recorded in
+    /// the history and session once it completes.
+    sent_request: Option<(Request, Request)>,
+    /// Every open tab in order. The one at `current_tab` is a placeholder,
+    /// as the shown tab's state is kept in the fields above.
+    pub tabs: Vec<Tab>,
+    pub current_tab: usize,
+}
+
+/// What background tasks send back to the UI loop.
+pub enum Message {
+    /// A request finished, tagged with the `request_id` it was started under.
+    /// Boxed, as a response is much bigger than the other messages.
+    Response(u64, Box<Result<ResponseData, AppError>>),
+    /// An OAuth2 token was fetched or refreshed, for this `oauth::token_key`.
+    Token(String, Result<Token, AppError>),
+    /// A line for the Logs pane.
+    Log(Level, String),
+    /// A request of a collection run finished: the run's id, the request's
+    /// index in it and how it went.
+    RunStep(u64, usize, Outcome),
+    /// The monitored request was sent again: the monitor's id and how it went.
+    MonitorSample(u64, Outcome),
+    /// A benchmark finished: its id and how it went.
+    Benchmarked(u64, Result<Stats, String>),
+    /// What a script run away from the UI printed and set.
+    Script(Effects),
+    /// The request prepared on the task that sent it, as it was sent, for
+    /// the request of this `request_id`.
+    Sent(u64, Box<Request>),
+    /// A request went through the recording proxy, and got a response
+    /// with this status if it got one.
+    Captured(Box<SavedRequest>, Option<u16>),
+}
+
+/// What the main loop opens in `$EDITOR`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExternalEdit {
+    Body,
+    PreRequestScript,
+    PostResponseScript,
+}
+
+impl Default for App {
+    fn default() -> App {
+        App {
+            config: Config::default(),
+            response: None,
+            error: None,
+            show_metadata: true,
+            show_raw: false,
+            response_scroll: 0,
+            response_height: 0,
+            logs_scroll: 0,
+            logs_height: 0,
+            follow_logs: true,
+            pane_areas: Vec::new(),
+            follow_events: true,
+            pending_g: false,
+            visual_anchor: 0,
+            visual_cursor: 0,
+            tree: None,
+            table: None,
+            quit: false,
+            external_edit: None,
+            stdin: None,
+            help_scroll: 0,
+            palette: Palette::default(),
+            finder: Finder::default(),
+            search: Search::default(),
+            prompt: None,
+            clipboard: Clipboard::default(),
+            history: History::default(),
+            history_selected: 0,
+            settings_selected: 0,
+            collections: Collections::default(),
+            environments: Environments::default(),
+            tokens: TokenStore::default(),
+            cookies: Arc::default(),
+            cookies_selected: 0,
+            snippets: Vec::new(),
+            snippet_selected: 0,
+            snippet_scroll: 0,
+            run: None,
+            monitor: None,
+            bench: None,
+            authorizing: None,
+            mocking: None,
+            proxying: None,
+            session: Har::session(),
+            input_mode: InputMode::Normal,
+            method: Method::GET,
+            url: String::new(),
+            url_cursor: 0,
+            url_undo: Undo::default(),
+            url_completions: Vec::new(),
+            completion_selected: None,
+            showing_recent_urls: false,
+            params: KeyValueTable::default(),
+            headers: KeyValueTable::default(),
+            auth: Auth::default(),
+            settings: Settings::default(),
+            body: String::new(),
+            body_editor: Editor::default(),
+            body_mode: BodyMode::Raw,
+            fields: KeyValueTable::default(),
+            variables: String::new(),
+            variables_editor: Editor::default(),
+            assertions: Vec::new(),
+            assertions_selected: 0,
+            pre_request: String::new(),
+            post_response: String::new(),
+            requires: Vec::new(),
+            logs: Logs::default(),
+            focus: Focus::default(),
+            in_flight: None,
+            request_started: None,
+            download: None,
+            events: None,
+            request_id: 0,
+            next_request_id: 0,
+            sent_request: None,
+            tabs: vec![Tab::default()],
+            current_tab: 0,
+        }
+    }
+}
+
+impl App {
+    /// Snapshot of the editors as a request ready to send.
+    pub fn build_request(&self) -> Request {
+        Request {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            params: self.params.active().cloned().collect(),
+            headers: self.headers.active().cloned().collect(),
+            body: self.body.clone(),
+            body_mode: self.body_mode,
+            fields: self.fields.active().cloned().collect(),
+            variables: self.variables.clone(),
+            auth: self.auth.clone(),
+            settings: self.settings.clone(),
+            assertions: self.assertions.clone(),
+            pre_request: self.pre_request.clone(),
+            post_response: self.post_response.clone(),
+            requires: self.requires.clone(),
+            cookie_jar: Some(Arc::clone(&self.cookies)),
+            download: self.download.clone(),
+            events: self.events.clone(),
+        }
+    }
+
+    pub fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Response(id, result) => self.finish_request(id, *result),
+            Message::Token(key, Ok(token)) => match self.tokens.insert(key, token) {
+                Ok(()) => self.logs.info("OAuth2 token stored"),
+                Err(e) => self.logs.error(format!("Could not save tokens: {}", e)),
+            },
+            Message::Token(_, Err(e)) => self.logs.error(e.to_string()),
+            Message::Log(level, line) => self.logs.push(level, line),
+            Message::RunStep(id, i, outcome) => self.record_run_step(id, i, outcome),
+            Message::MonitorSample(id, outcome) => self.record_monitor_sample(id, outcome),
+            Message::Benchmarked(id, result) => self.finish_bench(id, result),
+            Message::Script(effects) => self.keep_script_effects(effects),
+            Message::Sent(id, request) => self.update_sent(id, *request),
+            Message::Captured(saved, status) => self.record_captured(*saved, status),
+        }
+    }
+
+    /// Spawns the request on a background task; its result arrives on `tx`.
+    pub fn start_request(&mut self, tx: &UnboundedSender<Message>) {
+        if self.in_flight.is_some() {
+            self.logs.warn("A request is already in progress");
+            return;
+        }
+        self.logs
+            .info(format!("Sending {} {}", self.method, self.url));
+        self.events = Some(Arc::default());
+        self.follow_events = true;
+        // History keeps the request as written, before variables are filled in
+        let written = self.build_request();
+        if !written.requires.is_empty() {
+            return self.start_chain(tx, written);
+        }
+        let prepared = prepare_scripted(&written, &self.environments, &self.inherited_settings());
+        let request = match prepared {
+            Ok((request, effects)) => {
+                self.keep_script_effects(effects);
+                request
+            }
+            Err(e) => {
+                self.events = None;
+                self.logs.error(e.to_string());
+                self.error = Some(e);
+                return;
+            }
+        };
+        self.sent_request = Some((written, request.clone()));
+        let token = token_for(&self.tokens, &self.environments, &request);
+        let tx = tx.clone();
+        let id = self.begin_request();
+        self.in_flight = Some(tokio::spawn(async move {
+            let result = execute(request, token, &tx).await;
+            // The receiver only goes away when the app is quitting
+            let _ = tx.send(Message::Response(id, Box::new(result)));
+        }));
+    }
+
+    /// As `start_request`, for a request that requires others: they're
+    /// sent first on the same task, and it's prepared once they're done.
+    fn start_chain(&mut self, tx: &UnboundedSender<Message>, written: Request) {
+        let prerequisites = match self.prerequisites(&written, None) {
+            Ok(prerequisites) => prerequisites,
+            Err(e) => {
+                self.events = None;
+                self.logs.error(e.to_string());
+                self.error = Some(e);
+                return;
+            }
+        };
+        let names: Vec<&str> = prerequisites.iter().map(|(key, _)| key.as_str()).collect();
+        self.logs
+            .info(format!("Sending {} first", names.join(", ")));
+        // Replaced with the request as sent once the task has prepared it
+        let unscripted = prepare(&written, &self.environments, &self.inherited_settings());
+        self.sent_request = Some((written.clone(), unscripted));
+        let context = self.context();
+        let tx = tx.clone();
+        let id = self.begin_request();
+        self.in_flight = Some(tokio::spawn(async move {
+            let result = send_chain(&prerequisites, &written, &context, id, &tx).await;
+            let _ = tx.send(Message::Response(id, Box::new(result)));
+        }));
+    }
+
+    /// Makes the request send the saved requests `names` first, checking
+    /// they can all be found.
+    pub fn set_requires(&mut self, names: Vec<String>) {
+        if let Err(e) = self.collections.prerequisites(&names, None) {
+            self.logs.error(e.to_string());
+            return;
+        }
+        if names.is_empty() {
+            self.logs.info("Sends nothing first");
+        } else {
+            self.logs.info(format!("Sends {} first", names.join(", ")));
+        }
+        self.requires = names;
+    }
+
+    /// Gives the request being sent from this tab a new id, and starts
+    /// timing it.
+    fn begin_request(&mut self) -> u64 {
+        self.next_request_id += 1;
+        self.request_id = self.next_request_id;
+        self.request_started = Some(Instant::now());
+        self.request_id
+    }
+
+    /// The saved requests `written` requires, ready to send, each with its
+    /// `collection/name`.
+    fn prerequisites(
+        &self,
+        written: &Request,
+        near: Option<&str>,
+    ) -> Result<Vec<(String, Request)>, AppError> {
+        self.collections
+            .prerequisites(&written.requires, near)?
+            .into_iter()
+            .map(|(key, saved)| {
+                let mut request = saved.request()?;
+                request.cookie_jar = Some(Arc::clone(&self.cookies));
+                Ok((key, request))
+            })
+            .collect()
+    }
+
+    /// What a task needs to prepare and send requests away from the app.
+    fn context(&self) -> Context {
+        Context {
+            environments: self.environments.clone(),
+            inherited: self.inherited_settings(),
+            tokens: self.tokens.clone(),
+            throttle: Throttle::default(),
+        }
+    }
+
+    /// Records `request` as what request `id` was sent as, now that the
+    /// task sending it has said.
+    fn update_sent(&mut self, id: u64, request: Request) {
+        let sent = if id == self.request_id {
+            &mut self.sent_request
+        } else {
+            match self.tabs.iter_mut().find(|tab| tab.request_id == id) {
+                Some(tab) => &mut tab.sent_request,
+                None => return,
+            }
+        };
+        if let Some((_, sent)) = sent {
+            *sent = request;
+        }
+    }
+
+    /// Logs what a script printed and saves the variables it set to the
+    /// active environment.
+    fn keep_script_effects(&mut self, effects: Effects) {
+        for line in effects.logs {
+            self.logs.info(format!("Script: {}", line));
+        }
+        if effects.variables.is_empty() {
+            return;
+        }
+        if self.environments.active().is_none() {
+            let names: Vec<&str> = effects
+                .variables
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect();
+            self.logs.info(format!(
+                "Not saving {}: no environment is active",
+                names.join(", ")
+            ));
+            return;
+        }
+        for (name, value) in &effects.variables {
+            if let Err(e) = self.environments.set_variable(name, value) {
+                self.logs
+                    .error(format!("Could not save environments: {}", e));
+                return;
+            }
+        }
+    }
+
+    /// Runs the post-response script of the request just sent, if it has
+    /// one, keeping what it captured.
+    fn run_post_response(&mut self, sent: &Request, response: &ResponseData) {
+        match after_response(sent, response, &self.environments) {
+            Ok(effects) => self.keep_script_effects(effects),
+            Err(e) => self.logs.error(e.to_string()),
+        }
+    }
+
+    /// Sends the request in the editors and waits for the response, for
+    /// when there's no UI to report back to.
+    pub async fn send_and_wait(&mut self) -> Result<ResponseData, AppError> {
+        let written = self.build_request();
+        let prerequisites = self.prerequisites(&written, None)?;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let context = self.context();
+        let result = send_chain(&prerequisites, &written, &context, self.request_id, &tx).await;
+        let mut sent = None;
+        while let Ok(message) = rx.try_recv() {
+            match message {
+                Message::Sent(_, request) => sent = Some(*request),
+                message => self.handle_message(message),
+            }
+        }
+        if let (Some(request), Ok(response)) = (&sent, &result) {
+            self.run_post_response(request, response);
+        }
+        result
+    }
+
+    /// Sends the request, asking first if its method is one of the config's
+    /// `confirm_methods` and it's going to another machine.
+    pub fn send(&mut self, tx: &UnboundedSender<Message>) {
+        match self.host_to_confirm() {
+            Some(host) => {
+                let label = format!("Send {} to {}? y/n", self.method, host);
+                self.open_prompt(PromptAction::ConfirmSend, &label, "");
+            }
+            None => self.start_request(tx),
+        }
+    }
+
+    /// The host the request is going to, if sending there needs confirming.
+    fn host_to_confirm(&self) -> Option<String> {
+        let method = self.method.as_str();
+        if !self
+            .config
+            .confirm_methods
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(method))
+        {
+            return None;
+        }
+        let url = self
+            .build_request()
+            .interpolate(&self.environments)
+            .full_url();
+        let url = url::Url::parse(&url).ok()?;
+        let local = match url.host()? {
+            url::Host::Domain(domain) => domain == "localhost" || domain.ends_with(".localhost"),
+            url::Host::Ipv4(ip) => ip.is_loopback() || ip.is_unspecified(),
+            url::Host::Ipv6(ip) => ip.is_loopback() || ip.is_unspecified(),
+        };
+        (!local).then(|| url.host_str().unwrap_or_default().to_string())
+    }
+
+    /// Aborts the running request task, which drops the in-progress reqwest future.
+    pub fn cancel_request(&mut self) {
+        if let Some(task) = self.in_flight.take() {
+            task.abort();
+            self.request_started = None;
+            self.download = None;
+            self.events = None;
+            self.logs.info("Request cancelled");
+        }
+    }
+
+    fn finish_request(&mut self, id: u64, result: Result<ResponseData, AppError>) {
+        if id != self.request_id || self.in_flight.is_none() {
+            // Either another tab's, which is finished in that tab, or a late
+            // result from a request that was cancelled
+            let background = self
+                .tabs
+                .iter()
+                .position(|tab| tab.request_id == id && tab.in_flight.is_some());
+            if let Some(i) = background {
+                let current = self.current_tab;
+                self.swap_tab(current);
+                self.swap_tab(i);
+                self.finish_request(id, result);
+                self.swap_tab(i);
+                self.swap_tab(current);
+            }
+            return;
+        }
+        self.in_flight = None;
+        let took = self.request_started.take().map(|at| at.elapsed());
+        self.download = None;
+        self.events = None;
+        let status = result
+            .as_ref()
+            .ok()
+            .map(|response| response.status.as_u16());
+        let sent = self.sent_request.take();
+        if let Some((written, _)) = &sent {
+            let mut entry = HistoryEntry::new(written, status);
+            entry.elapsed_ms = result
+                .as_ref()
+                .ok()
+                .map(|response| response.elapsed.as_millis() as u64);
+            if let Err(e) = self.history.push(entry) {
+                self.logs.error(format!("Could not save history: {}", e));
+            }
+        }
+        if took.is_some_and(|took| took.as_secs_f64() >= self.config.notify_after_secs) {
+            let summary = match &result {
+                Ok(response) => format!("{} {}: {}", self.method, self.url, response.status_line()),
+                Err(e) => format!("{} {}: {}", self.method, self.url, e),
+            };
+            if let Err(e) = notify::send(self.config.notify, &summary) {
+                self.logs.error(format!("Could not notify: {}", e));
+            }
+        }
+        match result {
+            Ok(response) => {
+                if let Some((_, request)) = &sent {
+                    self.session
+                        .log
+                        .entries
+                        .push(har::Entry::new(request, &response));
+                }
+                if let Err(e) = self.cookies.save() {
+                    self.logs.error(format!("Could not save cookies: {}", e));
+                }
+                self.logs.info(format!("Done: {}", response.status_line()));
+                if let Some((_, request)) = &sent {
+                    let results = assertion::check_each(&request.assertions, &response);
+                    let failed: Vec<String> = results.into_iter().filter_map(Result::err).collect();
+                    if !failed.is_empty() {
+                        self.logs.warn(format!(
+                            "{} of {} assertions failed: {}",
+                            failed.len(),
+                            request.assertions.len(),
+                            failed.join("; ")
+                        ));
+                    }
+                    self.run_post_response(request, &response);
+                }
+                self.response = Some(response);
+                self.response_scroll = 0;
+                self.error = None;
+                self.update_search();
+                match self.input_mode {
+                    InputMode::Tree => {
+                        self.input_mode = InputMode::Normal;
+                        self.open_tree();
+                    }
+                    InputMode::JsonTable => {
+                        self.tree = None;
+                        self.close_table();
+                    }
+                    _ => {}
+                }
+            }
+            Err(e) => {
+                self.logs.error(e.to_string());
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Exchanges the shown tab's state with what's stored for tab `i`.
+    fn swap_tab(&mut self, i: usize) {
+        use std::mem::swap;
+        let tab = &mut self.tabs[i];
+        swap(&mut self.method, &mut tab.method);
+        swap(&mut self.url, &mut tab.url);
+        swap(&mut self.url_undo, &mut tab.url_undo);
+        swap(&mut self.params, &mut tab.params);
+        swap(&mut self.headers, &mut tab.headers);
+        swap(&mut self.auth, &mut tab.auth);
+        swap(&mut self.settings, &mut tab.settings);
+        swap(&mut self.body, &mut tab.body);
+        swap(&mut self.body_editor, &mut tab.body_editor);
+        swap(&mut self.body_mode, &mut tab.body_mode);
+        swap(&mut self.fields, &mut tab.fields);
+        swap(&mut self.variables, &mut tab.variables);
+        swap(&mut self.variables_editor, &mut tab.variables_editor);
+        swap(&mut self.assertions, &mut tab.assertions);
+        swap(&mut self.pre_request, &mut tab.pre_request);
+        swap(&mut self.post_response, &mut tab.post_response);
+        swap(&mut self.requires, &mut tab.requires);
+        swap(&mut self.response, &mut tab.response);
+        swap(&mut self.error, &mut tab.error);
+        swap(&mut self.show_raw, &mut tab.show_raw);
+        swap(&mut self.response_scroll, &mut tab.response_scroll);
+        swap(&mut self.follow_events, &mut tab.follow_events);
+        swap(&mut self.search, &mut tab.search);
+        swap(&mut self.in_flight, &mut tab.in_flight);
+        swap(&mut self.request_started, &mut tab.request_started);
+        swap(&mut self.download, &mut tab.download);
+        swap(&mut self.events, &mut tab.events);
+        swap(&mut self.request_id, &mut tab.request_id);
+        swap(&mut self.sent_request, &mut tab.sent_request);
+    }
+
+    /// Labels for the tab bar, in order.
+    pub fn tab_labels(&self) -> Vec<String> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| {
+                if i == self.current_tab {
+                    tabs::label(&self.method, &self.url)
+                } else {
+                    tabs::label(&tab.method, &tab.url)
+                }
+            })
+            .collect()
+    }
+
+    /// Opens an empty request in a new tab after the others and shows it.
+    pub fn new_tab(&mut self) {
+        self.swap_tab(self.current_tab);
+        self.tabs.push(Tab::default());
+        self.current_tab = self.tabs.len() - 1;
+        self.focus = Focus::Url;
+    }
+
+    /// Shows the tab `step` places along, wrapping around at either end.
+    pub fn switch_tab(&mut self, step: isize) {
+        let len = self.tabs.len() as isize;
+        let next = (self.current_tab as isize + step).rem_euclid(len) as usize;
+        if next != self.current_tab {
+            self.swap_tab(self.current_tab);
+            self.current_tab = next;
+            self.swap_tab(next);
+        }
+    }
+
+    /// Closes the shown tab, cancelling its request, and shows the one
+    /// that took its place.
+    pub fn close_tab(&mut self) {
+        if self.tabs.len() == 1 {
+            self.logs.warn("Can't close the last tab");
+            return;
+        }
+        if let Some(task) = self.in_flight.take() {
+            task.abort();
+        }
+        self.tabs.remove(self.current_tab);
+        self.current_tab = self.current_tab.min(self.tabs.len() - 1);
+        // The closed tab's state ends up in the placeholder, and is dropped
+        self.swap_tab(self.current_tab);
+        self.tabs[self.current_tab] = Tab::default();
+    }
+
+    /// The response body as currently displayed: raw or formatted.
+    pub fn response_text(&self) -> Option<&BodyText> {
+        self.response
+            .as_ref()
+            .map(|response| response.text(self.show_raw))
+    }
+
+    /// Flips between the raw and formatted body, keeping the scroll and search valid.
+    pub fn toggle_raw(&mut self) {
+        self.show_raw = !self.show_raw;
+        self.scroll_response(0);
+        self.update_search();
+    }
+
+    fn response_line_count(&self) -> usize {
+        match self.live_events() {
+            Some(events) => events.len(),
+            None => self.response_text().map_or(0, BodyText::line_count),
+        }
+    }
+
+    /// The event stream being received, shown in place of the last response.
+    pub fn live_events(&self) -> Option<&EventStream> {
+        self.events.as_deref().filter(|events| events.is_open())
+    }
+
+    /// Closes the event stream being received, which then completes as a
+    /// response listing every event.
+    pub fn disconnect_events(&mut self) {
+        match self.live_events() {
+            Some(events) => {
+                events.disconnect();
+                self.logs.info("Disconnecting from the event stream");
+            }
+            None => self.logs.warn("No event stream to disconnect"),
+        }
+    }
+
+    /// Moves the Response pane by `delta` lines, stopping once the last line is in view.
+    pub fn scroll_response(&mut self, delta: i32) {
+        let max = self
+            .response_line_count()
+            .saturating_sub(self.response_height);
+        let offset = (self.response_scroll as i64 + delta as i64).clamp(0, max as i64);
+        self.response_scroll = offset as usize;
+        if delta != 0 {
+            self.follow_events = offset as usize == max;
+        }
+    }
+
+    /// Moves the Logs pane by `delta` lines, stopping once the last line is
+    /// in view. Scrolling back to it follows new lines again.
+    pub fn scroll_logs(&mut self, delta: i32) {
+        let max = self.logs.len().saturating_sub(self.logs_height);
+        if self.follow_logs {
+            self.logs_scroll = max;
+        }
+        self.logs_scroll = (self.logs_scroll as i64 + delta as i64).clamp(0, max as i64) as usize;
+        self.follow_logs = self.logs_scroll == max;
+    }
+
+    /// The pane drawn at a terminal cell, if any.
+    pub fn window_at(&self, column: u16, row: u16) -> Option<Focus> {
+        self.pane_areas
+            .iter()
+            .find(|(_, area)| {
+                (area.left()..area.right()).contains(&column)
+                    && (area.top()..area.bottom()).contains(&row)
+            })
+            .map(|(window, _)| *window)
+    }
+
+    pub fn scroll_response_to_top(&mut self) {
+        self.response_scroll = 0;
+    }
+
+    pub fn scroll_response_to_bottom(&mut self) {
+        self.scroll_response(i32::MAX);
+    }
+
+    /// e.g. `12-40/310`, for the Response pane title.
+    pub fn response_position(&self) -> String {
+        let total = self.response_line_count();
+        let first = (self.response_scroll + 1).min(total);
+        let last = (self.response_scroll + self.response_height).min(total);
+        format!("{}-{}/{}", first, last, total)
+    }
+
+    /// Re-runs the search over the current body and jumps to the first match.
+    pub fn update_search(&mut self) {
+        let body = self
+            .response
+            .as_ref()
+            .map_or("", |response| response.text(self.show_raw).as_str());
+        self.search.update(body);
+        self.jump_to_match();
+    }
+
+    /// Scrolls the Response pane so the current match is in view.
+    pub fn jump_to_match(&mut self) {
+        if let Some(m) = self.search.current_match() {
+            let line = m.line;
+            let height = self.response_height.max(1);
+            if line < self.response_scroll || line >= self.response_scroll + height {
+                self.response_scroll = line.saturating_sub(height / 2);
+                self.scroll_response(0);
+            }
+        }
+    }
+
+    pub fn open_prompt(&mut self, action: PromptAction, label: &str, initial: &str) {
+        self.prompt = Some(Prompt::new(action, label, initial));
+        self.input_mode = InputMode::Prompt;
+    }
+
+    pub fn close_prompt(&mut self) {
+        // A setting's prompt goes back to the popup it was opened from
+        self.input_mode = match self.prompt.take() {
+            Some(prompt) if prompt.action == PromptAction::SettingField => InputMode::Settings,
+            Some(prompt) if prompt.action == PromptAction::CookieValue => InputMode::Cookies,
+            Some(prompt)
+                if matches!(
+                    prompt.action,
+                    PromptAction::AddAssertion | PromptAction::EditAssertion
+                ) =>
+            {
+                InputMode::Assertions
+            }
+            Some(prompt) if prompt.action == PromptAction::ExportCsv => self.json_view(),
+            _ => InputMode::Normal,
+        };
+    }
+
+    /// Closes the prompt and carries out its action with the entered text.
+    pub fn submit_prompt(&mut self, tx: &UnboundedSender<Message>) {
+        if let Some(prompt) = self.prompt.take() {
+            self.input_mode = InputMode::Normal;
+            match prompt.action {
+                PromptAction::SaveResponse => self.save_response(&prompt.input),
+                PromptAction::Download => self.start_download(&prompt.input, tx),
+                PromptAction::SaveRequest => self.save_request(&prompt.input),
+                PromptAction::ImportCurl => self.import_curl(&prompt.input),
+                PromptAction::ImportFile => self.import_file(&prompt.input),
+                PromptAction::ExportHar => self.export_session(&prompt.input),
+                PromptAction::ExportLogs => self.export_logs(&prompt.input),
+                PromptAction::AttachFile => self.attach_file(&prompt.input),
+                PromptAction::BearerToken => self.set_bearer_token(&prompt.input),
+                PromptAction::Command => self.run_command(&prompt.input, tx),
+                PromptAction::Filter => self.filter_response(&prompt.input),
+                PromptAction::ExportCsv => self.export_csv(&prompt.input),
+                PromptAction::ConfirmSend => {
+                    let answer = prompt.input.trim();
+                    if answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes") {
+                        self.start_request(tx);
+                    } else {
+                        self.logs.warn(format!("{} not sent", self.method));
+                    }
+                }
+                PromptAction::AuthField => {
+                    if let Some(field) = self.auth.selected_field() {
+                        self.auth.set(field.name, &prompt.input);
+                    }
+                }
+                PromptAction::CookieValue => {
+                    if let Err(e) = self.cookies.set_value(self.cookies_selected, &prompt.input) {
+                        self.logs.error(format!("Could not save cookies: {}", e));
+                    }
+                    self.input_mode = InputMode::Cookies;
+                }
+                PromptAction::SettingField => {
+                    let field = &settings::FIELDS[self.settings_selected];
+                    self.settings.set(field.name, &prompt.input);
+                    self.input_mode = InputMode::Settings;
+                }
+                PromptAction::AddAssertion => self.set_assertion(None, &prompt.input),
+                PromptAction::EditAssertion => {
+                    self.set_assertion(Some(self.assertions_selected), &prompt.input)
+                }
+            }
+        }
+    }
+
+    /// Prompts for a new value of the field selected in the Auth pane, or
+    /// moves a choice field to its next option.
+    pub fn edit_auth_field(&mut self) {
+        if let Some(field) = self.auth.selected_field() {
+            if !field.choices.is_empty() {
+                self.auth.cycle_choice(field);
+                return;
+            }
+            let value = String::from(self.auth.value(field.name));
+            self.open_prompt(PromptAction::AuthField, field.label, &value);
+            if let Some(prompt) = self.prompt.as_mut() {
+                prompt.masked = field.secret;
+            }
+        }
+    }
+
+    /// The settings from `config.json` overridden by the active environment's,
+    /// which the request's own settings override in turn.
+    pub fn inherited_settings(&self) -> Settings {
+        match self.environments.active() {
+            Some(env) => env.settings.over(&self.config.settings),
+            None => self.config.settings.clone(),
+        }
+    }
+
+    pub fn open_settings(&mut self) {
+        self.settings_selected = 0;
+        self.input_mode = InputMode::Settings;
+    }
+
+    pub fn select_setting(&mut self, step: isize) {
+        let last = settings::FIELDS.len() - 1;
+        self.settings_selected = self.settings_selected.saturating_add_signed(step).min(last);
+    }
+
+    /// Prompts for this request's value of the selected setting, or moves a
+    /// choice setting on to its next option.
+    pub fn edit_setting(&mut self) {
+        let field = &settings::FIELDS[self.settings_selected];
+        if !field.choices.is_empty() {
+            let current = self.settings.value(field.name);
+            let i = field
+                .choices
+                .iter()
+                .position(|c| *c == current)
+                .unwrap_or(0);
+            self.settings
+                .set(field.name, field.choices[(i + 1) % field.choices.len()]);
+            return;
+        }
+        let value = String::from(self.settings.value(field.name));
+        self.open_prompt(PromptAction::SettingField, field.label, &value);
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.masked = field.secret;
+        }
+    }
+
+    /// Drops this request's value of the selected setting so it is inherited again.
+    pub fn clear_setting(&mut self) {
+        self.settings
+            .set(settings::FIELDS[self.settings_selected].name, "");
+    }
+
+    pub fn open_cookies(&mut self) {
+        self.cookies_selected = 0;
+        self.input_mode = InputMode::Cookies;
+    }
+
+    pub fn select_cookie(&mut self, step: isize) {
+        let last = self.cookies.len().saturating_sub(1);
+        self.cookies_selected = self.cookies_selected.saturating_add_signed(step).min(last);
+    }
+
+    pub fn edit_cookie(&mut self) {
+        if let Some(cookie) = self.cookies.all().get(self.cookies_selected) {
+            let label = format!("Value of {} for {}", cookie.name, cookie.domain);
+            self.open_prompt(PromptAction::CookieValue, &label, &cookie.value);
+        }
+    }
+
+    pub fn remove_cookie(&mut self) {
+        if let Err(e) = self.cookies.remove(self.cookies_selected) {
+            self.logs.error(format!("Could not save cookies: {}", e));
+        }
+        self.select_cookie(0);
+    }
+
+    /// Writes the request, with variables filled in, as code in each
+    /// language and shows it. Scripts aren't run, so what they'd change
+    /// is left out.
+    pub fn open_snippets(&mut self) {
+        let request = prepare(
+            &self.build_request(),
+            &self.environments,
+            &self.inherited_settings(),
+        );
+        let snippets = Language::ALL
+            .iter()
+            .map(|&language| Ok((language, snippet::generate(&request, language)?)))
+            .collect::<Result<_, AppError>>();
+        match snippets {
+            Ok(snippets) => {
+                self.snippets = snippets;
+                self.snippet_scroll = 0;
+                self.input_mode = InputMode::Snippets;
+            }
+            Err(e) => self.logs.error(e.to_string()),
+        }
+    }
+
+    pub fn select_snippet(&mut self, step: isize) {
+        let len = self.snippets.len().max(1) as isize;
+        self.snippet_selected = (self.snippet_selected as isize + step).rem_euclid(len) as usize;
+        self.snippet_scroll = 0;
+    }
+
+    pub fn scroll_snippet(&mut self, step: i16) {
+        self.snippet_scroll = self.snippet_scroll.saturating_add_signed(step);
+    }
+
+    pub fn copy_snippet(&mut self) {
+        if let Some((language, code)) = self.snippets.get(self.snippet_selected).cloned() {
+            self.copy(&format!("the request as {}", language.name()), &code);
+        }
+    }
+
+    pub fn open_assertions(&mut self) {
+        self.assertions_selected = 0;
+        self.input_mode = InputMode::Assertions;
+    }
+
+    pub fn select_assertion(&mut self, step: isize) {
+        let last = self.assertions.len().saturating_sub(1);
+        self.assertions_selected = self
+            .assertions_selected
+            .saturating_add_signed(step)
+            .min(last);
+    }
+
+    pub fn prompt_add_assertion(&mut self) {
+        self.open_prompt(
+            PromptAction::AddAssertion,
+            "Assert, e.g. status == 200 or jsonpath \"$.id\" exists",
+            "",
+        );
+    }
+
+    pub fn prompt_edit_assertion(&mut self) {
+        if let Some(text) = self.assertions.get(self.assertions_selected).cloned() {
+            self.open_prompt(PromptAction::EditAssertion, "Assert", &text);
+        }
+    }
+
+    /// Sets assertion `i`, or adds one, if `text` reads as one once its
+    /// variables are filled in.
+    fn set_assertion(&mut self, i: Option<usize>, text: &str) {
+        self.input_mode = InputMode::Assertions;
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        if let Err(e) = Assertion::parse(&self.environments.interpolate(text)) {
+            self.logs.error(e);
+            return;
+        }
+        match i.and_then(|i| self.assertions.get_mut(i)) {
+            Some(assertion) => *assertion = String::from(text),
+            None => {
+                self.assertions.push(String::from(text));
+                self.assertions_selected = self.assertions.len() - 1;
+            }
+        }
+    }
+
+    pub fn remove_assertion(&mut self) {
+        if self.assertions_selected < self.assertions.len() {
+            self.assertions.remove(self.assertions_selected);
+            self.select_assertion(0);
+        }
+    }
+
+    /// Stores the response shown as the snapshot of each `snapshot`
+    /// assertion, for when a change to it is what was wanted.
+    pub fn update_snapshots(&mut self) {
+        let Some(response) = &self.response else {
+            self.logs.warn("No response to take snapshots of");
+            return;
+        };
+        let texts: Vec<String> = self
+            .assertions
+            .iter()
+            .map(|text| self.environments.interpolate(text))
+            .collect();
+        match assertion::update_snapshots(&texts, response) {
+            Ok(0) => self.logs.info("No snapshot assertions to update"),
+            Ok(updated) => self
+                .logs
+                .info(format!("Updated {} snapshots from the response", updated)),
+            Err(e) => self.logs.error(format!("Could not save snapshot: {}", e)),
+        }
+    }
+
+    /// How each assertion fares against the response shown, if there is one.
+    pub fn assertion_results(&self) -> Option<Vec<Result<(), String>>> {
+        let response = self.response.as_ref()?;
+        let texts: Vec<String> = self
+            .assertions
+            .iter()
+            .map(|text| self.environments.interpolate(text))
+            .collect();
+        Some(assertion::check_each(&texts, response))
+    }
+
+    /// Sends every request of the collection selected in the sidebar, one
+    /// after another, and opens the runner popup to follow them. Each is
+    /// sent as saved, with the active environment and the cookie jar. With
+    /// a `data` file of rows, the whole collection is sent once per row,
+    /// with the row's columns as variables over the environment's.
+    pub fn run_collection(&mut self, tx: &UnboundedSender<Message>, data: Option<&str>) {
+        if self.run.as_ref().is_some_and(Run::is_running) {
+            self.input_mode = InputMode::Runner;
+            return;
+        }
+        let Some(collection) = self.collections.containing_selection().cloned() else {
+            self.logs.warn("Select a collection to run");
+            return;
+        };
+        if collection.requests.is_empty() {
+            self.logs
+                .warn(format!("{} has no requests to run", collection.name));
+            return;
+        }
+        let rows = match data {
+            Some(path) => match dataset::load(&storage::expand_tilde(path)) {
+                Ok(rows) if rows.is_empty() => {
+                    self.logs.warn(format!("No rows in {}", path));
+                    return;
+                }
+                Ok(rows) => rows.into_iter().map(Some).collect(),
+                Err(e) => {
+                    self.logs.error(e.to_string());
+                    return;
+                }
+            },
+            None => vec![None],
+        };
+        let mut steps = Vec::new();
+        let mut requests = Vec::new();
+        for (r, row) in rows.iter().enumerate() {
+            for (i, saved) in collection.requests.iter().enumerate() {
+                self.queue(
+                    &collection,
+                    (i, saved),
+                    (r, row.as_ref()),
+                    &mut steps,
+                    &mut requests,
+                );
+            }
+        }
+        let nothing_to_send = requests.is_empty();
+        self.next_request_id += 1;
+        let id = self.next_request_id;
+        let tx = tx.clone();
+        let context = self.context();
+        // Each request is prepared only once those before it are done, so
+        // it has the variables their scripts set
+        let task = tokio::spawn(async move {
+            let mut captured = BTreeMap::new();
+            // Requests that passed for each row, so prerequisites are sent
+            // once a row
+            let mut sent = HashSet::new();
+            for queued in requests {
+                let mut failed = None;
+                for (key, prerequisite) in &queued.prerequisites {
+                    if !sent.insert((queued.row, key.clone())) {
+                        continue;
+                    }
+                    let result = send_prerequisite(
+                        key,
+                        prerequisite,
+                        &context,
+                        &queued.variables,
+                        &mut captured,
+                        &tx,
+                    )
+                    .await;
+                    if let Err(e) = result {
+                        failed = Some(e);
+                        break;
+                    }
+                }
+                let result = match failed {
+                    Some(e) => Err(e),
+                    None => {
+                        send_scripted(
+                            &queued.written,
+                            &context,
+                            &queued.variables,
+                            &mut captured,
+                            &tx,
+                        )
+                        .await
+                    }
+                };
+                let outcome = match result {
+                    Ok(sent) => sent.outcome(),
+                    Err(e) => Outcome::Failed(e.to_string()),
+                };
+                // One that failed is tried again by those requiring it
+                if outcome.passed(!queued.written.assertions.is_empty()) {
+                    sent.insert((queued.row, queued.key));
+                }
+                let _ = tx.send(Message::RunStep(id, queued.step, outcome));
+            }
+        });
+        let message = match data {
+            Some(path) => format!(
+                "Running {} for each of the {} rows of {}",
+                collection.name,
+                rows.len(),
+                path
+            ),
+            None => format!("Running {} requests of {}", steps.len(), collection.name),
+        };
+        self.logs.info(message);
+        let mut run = Run {
+            id,
+            collection: collection.name,
+            steps,
+            selected: 0,
+            started: Instant::now(),
+            took: None,
+            task: Some(task),
+        };
+        if nothing_to_send {
+            run.stop();
+            self.logs.info(run.summary());
+        }
+        self.run = Some(run);
+        self.input_mode = InputMode::Runner;
+    }
+
+    /// Adds the `i`th request of `collection` to a run's `steps`, and to the
+    /// `requests` to send if it can be prepared, with the `row`th dataset
+    /// row's variables if there is one.
+    fn queue(
+        &self,
+        collection: &Collection,
+        (i, saved): (usize, &SavedRequest),
+        (r, row): (usize, Option<&BTreeMap<String, String>>),
+        steps: &mut Vec<Step>,
+        requests: &mut Vec<Queued>,
+    ) {
+        let mut outcome = Outcome::Pending;
+        let mut url = saved.url.clone();
+        let prepared = saved.request().and_then(|written| {
+            let prerequisites = self.prerequisites(&written, Some(&collection.name))?;
+            Ok((written, prerequisites))
+        });
+        match prepared {
+            Ok((mut written, prerequisites)) => {
+                written.cookie_jar = Some(Arc::clone(&self.cookies));
+                let variables = row.cloned().unwrap_or_default();
+                // With the row's variables, so rows can be told apart
+                url = environments_with(&self.environments, &variables).interpolate(&written.url);
+                requests.push(Queued {
+                    step: steps.len(),
+                    row: r,
+                    key: format!("{}/{}", collection.name, saved.name),
+                    prerequisites,
+                    written,
+                    variables,
+                });
+            }
+            Err(e) => outcome = Outcome::Failed(e.to_string()),
+        }
+        steps.push(Step {
+            request: i,
+            row: row.map(|_| r + 1),
+            name: saved.name.clone(),
+            method: saved.method.clone(),
+            url,
+            asserted: !saved.assertions.is_empty(),
+            outcome,
+        });
+    }
+
+    /// Sends the requests marked in the sidebar all at once, and opens the
+    /// runner popup to follow them as they finish. The requests they
+    /// require are sent first, one after another and each only once, and
+    /// what their scripts set is shared by all.
+    pub fn run_marked(&mut self, tx: &UnboundedSender<Message>) {
+        if self.run.as_ref().is_some_and(Run::is_running) {
+            self.input_mode = InputMode::Runner;
+            return;
+        }
+        let Some((collection, marked)) = self.collections.marked_requests() else {
+            self.logs
+                .warn("Mark requests with Space to run them at once");
+            return;
+        };
+        let collection = collection.clone();
+        let marked: Vec<(usize, SavedRequest)> = marked
+            .into_iter()
+            .map(|(i, saved)| (i, saved.clone()))
+            .collect();
+        let mut steps = Vec::new();
+        let mut requests = Vec::new();
+        for (i, saved) in &marked {
+            self.queue(
+                &collection,
+                (*i, saved),
+                (0, None),
+                &mut steps,
+                &mut requests,
+            );
+        }
+        let nothing_to_send = requests.is_empty();
+        self.next_request_id += 1;
+        let id = self.next_request_id;
+        let tx = tx.clone();
+        let context = self.context();
+        let task = tokio::spawn(async move {
+            let mut captured = BTreeMap::new();
+            // Why each prerequisite that didn't pass failed
+            let mut failed = BTreeMap::new();
+            let mut sent = HashSet::new();
+            for queued in &requests {
+                for (key, prerequisite) in &queued.prerequisites {
+                    if !sent.insert(key.clone()) {
+                        continue;
+                    }
+                    let variables = BTreeMap::new();
+                    let result = send_prerequisite(
+                        key,
+                        prerequisite,
+                        &context,
+                        &variables,
+                        &mut captured,
+                        &tx,
+                    )
+                    .await;
+                    if let Err(e) = result {
+                        failed.insert(key.clone(), e.to_string());
+                    }
+                }
+            }
+            let sends = requests.into_iter().map(|queued| {
+                let mut captured = captured.clone();
+                let failure = queued
+                    .prerequisites
+                    .iter()
+                    .find_map(|(key, _)| failed.get(key).cloned());
+                let (context, tx) = (&context, &tx);
+                async move {
+                    let outcome = match failure {
+                        Some(e) => Outcome::Failed(e),
+                        None => {
+                            let result = send_scripted(
+                                &queued.written,
+                                context,
+                                &queued.variables,
+                                &mut captured,
+                                tx,
+                            )
+                            .await;
+                            match result {
+                                Ok(sent) => sent.outcome(),
+                                Err(e) => Outcome::Failed(e.to_string()),
+                            }
+                        }
+                    };
+                    let _ = tx.send(Message::RunStep(id, queued.step, outcome));
+                }
+            });
+            futures::future::join_all(sends).await;
+        });
+        self.logs.info(format!(
+            "Running {} requests of {} at once",
+            steps.len(),
+            collection.name
+        ));
+        let mut run = Run {
+            id,
+            collection: collection.name,
+            steps,
+            selected: 0,
+            started: Instant::now(),
+            took: None,
+            task: Some(task),
+        };
+        if nothing_to_send {
+            run.stop();
+            self.logs.info(run.summary());
+        }
+        self.run = Some(run);
+        self.input_mode = InputMode::Runner;
+    }
+
+    fn record_run_step(&mut self, id: u64, i: usize, outcome: Outcome) {
+        let Some(run) = self.run.as_mut().filter(|run| run.id == id) else {
+            return;
+        };
+        run.record(i, outcome);
+        if !run.is_running() {
+            let summary = run.summary();
+            self.logs.info(summary);
+        }
+    }
+
+    /// Stops the collection run, leaving the requests not yet sent unsent.
+    pub fn stop_run(&mut self) {
+        if let Some(run) = self.run.as_mut().filter(|run| run.is_running()) {
+            run.stop();
+            let summary = format!("Stopped {}", run.summary());
+            self.logs.info(summary);
+        }
+    }
+
+    /// Loads the request selected in the runner popup into the editors.
+    pub fn load_run_step(&mut self) {
+        let Some(run) = &self.run else {
+            return;
+        };
+        let saved = self
+            .collections
+            .collections
+            .iter()
+            .find(|c| c.name == run.collection)
+            .zip(run.steps.get(run.selected))
+            .and_then(|(c, step)| c.requests.get(step.request))
+            .cloned();
+        if let Some(saved) = saved {
+            let name = saved.name.clone();
+            self.load_saved(saved);
+            self.logs.info(format!("Loaded {}", name));
+            self.input_mode = InputMode::Normal;
+        }
+    }
+
+    /// Opens the monitor popup, first starting to monitor the request
+    /// every `monitor::DEFAULT_INTERVAL` if it isn't being already.
+    pub fn open_monitor(&mut self, tx: &UnboundedSender<Message>) {
+        if self.monitor.is_none() {
+            self.monitor(tx, Some(monitor::DEFAULT_INTERVAL));
+        }
+        self.input_mode = InputMode::Monitor;
+    }
+
+    /// Sends the request every `interval` until stopped, as written now,
+    /// adding how each send went to the monitor popup. It's sent with its
+    /// scripts, and with the requests it requires sent first until they
+    /// have all passed once. `None` stops the monitor running.
+    fn monitor(&mut self, tx: &UnboundedSender<Message>, interval: Option<Duration>) {
+        self.stop_monitor();
+        let Some(interval) = interval else {
+            return;
+        };
+        let written = self.build_request();
+        let prerequisites = match self.prerequisites(&written, None) {
+            Ok(prerequisites) => prerequisites,
+            Err(e) => {
+                self.logs.error(e.to_string());
+                return;
+            }
+        };
+        let label = format!(
+            "{} {}",
+            written.method,
+            self.environments.interpolate(&written.url)
+        );
+        self.next_request_id += 1;
+        let id = self.next_request_id;
+        let context = self.context();
+        let tx = tx.clone();
+        let task = tokio::spawn(async move {
+            let mut captured = BTreeMap::new();
+            let variables = BTreeMap::new();
+            let mut ready = prerequisites.is_empty();
+            let mut ticks = tokio::time::interval(interval);
+            // A send slower than the interval pushes the next one back
+            // rather than bunching them up
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let mut failed = None;
+                if !ready {
+                    for (key, prerequisite) in &prerequisites {
+                        let result = send_prerequisite(
+                            key,
+                            prerequisite,
+                            &context,
+                            &variables,
+                            &mut captured,
+                            &tx,
+                        )
+                        .await;
+                        if let Err(e) = result {
+                            failed = Some(e);
+                            break;
+                        }
+                    }
+                    ready = failed.is_none();
+                }
+                let result = match failed {
+                    Some(e) => Err(e),
+                    None => send_scripted(&written, &context, &variables, &mut captured, &tx).await,
+                };
+                let outcome = match result {
+                    Ok(sent) => sent.outcome(),
+                    Err(e) => Outcome::Failed(e.to_string()),
+                };
+                let _ = tx.send(Message::MonitorSample(id, outcome));
+            }
+        });
+        let monitor = Monitor {
+            id,
+            label,
+            interval,
+            asserted: !self.assertions.is_empty(),
+            samples: Default::default(),
+            selected: 0,
+            task: Some(task),
+        };
+        self.logs.info(format!(
+            "Monitoring {} every {} s",
+            monitor.label,
+            interval.as_secs_f64()
+        ));
+        self.monitor = Some(monitor);
+        self.input_mode = InputMode::Monitor;
+    }
+
+    /// Adds how the monitored request went to its samples, logging when it
+    /// starts or stops passing.
+    fn record_monitor_sample(&mut self, id: u64, outcome: Outcome) {
+        let Some(monitor) = self.monitor.as_mut().filter(|monitor| monitor.id == id) else {
+            return;
+        };
+        let was_passing = monitor
+            .samples
+            .front()
+            .map(|sample| sample.outcome.passed(monitor.asserted));
+        let passing = outcome.passed(monitor.asserted);
+        let line = match &outcome {
+            Outcome::Done { status, .. } => format!("{} {}", monitor.label, status),
+            Outcome::Failed(e) => format!("{}: {}", monitor.label, e),
+            Outcome::Pending => monitor.label.clone(),
+        };
+        monitor.record(outcome);
+        match (was_passing, passing) {
+            (Some(false), true) => self.logs.info(format!("Passing again: {}", line)),
+            (None | Some(true), false) => self.logs.warn(format!("Failing: {}", line)),
+            _ => {}
+        }
+    }
+
+    /// Stops sending the monitored request, keeping its samples.
+    pub fn stop_monitor(&mut self) {
+        if let Some(monitor) = self.monitor.as_mut().filter(|monitor| monitor.is_running()) {
+            monitor.stop();
+            let summary = format!("Stopped monitoring {}", monitor.summary());
+            self.logs.info(summary);
+        }
+    }
+
+    /// The request as a benchmark sends it: prepared once, running its
+    /// pre-request script, and with the stored OAuth2 token if it uses one.
+    /// The requests it requires aren't sent.
+    pub fn prepare_bench(&mut self) -> Result<Request, AppError> {
+        let written = self.build_request();
+        let (mut request, effects) =
+            prepare_scripted(&written, &self.environments, &self.inherited_settings())?;
+        self.keep_script_effects(effects);
+        if let Some((_, token)) = token_for(&self.tokens, &self.environments, &request) {
+            match token.filter(|token| !token.expired()) {
+                Some(token) => request.auth.set("access_token", &token.access_token),
+                None => {
+                    return Err(AppError::Auth(String::from(
+                        "no fresh OAuth2 token, send the request once to get one",
+                    )))
+                }
+            }
+        }
+        Ok(request)
+    }
+
+    /// Opens the benchmark popup, first starting a benchmark of the
+    /// request with the default numbers if there hasn't been one.
+    pub fn open_bench(&mut self, tx: &UnboundedSender<Message>) {
+        if self.bench.is_none() {
+            self.bench(tx, bench::DEFAULT_REQUESTS, bench::DEFAULT_CONCURRENCY);
+        }
+        self.input_mode = InputMode::Bench;
+    }
+
+    /// Sends the request `requests` times, `concurrency` at once, and opens
+    /// the benchmark popup to follow it, replacing any benchmark running.
+    fn bench(&mut self, tx: &UnboundedSender<Message>, requests: usize, concurrency: usize) {
+        self.stop_bench();
+        let request = match self.prepare_bench() {
+            Ok(request) => request,
+            Err(e) => {
+                self.logs.error(e.to_string());
+                return;
+            }
+        };
+        let label = format!("{} {}", request.method, request.full_url());
+        self.next_request_id += 1;
+        let id = self.next_request_id;
+        let done = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&done);
+        let tx = tx.clone();
+        let task = tokio::spawn(async move {
+            let result = bench::run(&request, requests, concurrency, &counted).await;
+            let _ = tx.send(Message::Benchmarked(id, result.map_err(|e| e.to_string())));
+        });
+        self.logs.info(format!(
+            "Benchmarking {}: {} requests, {} at a time",
+            label, requests, concurrency
+        ));
+        self.bench = Some(Bench {
+            id,
+            label,
+            requests,
+            concurrency,
+            done,
+            result: None,
+            task: Some(task),
+        });
+        self.input_mode = InputMode::Bench;
+    }
+
+    fn finish_bench(&mut self, id: u64, result: Result<Stats, String>) {
+        let Some(bench) = self.bench.as_mut().filter(|bench| bench.id == id) else {
+            return;
+        };
+        match &result {
+            Ok(stats) => {
+                let lines = stats.lines();
+                self.logs
+                    .info(format!("{}: {}", bench.label, lines.join(". ")));
+            }
+            Err(e) => self.logs.error(format!("Benchmark failed: {}", e)),
+        }
+        bench.finish(result);
+    }
+
+    /// Stops the benchmark running, dropping what it found so far.
+    pub fn stop_bench(&mut self) {
+        if let Some(bench) = self.bench.as_mut().filter(|bench| bench.is_running()) {
+            bench.stop();
+            let summary = format!("Stopped {}", bench.summary());
+            self.logs.info(summary);
+        }
+    }
+
+    /// Turns sending and storing cookies off or on for this request.
+    pub fn toggle_cookies(&mut self) {
+        let on = self
+            .settings
+            .over(&self.inherited_settings())
+            .sends_cookies();
+        self.settings.set("cookies", if on { "off" } else { "on" });
+    }
+
+    /// Starts the OAuth2 authorization-code flow: opens the browser and
+    /// waits on a local listener for the redirect in the background.
+    pub fn authorize(&mut self, tx: &UnboundedSender<Message>) {
+        if self.auth.kind != AuthKind::OAuth2 {
+            self.logs.warn("Auth type is not OAuth2");
+            return;
+        }
+        let auth = self.auth.interpolate(&self.environments);
+        let settings = self
+            .settings
+            .over(&self.inherited_settings())
+            .interpolate(&self.environments);
+        let flow = match Pkce::new(&auth) {
+            Ok(flow) => flow,
+            Err(e) => {
+                self.logs.error(e.to_string());
+                return;
+            }
+        };
+        self.logs
+            .info(format!("Authorize in the browser: {}", flow.url));
+        if let Err(e) = oauth::open_browser(&flow.url) {
+            self.logs.error(format!(
+                "Could not open a browser ({}), open the URL above",
+                e
+            ));
+        }
+        // Only one flow can listen on the redirect port at a time
+        if let Some(task) = self.authorizing.take() {
+            task.abort();
+        }
+        let key = oauth::token_key(self.environments.active.as_deref(), &auth);
+        let tx = tx.clone();
+        self.authorizing = Some(tokio::spawn(async move {
+            let result = flow.finish(&auth, &settings).await;
+            let _ = tx.send(Message::Token(key, result));
+        }));
+    }
+
+    /// Serves the collection selected in the sidebar as a mock on `port`,
+    /// logging each request to it, or stops the one running for `None`.
+    fn mock(&mut self, tx: &UnboundedSender<Message>, port: Option<u16>) {
+        if let Some(task) = self.mocking.take() {
+            task.abort();
+            self.logs.info("Stopped the mock server");
+        }
+        let Some(port) = port else {
+            return;
+        };
+        let Some(collection) = self.collections.containing_selection() else {
+            self.logs.warn("Select a collection to mock");
+            return;
+        };
+        let mock = Arc::new(Mock::from_collection(collection));
+        let tx = tx.clone();
+        self.mocking = Some(tokio::spawn(async move {
+            if let Err(e) = mock::serve(mock, port, tx.clone()).await {
+                let _ = tx.send(Message::Log(
+                    Level::Error,
+                    format!("Mock server stopped: {}", e),
+                ));
+            }
+        }));
+    }
+
+    /// Runs a forward proxy on `port` that records each request through it,
+    /// or stops the one running for `None`.
+    fn proxy(&mut self, tx: &UnboundedSender<Message>, port: Option<u16>) {
+        if let Some(task) = self.proxying.take() {
+            task.abort();
+            self.logs.info("Stopped the proxy");
+        }
+        let Some(port) = port else {
+            return;
+        };
+        let tx = tx.clone();
+        self.proxying = Some(tokio::spawn(async move {
+            if let Err(e) = proxy::serve(port, tx.clone()).await {
+                let _ = tx.send(Message::Log(Level::Error, format!("Proxy stopped: {}", e)));
+            }
+        }));
+    }
+
+    /// Keeps a request that went through the proxy in the history and the
+    /// captured collection, to be loaded from either and sent again.
+    fn record_captured(&mut self, saved: SavedRequest, status: Option<u16>) {
+        if let Ok(request) = saved.request() {
+            if let Err(e) = self.history.push(HistoryEntry::new(&request, status)) {
+                self.logs.error(format!("Could not save history: {}", e));
+            }
+        }
+        if let Err(e) = self.collections.append(proxy::COLLECTION, saved) {
+            self.logs
+                .error(format!("Could not save collections: {}", e));
+        }
+    }
+
+    /// Prompts for a fresh bearer token, switching the request to Bearer auth.
+    /// When the token is a `{{variable}}`, the environment's value is shown and replaced.
+    pub fn prompt_bearer_token(&mut self) {
+        self.auth.kind = AuthKind::Bearer;
+        let token = self.auth.value("token");
+        let (label, value) = match Auth::variable_name(token) {
+            Some(name) => (
+                format!("Bearer token ({{{{{}}}}})", name),
+                self.environments
+                    .active()
+                    .and_then(|env| env.variables.get(name))
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+            None => (String::from("Bearer token"), String::from(token)),
+        };
+        self.open_prompt(PromptAction::BearerToken, &label, &value);
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.masked = true;
+        }
+    }
+
+    fn set_bearer_token(&mut self, token: &str) {
+        let token = token.trim();
+        let variable = Auth::variable_name(self.auth.value("token")).map(String::from);
+        match variable {
+            Some(name) if self.environments.active().is_some() => {
+                match self.environments.set_variable(&name, token) {
+                    Ok(()) => self.logs.info(format!("Updated {{{{{}}}}}", name)),
+                    Err(e) => self
+                        .logs
+                        .error(format!("Could not save environments: {}", e)),
+                }
+            }
+            _ => {
+                self.auth.set("token", token);
+                self.logs.info("Updated bearer token");
+            }
+        }
+    }
+
+    /// Asks where to save the response body, suggesting a name from its kind.
+    pub fn prompt_save_response(&mut self) {
+        match &self.response {
+            Some(response) => {
+                let name = format!("response.{}", response.kind.extension());
+                self.open_prompt(PromptAction::SaveResponse, "Save response body to", &name);
+            }
+            None => self.logs.warn("No response to save"),
+        }
+    }
+
+    /// Asks for a path to filter the JSON or XML response by, starting from
+    /// the one it's filtered by already.
+    pub fn prompt_filter(&mut self) {
+        let label = match &self.response {
+            Some(response) if response.is_json() => "Filter the JSON by (e.g. .items[0].name)",
+            Some(response) if response.is_xml() => "Filter the XML by XPath (e.g. //item/name)",
+            _ => {
+                self.logs.warn("No JSON or XML response to filter");
+                return;
+            }
+        };
+        let query = self
+            .response
+            .as_ref()
+            .and_then(|response| response.filter.as_ref())
+            .map_or_else(String::new, |(query, _)| query.clone());
+        self.open_prompt(PromptAction::Filter, label, &query);
+    }
+
+    /// Shows only what `query` picks out of the JSON or XML response, or
+    /// all of it again if `query` is empty.
+    fn filter_response(&mut self, query: &str) {
+        if query.trim().is_empty() {
+            self.unfilter_response();
+            return;
+        }
+        let Some(response) = self.response.as_mut() else {
+            self.logs.warn("No response to filter");
+            return;
+        };
+        match response.filter(query) {
+            Ok(()) => {
+                self.response_scroll = 0;
+                self.update_search();
+            }
+            Err(e) => self.logs.warn(e),
+        }
+    }
+
+    pub fn unfilter_response(&mut self) {
+        match self.response.as_mut().and_then(|r| r.filter.take()) {
+            Some(_) => {
+                self.response_scroll = 0;
+                self.update_search();
+            }
+            None => self.logs.warn("The response isn't filtered"),
+        }
+    }
+
+    /// Writes the body as received by the server to `path`.
+    fn save_response(&mut self, path: &str) {
+        let body = match &self.response {
+            Some(response) => &response.bytes,
+            None => return,
+        };
+        let path = storage::expand_tilde(path.trim());
+        match fs::write(&path, body) {
+            Ok(()) => self
+                .logs
+                .info(format!("Saved {} bytes to {}", body.len(), path.display())),
+            Err(e) => self
+                .logs
+                .error(format!("Could not save to {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Asks where to download the response to, suggesting the file name
+    /// at the end of the URL.
+    pub fn prompt_download(&mut self) {
+        let url = self
+            .build_request()
+            .interpolate(&self.environments)
+            .full_url();
+        let name = url::Url::parse(&url)
+            .ok()
+            .and_then(|url| {
+                url.path_segments()
+                    .and_then(|mut segments| segments.next_back().map(String::from))
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| String::from("download"));
+        self.open_prompt(PromptAction::Download, "Download response body to", &name);
+    }
+
+    /// Sends the request with its body streamed to `path` rather than kept,
+    /// for responses too big to hold in memory or display.
+    fn start_download(&mut self, path: &str, tx: &UnboundedSender<Message>) {
+        if self.in_flight.is_some() {
+            self.logs.warn("A request is already in progress");
+            return;
+        }
+        let path = storage::expand_tilde(path.trim());
+        self.logs.info(format!("Downloading to {}", path.display()));
+        self.download = Some(Arc::new(Download::new(path)));
+        self.start_request(tx);
+    }
+
+    pub fn cycle_environment(&mut self) {
+        if self.environments.environments.is_empty() {
+            self.logs
+                .warn("No environments defined in environments.json");
+            return;
+        }
+        if let Err(e) = self.environments.cycle() {
+            self.logs
+                .error(format!("Could not save environments: {}", e));
+        }
+        let name = self.environments.active.as_deref().unwrap_or("none");
+        self.logs.info(format!("Environment: {}", name));
+    }
+
+    pub fn open_history(&mut self) {
+        if self.history.entries.is_empty() {
+            self.logs.warn("No requests in history yet");
+            return;
+        }
+        self.history_selected = 0;
+        self.input_mode = InputMode::History;
+    }
+
+    /// Opens a chart of how long the request in the editors has taken each
+    /// time it was sent, from the history.
+    pub fn open_trend(&mut self) {
+        if self.trend().is_empty() {
+            self.logs.warn(format!(
+                "No response times in history for {} {} yet",
+                self.method, self.url
+            ));
+            return;
+        }
+        self.input_mode = InputMode::Trend;
+    }
+
+    /// The request's response times from the history, for the trend chart.
+    pub fn trend(&self) -> Vec<(DateTime<Utc>, u64)> {
+        self.history.response_times(self.method.as_str(), &self.url)
+    }
+
+    pub fn select_history(&mut self, step: isize) {
+        let last = self.history.entries.len().saturating_sub(1) as isize;
+        self.history_selected = (self.history_selected as isize + step).clamp(0, last) as usize;
+    }
+
+    /// Replaces the editors' contents with the selected history entry.
+    pub fn load_selected_history(&mut self) {
+        self.input_mode = InputMode::Normal;
+        if let Some(entry) = self.history.newest(self.history_selected).cloned() {
+            self.load_into_editors(&entry.method, entry.url, entry.headers, entry.body);
+            self.body_mode = entry.body_mode;
+            self.fields.rows = entry.fields;
+            self.variables = entry.variables;
+            self.logs
+                .info(format!("Loaded {} {} from history", self.method, self.url));
+        }
+    }
+
+    /// Replaces the method, URL, headers and body being edited, going back
+    /// to a raw body.
+    fn load_into_editors(
+        &mut self,
+        method: &str,
+        url: String,
+        headers: Vec<KeyValue>,
+        body: String,
+    ) {
+        match Method::from_bytes(method.as_bytes()) {
+            Ok(method) => self.method = method,
+            Err(_) => self.logs.warn(format!(
+                "Unknown method {}, keeping {}",
+                method, self.method
+            )),
+        }
+        self.url = url;
+        self.sync_params_from_url();
+        self.headers.rows = headers;
+        self.headers.selected = 0;
+        self.body = body;
+        self.body_mode = BodyMode::Raw;
+        self.fields = KeyValueTable::default();
+        self.variables = String::new();
+    }
+
+    /// Moves to the next body mode. GraphQL is sent as a POST, so the
+    /// method is switched to one if it can't carry a body.
+    pub fn cycle_body_mode(&mut self) {
+        self.body_mode = self.body_mode.next();
+        if self.body_mode == BodyMode::GraphQl && !request::method_has_body(&self.method) {
+            self.method = Method::POST;
+        }
+    }
+
+    /// Moves to the next layout preset, leaving a pane it hides for the response.
+    pub fn cycle_layout(&mut self) {
+        self.config.layout = self.config.layout.next();
+        if !self.config.layout.shows(self.focus) {
+            self.focus = Focus::Response;
+        }
+        self.logs
+            .info(format!("Layout: {}", self.config.layout.name()));
+    }
+
+    pub fn cycle_theme(&mut self) {
+        self.config.theme = self.config.theme.next();
+        self.logs
+            .info(format!("Theme: {}", self.config.theme.name()));
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.config.theme.theme()
+    }
+
+    /// Whether Tab and Ctrl+h/j/k/l stop at `focus`: not if the layout
+    /// hides it, or it's the Body pane and the method sends no body.
+    pub fn can_focus(&self, focus: Focus) -> bool {
+        self.config.layout.shows(focus)
+            && (focus != Focus::Body || request::method_has_body(&self.method))
+    }
+
+    /// Moves focus `step` panes on in Tab order, skipping those that can't
+    /// be focused.
+    pub fn cycle_focus(&mut self, step: isize) {
+        self.focus = self.focus.cycle(step, |focus| self.can_focus(focus));
+    }
+
+    /// Moves focus to the pane next to the focused one on screen.
+    pub fn focus_towards(&mut self, towards: Towards) {
+        let panes: Vec<(Focus, Rect)> = self
+            .pane_areas
+            .iter()
+            .copied()
+            .filter(|(focus, _)| *focus == self.focus || self.can_focus(*focus))
+            .collect();
+        if let Some(focus) = layout::neighbour(&panes, self.focus, towards) {
+            self.focus = focus;
+        }
+    }
+
+    pub fn open_help(&mut self) {
+        self.help_scroll = 0;
+        self.input_mode = InputMode::Help;
+    }
+
+    pub fn open_palette(&mut self) {
+        self.palette = Palette::default();
+        self.input_mode = InputMode::Palette;
+    }
+
+    pub fn open_finder(&mut self) {
+        self.finder = Finder::default();
+        self.input_mode = InputMode::Finder;
+    }
+
+    /// The finder's matches for its query.
+    pub fn finder_matches(&self) -> Vec<(Target, String)> {
+        self.finder.matches(&self.collections, &self.history)
+    }
+
+    pub fn select_in_finder(&mut self, step: isize) {
+        let count = self.finder_matches().len();
+        self.finder.select(step, count);
+    }
+
+    /// Loads the highlighted finder entry into the editors.
+    pub fn load_found(&mut self) {
+        self.input_mode = InputMode::Normal;
+        match self.finder_matches().get(self.finder.selected) {
+            Some((Target::Saved(row), _)) => {
+                self.collections.select(*row);
+                self.load_selected_collection();
+            }
+            Some((Target::History(i), _)) => {
+                self.history_selected = *i;
+                self.load_selected_history();
+            }
+            None => {}
+        }
+    }
+
+    pub fn open_command_line(&mut self) {
+        self.open_prompt(PromptAction::Command, ":", "");
+    }
+
+    fn run_command(&mut self, line: &str, tx: &UnboundedSender<Message>) {
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(e) => {
+                self.logs.warn(e);
+                return;
+            }
+        };
+        match command {
+            Command::Send => self.send(tx),
+            Command::Save(name) => self.save_request(&name),
+            Command::Env(name) => {
+                if let Some(name) = &name {
+                    if !self
+                        .environments
+                        .environments
+                        .iter()
+                        .any(|env| env.name == *name)
+                    {
+                        self.logs.warn(format!("No environment called {}", name));
+                        return;
+                    }
+                }
+                if let Err(e) = self.environments.select(name.as_deref()) {
+                    self.logs
+                        .error(format!("Could not save environments: {}", e));
+                }
+                let name = self.environments.active.as_deref().unwrap_or("none");
+                self.logs.info(format!("Environment: {}", name));
+            }
+            Command::Run(data) => self.run_collection(tx, data.as_deref()),
+            Command::Requires(names) => self.set_requires(names),
+            Command::Mock(port) => self.mock(tx, port),
+            Command::Proxy(port) => self.proxy(tx, port),
+            Command::Monitor(interval) => self.monitor(tx, interval),
+            Command::Bench(requests, concurrency) => self.bench(tx, requests, concurrency),
+            Command::Filter(Some(query)) => self.filter_response(&query),
+            Command::Filter(None) => self.unfilter_response(),
+            Command::TabNew => self.new_tab(),
+            Command::Layout => self.cycle_layout(),
+            Command::Theme => self.cycle_theme(),
+            Command::Quit => self.quit = true,
+        }
+    }
+
+    /// Starts selecting response lines from the top one in view.
+    pub fn start_visual(&mut self) {
+        if self.response_text().is_none() || self.live_events().is_some() {
+            self.logs.warn("No response to select from");
+            return;
+        }
+        self.visual_anchor = self.response_scroll;
+        self.visual_cursor = self.response_scroll;
+        self.input_mode = InputMode::Visual;
+    }
+
+    /// Moves the end of the visual selection, scrolling to keep it in view.
+    pub fn move_visual(&mut self, step: isize) {
+        let last = self.response_line_count().saturating_sub(1);
+        self.visual_cursor = self.visual_cursor.saturating_add_signed(step).min(last);
+        if self.visual_cursor < self.response_scroll {
+            self.response_scroll = self.visual_cursor;
+        } else if self.visual_cursor >= self.response_scroll + self.response_height {
+            self.response_scroll = self.visual_cursor + 1 - self.response_height.max(1);
+        }
+    }
+
+    /// Shows the JSON response as a tree in place of its text.
+    pub fn open_tree(&mut self) {
+        let Some(response) = self.response.as_ref().filter(|r| r.is_json()) else {
+            self.logs.warn("No JSON response to browse as a tree");
+            return;
+        };
+        match JsonTree::parse(response.text(self.show_raw).as_str()) {
+            Ok(tree) => {
+                self.tree = Some(tree);
+                self.input_mode = InputMode::Tree;
+            }
+            Err(e) => self.logs.warn(format!("Could not parse the JSON: {}", e)),
+        }
+    }
+
+    /// Copies the path of the node selected in the JSON tree, as JSONPath
+    /// for assertions and scripts or, with `jq`, as jq writes it.
+    pub fn copy_tree_path(&mut self, jq: bool) {
+        let Some(tree) = &self.tree else {
+            return;
+        };
+        let path = if jq { tree.jq_path() } else { tree.path() };
+        self.copy(&format!("path {}", path), &path);
+    }
+
+    pub fn close_tree(&mut self) {
+        self.tree = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Shows the JSON response, or the node selected in the JSON tree, as a
+    /// table, if it's an array of objects.
+    pub fn open_table(&mut self) {
+        let value = match (&self.input_mode, &self.tree) {
+            (InputMode::Tree, Some(tree)) => tree.selected_value().cloned(),
+            _ => self
+                .response_text()
+                .and_then(|text| serde_json::from_str(text.as_str()).ok()),
+        };
+        match value.as_ref().and_then(JsonTable::new) {
+            Some(table) => {
+                self.table = Some(table);
+                self.input_mode = InputMode::JsonTable;
+            }
+            None => self
+                .logs
+                .warn("Not a JSON array of objects to show as a table"),
+        }
+    }
+
+    /// Goes back to the JSON tree if the table was opened from it.
+    pub fn close_table(&mut self) {
+        self.table = None;
+        self.input_mode = self.json_view();
+    }
+
+    /// The mode to go back to for the JSON table or tree being browsed.
+    fn json_view(&self) -> InputMode {
+        match (&self.table, &self.tree) {
+            (Some(_), _) => InputMode::JsonTable,
+            (None, Some(_)) => InputMode::Tree,
+            (None, None) => InputMode::Normal,
+        }
+    }
+
+    /// The JSON table being browsed, else the array selected in the JSON
+    /// tree, as CSV, with how many rows it has.
+    fn table_csv(&self) -> Option<(usize, String)> {
+        let csv = |table: &JsonTable| (table.len(), table.to_csv());
+        match (&self.table, &self.tree) {
+            (Some(table), _) => Some(csv(table)),
+            (None, Some(tree)) => tree
+                .selected_value()
+                .and_then(JsonTable::new)
+                .map(|table| csv(&table)),
+            (None, None) => None,
+        }
+    }
+
+    pub fn prompt_export_csv(&mut self) {
+        if self.table_csv().is_none() {
+            self.logs.warn("Not a JSON array of objects to export");
+            return;
+        }
+        self.open_prompt(PromptAction::ExportCsv, "Export as CSV to", "response.csv");
+    }
+
+    fn export_csv(&mut self, path: &str) {
+        self.input_mode = self.json_view();
+        let Some((rows, csv)) = self.table_csv() else {
+            return;
+        };
+        let path = storage::expand_tilde(path.trim());
+        match fs::write(&path, csv) {
+            Ok(()) => self
+                .logs
+                .info(format!("Exported {} rows to {}", rows, path.display())),
+            Err(e) => self
+                .logs
+                .error(format!("Could not export to {}: {}", path.display(), e)),
+        }
+    }
+
+    /// The first and last response lines selected, while in visual mode.
+    pub fn visual_range(&self) -> Option<(usize, usize)> {
+        match self.input_mode {
+            InputMode::Visual => Some((
+                self.visual_anchor.min(self.visual_cursor),
+                self.visual_anchor.max(self.visual_cursor),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn copy_visual_selection(&mut self) {
+        let text = match (self.visual_range(), self.response_text()) {
+            (Some((first, last)), Some(text)) => (first..=last)
+                .map(|i| text.line(i))
+                .collect::<Vec<&str>>()
+                .join("\n"),
+            _ => return,
+        };
+        self.input_mode = InputMode::Normal;
+        self.copy("selected lines", &text);
+    }
+
+    /// The text being typed into, in the body or variables editor, with
+    /// its cursor.
+    pub fn edited_text(&mut self) -> Option<(&mut String, &mut Editor)> {
+        match self.input_mode {
+            InputMode::EditingBody => Some((&mut self.body, &mut self.body_editor)),
+            InputMode::EditingVariables => Some((&mut self.variables, &mut self.variables_editor)),
+            _ => None,
+        }
+    }
+
+    /// Starts typing into the body, or the GraphQL variables when `mode`
+    /// is `EditingVariables`, with the cursor at the end.
+    pub fn start_editing_text(&mut self, mode: InputMode) {
+        self.input_mode = mode;
+        if let Some((text, editor)) = self.edited_text() {
+            editor.move_to_end(text);
+        }
+    }
+
+    /// Asks the main loop to open the body in `$EDITOR`, unless the body is
+    /// made of fields rather than text.
+    pub fn open_body_externally(&mut self) {
+        if self.body_mode.uses_fields() {
+            self.logs.warn(format!(
+                "A {} body has no text to edit",
+                self.body_mode.name()
+            ));
+            return;
+        }
+        self.external_edit = Some(ExternalEdit::Body);
+    }
+
+    /// Asks the main loop to open the pre-request or post-response script
+    /// in `$EDITOR`.
+    pub fn open_script(&mut self, target: ExternalEdit) {
+        self.external_edit = Some(target);
+    }
+
+    /// Replaces the body with what was saved in `$EDITOR`.
+    pub fn set_body_from_editor(&mut self, body: String) {
+        if body != self.body {
+            self.body = body;
+            self.body_editor.move_to_end(&self.body);
+        }
+    }
+
+    /// Replaces the script `target` with what was saved in `$EDITOR`.
+    pub fn set_script(&mut self, target: ExternalEdit, script: String) {
+        let (current, name) = match target {
+            ExternalEdit::PreRequestScript => (&mut self.pre_request, "Pre-request"),
+            ExternalEdit::PostResponseScript => (&mut self.post_response, "Post-response"),
+            ExternalEdit::Body => return self.set_body_from_editor(script),
+        };
+        if script.trim() == current.trim() {
+            return;
+        }
+        *current = script;
+        if current.trim().is_empty() {
+            self.logs.info(format!("{} script removed", name));
+        } else {
+            self.logs.info(format!("{} script set", name));
+        }
+    }
+
+    /// Replaces the body with what was piped to stdin at startup.
+    pub fn body_from_stdin(&mut self) {
+        match self.stdin.clone() {
+            Some(text) => {
+                self.body_mode = BodyMode::Raw;
+                self.set_body_from_editor(text);
+                self.logs.info("Body read from stdin");
+            }
+            None => self.logs.warn("Nothing was piped to stdin"),
+        }
+    }
+
+    /// Asks for a file to upload as a new multipart field.
+    pub fn prompt_attach_file(&mut self) {
+        self.open_prompt(PromptAction::AttachFile, "Attach file", "");
+    }
+
+    /// Adds the file at `path` as a multipart field, switching the body to
+    /// multipart if it wasn't already. The file is read when the request is sent.
+    fn attach_file(&mut self, path: &str) {
+        let path = path.trim();
+        if path.is_empty() {
+            return;
+        }
+        if !storage::expand_tilde(path).is_file() {
+            self.logs.warn(format!("No file at {}", path));
+            return;
+        }
+        self.body_mode = BodyMode::Multipart;
+        self.fields.rows.push(body::file_field(path));
+        self.fields.selected = self.fields.rows.len() - 1;
+        self.logs.info(format!("Attached {}", path));
+    }
+
+    /// Asks for a name to save the current request under, within the
+    /// collection selected in the sidebar if there is one.
+    pub fn prompt_save_request(&mut self) {
+        let initial = self
+            .collections
+            .selected_collection()
+            .map_or_else(String::new, |name| format!("{}/", name));
+        self.open_prompt(
+            PromptAction::SaveRequest,
+            "Save request as [collection/]name",
+            &initial,
+        );
+    }
+
+    fn save_request(&mut self, path: &str) {
+        let path = path.trim();
+        if path.is_empty() || path.ends_with('/') {
+            self.logs.warn("A name is needed to save the request");
+            return;
+        }
+        let request = self.build_request();
+        match self.collections.save(path, &request) {
+            Ok(()) => self.logs.info(format!("Saved request as {}", path)),
+            Err(e) => self
+                .logs
+                .error(format!("Could not save collections: {}", e)),
+        }
+    }
+
+    fn import_curl(&mut self, input: &str) {
+        let input = input.trim();
+        let command = match input.strip_prefix('@') {
+            Some(path) => {
+                let path = storage::expand_tilde(path);
+                match fs::read_to_string(&path) {
+                    Ok(command) => command,
+                    Err(e) => {
+                        self.logs
+                            .error(format!("Could not read {}: {}", path.display(), e));
+                        return;
+                    }
+                }
+            }
+            None => String::from(input),
+        };
+        match curl::parse(&command) {
+            Ok(request) => {
+                let method = request.method.to_string();
+                self.load_into_editors(&method, request.url, request.headers, request.body);
+                self.logs
+                    .info(format!("Imported {} {} from curl", self.method, self.url));
+            }
+            Err(e) => self.logs.error(e.to_string()),
+        }
+    }
+
+    /// Imports a `.http` request file, or a Postman collection, an
+    /// OpenAPI/Swagger spec or a HAR file, going by the file's contents
+    /// rather than its name. Specs may be JSON or YAML.
+    fn import_file(&mut self, path: &str) {
+        let path = storage::expand_tilde(path.trim());
+        let name = path.file_stem().map_or_else(
+            || String::from("Imported"),
+            |s| s.to_string_lossy().into_owned(),
+        );
+        if httpfile::detect(&path) {
+            match HttpFile::load(&path) {
+                Ok(file) => {
+                    let (collection, environment) = file.import(&name);
+                    self.add_imported(collection, environment);
+                }
+                Err(e) => self.logs.error(e.to_string()),
+            }
+            return;
+        }
+        let imported = fs::read_to_string(&path)
+            .map_err(AppError::from)
+            .and_then(|contents| {
+                serde_json::from_str::<serde_json::Value>(&contents)
+                    .or_else(|_| serde_yaml::from_str(&contents))
+                    .map_err(|e| AppError::Parse(format!("{}: {}", path.display(), e)))
+            })
+            .and_then(|json| {
+                if postman::detect(&json) {
+                    postman::import(json)
+                } else if openapi::detect(&json) {
+                    openapi::save_spec(openapi::title(&json), &json)?;
+                    openapi::import(&json)
+                } else if har::detect(&json) {
+                    har::import(json, &name).map(|collection| (collection, None))
+                } else {
+                    Err(AppError::Parse(format!(
+                        "{}: not a Postman collection, OpenAPI document or HAR file",
+                        path.display()
+                    )))
+                }
+            });
+        match imported {
+            Ok((collection, environment)) => self.add_imported(collection, environment),
+            Err(e) => self.logs.error(e.to_string()),
+        }
+    }
+
+    fn export_session(&mut self, path: &str) {
+        let path = storage::expand_tilde(path.trim());
+        let count = self.session.log.entries.len();
+        match self.session.export(&path) {
+            Ok(()) => self
+                .logs
+                .info(format!("Exported {} requests to {}", count, path.display())),
+            Err(e) => self
+                .logs
+                .error(format!("Could not export to {}: {}", path.display(), e)),
+        }
+    }
+
+    fn export_logs(&mut self, path: &str) {
+        let path = storage::expand_tilde(path.trim());
+        match self.logs.export(&path) {
+            Ok(()) => self
+                .logs
+                .info(format!("Exported logs to {}", path.display())),
+            Err(e) => self
+                .logs
+                .error(format!("Could not export to {}: {}", path.display(), e)),
+        }
+    }
+
+    fn add_imported(&mut self, collection: Collection, environment: Option<Environment>) {
+        let message = format!(
+            "Imported {} requests into {}",
+            collection.requests.len(),
+            collection.name
+        );
+        if let Err(e) = self.collections.add(collection) {
+            self.logs
+                .error(format!("Could not save collections: {}", e));
+            return;
+        }
+        self.logs.info(message);
+        if let Some(environment) = environment {
+            let name = environment.name.clone();
+            match self.environments.add(environment) {
+                Ok(()) => self.logs.info(format!("Imported environment {}", name)),
+                Err(e) => self
+                    .logs
+                    .error(format!("Could not save environments: {}", e)),
+            }
+        }
+    }
+
+    /// Loads the request selected in the sidebar into the editors.
+    pub fn load_selected_collection(&mut self) {
+        if let Some(saved) = self.collections.selected_request().cloned() {
+            let name = saved.name.clone();
+            self.load_saved(saved);
+            self.logs.info(format!("Loaded {}", name));
+        }
+    }
+
+    pub fn load_saved(&mut self, saved: SavedRequest) {
+        self.load_into_editors(&saved.method, saved.url, saved.headers, saved.body);
+        self.body_mode = saved.body_mode;
+        self.fields.rows = saved.fields;
+        self.variables = saved.variables;
+        self.auth = saved.auth;
+        self.settings = saved.settings;
+        self.assertions = saved.assertions;
+        self.pre_request = saved.pre_request;
+        self.post_response = saved.post_response;
+        self.requires = saved.requires;
+    }
+
+    /// What to pick up from next time: the request being edited, the
+    /// environment and the layout.
+    pub fn state(&self) -> State {
+        let mut request = SavedRequest::new("", &self.build_request());
+        // Disabled rows are kept too, unlike when sending
+        request.headers = self.headers.rows.clone();
+        request.fields = self.fields.rows.clone();
+        State {
+            request: Some(request),
+            environment: self.environments.active.clone(),
+            layout: Some(self.config.layout),
+        }
+    }
+
+    /// Puts back what was open when the app last quit. An environment that
+    /// has since been removed is left unselected.
+    pub fn restore(&mut self, state: State) {
+        if let Some(saved) = state.request {
+            self.load_saved(saved);
+        }
+        if let Some(name) = state.environment {
+            if self
+                .environments
+                .environments
+                .iter()
+                .any(|env| env.name == name)
+            {
+                self.environments.active = Some(name);
+            }
+        }
+        if let Some(layout) = state.layout {
+            self.config.layout = layout;
+        }
+    }
+
+    pub fn remove_selected_collection(&mut self) {
+        if let Err(e) = self.collections.remove_selected() {
+            self.logs
+                .error(format!("Could not save collections: {}", e));
+        }
+    }
+
+    /// Whether keys are currently typed into a text field rather than acting as commands.
+    pub fn accepts_text(&self) -> bool {
+        !matches!(
+            self.input_mode,
+            InputMode::Normal
+                | InputMode::History
+                | InputMode::Visual
+                | InputMode::Tree
+                | InputMode::JsonTable
+                | InputMode::Help
+        )
+    }
+
+    /// Inserts pasted text into the field being edited. Single-line fields
+    /// get line breaks flattened to spaces.
+    pub fn paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n");
+        // Shell line continuations are joined too, so pasted curl commands survive
+        let single_line = || {
+            text.trim_end_matches('\n')
+                .replace("\\\n", " ")
+                .replace('\n', " ")
+        };
+        match self.input_mode {
+            InputMode::Normal
+            | InputMode::History
+            | InputMode::Settings
+            | InputMode::Cookies
+            | InputMode::Assertions
+            | InputMode::Runner
+            | InputMode::Snippets
+            | InputMode::Monitor
+            | InputMode::Bench
+            | InputMode::Trend
+            | InputMode::Visual
+            | InputMode::Tree
+            | InputMode::JsonTable
+            | InputMode::Help => {}
+            InputMode::Editing => {
+                self.url_undo
+                    .record((self.url.clone(), self.url_cursor), None);
+                input::insert(&mut self.url, &mut self.url_cursor, &single_line());
+                self.sync_params_from_url();
+                self.update_url_completions();
+            }
+            InputMode::EditingBody | InputMode::EditingVariables => {
+                if let Some((edited, editor)) = self.edited_text() {
+                    editor.insert(edited, &text);
+                }
+            }
+            InputMode::EditingTable => {
+                let text = single_line();
+                self.edit_focused_table(|table| table.push_str(&text));
+            }
+            InputMode::Search => {
+                self.search.query.push_str(&single_line());
+                self.update_search();
+            }
+            InputMode::Prompt => {
+                if let Some(prompt) = self.prompt.as_mut() {
+                    prompt.input.push_str(&single_line());
+                }
+            }
+            InputMode::Palette => {
+                for c in single_line().chars() {
+                    self.palette.type_char(c);
+                }
+            }
+            InputMode::Finder => {
+                for c in single_line().chars() {
+                    self.finder.type_char(c);
+                }
+            }
+        }
+    }
+
+    pub fn paste_from_clipboard(&mut self) {
+        match self.clipboard.paste() {
+            Ok(text) => self.paste(&text),
+            Err(e) => self.logs.error(e.to_string()),
+        }
+    }
+
+    /// Puts `text` on the system clipboard, logging what was copied as `what`.
+    fn copy(&mut self, what: &str, text: &str) {
+        match self.clipboard.copy(text) {
+            Ok(()) => self
+                .logs
+                .info(format!("Copied {} ({} bytes)", what, text.len())),
+            Err(e) => self.logs.error(e.to_string()),
+        }
+    }
+
+    /// Copies the body as currently displayed, raw or formatted.
+    pub fn copy_response_body(&mut self) {
+        match self.response_text() {
+            Some(text) => {
+                let text = text.as_str().to_string();
+                self.copy("response body", &text);
+            }
+            None => self.logs.warn("No response to copy"),
+        }
+    }
+
+    pub fn copy_response_headers(&mut self) {
+        match &self.response {
+            Some(response) => {
+                let mut text = response.status_line();
+                for line in response.header_lines() {
+                    text.push('\n');
+                    text.push_str(&line);
+                }
+                self.copy("response headers", &text);
+            }
+            None => self.logs.warn("No response to copy"),
+        }
+    }
+
+    pub fn copy_search_match(&mut self) {
+        let text = match (self.search.current_match(), self.response_text()) {
+            (Some(m), Some(text)) => text.line(m.line)[m.start..m.end].to_string(),
+            _ => {
+                self.logs.warn("No search match to copy");
+                return;
+            }
+        };
+        self.copy("search match", &text);
+    }
+
+    /// The key/value editor shown in the focused pane, if any.
+    pub fn focused_table(&mut self) -> Option<&mut KeyValueTable> {
+        match self.focus {
+            Focus::Params => Some(&mut self.params),
+            Focus::Headers => Some(&mut self.headers),
+            Focus::Body if self.body_mode.uses_fields() => Some(&mut self.fields),
+            _ => None,
+        }
+    }
+
+    /// Looks up the history for URLs like the one being typed.
+    pub fn update_url_completions(&mut self) {
+        self.completion_selected = None;
+        self.showing_recent_urls = false;
+        self.url_completions = if self.url.trim().is_empty() {
+            Vec::new()
+        } else {
+            self.history.urls_matching(self.url.trim(), MAX_COMPLETIONS)
+        };
+    }
+
+    pub fn close_url_completions(&mut self) {
+        self.url_completions.clear();
+        self.completion_selected = None;
+        self.showing_recent_urls = false;
+    }
+
+    /// Drops down the last few URLs sent under the URL field, to pick one
+    /// with Enter.
+    pub fn open_recent_urls(&mut self) {
+        let urls = self.history.recent_urls(MAX_COMPLETIONS);
+        if urls.is_empty() {
+            self.logs.warn("No requests in history yet");
+            return;
+        }
+        self.url_completions = urls;
+        self.completion_selected = Some(0);
+        self.showing_recent_urls = true;
+        self.url_cursor = self.url.len();
+        self.input_mode = InputMode::Editing;
+    }
+
+    /// Moves the highlight through the completions, starting from the first.
+    pub fn select_completion(&mut self, step: isize) {
+        let last = self.url_completions.len().saturating_sub(1);
+        self.completion_selected = Some(match self.completion_selected {
+            Some(i) => i.saturating_add_signed(step).min(last),
+            None if step < 0 => last,
+            None => 0,
+        });
+    }
+
+    /// Replaces the URL with the highlighted completion, or the first if
+    /// none is.
+    pub fn accept_completion(&mut self) {
+        let Some(url) = self
+            .url_completions
+            .get(self.completion_selected.unwrap_or(0))
+            .cloned()
+        else {
+            return;
+        };
+        self.url_undo
+            .record((self.url.clone(), self.url_cursor), None);
+        self.url_cursor = url.len();
+        self.url = url;
+        self.sync_params_from_url();
+        if self.showing_recent_urls {
+            self.input_mode = InputMode::Normal;
+        }
+        self.close_url_completions();
+    }
+
+    /// Re-reads the params table from the query string typed into the URL.
+    /// Disabled rows are not part of the URL, so they are kept as they were.
+    pub fn sync_params_from_url(&mut self) {
+        let (_, query) = request::split_url(&self.url);
+        let mut rows = request::parse_query(query.unwrap_or(""));
+        rows.extend(self.params.rows.iter().filter(|kv| !kv.enabled).cloned());
+        self.params.rows = rows;
+        if self.params.selected >= self.params.rows.len() {
+            self.params.selected = self.params.rows.len().saturating_sub(1);
+        }
+    }
+
+    /// Rewrites the URL's query string from the params table.
+    pub fn sync_url_from_params(&mut self) {
+        let (base, _) = request::split_url(&self.url);
+        self.url = request::join_url(base, self.params.active(), false);
+    }
+
+    /// Applies `edit` to the focused table and keeps the URL in sync with it.
+    pub fn edit_focused_table(&mut self, edit: impl FnOnce(&mut KeyValueTable)) {
+        if let Some(table) = self.focused_table() {
+            edit(table);
+        }
+        if self.focus == Focus::Params {
+            self.sync_url_from_params();
+        }
+    }
+}
+
+/// `written` with variables from `environments` filled in and the
+/// `inherited` settings, the config's and environment's, under its own.
+fn prepare(written: &Request, environments: &Environments, inherited: &Settings) -> Request {
+    let mut request = written.interpolate(environments);
+    request.settings = request.settings.over(&inherited.interpolate(environments));
+    request
+}
+
+/// As `prepare`, after running `written`'s pre-request script, if it has
+/// one, on the request as it would otherwise be sent. The variables it sets
+/// are filled in and its header, body and URL changes made; they're
+/// returned with what it printed, for the caller to keep.
+fn prepare_scripted(
+    written: &Request,
+    environments: &Environments,
+    inherited: &Settings,
+) -> Result<(Request, Effects), AppError> {
+    let request = prepare(written, environments, inherited);
+    if written.pre_request.trim().is_empty() {
+        return Ok((request, Effects::default()));
+    }
+    let globals = vec![("request", script::request_value(&request))];
+    let effects = script::run(&written.pre_request, globals, &variables_of(environments))
+        .map_err(|e| AppError::Script(format!("pre-request: {}", e)))?;
+    let set: BTreeMap<String, String> = effects.variables.iter().cloned().collect();
+    let mut request = prepare(written, &environments.with_variables(&set), inherited);
+    effects.apply(&mut request);
+    Ok((request, effects))
+}
+
+/// Runs the post-response script of `sent`, if it has one, on `response`.
+/// Only the variables it sets and what it prints mean anything by then.
+fn after_response(
+    sent: &Request,
+    response: &ResponseData,
+    environments: &Environments,
+) -> Result<Effects, AppError> {
+    if sent.post_response.trim().is_empty() {
+        return Ok(Effects::default());
+    }
+    let globals = vec![
+        ("request", script::request_value(sent)),
+        ("response", script::response_value(response)),
+    ];
+    script::run(&sent.post_response, globals, &variables_of(environments))
+        .map_err(|e| AppError::Script(format!("post-response: {}", e)))
+}
+
+/// `environments` with `variables` over the active one's, or as they are
+/// when there are none, so the active environment's name is kept.
+fn environments_with(
+    environments: &Environments,
+    variables: &BTreeMap<String, String>,
+) -> Environments {
+    if variables.is_empty() {
+        environments.clone()
+    } else {
+        environments.with_variables(variables)
+    }
+}
+
+fn variables_of(environments: &Environments) -> BTreeMap<String, String> {
+    environments
+        .active()
+        .map(|env| env.variables.clone())
+        .unwrap_or_default()
+}
+
+/// The stored OAuth2 token to send `request` with, and its key, if its
+/// auth uses one.
+fn token_for(
+    tokens: &TokenStore,
+    environments: &Environments,
+    request: &Request,
+) -> Option<(String, Option<Token>)> {
+    if request.auth.kind.uses_token() {
+        let key = oauth::token_key(environments.active.as_deref(), &request.auth);
+        let token = tokens.tokens.get(&key).cloned();
+        Some((key, token))
+    } else {
+        None
+    }
+}
+
+/// What a task needs to prepare and send requests away from the app.
+struct Context {
+    environments: Environments,
+    /// The config's and active environment's settings.
+    inherited: Settings,
+    tokens: TokenStore,
+    /// Shared by the requests the task sends, so they keep to their
+    /// `rate_limit`.
+    throttle: Throttle,
+}
+
+/// A request of a collection run, waiting its turn.
+struct Queued {
+    /// Its step in the run.
+    step: usize,
+    /// The dataset row it's sent with, counting from 0.
+    row: usize,
+    /// Its `collection/name`, so requests that require it don't send it again.
+    key: String,
+    prerequisites: Vec<(String, Request)>,
+    written: Request,
+    /// The row's variables.
+    variables: BTreeMap<String, String>,
+}
+
+/// How a request sent with its scripts went.
+struct Sent {
+    request: Request,
+    result: Result<ResponseData, AppError>,
+    /// Why its post-response script failed, if it did.
+    script_error: Option<AppError>,
+}
+
+impl Sent {
+    fn outcome(self) -> Outcome {
+        let mut outcome = Outcome::new(self.result, &self.request.assertions);
+        if let Some(e) = self.script_error {
+            outcome.fail(e.to_string());
+        }
+        outcome
+    }
+}
+
+/// Prepares `written` with `variables`, e.g. a dataset row's, and then
+/// `captured` over the environment's, and sends it, running its scripts
+/// around it. What they set is added to `captured` and reported on `tx`
+/// with what they printed. Fails if its pre-request script does.
+async fn send_scripted(
+    written: &Request,
+    context: &Context,
+    variables: &BTreeMap<String, String>,
+    captured: &mut BTreeMap<String, String>,
+    tx: &UnboundedSender<Message>,
+) -> Result<Sent, AppError> {
+    let with_captured = |captured: &BTreeMap<String, String>| {
+        let mut all = variables.clone();
+        all.extend(captured.clone());
+        environments_with(&context.environments, &all)
+    };
+    let environments = with_captured(captured);
+    let (request, effects) = prepare_scripted(written, &environments, &context.inherited)?;
+    context.throttle.wait(&request.settings).await?;
+    captured.extend(effects.variables.iter().cloned());
+    let _ = tx.send(Message::Script(effects));
+    let token = token_for(&context.tokens, &environments, &request);
+    let result = execute(request.clone(), token, tx).await;
+    let mut script_error = None;
+    if let Ok(response) = &result {
+        match after_response(&request, response, &with_captured(captured)) {
+            Ok(effects) => {
+                captured.extend(effects.variables.iter().cloned());
+                let _ = tx.send(Message::Script(effects));
+            }
+            Err(e) => script_error = Some(e),
+        }
+    }
+    Ok(Sent {
+        request,
+        result,
+        script_error,
+    })
+}
+
+/// Sends `written`, the prerequisite `key`, as `send_scripted` does. Fails
+/// unless it passes as a step of a run would.
+async fn send_prerequisite(
+    key: &str,
+    written: &Request,
+    context: &Context,
+    variables: &BTreeMap<String, String>,
+    captured: &mut BTreeMap<String, String>,
+    tx: &UnboundedSender<Message>,
+) -> Result<(), AppError> {
+    let failed = |reason: String| AppError::Prerequisite(format!("{}: {}", key, reason));
+    let sent = send_scripted(written, context, variables, captured, tx)
+        .await
+        .map_err(|e| failed(e.to_string()))?;
+    let asserted = !sent.request.assertions.is_empty();
+    let outcome = sent.outcome();
+    if outcome.passed(asserted) {
+        if let Outcome::Done { status, .. } = outcome {
+            let _ = tx.send(Message::Log(
+                Level::Info,
+                format!("Sent {} first: {}", key, status),
+            ));
+        }
+        return Ok(());
+    }
+    Err(failed(match outcome {
+        Outcome::Done { failures, .. } if !failures.is_empty() => failures.join("; "),
+        Outcome::Done { status, .. } => format!("got {}", status),
+        Outcome::Failed(e) => e,
+        Outcome::Pending => String::from("not sent"),
+    }))
+}
+
+/// Sends each of `prerequisites` and then `written`, prepared with what
+/// their scripts captured, as the request `id`. The request it's sent as
+/// is reported on `tx`, and its post-response script left to the caller.
+async fn send_chain(
+    prerequisites: &[(String, Request)],
+    written: &Request,
+    context: &Context,
+    id: u64,
+    tx: &UnboundedSender<Message>,
+) -> Result<ResponseData, AppError> {
+    let mut captured = BTreeMap::new();
+    for (key, prerequisite) in prerequisites {
+        send_prerequisite(
+            key,
+            prerequisite,
+            context,
+            &BTreeMap::new(),
+            &mut captured,
+            tx,
+        )
+        .await?;
+    }
+    let environments = environments_with(&context.environments, &captured);
+    let (request, effects) = prepare_scripted(written, &environments, &context.inherited)?;
+    let _ = tx.send(Message::Script(effects));
+    let _ = tx.send(Message::Sent(id, Box::new(request.clone())));
+    let token = token_for(&context.tokens, &environments, &request);
+    execute(request, token, tx).await
+}
+
+/// Sends `request` the way its auth needs, trying again for as long as its
+/// retry settings call for. `token` is the OAuth2 token and where it's
+/// stored, for auth kinds that use one; refreshed tokens and each retry are
+/// reported on `tx`.
+async fn execute(
+    request: Request,
+    mut token: Option<(String, Option<Token>)>,
+    tx: &UnboundedSender<Message>,
+) -> Result<ResponseData, AppError> {
+    let retry = Retry::new(&request.settings)?;
+    let mut attempt = 1;
+    loop {
+        let mut result = match &mut token {
+            Some((key, token)) => {
+                let (result, refreshed) = oauth::send(request.clone(), token.clone()).await;
+                if let Some(refreshed) = refreshed {
+                    *token = Some(refreshed.clone());
+                    let _ = tx.send(Message::Token(key.clone(), Ok(refreshed)));
+                }
+                result
+            }
+            None => match request.auth.kind {
+                AuthKind::Digest => digest::send(request.clone()).await,
+                _ => request::send(&request).await,
+            },
+        };
+        if let (BodyMode::GraphQl, Ok(response)) = (request.body_mode, &mut result) {
+            response.graphql_errors = body::graphql_errors(response.raw_body.as_str());
+        }
+        let delay = match retry.delay(attempt, &result) {
+            Some(delay) => delay,
+            None => return result,
+        };
+        let outcome = match &result {
+            Ok(response) => response.status.to_string(),
+            Err(e) => e.to_string(),
+        };
+        let _ = tx.send(Message::Log(
+            Level::Warning,
+            format!(
+                "Attempt {} of {}: {}, retrying in {:?}",
+                attempt,
+                retry.attempts(),
+                outcome,
+                delay
+            ),
+        ));
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+pub fn cycle_method(method: &Method, step: isize) -> Method {
+    let len = METHODS.len() as isize;
+    let current = METHODS.iter().position(|m| m == method).unwrap_or(0) as isize;
+    METHODS[(current + step).rem_euclid(len) as usize].clone()
+}
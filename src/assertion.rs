@@ -0,0 +1,536 @@
+use crate::error::AppError;
+use crate::jsonpath::JsonPath;
+use crate::openapi;
+use crate::response::ResponseData;
+use crate::snapshot;
+use regex::Regex;
+use serde_json::Value;
+
+/// The part of a response an assertion or capture looks at.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// `status`
+    Status,
+    /// `header "Name"`, the first value of that header.
+    Header(String),
+    /// `body`, as received.
+    Body,
+    /// `jsonpath "$.path"` into a JSON body.
+    JsonPath(JsonPath),
+    /// `duration`, in milliseconds.
+    Duration,
+}
+
+/// Reads a JSON literal, e.g. `"text"` or `42`, from the start of `text`,
+/// returning it and what follows.
+fn literal(text: &str) -> Result<(Value, &str), String> {
+    let text = text.trim_start();
+    let mut values = serde_json::Deserializer::from_str(text).into_iter::<Value>();
+    match values.next() {
+        Some(Ok(value)) => Ok((value, &text[values.byte_offset()..])),
+        _ if text.is_empty() => Err(String::from("expected a value at the end")),
+        _ => Err(format!("expected a value at {}", text)),
+    }
+}
+
+fn quoted(text: &str) -> Result<(String, &str), String> {
+    match literal(text)? {
+        (Value::String(s), rest) => Ok((s, rest)),
+        _ => Err(format!("expected a quoted string at {}", text.trim_start())),
+    }
+}
+
+impl Query {
+    /// Reads a query from the start of `text`, returning it and what follows.
+    pub fn parse(text: &str) -> Result<(Query, &str), String> {
+        let text = text.trim_start();
+        let (word, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        match word {
+            "status" => Ok((Query::Status, rest)),
+            "body" => Ok((Query::Body, rest)),
+            "duration" => Ok((Query::Duration, rest)),
+            "header" => {
+                let (name, rest) = quoted(rest)?;
+                Ok((Query::Header(name), rest))
+            }
+            "jsonpath" => {
+                let (path, rest) = quoted(rest)?;
+                Ok((Query::JsonPath(JsonPath::parse(&path)?), rest))
+            }
+            _ => Err(format!("unknown query {}", word)),
+        }
+    }
+
+    /// What it picks out of `response`, if anything.
+    pub fn value(&self, response: &ResponseData) -> Option<Value> {
+        match self {
+            Query::Status => Some(Value::from(response.status.as_u16())),
+            Query::Header(name) => response
+                .headers
+                .get(name.as_str())
+                .map(|value| Value::from(String::from_utf8_lossy(value.as_bytes()).into_owned())),
+            Query::Body => Some(Value::from(response.raw_body.as_str())),
+            Query::JsonPath(path) => {
+                let json: Value = serde_json::from_str(response.raw_body.as_str()).ok()?;
+                let found = path.select(&json);
+                match (path.is_single(), found.as_slice()) {
+                    (_, []) => None,
+                    (true, [value]) => Some((*value).clone()),
+                    _ => Some(Value::Array(found.into_iter().cloned().collect())),
+                }
+            }
+            Query::Duration => Some(Value::from(response.elapsed.as_millis() as u64)),
+        }
+    }
+}
+
+/// How the value found is compared with the expected one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Equals,
+    NotEquals,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Matches,
+    Exists,
+}
+
+impl Operator {
+    fn parse(word: &str) -> Option<Operator> {
+        Some(match word {
+            "==" => Operator::Equals,
+            "!=" => Operator::NotEquals,
+            ">" => Operator::Greater,
+            ">=" => Operator::GreaterOrEqual,
+            "<" => Operator::Less,
+            "<=" => Operator::LessOrEqual,
+            "contains" => Operator::Contains,
+            "startsWith" => Operator::StartsWith,
+            "endsWith" => Operator::EndsWith,
+            "matches" => Operator::Matches,
+            "exists" => Operator::Exists,
+            _ => return None,
+        })
+    }
+
+    /// Whether it compares by size, so only numbers make sense.
+    fn is_ordered(self) -> bool {
+        matches!(
+            self,
+            Operator::Greater | Operator::GreaterOrEqual | Operator::Less | Operator::LessOrEqual
+        )
+    }
+}
+
+/// A check on a response, written as in Hurl: a query, then optionally
+/// `count` and `not`, an operator and the value expected, e.g.
+/// `status == 200`, `header "Content-Type" contains "json"`,
+/// `jsonpath "$.items" count >= 1` or `duration < 500`. Or a snapshot,
+/// `snapshot "users"`, which can leave out fields that change each time,
+/// e.g. `snapshot "users" ignoring "$.id" "$..updatedAt"`. Or that it's
+/// as an imported OpenAPI spec says, `openapi "Petstore" "GET /pets"`.
+#[derive(Clone, Debug)]
+pub struct Assertion {
+    /// As written, for reports.
+    pub text: String,
+    kind: Kind,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Compare {
+        query: Query,
+        count: bool,
+        negated: bool,
+        operator: Operator,
+        expected: Value,
+    },
+    /// The whole response against the copy stored as `name`, as for
+    /// `snapshot::check`.
+    Snapshot {
+        name: String,
+        ignored: Vec<JsonPath>,
+    },
+    /// The response against the spec of the API `name`, as for
+    /// `openapi::validate`.
+    OpenApi { name: String, operation: String },
+}
+
+/// A string to compare, with numbers and other values as they'd be written.
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => s.trim().parse().ok(),
+        other => other.as_f64(),
+    }
+}
+
+/// Equal as values, or as numbers, or as text when only one is a string,
+/// so a header's `"12"` equals `12`.
+fn equal(actual: &Value, expected: &Value) -> bool {
+    actual == expected
+        || matches!((as_number(actual), as_number(expected)), (Some(a), Some(b)) if a == b)
+        || (actual.is_string() != expected.is_string() && as_text(actual) == as_text(expected))
+}
+
+impl Assertion {
+    pub fn parse(text: &str) -> Result<Assertion, String> {
+        if let Some(rest) = text.trim_start().strip_prefix("snapshot ") {
+            return Assertion::parse_snapshot(text, rest);
+        }
+        if let Some(rest) = text.trim_start().strip_prefix("openapi ") {
+            let (name, rest) = quoted(rest)?;
+            let (operation, rest) = quoted(rest)?;
+            if !rest.trim().is_empty() {
+                return Err(format!("unexpected {} in {}", rest.trim(), text.trim()));
+            }
+            return Ok(Assertion {
+                text: String::from(text.trim()),
+                kind: Kind::OpenApi { name, operation },
+            });
+        }
+        let (query, rest) = Query::parse(text)?;
+        let mut words = rest.trim_start();
+        let mut take = |word: &str| match words.strip_prefix(word) {
+            Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+                words = rest.trim_start();
+                true
+            }
+            _ => false,
+        };
+        let count = take("count");
+        let negated = take("not");
+        let (operator_word, rest) = words.split_once(char::is_whitespace).unwrap_or((words, ""));
+        let operator = Operator::parse(operator_word)
+            .ok_or_else(|| format!("unknown operator {} in {}", operator_word, text.trim()))?;
+        let expected = match operator {
+            Operator::Exists => Value::Null,
+            _ => {
+                let (expected, rest) = literal(rest)?;
+                if !rest.trim().is_empty() {
+                    return Err(format!("unexpected {} in {}", rest.trim(), text.trim()));
+                }
+                if operator.is_ordered() && as_number(&expected).is_none() {
+                    return Err(format!(
+                        "{} needs a number, got {} in {}",
+                        operator_word,
+                        expected,
+                        text.trim()
+                    ));
+                }
+                expected
+            }
+        };
+        Ok(Assertion {
+            text: String::from(text.trim()),
+            kind: Kind::Compare {
+                query,
+                count,
+                negated,
+                operator,
+                expected,
+            },
+        })
+    }
+
+    /// Reads `rest`, what follows `snapshot` in `text`.
+    fn parse_snapshot(text: &str, rest: &str) -> Result<Assertion, String> {
+        let (name, rest) = quoted(rest)?;
+        let mut rest = rest.trim_start();
+        let mut ignored = Vec::new();
+        if let Some(paths) = rest.strip_prefix("ignoring") {
+            rest = paths;
+            while !rest.trim().is_empty() {
+                let (path, after) = quoted(rest)?;
+                ignored.push(JsonPath::parse(&path)?);
+                rest = after;
+            }
+            if ignored.is_empty() {
+                return Err(format!("expected paths to ignore in {}", text.trim()));
+            }
+        } else if !rest.is_empty() {
+            return Err(format!("unexpected {} in {}", rest.trim(), text.trim()));
+        }
+        Ok(Assertion {
+            text: String::from(text.trim()),
+            kind: Kind::Snapshot { name, ignored },
+        })
+    }
+
+    /// Checks `response`, saying what was found instead if it fails.
+    pub fn check(&self, response: &ResponseData) -> Result<(), String> {
+        let (query, count, negated, operator) = match &self.kind {
+            Kind::Compare {
+                query,
+                count,
+                negated,
+                operator,
+                ..
+            } => (query, *count, *negated, *operator),
+            Kind::Snapshot { name, ignored } => return snapshot::check(name, ignored, response),
+            Kind::OpenApi { name, operation } => {
+                return openapi::validate(name, operation, response)
+            }
+        };
+        let mut actual = query.value(response);
+        if count {
+            actual = actual.map(|value| match value {
+                Value::Array(items) => Value::from(items.len()),
+                Value::Object(map) => Value::from(map.len()),
+                _ => Value::from(1),
+            });
+        }
+        let passed = match (&actual, operator) {
+            (found, Operator::Exists) => found.is_some(),
+            (None, Operator::NotEquals) => true,
+            (None, _) => false,
+            (Some(found), operator) => self.compare(found, operator)?,
+        };
+        if passed != negated {
+            return Ok(());
+        }
+        Err(match actual {
+            Some(found) => format!("{}: got {}", self.text, found),
+            None => format!("{}: nothing found", self.text),
+        })
+    }
+
+    fn compare(&self, actual: &Value, operator: Operator) -> Result<bool, String> {
+        let expected = match &self.kind {
+            Kind::Compare { expected, .. } => expected,
+            Kind::Snapshot { .. } | Kind::OpenApi { .. } => &Value::Null,
+        };
+        let ordered = |test: fn(f64, f64) -> bool| matches!((as_number(actual), as_number(expected)), (Some(a), Some(b)) if test(a, b));
+        Ok(match operator {
+            Operator::Equals => equal(actual, expected),
+            Operator::NotEquals => !equal(actual, expected),
+            Operator::Greater => ordered(|a, b| a > b),
+            Operator::GreaterOrEqual => ordered(|a, b| a >= b),
+            Operator::Less => ordered(|a, b| a < b),
+            Operator::LessOrEqual => ordered(|a, b| a <= b),
+            Operator::Contains => match actual {
+                Value::Array(items) => items.iter().any(|item| equal(item, expected)),
+                _ => as_text(actual).contains(&as_text(expected)),
+            },
+            Operator::StartsWith => as_text(actual).starts_with(&as_text(expected)),
+            Operator::EndsWith => as_text(actual).ends_with(&as_text(expected)),
+            Operator::Matches => Regex::new(&as_text(expected))
+                .map_err(|e| format!("{}: {}", self.text, e))?
+                .is_match(&as_text(actual)),
+            Operator::Exists => true,
+        })
+    }
+}
+
+/// Checks `response` against each of `texts` in turn. One that can't be
+/// read fails with why.
+pub fn check_each(texts: &[String], response: &ResponseData) -> Vec<Result<(), String>> {
+    texts
+        .iter()
+        .map(|text| Assertion::parse(text).and_then(|assertion| assertion.check(response)))
+        .collect()
+}
+
+/// Stores `response` as the snapshot of each of `texts` that takes one,
+/// replacing what was kept. Returns how many were.
+pub fn update_snapshots(texts: &[String], response: &ResponseData) -> Result<usize, AppError> {
+    let mut updated = 0;
+    for text in texts {
+        if let Ok(Assertion {
+            kind: Kind::Snapshot { name, ignored },
+            ..
+        }) = Assertion::parse(text)
+        {
+            snapshot::record(&name, &ignored, response)?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+/// A response matching `texts`, for the mock server, if one of them says
+/// what it looks like: a snapshot that's been stored, or an OpenAPI check
+/// whose spec has an example. Its status, Content-Type and body.
+pub fn example(texts: &[String]) -> Option<(u16, String, String)> {
+    texts
+        .iter()
+        .filter_map(|text| Assertion::parse(text).ok())
+        .find_map(|assertion| match assertion.kind {
+            Kind::Snapshot { name, .. } => {
+                let stored = snapshot::load(&name)?;
+                let (content_type, body) = match stored.body {
+                    Value::String(text) => ("text/plain", text),
+                    json => (
+                        "application/json",
+                        serde_json::to_string_pretty(&json).unwrap_or_default(),
+                    ),
+                };
+                Some((stored.status, String::from(content_type), body))
+            }
+            Kind::OpenApi { name, operation } => {
+                let spec = openapi::load_spec(&name).ok()?;
+                openapi::example(&spec, &operation)
+            }
+            Kind::Compare { .. } => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use reqwest::{StatusCode, Version};
+    use std::time::Duration;
+
+    fn response() -> ResponseData {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        headers.insert("content-length", HeaderValue::from_static("12"));
+        let body = br#"{"items": [{"id": 1, "name": "Ada"}, {"id": 2}], "next": null}"#;
+        ResponseData::new(
+            StatusCode::CREATED,
+            Version::HTTP_11,
+            headers,
+            Duration::from_millis(120),
+            body,
+            true,
+        )
+    }
+
+    fn check(text: &str) -> Result<(), String> {
+        Assertion::parse(text)?.check(&response())
+    }
+
+    #[test]
+    fn compares_with_each_operator() {
+        for text in [
+            "status == 201",
+            "status != 200",
+            "status > 200",
+            "status >= 201",
+            "status < 300",
+            "status <= 201",
+            r#"header "Content-Type" contains "json""#,
+            r#"header "Content-Type" startsWith "application/""#,
+            r#"header "Content-Type" endsWith "/json""#,
+            r#"header "Content-Type" matches "^app.*n$""#,
+            r#"jsonpath "$.items[0].name" exists"#,
+            r#"jsonpath "$.items[*].id" contains 2"#,
+        ] {
+            assert_eq!(check(text), Ok(()), "{}", text);
+        }
+        for text in [
+            "status == 200",
+            "status != 201",
+            "status > 201",
+            "status < 201",
+            r#"header "Content-Type" contains "xml""#,
+            r#"header "Content-Type" matches "^json""#,
+            r#"jsonpath "$.items[1].name" exists"#,
+        ] {
+            assert!(check(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn negates_and_counts() {
+        assert_eq!(check("status not == 200"), Ok(()));
+        assert!(check("status not == 201").is_err());
+        assert_eq!(check(r#"jsonpath "$.items" count == 2"#), Ok(()));
+        assert_eq!(check(r#"jsonpath "$.items[0]" count >= 2"#), Ok(()));
+        assert_eq!(check(r#"jsonpath "$.items" count not > 2"#), Ok(()));
+        assert_eq!(check(r#"header "X-Missing" not exists"#), Ok(()));
+    }
+
+    #[test]
+    fn reads_each_query() {
+        let response = response();
+        let value = |text: &str| Query::parse(text).unwrap().0.value(&response);
+        assert_eq!(value("status"), Some(Value::from(201)));
+        assert_eq!(value(r#"header "content-length""#), Some(Value::from("12")));
+        assert_eq!(value(r#"header "X-Missing""#), None);
+        assert_eq!(value(r#"jsonpath "$.items[1].id""#), Some(Value::from(2)));
+        assert_eq!(
+            value(r#"jsonpath "$.items[*].id""#),
+            Some(serde_json::json!([1, 2]))
+        );
+        assert_eq!(value(r#"jsonpath "$.missing""#), None);
+        assert_eq!(value("duration"), Some(Value::from(120)));
+        assert_eq!(value("body"), Some(Value::from(response.raw_body.as_str())));
+        // A header's text equals the number it holds
+        assert_eq!(check(r#"header "Content-Length" == 12"#), Ok(()));
+        assert_eq!(check("duration < 500"), Ok(()));
+        assert_eq!(check(r#"body contains "Ada""#), Ok(()));
+    }
+
+    #[test]
+    fn reads_snapshot_and_openapi_forms() {
+        let kind = |text: &str| Assertion::parse(text).unwrap().kind;
+        match kind(r#"snapshot "users""#) {
+            Kind::Snapshot { name, ignored } => {
+                assert_eq!(name, "users");
+                assert!(ignored.is_empty());
+            }
+            other => panic!("{:?}", other),
+        }
+        match kind(r#"snapshot "users" ignoring "$.id" "$..updatedAt""#) {
+            Kind::Snapshot { ignored, .. } => assert_eq!(ignored.len(), 2),
+            other => panic!("{:?}", other),
+        }
+        match kind(r#"openapi "Petstore" "GET /pets""#) {
+            Kind::OpenApi { name, operation } => {
+                assert_eq!(name, "Petstore");
+                assert_eq!(operation, "GET /pets");
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_assertions() {
+        for text in [
+            "",
+            "status",
+            "status is 200",
+            "status == 200 extra",
+            "status == ",
+            "header Content-Type == \"json\"",
+            "header \"Content-Type == \"json\"",
+            "jsonpath \"items\" exists",
+            "status < \"abc\"",
+            "duration >= true",
+            "unknown == 1",
+            "snapshot users",
+            "snapshot \"users\" ignoring",
+            "snapshot \"users\" extra",
+            "openapi \"Petstore\"",
+            "openapi \"Petstore\" \"GET /pets\" extra",
+        ] {
+            assert!(Assertion::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn reports_what_it_found() {
+        assert_eq!(
+            check("status == 200"),
+            Err(String::from("status == 200: got 201"))
+        );
+        assert_eq!(
+            check(r#"jsonpath "$.missing" == 1"#),
+            Err(String::from(r#"jsonpath "$.missing" == 1: nothing found"#))
+        );
+        assert!(check(r#"body matches "(""#).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+use crate::environment::Environments;
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One input of an auth type, e.g. the Basic password.
+#[derive(Debug)]
+pub struct Field {
+    /// Key the value is stored under in `Auth::values`.
+    pub name: &'static str,
+    pub label: &'static str,
+    /// Secret values are masked in the Auth pane and prompt.
+    pub secret: bool,
+    /// Fixed options to cycle through instead of typing; the first is the default.
+    pub choices: &'static [&'static str],
+}
+
+const BASIC_FIELDS: [Field; 2] = [
+    Field {
+        name: "username",
+        label: "Username",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "password",
+        label: "Password",
+        secret: true,
+        choices: &[],
+    },
+];
+
+const BEARER_FIELDS: [Field; 1] = [Field {
+    name: "token",
+    label: "Token",
+    secret: true,
+    choices: &[],
+}];
+
+const API_KEY_FIELDS: [Field; 3] = [
+    Field {
+        name: "key_name",
+        label: "Key",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "key_value",
+        label: "Value",
+        secret: true,
+        choices: &[],
+    },
+    Field {
+        name: "key_in",
+        label: "Add to",
+        secret: false,
+        choices: &["header", "query"],
+    },
+];
+
+const OAUTH2_FIELDS: [Field; 6] = [
+    Field {
+        name: "auth_url",
+        label: "Authorization URL",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "token_url",
+        label: "Token URL",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "client_id",
+        label: "Client ID",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "client_secret",
+        label: "Client secret",
+        secret: true,
+        choices: &[],
+    },
+    Field {
+        name: "scope",
+        label: "Scope",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "redirect_uri",
+        label: "Redirect URI",
+        secret: false,
+        choices: &[],
+    },
+];
+
+const CLIENT_CREDENTIALS_FIELDS: [Field; 4] = [
+    Field {
+        name: "token_url",
+        label: "Token URL",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "client_id",
+        label: "Client ID",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "client_secret",
+        label: "Client secret",
+        secret: true,
+        choices: &[],
+    },
+    Field {
+        name: "scope",
+        label: "Scope",
+        secret: false,
+        choices: &[],
+    },
+];
+
+const AWS_FIELDS: [Field; 5] = [
+    Field {
+        name: "access_key",
+        label: "Access key ID",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "secret_key",
+        label: "Secret access key",
+        secret: true,
+        choices: &[],
+    },
+    Field {
+        name: "session_token",
+        label: "Session token",
+        secret: true,
+        choices: &[],
+    },
+    Field {
+        name: "region",
+        label: "Region",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "service",
+        label: "Service",
+        secret: false,
+        choices: &[],
+    },
+];
+
+/// The authentication schemes the Auth pane cycles through, in order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum AuthKind {
+    #[default]
+    None,
+    Basic,
+    Bearer,
+    ApiKey,
+    /// Authorization code with PKCE. The token comes from `oauth::TokenStore`.
+    OAuth2,
+    /// OAuth2 client credentials, fetched and cached in `oauth::TokenStore`.
+    ClientCredentials,
+    /// Digest, answered after the server's 401 challenge by `digest::send`.
+    Digest,
+    /// AWS Signature Version 4, signed by `sigv4::sign` when the request is built.
+    AwsSigV4,
+}
+
+impl AuthKind {
+    const ALL: [AuthKind; 8] = [
+        AuthKind::None,
+        AuthKind::Basic,
+        AuthKind::Bearer,
+        AuthKind::ApiKey,
+        AuthKind::OAuth2,
+        AuthKind::ClientCredentials,
+        AuthKind::Digest,
+        AuthKind::AwsSigV4,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AuthKind::None => "None",
+            AuthKind::Basic => "Basic",
+            AuthKind::Bearer => "Bearer",
+            AuthKind::ApiKey => "API key",
+            AuthKind::OAuth2 => "OAuth2 (a to authorize)",
+            AuthKind::ClientCredentials => "OAuth2 client credentials",
+            AuthKind::Digest => "Digest",
+            AuthKind::AwsSigV4 => "AWS Signature v4",
+        }
+    }
+
+    pub fn fields(self) -> &'static [Field] {
+        match self {
+            AuthKind::None => &[],
+            AuthKind::Basic => &BASIC_FIELDS,
+            AuthKind::Bearer => &BEARER_FIELDS,
+            AuthKind::ApiKey => &API_KEY_FIELDS,
+            AuthKind::OAuth2 => &OAUTH2_FIELDS,
+            AuthKind::ClientCredentials => &CLIENT_CREDENTIALS_FIELDS,
+            AuthKind::Digest => &BASIC_FIELDS,
+            AuthKind::AwsSigV4 => &AWS_FIELDS,
+        }
+    }
+
+    /// Whether the credentials are a token kept in `oauth::TokenStore`.
+    pub fn uses_token(self) -> bool {
+        matches!(self, AuthKind::OAuth2 | AuthKind::ClientCredentials)
+    }
+
+    fn next(self) -> AuthKind {
+        let i = AuthKind::ALL.iter().position(|k| *k == self).unwrap_or(0);
+        AuthKind::ALL[(i + 1) % AuthKind::ALL.len()]
+    }
+}
+
+/// How a request authenticates. Values are kept by field name, so switching
+/// the kind back and forth doesn't lose what was typed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Auth {
+    pub kind: AuthKind,
+    pub values: BTreeMap<String, String>,
+    /// Field selected in the Auth pane.
+    #[serde(skip)]
+    pub selected: usize,
+}
+
+impl Auth {
+    /// The value of field `name`, or the field's default choice if unset.
+    pub fn value(&self, name: &str) -> &str {
+        match self.values.get(name) {
+            Some(value) => value,
+            None => self
+                .kind
+                .fields()
+                .iter()
+                .find(|field| field.name == name)
+                .and_then(|field| field.choices.first())
+                .copied()
+                .unwrap_or(""),
+        }
+    }
+
+    /// Moves a choice field on to its next option.
+    pub fn cycle_choice(&mut self, field: &Field) {
+        let current = self.value(field.name);
+        let i = field
+            .choices
+            .iter()
+            .position(|c| *c == current)
+            .unwrap_or(0);
+        let next = field.choices[(i + 1) % field.choices.len()];
+        self.set(field.name, next);
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.values.insert(String::from(name), String::from(value));
+    }
+
+    pub fn cycle_kind(&mut self) {
+        self.kind = self.kind.next();
+        self.selected = 0;
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.kind.fields().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_field(&self) -> Option<&'static Field> {
+        self.kind.fields().get(self.selected)
+    }
+
+    /// The values as shown in the Auth pane, with secrets masked.
+    pub fn display(&self, field: &Field) -> String {
+        let value = self.value(field.name);
+        if field.secret {
+            "*".repeat(value.chars().count())
+        } else {
+            String::from(value)
+        }
+    }
+
+    /// The variable name if `value` is just a `{{variable}}` reference.
+    pub fn variable_name(value: &str) -> Option<&str> {
+        let name = value.trim().strip_prefix("{{")?.strip_suffix("}}")?.trim();
+        if name.is_empty() || name.contains("{{") || name.contains("}}") {
+            None
+        } else {
+            Some(name)
+        }
+    }
+
+    /// A copy with `{{variables}}` in every value filled in.
+    pub fn interpolate(&self, environments: &Environments) -> Auth {
+        Auth {
+            kind: self.kind,
+            values: self
+                .values
+                .iter()
+                .map(|(name, value)| (name.clone(), environments.interpolate(value)))
+                .collect(),
+            selected: self.selected,
+        }
+    }
+
+    /// The header the credentials go in, if any. A header of that name typed
+    /// into the Headers pane takes precedence.
+    pub fn header_name(&self) -> Option<&str> {
+        match self.kind {
+            AuthKind::None => None,
+            AuthKind::Basic | AuthKind::Bearer => Some("Authorization"),
+            AuthKind::OAuth2
+            | AuthKind::ClientCredentials
+            | AuthKind::Digest
+            | AuthKind::AwsSigV4 => Some("Authorization"),
+            AuthKind::ApiKey if self.value("key_in") == "header" => Some(self.value("key_name")),
+            AuthKind::ApiKey => None,
+        }
+    }
+
+    /// Adds the credentials to `builder`.
+    pub fn apply(&self, builder: RequestBuilder) -> RequestBuilder {
+        match self.kind {
+            AuthKind::None => builder,
+            AuthKind::Basic => {
+                builder.basic_auth(self.value("username"), Some(self.value("password")))
+            }
+            AuthKind::Bearer => builder.bearer_auth(self.value("token")),
+            // Set on the copy being sent by `oauth::send`
+            AuthKind::OAuth2 | AuthKind::ClientCredentials => {
+                builder.bearer_auth(self.value("access_token"))
+            }
+            // Nothing to send until the server's challenge has been answered
+            AuthKind::Digest if self.value("digest_header").is_empty() => builder,
+            AuthKind::Digest => builder.header("Authorization", self.value("digest_header")),
+            // Signed in `Request::build`, which has the URL and body
+            AuthKind::AwsSigV4 => builder,
+            AuthKind::ApiKey if self.value("key_name").is_empty() => builder,
+            AuthKind::ApiKey => {
+                let (name, value) = (self.value("key_name"), self.value("key_value"));
+                if self.value("key_in") == "query" {
+                    builder.query(&[(name, value)])
+                } else {
+                    builder.header(name, value)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::auth::AuthKind;
+use crate::digest;
+use crate::error::AppError;
+use crate::request::{self, Request};
+use crate::throttle::{self, Throttle};
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// How many requests a benchmark sends when not told.
+pub const DEFAULT_REQUESTS: usize = 100;
+
+/// How many of them are in flight at once when not told.
+pub const DEFAULT_CONCURRENCY: usize = 10;
+
+/// How a benchmark went.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /// How long each request that got a response took, shortest first.
+    pub latencies: Vec<Duration>,
+    /// How many responses came with each status.
+    pub statuses: BTreeMap<u16, usize>,
+    /// How many requests got no response, by why.
+    pub errors: BTreeMap<String, usize>,
+    /// From the first request going out to the last response.
+    pub took: Duration,
+}
+
+impl Stats {
+    pub fn sent(&self) -> usize {
+        self.latencies.len() + self.failed()
+    }
+
+    /// How many requests got no response.
+    pub fn failed(&self) -> usize {
+        self.errors.values().sum()
+    }
+
+    /// The latency `percent` of responses came within, by nearest rank.
+    pub fn percentile(&self, percent: f64) -> Option<Duration> {
+        let rank = (percent / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        self.latencies.get(rank.saturating_sub(1)).copied()
+    }
+
+    fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.latencies.len())
+            .ok()
+            .filter(|n| *n > 0)?;
+        Some(self.latencies.iter().sum::<Duration>() / count)
+    }
+
+    /// What to show or print, a line for each of throughput, latency,
+    /// statuses and errors.
+    pub fn lines(&self) -> Vec<String> {
+        let seconds = self.took.as_secs_f64();
+        let throughput = if seconds > 0.0 {
+            self.sent() as f64 / seconds
+        } else {
+            0.0
+        };
+        let mut lines = vec![format!(
+            "{} requests in {:.2} s, {:.1} requests/s",
+            self.sent(),
+            seconds,
+            throughput
+        )];
+        if let (Some(min), Some(max), Some(mean)) =
+            (self.latencies.first(), self.latencies.last(), self.mean())
+        {
+            let ms = |latency: Duration| format!("{:.1} ms", millis(latency));
+            let at = |percent| self.percentile(percent).map_or_else(String::new, ms);
+            lines.push(format!(
+                "Latency: min {}, mean {}, p50 {}, p95 {}, p99 {}, max {}",
+                ms(*min),
+                ms(mean),
+                at(50.0),
+                at(95.0),
+                at(99.0),
+                ms(*max)
+            ));
+        }
+        if !self.statuses.is_empty() {
+            let statuses: Vec<String> = self
+                .statuses
+                .iter()
+                .map(|(status, count)| format!("{} x {}", status, count))
+                .collect();
+            lines.push(format!("Statuses: {}", statuses.join(", ")));
+        }
+        let errors: Vec<String> = self
+            .errors
+            .iter()
+            .map(|(error, count)| format!("{} x {}", error, count))
+            .collect();
+        lines.push(match self.failed() {
+            0 => String::from("Errors: none"),
+            failed => format!("Errors: {} ({})", failed, errors.join("; ")),
+        });
+        lines
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// `latencies` counted into `buckets` of equal width from the shortest to
+/// the longest, each labelled with where it starts in milliseconds, for a
+/// bar chart. Fewer buckets are used if they'd be narrower than 0.1 ms.
+pub fn histogram(latencies: &[Duration], buckets: usize) -> Vec<(String, u64)> {
+    let (Some(min), Some(max)) = (latencies.iter().min(), latencies.iter().max()) else {
+        return Vec::new();
+    };
+    let (min, span) = (millis(*min), millis(*max) - millis(*min));
+    let buckets = buckets.clamp(1, (span * 10.0) as usize + 1);
+    let width = span / buckets as f64;
+    let mut counts = vec![0; buckets];
+    for latency in latencies {
+        let i = if width > 0.0 {
+            ((millis(*latency) - min) / width) as usize
+        } else {
+            0
+        };
+        counts[i.min(buckets - 1)] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let start = min + width * i as f64;
+            let label = if start < 10.0 {
+                format!("{:.1}", start)
+            } else {
+                format!("{:.0}", start)
+            };
+            (label, count)
+        })
+        .collect()
+}
+
+/// Sends `request` once, on `client` if it can share one, returning its
+/// status and how long it took to read the whole response.
+async fn send_one(client: Option<&Client>, request: &Request) -> Result<(u16, Duration), String> {
+    let Some(client) = client else {
+        let result = match request.auth.kind {
+            AuthKind::Digest => digest::send(request.clone()).await,
+            _ => request::send(request).await,
+        };
+        return result
+            .map(|response| (response.status.as_u16(), response.elapsed))
+            .map_err(|e| e.to_string());
+    };
+    let start = Instant::now();
+    let response = request
+        .build(client)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| request.settings.timeout_error(e).to_string())?;
+    let status = response.status().as_u16();
+    response
+        .bytes()
+        .await
+        .map_err(|e| request.settings.timeout_error(e).to_string())?;
+    Ok((status, start.elapsed()))
+}
+
+/// Sends `request`, with its variables already filled in, `requests` times
+/// with up to `concurrency` in flight at once and no more often than its
+/// `rate_limit`, counting each that's done in `done`. They share one
+/// client, so connections are reused as a browser or `wrk` would, except
+/// for digest auth and Unix sockets, which connect for each request. Fails
+/// if the client can't be built.
+pub async fn run(
+    request: &Request,
+    requests: usize,
+    concurrency: usize,
+    done: &AtomicUsize,
+) -> Result<Stats, AppError> {
+    let shared =
+        request.auth.kind != AuthKind::Digest && request.settings.value("unix_socket").is_empty();
+    let client = if shared {
+        let redirects = Arc::new(Mutex::new(Vec::new()));
+        Some(
+            request
+                .settings
+                .client(&redirects, request.cookie_jar.as_ref())?,
+        )
+    } else {
+        None
+    };
+    throttle::interval(&request.settings)?;
+    let throttle = Throttle::default();
+    let mut stats = Stats::default();
+    let started = Instant::now();
+    let mut results = stream::iter(0..requests)
+        .map(|_| async {
+            throttle
+                .wait(&request.settings)
+                .await
+                .map_err(|e| e.to_string())?;
+            send_one(client.as_ref(), request).await
+        })
+        .buffer_unordered(concurrency.max(1));
+    while let Some(result) = results.next().await {
+        match result {
+            Ok((status, elapsed)) => {
+                *stats.statuses.entry(status).or_default() += 1;
+                stats.latencies.push(elapsed);
+            }
+            Err(e) => *stats.errors.entry(e).or_default() += 1,
+        }
+        done.fetch_add(1, Ordering::Relaxed);
+    }
+    stats.took = started.elapsed();
+    stats.latencies.sort();
+    Ok(stats)
+}
+
+/// A benchmark started from the TUI, kept after it finishes to look over.
+#[derive(Debug)]
+pub struct Bench {
+    /// Told apart from the results of a benchmark that has since been
+    /// replaced.
+    pub id: u64,
+    /// e.g. `GET https://example.com/health`
+    pub label: String,
+    pub requests: usize,
+    pub concurrency: usize,
+    /// How many requests are done, while it's running.
+    pub done: Arc<AtomicUsize>,
+    /// How it went, once it's finished, or why it couldn't run.
+    pub result: Option<Result<Stats, String>>,
+    pub task: Option<JoinHandle<()>>,
+}
+
+impl Bench {
+    pub fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+
+    pub fn finish(&mut self, result: Result<Stats, String>) {
+        self.task = None;
+        self.result = Some(result);
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// e.g. `GET https://example.com/health: 37 of 100 sent, 10 at a time`
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: {} of {} sent, {} at a time",
+            self.label,
+            self.done.load(Ordering::Relaxed),
+            self.requests,
+            self.concurrency
+        )
+    }
+}
@@ -0,0 +1,214 @@
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::storage;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::{fs, io};
+use url::form_urlencoded;
+
+/// How the Body pane becomes the request body.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyMode {
+    /// The text typed into the body editor, sent as is.
+    #[default]
+    Raw,
+    /// `application/x-www-form-urlencoded` built from the fields table.
+    Form,
+    /// `multipart/form-data` built from the fields table.
+    Multipart,
+    /// The body editor's text as a GraphQL query, sent as JSON along with
+    /// the variables editor's.
+    #[serde(rename = "graphql")]
+    GraphQl,
+}
+
+impl BodyMode {
+    pub const ALL: [BodyMode; 4] = [
+        BodyMode::Raw,
+        BodyMode::Form,
+        BodyMode::Multipart,
+        BodyMode::GraphQl,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BodyMode::Raw => "Raw",
+            BodyMode::Form => "Form URL-encoded",
+            BodyMode::Multipart => "Multipart",
+            BodyMode::GraphQl => "GraphQL",
+        }
+    }
+
+    pub fn next(self) -> BodyMode {
+        let i = BodyMode::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        BodyMode::ALL[(i + 1) % BodyMode::ALL.len()]
+    }
+
+    /// Whether the Body pane edits the fields table rather than the text.
+    pub fn uses_fields(&self) -> bool {
+        matches!(self, BodyMode::Form | BodyMode::Multipart)
+    }
+}
+
+/// A multipart field's value as typed: `@path` uploads the file, optionally
+/// followed by `;type=` to give its Content-Type, as with curl's `-F`.
+#[derive(Debug, PartialEq)]
+pub enum FieldValue<'a> {
+    Text(&'a str),
+    File {
+        path: &'a str,
+        content_type: Option<&'a str>,
+    },
+}
+
+impl FieldValue<'_> {
+    pub fn parse(value: &str) -> FieldValue<'_> {
+        match value.strip_prefix('@') {
+            Some(file) => match file.rsplit_once(";type=") {
+                Some((path, content_type)) => FieldValue::File {
+                    path,
+                    content_type: Some(content_type.trim()),
+                },
+                None => FieldValue::File {
+                    path: file,
+                    content_type: None,
+                },
+            },
+            None => FieldValue::Text(value),
+        }
+    }
+}
+
+/// A Content-Type for an uploaded file, from its extension.
+pub fn guess_content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The field row added when a file is picked: named after the file, with
+/// its guessed type spelled out so it can be changed.
+pub fn file_field(path: &str) -> KeyValue {
+    let expanded = storage::expand_tilde(path);
+    let name = expanded
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file");
+    KeyValue::new(
+        name,
+        &format!("@{};type={}", path, guess_content_type(&expanded)),
+    )
+}
+
+/// Encodes `fields` as `key=value` pairs joined by `&`.
+pub fn form_urlencoded(fields: &[KeyValue]) -> String {
+    let mut form = form_urlencoded::Serializer::new(String::new());
+    for field in fields {
+        form.append_pair(&field.key, &field.value);
+    }
+    form.finish()
+}
+
+/// Wraps a GraphQL query as `{"query": ..., "variables": ...}`. Empty
+/// variables are sent as `{}`; anything else has to be a JSON object.
+pub fn graphql(query: &str, variables: &str) -> Result<String, AppError> {
+    let variables = if variables.trim().is_empty() {
+        serde_json::Value::Object(Default::default())
+    } else {
+        serde_json::from_str::<serde_json::Value>(variables)
+            .ok()
+            .filter(serde_json::Value::is_object)
+            .ok_or_else(|| AppError::Parse(String::from("GraphQL variables as a JSON object")))?
+    };
+    Ok(serde_json::json!({ "query": query, "variables": variables }).to_string())
+}
+
+/// The `message` of each entry in a GraphQL response's `errors` array,
+/// followed by the `path` of the field it's about when there is one.
+pub fn graphql_errors(body: &str) -> Vec<String> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return Vec::new();
+    };
+    let Some(errors) = json.get("errors").and_then(|e| e.as_array()) else {
+        return Vec::new();
+    };
+    errors
+        .iter()
+        .map(|error| {
+            let message = match error.get("message") {
+                Some(serde_json::Value::String(message)) => message.clone(),
+                _ => error.to_string(),
+            };
+            let path: Vec<String> = error
+                .get("path")
+                .and_then(|p| p.as_array())
+                .into_iter()
+                .flatten()
+                .map(|segment| match segment {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect();
+            if path.is_empty() {
+                message
+            } else {
+                format!("{} (at {})", message, path.join("."))
+            }
+        })
+        .collect()
+}
+
+/// Builds the multipart form for `fields`, reading files as it goes.
+pub fn multipart_form(fields: &[KeyValue]) -> Result<Form, AppError> {
+    let mut form = Form::new();
+    for field in fields {
+        let part = match FieldValue::parse(&field.value) {
+            FieldValue::Text(text) => Part::text(String::from(text)),
+            FieldValue::File { path, content_type } => {
+                let path = storage::expand_tilde(path.trim());
+                let bytes = fs::read(&path).map_err(|e| {
+                    AppError::Io(io::Error::new(
+                        e.kind(),
+                        format!("{}: {}", path.display(), e),
+                    ))
+                })?;
+                let content_type = content_type.unwrap_or_else(|| guess_content_type(&path));
+                let file_name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                Part::bytes(bytes)
+                    .file_name(file_name)
+                    .mime_str(content_type)
+                    .map_err(|_| {
+                        AppError::Parse(format!(
+                            "content type '{}' of field {}",
+                            content_type, field.key
+                        ))
+                    })?
+            }
+        };
+        form = form.part(field.key.clone(), part);
+    }
+    Ok(form)
+}
@@ -0,0 +1,686 @@
+use crate::app::{App, Message};
+use crate::assertion::{self, Query};
+use crate::bench;
+use crate::body::BodyMode;
+use crate::collections::Collection;
+use crate::dataset::{self, Row};
+use crate::environment::Environments;
+use crate::error::AppError;
+use crate::httpfile::HttpFile;
+use crate::hurl::{self, Entry};
+use crate::kv::KeyValue;
+use crate::logs::Level;
+use crate::mock::{self, Mock};
+use crate::openapi;
+use crate::report::{Case, Report};
+use crate::request;
+use crate::response::ResponseData;
+use crate::storage;
+use reqwest::Method;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+pub const USAGE: &str = "\
+Usage: heade-rs [OPTIONS]              start the TUI, with the request from the options
+       heade-rs [METHOD] URL [OPTIONS] send a request and print the response body
+       heade-rs run FILE [OPTIONS]     send each request in a .http file in turn, or
+                                       run a .hurl file's requests and asserts
+       heade-rs run NAME [OPTIONS]     run the saved collection NAME and its asserts
+       heade-rs mock NAME [OPTIONS]    serve the saved collection NAME, or an
+                                       OpenAPI file, with example responses
+       heade-rs bench [METHOD] URL [OPTIONS]
+                                       send a request many times at once and
+                                       print how long the responses took
+
+When stdout isn't a terminal the request from the options is sent instead
+of starting the TUI, so the body can be piped on.
+
+Options:
+      --method METHOD         the request's method, GET if not given
+      --url URL               the request's URL
+  -H, --header 'NAME: VALUE'  add a header, can be repeated
+  -d, --data, --body BODY     send BODY as the request body, the contents of
+                              a file with @FILE or stdin with @-. Whatever is
+                              piped to stdin is sent if no body is given
+  -i, --include               print the status line and headers before the body
+      --raw                   print the body as received instead of formatted
+      --dataset ROWS          with run, go through the file once for each row
+                              of ROWS, a CSV file with a header line or a JSON
+                              array of objects, with its columns as variables
+      --report junit:PATH     with run, write how each request went to PATH
+                              as JUnit XML
+      --update-snapshots      with run, store each response as the snapshot
+                              its snapshot asserts compare with, instead of
+                              comparing
+      --port PORT             with mock, listen on PORT, 8080 if not given
+  -n, --requests N            with bench, send N requests, 100 if not given
+  -c, --concurrency C         with bench, keep C in flight at once, 10 if not
+                              given
+  -q, --quiet                 print nothing but what failed
+  -h, --help                  show this help
+
+Exit status: 0 when everything was sent and passed, 1 when an assertion
+didn't hold, 2 for a bad command line and 3 when a request got no response
+or the run couldn't start.";
+
+/// A `run` request's assertions didn't hold.
+pub const EXIT_FAILED: i32 = 1;
+/// The command line couldn't be read.
+pub const EXIT_USAGE: i32 = 2;
+/// A request got no response, or there was nothing to run.
+pub const EXIT_ERROR: i32 = 3;
+
+/// What the command line asks for.
+#[derive(Debug)]
+pub enum Invocation {
+    /// Start the TUI, with its editors filled in if any request options
+    /// were given.
+    Tui(Option<Args>),
+    /// Send a request and print its response without starting the TUI.
+    Send(Args),
+    /// `run FILE`: send each request in a `.http` or `.hurl` file, printing
+    /// how it went with the output options from `Args`.
+    Run(PathBuf, Args),
+    /// `mock NAME`: serve a saved collection or OpenAPI file until stopped.
+    Mock(PathBuf, Args),
+    /// `bench URL`: send the request many times and print how it went.
+    Bench(Args),
+    Help,
+}
+
+/// A request given on the command line, e.g. `heade-rs get https://api.example.com/users`.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<KeyValue>,
+    pub body: String,
+    /// Print the status line and headers too.
+    pub include: bool,
+    /// Print the body as received rather than formatted.
+    pub raw: bool,
+    /// `--dataset`: the rows to run a file's requests once for each of.
+    pub dataset: Option<PathBuf>,
+    /// `--report`: where to write how a run went.
+    pub report: Option<Report>,
+    /// Print only what failed.
+    pub quiet: bool,
+    /// `--update-snapshots`: store responses as their snapshots.
+    pub update_snapshots: bool,
+    /// `--port`: where to serve a mock.
+    pub port: Option<u16>,
+    /// `--requests`: how many requests a benchmark sends.
+    pub requests: Option<usize>,
+    /// `--concurrency`: how many of them are in flight at once.
+    pub concurrency: Option<usize>,
+}
+
+impl Args {
+    /// Puts the request into `app`'s editors, replacing what was there.
+    pub fn fill(self, app: &mut App) {
+        app.method = self.method;
+        app.url = self.url;
+        app.url_cursor = app.url.len();
+        app.sync_params_from_url();
+        app.headers.rows = self.headers;
+        app.headers.selected = 0;
+        app.body = self.body;
+        app.body_mode = BodyMode::Raw;
+    }
+}
+
+fn parse_error(message: &str) -> AppError {
+    AppError::Parse(format!("command line: {}", message))
+}
+
+fn parse_method(method: &str) -> Result<Method, AppError> {
+    Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| parse_error(&format!("unknown method {}", method)))
+}
+
+fn parse_count(count: &str) -> Result<usize, AppError> {
+    count
+        .parse()
+        .ok()
+        .filter(|count| *count > 0)
+        .ok_or_else(|| parse_error(&format!("not a count: {}", count)))
+}
+
+/// A body given as `@path` is read from that file, or from stdin for `@-`.
+fn read_body(body: String) -> Result<String, AppError> {
+    match body.strip_prefix('@') {
+        Some("-") => read_stdin().map_err(|e| parse_error(&format!("stdin: {}", e))),
+        Some(path) => fs::read_to_string(storage::expand_tilde(path))
+            .map_err(|e| parse_error(&format!("{}: {}", path, e))),
+        None => Ok(body),
+    }
+}
+
+/// Everything piped to stdin, or nothing if it's the terminal.
+pub fn read_stdin() -> io::Result<String> {
+    let mut stdin = io::stdin();
+    let mut text = String::new();
+    if !stdin.is_terminal() {
+        stdin.read_to_string(&mut text)?;
+    }
+    Ok(text)
+}
+
+/// Reads the arguments after the program name. A lone URL is sent as a GET,
+/// as is the request from the options when stdout is piped, and a request
+/// that can have a body but wasn't given one is sent with whatever is piped
+/// to stdin.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Invocation, AppError> {
+    let mut args = args.into_iter();
+    let mut request = Args::default();
+    let mut method = None;
+    let mut url = None;
+    let mut prefilled = false;
+    let mut has_body = false;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| parse_error(&format!("{} needs a value", arg)))
+        };
+        prefilled |= ![
+            "--raw",
+            "-i",
+            "--include",
+            "--dataset",
+            "--report",
+            "-q",
+            "--quiet",
+            "--update-snapshots",
+            "--port",
+            "-n",
+            "--requests",
+            "-c",
+            "--concurrency",
+        ]
+        .contains(&arg.as_str());
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Invocation::Help),
+            "--method" => method = Some(parse_method(&value()?)?),
+            "--url" => url = Some(value()?),
+            "-H" | "--header" => {
+                let header = value()?;
+                match header.split_once(':') {
+                    Some((name, value)) => request
+                        .headers
+                        .push(KeyValue::new(name.trim(), value.trim())),
+                    None => return Err(parse_error(&format!("malformed header {}", header))),
+                }
+            }
+            "-d" | "--data" | "--body" => {
+                request.body = read_body(value()?)?;
+                has_body = true;
+            }
+            "-i" | "--include" => request.include = true,
+            "--raw" => request.raw = true,
+            "-q" | "--quiet" => request.quiet = true,
+            "--update-snapshots" => request.update_snapshots = true,
+            "--port" => {
+                let port = value()?;
+                request.port = Some(
+                    port.parse()
+                        .map_err(|_| parse_error(&format!("not a port: {}", port)))?,
+                )
+            }
+            "-n" | "--requests" => request.requests = Some(parse_count(&value()?)?),
+            "-c" | "--concurrency" => request.concurrency = Some(parse_count(&value()?)?),
+            "--dataset" => request.dataset = Some(storage::expand_tilde(&value()?)),
+            "--report" => {
+                request.report = Some(Report::parse(&value()?).map_err(|e| parse_error(&e))?)
+            }
+            _ if arg.starts_with('-') => {
+                return Err(parse_error(&format!("unknown option {}", arg)))
+            }
+            _ => positional.push(arg),
+        }
+    }
+    if positional.first().map(String::as_str) == Some("run") {
+        return match positional.as_slice() {
+            [_, path] => Ok(Invocation::Run(storage::expand_tilde(path), request)),
+            _ => Err(parse_error(
+                "run needs a .http or .hurl file, or a collection",
+            )),
+        };
+    }
+    if positional.first().map(String::as_str) == Some("mock") {
+        return match positional.as_slice() {
+            [_, source] => Ok(Invocation::Mock(storage::expand_tilde(source), request)),
+            _ => Err(parse_error("mock needs a collection or an OpenAPI file")),
+        };
+    }
+    let bench = positional.first().map(String::as_str) == Some("bench");
+    if bench {
+        positional.remove(0);
+    }
+    match positional.as_slice() {
+        [] => {}
+        [only] => url = Some(only.clone()),
+        [first, second] => {
+            method = Some(parse_method(first)?);
+            url = Some(second.clone());
+        }
+        [_, _, extra, ..] => return Err(parse_error(&format!("unexpected {}", extra))),
+    }
+    request.method = method.unwrap_or(Method::GET);
+    request.url = url.unwrap_or_default();
+    // Without a terminal to draw on, the TUI would only garble the output
+    if !bench && positional.is_empty() && io::stdout().is_terminal() {
+        return Ok(Invocation::Tui(prefilled.then_some(request)));
+    }
+    if request.url.is_empty() {
+        return Err(parse_error(
+            "no URL given, and stdout isn't a terminal for the TUI",
+        ));
+    }
+    if !has_body && request::method_has_body(&request.method) {
+        request.body = read_body(String::from("@-"))?;
+    }
+    if bench {
+        return Ok(Invocation::Bench(request));
+    }
+    Ok(Invocation::Send(request))
+}
+
+/// Sends the request in `app`'s editors, passing on to stderr any warnings
+/// or errors it logs, e.g. retries, which would otherwise have gone to the
+/// Logs pane.
+async fn send(app: &mut App) -> Result<ResponseData, AppError> {
+    let logged = app.logs.len();
+    let result = app.send_and_wait().await;
+    for entry in app.logs.iter().skip(logged) {
+        if entry.level != Level::Info {
+            eprintln!("{}", entry.message);
+        }
+    }
+    result
+}
+
+/// The body, formatted unless `raw` is set, after the status line and
+/// headers if `include` is. The raw body is its bytes as received, without
+/// a newline added.
+fn output(response: &ResponseData, include: bool, raw: bool) -> Vec<u8> {
+    let mut output = String::new();
+    if include {
+        output += &format!("{:?} {}\n", response.version, response.status);
+        for line in response.header_lines() {
+            output += &line;
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+    if raw && response.filter.is_none() {
+        return [output.into_bytes(), response.bytes.clone()].concat();
+    }
+    output += response.text(raw).as_str();
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output.into_bytes()
+}
+
+fn print(output: impl AsRef<[u8]>) -> Result<(), AppError> {
+    match io::stdout().lock().write_all(output.as_ref()) {
+        // Whatever it was piped to has seen all it wanted, e.g. `head`
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Sends the request with `app`'s config, environment and cookies, and
+/// prints the response body to stdout, after its headers with `--include`.
+pub async fn run(mut app: App, args: Args) -> Result<(), AppError> {
+    let (include, raw, quiet) = (args.include, args.raw, args.quiet);
+    args.fill(&mut app);
+    let response = send(&mut app).await?;
+    if quiet {
+        return Ok(());
+    }
+    print(output(&response, include, raw))
+}
+
+/// Sends the request `--requests` times, `--concurrency` at once, with
+/// `app`'s config and environment, and prints how long the responses took.
+/// Returns the exit status: whether every request got a response.
+pub async fn bench(mut app: App, args: Args) -> Result<i32, AppError> {
+    let requests = args.requests.unwrap_or(bench::DEFAULT_REQUESTS);
+    let concurrency = args.concurrency.unwrap_or(bench::DEFAULT_CONCURRENCY);
+    let quiet = args.quiet;
+    args.fill(&mut app);
+    let request = app.prepare_bench()?;
+    if !quiet {
+        print(format!(
+            "Benchmarking {} {}: {} requests, {} at a time\n",
+            request.method,
+            request.full_url(),
+            requests,
+            concurrency
+        ))?;
+    }
+    let stats = bench::run(&request, requests, concurrency, &AtomicUsize::new(0)).await?;
+    if !quiet || stats.failed() > 0 {
+        print(&(stats.lines().join("\n") + "\n"))?;
+    }
+    Ok(if stats.failed() > 0 { EXIT_ERROR } else { 0 })
+}
+
+/// Makes the file's variables available to its requests, over those of the
+/// active environment. Values may use the environment's variables.
+fn use_variables(app: &mut App, name: &str, variables: BTreeMap<String, String>) {
+    let mut environment = app.environments.active().cloned().unwrap_or_default();
+    for (key, value) in variables {
+        let value = app.environments.interpolate(&value);
+        environment.variables.insert(key, value);
+    }
+    environment.name = String::from(name);
+    app.environments.environments.push(environment);
+    app.environments.active = Some(String::from(name));
+}
+
+/// What `run` goes through: a file's requests, or a saved collection's.
+enum Runnable {
+    Http(HttpFile),
+    Hurl(Vec<Entry>),
+    /// Checked as Hurl entries are, by their assertions.
+    Collection(Vec<Entry>),
+}
+
+/// A `.http` or `.hurl` file at `path`, or else the saved collection of
+/// that name.
+fn runnable(app: &App, path: &Path) -> Result<Runnable, AppError> {
+    if hurl::detect(path) {
+        return Ok(Runnable::Hurl(hurl::load(path)?));
+    }
+    if path.exists() {
+        return Ok(Runnable::Http(HttpFile::load(path)?));
+    }
+    let collection = saved_collection(app, path)?;
+    let entries = collection
+        .requests
+        .iter()
+        .map(|saved| {
+            let mut request = saved.clone();
+            // As in the runner popup, an error status fails a request
+            // that has no assertions of its own
+            if request.assertions.is_empty() {
+                request.assertions.push(String::from("status < 400"));
+            }
+            Entry {
+                request,
+                captures: Vec::new(),
+            }
+        })
+        .collect();
+    Ok(Runnable::Collection(entries))
+}
+
+/// The saved collection named `path`, given where a file could have been.
+fn saved_collection<'a>(app: &'a App, path: &Path) -> Result<&'a Collection, AppError> {
+    let name = path.to_string_lossy();
+    app.collections
+        .collections
+        .iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| parse_error(&format!("no file or saved collection called {}", name)))
+}
+
+/// Sends each request in a `.http` or `.hurl` file, or a saved collection,
+/// in turn, for each row of the `--dataset` file if there is one, then
+/// writes the `--report` if asked to. Returns the exit status: whether they
+/// were all sent, and for Hurl and collections whether their assertions all
+/// held. Those that didn't are reported and the rest still run.
+pub async fn run_file(mut app: App, path: &Path, args: Args) -> Result<i32, AppError> {
+    let runnable = runnable(&app, path)?;
+    let rows = match &args.dataset {
+        Some(path) => dataset::load(path)?,
+        None => vec![Row::new()],
+    };
+    let name = path.display().to_string();
+    let environments = app.environments.clone();
+    let mut cases = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let mut variables = match &runnable {
+            Runnable::Http(file) => file.variables.clone(),
+            _ => Row::new(),
+        };
+        variables.extend(row.clone());
+        let row = start_row(
+            &mut app,
+            &args,
+            &environments,
+            &name,
+            (i, rows.len()),
+            &variables,
+        )?;
+        cases.extend(match &runnable {
+            Runnable::Http(file) => run_http_file(&mut app, file, &args, &row).await?,
+            Runnable::Hurl(entries) | Runnable::Collection(entries) => {
+                run_hurl(&mut app, entries, &row, &args).await?
+            }
+        });
+    }
+    if let Some(report) = &args.report {
+        report.write(&name, &cases)?;
+    }
+    Ok(if cases.iter().any(|case| case.error.is_some()) {
+        EXIT_ERROR
+    } else if cases.iter().any(|case| !case.passed()) {
+        EXIT_FAILED
+    } else {
+        0
+    })
+}
+
+/// Goes back to `environments` with `variables` over the active one, so
+/// nothing carries over from the row before, e.g. Hurl captures, and says
+/// which row of how many it is if there's a dataset. Returns what to put
+/// after the names of the row's requests in reports.
+fn start_row(
+    app: &mut App,
+    args: &Args,
+    environments: &Environments,
+    name: &str,
+    (i, count): (usize, usize),
+    variables: &Row,
+) -> Result<String, AppError> {
+    app.environments = environments.clone();
+    use_variables(app, name, variables.clone());
+    if args.dataset.is_none() {
+        return Ok(String::new());
+    }
+    if !args.quiet {
+        print(format!("Row {} of {}\n", i + 1, count))?;
+    }
+    Ok(format!(" (row {})", i + 1))
+}
+
+/// Prints each request's status line and response, unless `--quiet`.
+async fn run_http_file(
+    app: &mut App,
+    file: &HttpFile,
+    args: &Args,
+    row: &str,
+) -> Result<Vec<Case>, AppError> {
+    let mut cases = Vec::new();
+    for saved in &file.requests {
+        let label = format!("{} {}", saved.method, saved.url);
+        app.load_saved(saved.clone());
+        let mut case = Case {
+            name: format!("{}{}", saved.name, row),
+            elapsed: Duration::ZERO,
+            failures: Vec::new(),
+            error: None,
+        };
+        match send(app).await {
+            Ok(response) if args.quiet => case.elapsed = response.elapsed,
+            Ok(response) => {
+                case.elapsed = response.elapsed;
+                print(format!("{}\n{}\n", label, response.status_line()))?;
+                print(output(&response, args.include, args.raw))?;
+                print("\n")?
+            }
+            Err(e) => {
+                eprintln!("{}: {}\n", label, e);
+                case.error = Some(e.to_string());
+            }
+        }
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+/// Checks a response against an entry's asserts, and sets the variables it
+/// captures. Returns what failed. With `update_snapshots`, the response is
+/// stored as the snapshot of each snapshot assert first.
+fn check_entry(
+    app: &mut App,
+    entry: &Entry,
+    response: &ResponseData,
+    update_snapshots: bool,
+) -> Vec<String> {
+    let asserts: Vec<String> = entry
+        .request
+        .assertions
+        .iter()
+        .map(|text| app.environments.interpolate(text))
+        .collect();
+    let mut failures = Vec::new();
+    if update_snapshots {
+        if let Err(e) = assertion::update_snapshots(&asserts, response) {
+            failures.push(format!("Could not save snapshot: {}", e));
+        }
+    }
+    failures.extend(
+        assertion::check_each(&asserts, response)
+            .into_iter()
+            .filter_map(Result::err),
+    );
+    for (name, query) in &entry.captures {
+        let value = Query::parse(&app.environments.interpolate(query))
+            .map(|(query, _)| query.value(response));
+        match value {
+            Ok(Some(value)) => {
+                let text = match value {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                if let Some(env) = app.environments.environments.last_mut() {
+                    env.variables.insert(name.clone(), text);
+                }
+            }
+            Ok(None) => failures.push(format!("capture {}: nothing found", name)),
+            Err(e) => failures.push(format!("capture {}: {}", name, e)),
+        }
+    }
+    failures
+}
+
+/// Sends each entry of a Hurl file or collection, checking its asserts and
+/// passing its captures on to the entries after it, then prints a line per
+/// entry and a summary. `quiet` leaves out all but the entries that failed.
+async fn run_hurl(
+    app: &mut App,
+    entries: &[Entry],
+    row: &str,
+    args: &Args,
+) -> Result<Vec<Case>, AppError> {
+    let mut report = String::new();
+    let mut cases = Vec::new();
+    for entry in entries {
+        let request = &entry.request;
+        let mut label = format!("{} {}", request.method, request.url);
+        if request.name != label {
+            label = format!("{}  {}", request.name, label);
+        }
+        label += row;
+        app.load_saved(request.clone());
+        let mut case = Case {
+            name: format!("{}{}", request.name, row),
+            elapsed: Duration::ZERO,
+            failures: Vec::new(),
+            error: None,
+        };
+        match send(app).await {
+            Ok(response) => {
+                case.elapsed = response.elapsed;
+                case.failures = check_entry(app, entry, &response, args.update_snapshots);
+                report += &format!(
+                    "{} {}  ({} ms)\n",
+                    if case.passed() { "PASS" } else { "FAIL" },
+                    label,
+                    response.elapsed.as_millis()
+                );
+                for failure in &case.failures {
+                    report += &format!("    {}\n", failure);
+                }
+            }
+            Err(e) => {
+                report += &format!("FAIL {}\n    {}\n", label, e);
+                case.error = Some(e.to_string());
+            }
+        };
+        if !case.passed() {
+            // Later entries may need what this one should have captured
+            print(&report)?;
+        }
+        if !case.passed() || args.quiet {
+            report.clear();
+        }
+        cases.push(case);
+    }
+    if args.quiet {
+        return Ok(cases);
+    }
+    let failed = cases.iter().filter(|case| !case.passed()).count();
+    report += &format!(
+        "{} requests: {} passed, {} failed\n",
+        cases.len(),
+        cases.len() - failed,
+        failed
+    );
+    print(&report)?;
+    Ok(cases)
+}
+
+/// Serves `source`, an OpenAPI file or a saved collection, as a mock until
+/// the process is stopped, printing a line for each request it gets.
+pub async fn mock(app: App, source: &Path, args: Args) -> Result<(), AppError> {
+    let mock = if source.exists() {
+        let contents = fs::read_to_string(source)?;
+        let spec: Value = serde_json::from_str(&contents)
+            .or_else(|_| serde_yaml::from_str(&contents))
+            .map_err(|e| AppError::Parse(format!("{}: {}", source.display(), e)))?;
+        if !openapi::detect(&spec) {
+            return Err(AppError::Parse(format!(
+                "{}: not an OpenAPI document",
+                source.display()
+            )));
+        }
+        Mock::from_spec(&spec)
+    } else {
+        Mock::from_collection(saved_collection(&app, source)?)
+    };
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let quiet = args.quiet;
+    tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            if let Message::Log(level, line) = message {
+                if !quiet || level != Level::Info {
+                    let _ = print(format!("{}\n", line));
+                }
+            }
+        }
+    });
+    mock::serve(Arc::new(mock), args.port.unwrap_or(mock::DEFAULT_PORT), tx).await
+}
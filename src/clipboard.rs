@@ -0,0 +1,29 @@
+use crate::error::AppError;
+
+/// The system clipboard, opened on first use.
+///
+/// The handle is kept for the life of the app because on X11 copied text is
+/// only available while the clipboard that set it is still alive.
+#[derive(Default)]
+pub struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    fn handle(&mut self) -> Result<&mut arboard::Clipboard, AppError> {
+        let clipboard = match self.inner.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new()?,
+        };
+        Ok(self.inner.insert(clipboard))
+    }
+
+    pub fn copy(&mut self, text: &str) -> Result<(), AppError> {
+        self.handle()?.set_text(text)?;
+        Ok(())
+    }
+
+    pub fn paste(&mut self) -> Result<String, AppError> {
+        Ok(self.handle()?.get_text()?)
+    }
+}
@@ -0,0 +1,405 @@
+use crate::auth::Auth;
+use crate::body::BodyMode;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::request::{self, Request};
+use crate::settings::Settings;
+use crate::storage;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Collection that requests saved without a `collection/` prefix go into.
+const DEFAULT_COLLECTION: &str = "Default";
+
+/// A request saved under a name, as stored in `collections.json`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SavedRequest {
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: Vec<KeyValue>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub body_mode: BodyMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<KeyValue>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub variables: String,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub settings: Settings,
+    /// Checked against every response to it, e.g. `status == 200`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<String>,
+    /// Script run before each send, e.g. to sign the request.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub pre_request: String,
+    /// Script run on each response, e.g. to capture a token.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub post_response: String,
+    /// Saved requests sent before this one, e.g. `Login`, so what their
+    /// scripts capture can be used here.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+}
+
+impl SavedRequest {
+    pub fn new(name: &str, request: &Request) -> SavedRequest {
+        SavedRequest {
+            name: String::from(name),
+            method: request.method.to_string(),
+            url: request.url.clone(),
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+            body_mode: request.body_mode,
+            fields: request.fields.clone(),
+            variables: request.variables.clone(),
+            auth: request.auth.clone(),
+            settings: request.settings.clone(),
+            assertions: request.assertions.clone(),
+            pre_request: request.pre_request.clone(),
+            post_response: request.post_response.clone(),
+            requires: request.requires.clone(),
+        }
+    }
+
+    /// The request ready to send as saved, without going through the editors.
+    pub fn request(&self) -> Result<Request, AppError> {
+        let method = Method::from_bytes(self.method.as_bytes()).map_err(|_| {
+            AppError::Parse(format!("{}: unknown method {}", self.name, self.method))
+        })?;
+        let (_, query) = request::split_url(&self.url);
+        let active = |rows: &[KeyValue]| rows.iter().filter(|kv| kv.enabled).cloned().collect();
+        Ok(Request {
+            method,
+            url: self.url.clone(),
+            params: request::parse_query(query.unwrap_or("")),
+            headers: active(&self.headers),
+            body: self.body.clone(),
+            body_mode: self.body_mode,
+            fields: active(&self.fields),
+            variables: self.variables.clone(),
+            auth: self.auth.clone(),
+            settings: self.settings.clone(),
+            assertions: self.assertions.clone(),
+            pre_request: self.pre_request.clone(),
+            post_response: self.post_response.clone(),
+            requires: self.requires.clone(),
+            cookie_jar: None,
+            download: None,
+            events: None,
+        })
+    }
+}
+
+/// A named group of saved requests.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Collection {
+    pub name: String,
+    #[serde(default)]
+    pub requests: Vec<SavedRequest>,
+}
+
+impl Collection {
+    /// Its request called `name`, with its own name.
+    fn named(&self, name: &str) -> Option<(&str, &SavedRequest)> {
+        let saved = self.requests.iter().find(|r| r.name == name)?;
+        Some((self.name.as_str(), saved))
+    }
+}
+
+/// One line of the sidebar: a collection, or a request inside one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Row {
+    Collection(usize),
+    Request(usize, usize),
+}
+
+/// All collections, persisted to the data directory, plus the sidebar selection.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Collections {
+    pub collections: Vec<Collection>,
+    /// Index into `rows()`.
+    #[serde(skip)]
+    pub selected: usize,
+    /// Requests marked to run at once, by index into the collection
+    /// `marked_in`. Dropped when requests are removed or replaced.
+    #[serde(skip)]
+    marked: BTreeSet<usize>,
+    #[serde(skip)]
+    marked_in: usize,
+}
+
+impl Collections {
+    fn path() -> PathBuf {
+        storage::data_dir().join("collections.json")
+    }
+
+    pub fn load() -> Result<Collections, AppError> {
+        storage::load_json(&Collections::path())
+    }
+
+    fn store(&self) -> Result<(), AppError> {
+        storage::save_json(&Collections::path(), self)
+    }
+
+    /// The sidebar lines in display order, each collection followed by its requests.
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (c, collection) in self.collections.iter().enumerate() {
+            rows.push(Row::Collection(c));
+            rows.extend((0..collection.requests.len()).map(|r| Row::Request(c, r)));
+        }
+        rows
+    }
+
+    pub fn next(&mut self) {
+        let count = self.rows().len();
+        if self.selected + 1 < count {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Moves the sidebar selection onto `row`, if it's there.
+    pub fn select(&mut self, row: Row) {
+        if let Some(i) = self.rows().iter().position(|r| *r == row) {
+            self.selected = i;
+        }
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.rows().get(self.selected).copied()
+    }
+
+    /// The collection the selection is in, or is.
+    pub fn containing_selection(&self) -> Option<&Collection> {
+        let c = match self.selected_row()? {
+            Row::Collection(c) | Row::Request(c, _) => c,
+        };
+        Some(&self.collections[c])
+    }
+
+    /// Name of the collection the selection is in, used to prefill the save prompt.
+    pub fn selected_collection(&self) -> Option<&str> {
+        Some(self.containing_selection()?.name.as_str())
+    }
+
+    pub fn selected_request(&self) -> Option<&SavedRequest> {
+        match self.selected_row()? {
+            Row::Collection(_) => None,
+            Row::Request(c, r) => Some(&self.collections[c].requests[r]),
+        }
+    }
+
+    /// Marks the selected request to be run with the others marked, or
+    /// unmarks it. Marking a request of another collection unmarks those
+    /// of the last one.
+    pub fn toggle_mark(&mut self) {
+        let Some(Row::Request(c, r)) = self.selected_row() else {
+            return;
+        };
+        if c != self.marked_in {
+            self.marked.clear();
+            self.marked_in = c;
+        }
+        if !self.marked.remove(&r) {
+            self.marked.insert(r);
+        }
+    }
+
+    pub fn is_marked(&self, row: Row) -> bool {
+        matches!(row, Row::Request(c, r) if c == self.marked_in && self.marked.contains(&r))
+    }
+
+    /// The collection the selection is in and its marked requests, if any
+    /// are.
+    pub fn marked_requests(&self) -> Option<(&Collection, Vec<(usize, &SavedRequest)>)> {
+        let (Row::Collection(c) | Row::Request(c, _)) = self.selected_row()?;
+        if c != self.marked_in {
+            return None;
+        }
+        let collection = &self.collections[c];
+        let requests: Vec<_> = self
+            .marked
+            .iter()
+            .filter_map(|&r| Some((r, collection.requests.get(r)?)))
+            .collect();
+        (!requests.is_empty()).then_some((collection, requests))
+    }
+
+    /// The saved requests `requires` names and those they require in turn,
+    /// in the order to send them: each once, after what it requires. A name
+    /// is `collection/name`, or just a name, looked for in the collection
+    /// `near` first and then in all of them. Each comes with its
+    /// `collection/name`.
+    pub fn prerequisites(
+        &self,
+        requires: &[String],
+        near: Option<&str>,
+    ) -> Result<Vec<(String, &SavedRequest)>, AppError> {
+        let mut order = Vec::new();
+        let mut visiting = Vec::new();
+        for name in requires {
+            self.visit(name, near, &mut visiting, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        name: &str,
+        near: Option<&str>,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<(String, &'a SavedRequest)>,
+    ) -> Result<(), AppError> {
+        let (collection, saved) = self
+            .find(name, near)
+            .ok_or_else(|| AppError::Prerequisite(format!("no saved request called {}", name)))?;
+        let key = format!("{}/{}", collection, saved.name);
+        if order.iter().any(|(done, _)| *done == key) {
+            return Ok(());
+        }
+        if visiting.contains(&key) {
+            return Err(AppError::Prerequisite(format!(
+                "{} ends up requiring itself",
+                key
+            )));
+        }
+        visiting.push(key.clone());
+        for required in &saved.requires {
+            self.visit(required, Some(collection), visiting, order)?;
+        }
+        visiting.pop();
+        order.push((key, saved));
+        Ok(())
+    }
+
+    /// The request `name` refers to and its collection's name.
+    fn find(&self, name: &str, near: Option<&str>) -> Option<(&str, &SavedRequest)> {
+        let (collection, name) = match name.split_once('/') {
+            Some((collection, name)) => (Some(collection.trim()), name.trim()),
+            None => (None, name.trim()),
+        };
+        let nearby = collection.or(near).and_then(|wanted| {
+            self.collections
+                .iter()
+                .filter(|c| c.name == wanted)
+                .find_map(|c| c.named(name))
+        });
+        match collection {
+            Some(_) => nearby,
+            None => nearby.or_else(|| self.collections.iter().find_map(|c| c.named(name))),
+        }
+    }
+
+    /// Saves `request` under `path`, a name optionally prefixed with
+    /// `collection/`. A request already saved under that name is replaced.
+    pub fn save(&mut self, path: &str, request: &Request) -> Result<(), AppError> {
+        let (collection, name) = match path.split_once('/') {
+            Some((collection, name)) => (collection.trim(), name.trim()),
+            None => (DEFAULT_COLLECTION, path.trim()),
+        };
+        let collection = if collection.is_empty() {
+            DEFAULT_COLLECTION
+        } else {
+            collection
+        };
+        let c = match self.collections.iter().position(|c| c.name == collection) {
+            Some(c) => c,
+            None => {
+                self.collections.push(Collection {
+                    name: String::from(collection),
+                    requests: Vec::new(),
+                });
+                self.collections.len() - 1
+            }
+        };
+        let saved = SavedRequest::new(name, request);
+        let requests = &mut self.collections[c].requests;
+        let r = match requests.iter().position(|r| r.name == name) {
+            Some(r) => {
+                requests[r] = saved;
+                r
+            }
+            None => {
+                requests.push(saved);
+                requests.len() - 1
+            }
+        };
+        self.selected = self
+            .rows()
+            .iter()
+            .position(|row| *row == Row::Request(c, r))
+            .unwrap_or(0);
+        self.store()
+    }
+
+    /// Adds `saved` to the end of `collection`, creating it if need be,
+    /// without moving the selection. A number is put after its name if
+    /// the collection already has a request called that.
+    pub fn append(&mut self, collection: &str, mut saved: SavedRequest) -> Result<(), AppError> {
+        let c = match self.collections.iter().position(|c| c.name == collection) {
+            Some(c) => c,
+            None => {
+                self.collections.push(Collection {
+                    name: String::from(collection),
+                    requests: Vec::new(),
+                });
+                self.collections.len() - 1
+            }
+        };
+        let requests = &mut self.collections[c].requests;
+        let name = saved.name.clone();
+        let mut n = 1;
+        while requests.iter().any(|r| r.name == saved.name) {
+            n += 1;
+            saved.name = format!("{} ({})", name, n);
+        }
+        requests.push(saved);
+        self.store()
+    }
+
+    /// Adds an imported collection, replacing any existing one of the same name.
+    pub fn add(&mut self, collection: Collection) -> Result<(), AppError> {
+        match self
+            .collections
+            .iter_mut()
+            .find(|c| c.name == collection.name)
+        {
+            Some(existing) => {
+                *existing = collection;
+                self.marked.clear();
+            }
+            None => self.collections.push(collection),
+        }
+        self.store()
+    }
+
+    /// Deletes the selected request, or the whole collection if that is selected.
+    pub fn remove_selected(&mut self) -> Result<(), AppError> {
+        match self.selected_row() {
+            Some(Row::Collection(c)) => {
+                self.collections.remove(c);
+            }
+            Some(Row::Request(c, r)) => {
+                self.collections[c].requests.remove(r);
+            }
+            None => return Ok(()),
+        }
+        self.marked.clear();
+        let count = self.rows().len();
+        if self.selected >= count {
+            self.selected = count.saturating_sub(1);
+        }
+        self.store()
+    }
+}
@@ -0,0 +1,115 @@
+use crate::bench;
+use crate::mock;
+use crate::monitor;
+use crate::proxy;
+use std::time::Duration;
+
+/// An action typed on the `:` command line, as in vim.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// `:send`
+    Send,
+    /// `:save name`, saving the request to a collection.
+    Save(String),
+    /// `:env name`, or `:env` alone to stop using environments.
+    Env(Option<String>),
+    /// `:run`, running the collection selected in the sidebar, or `:run
+    /// file` to run it once per row of a CSV or JSON file.
+    Run(Option<String>),
+    /// `:requires Login, Users/Create`, naming saved requests to send before
+    /// this one, or `:requires` alone for none.
+    Requires(Vec<String>),
+    /// `:mock 8080`, serving the collection selected in the sidebar on that
+    /// port, `mock::DEFAULT_PORT` if none is given, or `:mock off` to stop.
+    Mock(Option<u16>),
+    /// `:proxy 8888`, recording what goes through a proxy on that port,
+    /// `proxy::DEFAULT_PORT` if none is given, or `:proxy off` to stop.
+    Proxy(Option<u16>),
+    /// `:monitor 10`, sending the request every that many seconds,
+    /// `monitor::DEFAULT_INTERVAL` if none is given, or `:monitor off` to
+    /// stop.
+    Monitor(Option<Duration>),
+    /// `:bench 200 20`, sending the request 200 times with 20 in flight at
+    /// once, `bench::DEFAULT_REQUESTS` and `bench::DEFAULT_CONCURRENCY` for
+    /// the numbers not given.
+    Bench(usize, usize),
+    /// `:filter .items[0].name`, showing only that part of the JSON
+    /// response, or `:filter //item/name` for XML, or `:filter` alone to
+    /// show all of it again.
+    Filter(Option<String>),
+    /// `:tabnew`
+    TabNew,
+    /// `:layout`, moving to the next layout preset.
+    Layout,
+    /// `:theme`, moving to the next color theme.
+    Theme,
+    /// `:q` or `:quit`
+    Quit,
+}
+
+impl Command {
+    /// Reads a command line, without its `:`. The error says what was wrong
+    /// with it, for the logs.
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        match name {
+            "send" => Ok(Command::Send),
+            "save" | "w" if !argument.is_empty() => Ok(Command::Save(String::from(argument))),
+            "save" | "w" => Err(String::from("A name is needed to save the request")),
+            "env" if argument.is_empty() => Ok(Command::Env(None)),
+            "env" => Ok(Command::Env(Some(String::from(argument)))),
+            "run" if argument.is_empty() => Ok(Command::Run(None)),
+            "run" => Ok(Command::Run(Some(String::from(argument)))),
+            "requires" => Ok(Command::Requires(
+                argument
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect(),
+            )),
+            "mock" if argument.is_empty() => Ok(Command::Mock(Some(mock::DEFAULT_PORT))),
+            "mock" if argument == "off" => Ok(Command::Mock(None)),
+            "mock" => match argument.parse() {
+                Ok(port) => Ok(Command::Mock(Some(port))),
+                Err(_) => Err(format!("Not a port to mock on: {}", argument)),
+            },
+            "proxy" if argument.is_empty() => Ok(Command::Proxy(Some(proxy::DEFAULT_PORT))),
+            "proxy" if argument == "off" => Ok(Command::Proxy(None)),
+            "proxy" => match argument.parse() {
+                Ok(port) => Ok(Command::Proxy(Some(port))),
+                Err(_) => Err(format!("Not a port to proxy on: {}", argument)),
+            },
+            "monitor" if argument.is_empty() => {
+                Ok(Command::Monitor(Some(monitor::DEFAULT_INTERVAL)))
+            }
+            "monitor" if argument == "off" => Ok(Command::Monitor(None)),
+            "monitor" => match argument.parse().map(Duration::try_from_secs_f64) {
+                Ok(Ok(interval)) if !interval.is_zero() => Ok(Command::Monitor(Some(interval))),
+                _ => Err(format!("Not a number of seconds: {}", argument)),
+            },
+            "bench" => {
+                let mut numbers = argument.split_whitespace().map(|n| match n.parse() {
+                    Ok(n) if n > 0 => Ok(n),
+                    _ => Err(format!("Not a number to benchmark with: {}", n)),
+                });
+                let requests = numbers.next().unwrap_or(Ok(bench::DEFAULT_REQUESTS))?;
+                let concurrency = numbers.next().unwrap_or(Ok(bench::DEFAULT_CONCURRENCY))?;
+                match numbers.next() {
+                    Some(_) => Err(String::from("bench takes a count and a concurrency")),
+                    None => Ok(Command::Bench(requests, concurrency)),
+                }
+            }
+            "filter" if argument.is_empty() => Ok(Command::Filter(None)),
+            "filter" => Ok(Command::Filter(Some(String::from(argument)))),
+            "tabnew" => Ok(Command::TabNew),
+            "layout" => Ok(Command::Layout),
+            "theme" => Ok(Command::Theme),
+            "q" | "quit" => Ok(Command::Quit),
+            "" => Err(String::from("No command given")),
+            _ => Err(format!("Unknown command :{}", name)),
+        }
+    }
+}
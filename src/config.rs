@@ -0,0 +1,51 @@
+use crate::error::AppError;
+use crate::layout::Preset;
+use crate::notify::Notification;
+use crate::settings::Settings;
+use crate::storage;
+use crate::theme::ThemeName;
+use serde::Deserialize;
+
+/// User settings, read from `config.json` in the config directory.
+/// Every field has a default, so the file only needs the ones being changed.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Color JSON responses by token type.
+    pub syntax_highlighting: bool,
+    /// How the panes are arranged at startup.
+    pub layout: Preset,
+    /// The colors the panes are drawn with.
+    pub theme: ThemeName,
+    /// Methods that ask "are you sure?" before being sent anywhere but
+    /// this machine.
+    pub confirm_methods: Vec<String>,
+    /// How to say a request has finished: `none`, `bell` or `desktop`.
+    pub notify: Notification,
+    /// Only requests taking at least this many seconds notify.
+    pub notify_after_secs: f64,
+    /// Connection settings for every request, unless an environment or the
+    /// request itself overrides them.
+    pub settings: Settings,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            syntax_highlighting: true,
+            layout: Preset::default(),
+            theme: ThemeName::default(),
+            confirm_methods: vec![String::from("DELETE")],
+            notify: Notification::default(),
+            notify_after_secs: 5.0,
+            settings: Settings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file, falling back to defaults when there isn't one.
+    pub fn load() -> Result<Config, AppError> {
+        storage::load_json(&storage::config_dir().join("config.json"))
+    }
+}
@@ -0,0 +1,206 @@
+use crate::error::AppError;
+use crate::storage;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::{cookie::CookieStore, header::HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A cookie a server set, as stored in `cookies.json`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// Sent to `domain` only and not its subdomains, as the server gave no `Domain`.
+    #[serde(default)]
+    pub host_only: bool,
+    pub path: String,
+    /// Missing for session cookies, which are kept until deleted.
+    #[serde(default)]
+    pub expires: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+}
+
+impl Cookie {
+    /// Parses a `Set-Cookie` value received from `url`.
+    pub fn parse(header: &str, url: &url::Url) -> Option<Cookie> {
+        let mut parts = header.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        // Without a Path the cookie applies to the directory of the request path
+        let default_path = match url.path().rfind('/') {
+            Some(0) | None => String::from("/"),
+            Some(i) => String::from(&url.path()[..i]),
+        };
+        let mut cookie = Cookie {
+            name: String::from(name.trim()),
+            value: String::from(value.trim().trim_matches('"')),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path,
+            expires: None,
+            secure: false,
+            http_only: false,
+        };
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    // A server can't set cookies for a domain it isn't part of
+                    if host != domain && !host.ends_with(&format!(".{}", domain)) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = String::from(value),
+                "expires" => {
+                    cookie.expires = DateTime::parse_from_rfc2822(value)
+                        .ok()
+                        .map(|at| at.with_timezone(&Utc));
+                }
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+        // Max-Age wins over Expires
+        if let Some(seconds) = max_age {
+            cookie.expires = Some(Utc::now() + Duration::seconds(seconds));
+        }
+        Some(cookie)
+    }
+
+    pub fn expired(&self) -> bool {
+        self.expires.is_some_and(|at| at <= Utc::now())
+    }
+
+    fn matches(&self, url: &url::Url) -> bool {
+        let host = url.host_str().unwrap_or("").to_ascii_lowercase();
+        let domain_matches = host == self.domain
+            || (!self.host_only && host.ends_with(&format!(".{}", self.domain)));
+        let path = url.path();
+        let path_matches = path == self.path
+            || (path.starts_with(&self.path)
+                && (self.path.ends_with('/') || path[self.path.len()..].starts_with('/')));
+        domain_matches
+            && path_matches
+            && (!self.secure || url.scheme() == "https")
+            && !self.expired()
+    }
+
+    fn same_slot(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+
+    /// e.g. `example.com/  session=abc123  (until 2024-05-01 13:37)`
+    pub fn summary(&self) -> String {
+        let expires = self.expires.map_or_else(
+            || String::from("session"),
+            |at| format!("until {}", at.format("%Y-%m-%d %H:%M")),
+        );
+        format!(
+            "{}{}  {}={}  ({})",
+            self.domain, self.path, self.name, self.value, expires
+        )
+    }
+}
+
+/// Every cookie received, kept sorted by domain and name and persisted to the
+/// data directory. Clients built for each request share it through `Arc`.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: Mutex<Vec<Cookie>>,
+}
+
+impl CookieJar {
+    fn path() -> PathBuf {
+        storage::data_dir().join("cookies.json")
+    }
+
+    pub fn load() -> Result<CookieJar, AppError> {
+        let mut cookies: Vec<Cookie> = storage::load_json(&CookieJar::path())?;
+        cookies.retain(|c| !c.expired());
+        Ok(CookieJar {
+            cookies: Mutex::new(cookies),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Cookie>> {
+        // A panic while holding the lock can't leave the list half-changed
+        self.cookies.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        storage::save_json(&CookieJar::path(), &*self.lock())
+    }
+
+    /// A copy of the cookies, in display order.
+    pub fn all(&self) -> Vec<Cookie> {
+        self.lock().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn set_value(&self, i: usize, value: &str) -> Result<(), AppError> {
+        if let Some(cookie) = self.lock().get_mut(i) {
+            cookie.value = String::from(value);
+        }
+        self.save()
+    }
+
+    pub fn remove(&self, i: usize) -> Result<(), AppError> {
+        {
+            let mut cookies = self.lock();
+            if i < cookies.len() {
+                cookies.remove(i);
+            }
+        }
+        self.save()
+    }
+
+    /// Adds or replaces `cookie`; an already expired one deletes it instead.
+    fn store(&self, cookie: Cookie) {
+        let mut cookies = self.lock();
+        cookies.retain(|c| !c.same_slot(&cookie) && !c.expired());
+        if !cookie.expired() {
+            cookies.push(cookie);
+            cookies
+                .sort_by(|a, b| (&a.domain, &a.name, &a.path).cmp(&(&b.domain, &b.name, &b.path)));
+        }
+    }
+}
+
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &url::Url) {
+        for header in cookie_headers {
+            if let Some(cookie) = header.to_str().ok().and_then(|h| Cookie::parse(h, url)) {
+                self.store(cookie);
+            }
+        }
+    }
+
+    fn cookies(&self, url: &url::Url) -> Option<HeaderValue> {
+        let pairs: Vec<String> = self
+            .lock()
+            .iter()
+            .filter(|c| c.matches(url))
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        if pairs.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&pairs.join("; ")).ok()
+        }
+    }
+}
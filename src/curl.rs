@@ -0,0 +1,420 @@
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::request::Request;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Method;
+use url::form_urlencoded;
+
+/// Boundary used for the multipart body built from `-F` fields.
+const BOUNDARY: &str = "httrs-form-boundary";
+
+/// curl options that take a value but don't affect what is imported.
+const IGNORED_WITH_VALUE: [&str; 22] = [
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "-w",
+    "--write-out",
+    "--retry",
+    "-x",
+    "--proxy",
+    "--cacert",
+    "-E",
+    "--cert",
+    "--key",
+    "-c",
+    "--cookie-jar",
+    "-r",
+    "--range",
+    "--resolve",
+    "--max-redirs",
+    "-T",
+    "--upload-file",
+];
+
+/// Single-letter options that take a value, which may be glued on as in `-XPOST`.
+const SHORT_WITH_VALUE: &str = "XHduFAebomwxcErT";
+
+fn parse_error(message: &str) -> AppError {
+    AppError::Parse(format!("curl command: {}", message))
+}
+
+/// Splits a command line into words the way a POSIX shell would, handling
+/// single and double quotes, backslash escapes and line continuations.
+pub fn split_words(command: &str) -> Result<Vec<String>, AppError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(parse_error("unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // Inside double quotes a backslash only escapes these
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(parse_error("unterminated double quote")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(parse_error("unterminated double quote")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+                None => {}
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// `--data-urlencode` encodes the content after the first `=`, keeping the name.
+fn data_urlencode(value: &str) -> String {
+    let encode = |s: &str| -> String { form_urlencoded::byte_serialize(s.as_bytes()).collect() };
+    match value.split_once('=') {
+        Some(("", content)) => encode(content),
+        Some((name, content)) => format!("{}={}", name, encode(content)),
+        None => encode(value),
+    }
+}
+
+/// The Content-Type matching bodies built by `multipart_body`.
+pub fn multipart_content_type() -> String {
+    format!("multipart/form-data; boundary={}", BOUNDARY)
+}
+
+/// Builds a `multipart/form-data` body from `-F name=value` fields.
+/// `name=@path` file fields are kept as a reference to the path.
+pub fn multipart_body(fields: &[(String, String)]) -> String {
+    let mut body = String::new();
+    for (name, value) in fields {
+        body.push_str(&format!("--{}\r\n", BOUNDARY));
+        match value.strip_prefix('@') {
+            Some(path) => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\n<{}>\r\n",
+                name, path, path
+            )),
+            None => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                name, value
+            )),
+        }
+    }
+    body.push_str(&format!("--{}--\r\n", BOUNDARY));
+    body
+}
+
+/// Parses a `curl` command line into a request. Options that don't change
+/// the request itself, like `-s` or `-L`, are ignored.
+pub fn parse(command: &str) -> Result<Request, AppError> {
+    let mut words = split_words(command)?.into_iter().peekable();
+    if words.peek().map(String::as_str) == Some("curl") {
+        words.next();
+    }
+
+    let mut method: Option<Method> = None;
+    let mut url: Option<String> = None;
+    let mut headers: Vec<KeyValue> = Vec::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut form: Vec<(String, String)> = Vec::new();
+    let mut get = false;
+    let mut head = false;
+
+    while let Some(word) = words.next() {
+        // Split bundled short options such as `-sSL` or `-XPOST`
+        let (option, glued) = if word.starts_with('-') && !word.starts_with("--") && word.len() > 2
+        {
+            let letters = &word[1..];
+            let value_at = letters
+                .char_indices()
+                .find(|(_, c)| SHORT_WITH_VALUE.contains(*c));
+            // Of the flags before any value, the ones that matter are `-G` and `-I`
+            let flags = &letters[..value_at.map_or(letters.len(), |(i, _)| i)];
+            get |= flags.contains('G');
+            head |= flags.contains('I');
+            match value_at {
+                Some((i, c)) => {
+                    let rest = &letters[i + c.len_utf8()..];
+                    let value = if rest.is_empty() {
+                        None
+                    } else {
+                        Some(String::from(rest))
+                    };
+                    (format!("-{}", c), value)
+                }
+                None => continue,
+            }
+        } else {
+            (word, None)
+        };
+
+        let mut value = |name: &str| -> Result<String, AppError> {
+            match glued.clone().or_else(|| words.next()) {
+                Some(value) => Ok(value),
+                None => Err(parse_error(&format!("{} needs a value", name))),
+            }
+        };
+
+        match option.as_str() {
+            "-X" | "--request" => {
+                let name = value(&option)?;
+                let parsed = Method::from_bytes(name.to_ascii_uppercase().as_bytes())
+                    .map_err(|_| parse_error(&format!("unknown method {}", name)))?;
+                method = Some(parsed);
+            }
+            "-H" | "--header" => {
+                let header = value(&option)?;
+                match header.split_once(':') {
+                    Some((name, value)) => headers.push(KeyValue::new(name.trim(), value.trim())),
+                    None => return Err(parse_error(&format!("malformed header {}", header))),
+                }
+            }
+            "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => {
+                data.push(value(&option)?)
+            }
+            "--data-urlencode" => data.push(data_urlencode(&value(&option)?)),
+            "-F" | "--form" => {
+                let field = value(&option)?;
+                match field.split_once('=') {
+                    Some((name, value)) => form.push((String::from(name), String::from(value))),
+                    None => return Err(parse_error(&format!("malformed form field {}", field))),
+                }
+            }
+            "-u" | "--user" => {
+                let credentials = value(&option)?;
+                headers.push(KeyValue::new(
+                    "Authorization",
+                    &format!("Basic {}", STANDARD.encode(credentials)),
+                ));
+            }
+            "-A" | "--user-agent" => headers.push(KeyValue::new("User-Agent", &value(&option)?)),
+            "-e" | "--referer" => headers.push(KeyValue::new("Referer", &value(&option)?)),
+            "-b" | "--cookie" => headers.push(KeyValue::new("Cookie", &value(&option)?)),
+            "--url" => url = Some(value(&option)?),
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => head = true,
+            option if IGNORED_WITH_VALUE.contains(&option) => {
+                value(option)?;
+            }
+            option if option.starts_with('-') && option.len() > 1 => {}
+            _ => {
+                if url.is_some() {
+                    return Err(parse_error(&format!("unexpected argument {}", option)));
+                }
+                url = Some(option.clone());
+            }
+        }
+    }
+
+    let mut url = url.ok_or_else(|| parse_error("no URL given"))?;
+    let has_header = |headers: &[KeyValue], name: &str| {
+        headers.iter().any(|kv| kv.key.eq_ignore_ascii_case(name))
+    };
+
+    let mut body = String::new();
+    if !form.is_empty() {
+        body = multipart_body(&form);
+        if !has_header(&headers, "Content-Type") {
+            headers.push(KeyValue::new("Content-Type", &multipart_content_type()));
+        }
+    } else if !data.is_empty() {
+        let joined = data.join("&");
+        if get {
+            // `-G` sends the data as the query string instead
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&joined);
+        } else {
+            body = joined;
+            if !has_header(&headers, "Content-Type") {
+                headers.push(KeyValue::new(
+                    "Content-Type",
+                    "application/x-www-form-urlencoded",
+                ));
+            }
+        }
+    }
+
+    // `-X` wins; otherwise curl picks the method from the other options
+    let method = method.unwrap_or(if head {
+        Method::HEAD
+    } else if !body.is_empty() {
+        Method::POST
+    } else {
+        Method::GET
+    });
+
+    Ok(Request {
+        method,
+        url,
+        headers,
+        body,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+        request
+            .headers
+            .iter()
+            .find(|kv| kv.key == name)
+            .map(|kv| kv.value.as_str())
+    }
+
+    #[test]
+    fn splits_quoted_words() {
+        let words = split_words(r#"curl 'a b' "c \"d\"" e\ f"#).unwrap();
+        assert_eq!(words, ["curl", "a b", "c \"d\"", "e f"]);
+    }
+
+    #[test]
+    fn joins_line_continuations() {
+        let words = split_words("curl \\\n  -H 'A: b' \\\n  example.com").unwrap();
+        assert_eq!(words, ["curl", "-H", "A: b", "example.com"]);
+    }
+
+    #[test]
+    fn rejects_unterminated_quote() {
+        assert!(split_words("curl 'example.com").is_err());
+    }
+
+    #[test]
+    fn parses_plain_get() {
+        let request = parse("curl https://example.com/users?page=2").unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.url, "https://example.com/users?page=2");
+        assert!(request.headers.is_empty());
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn parses_method_and_headers() {
+        let request = parse(
+            "curl -X PUT -H 'Accept: application/json' --header 'X-Id:  7 ' https://example.com",
+        )
+        .unwrap();
+        assert_eq!(request.method, Method::PUT);
+        assert_eq!(header(&request, "Accept"), Some("application/json"));
+        assert_eq!(header(&request, "X-Id"), Some("7"));
+    }
+
+    #[test]
+    fn parses_bundled_and_glued_options() {
+        let request = parse("curl -sSL -XDELETE https://example.com").unwrap();
+        assert_eq!(request.method, Method::DELETE);
+        let request = parse("curl -sX patch https://example.com").unwrap();
+        assert_eq!(request.method, Method::PATCH);
+    }
+
+    #[test]
+    fn data_implies_post_and_form_content_type() {
+        let request = parse("curl https://example.com -d a=1 -d b=2").unwrap();
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.body, "a=1&b=2");
+        assert_eq!(
+            header(&request, "Content-Type"),
+            Some("application/x-www-form-urlencoded")
+        );
+    }
+
+    #[test]
+    fn keeps_explicit_content_type() {
+        let request =
+            parse(r#"curl example.com -H 'Content-Type: application/json' --data-raw '{"a": 1}'"#)
+                .unwrap();
+        assert_eq!(request.body, r#"{"a": 1}"#);
+        assert_eq!(header(&request, "Content-Type"), Some("application/json"));
+        assert_eq!(request.headers.len(), 1);
+    }
+
+    #[test]
+    fn encodes_data_urlencode() {
+        let request =
+            parse("curl example.com --data-urlencode 'q=a b&c' --data-urlencode '=x y'").unwrap();
+        assert_eq!(request.body, "q=a+b%26c&x+y");
+    }
+
+    #[test]
+    fn get_moves_data_into_query() {
+        let request = parse("curl -G example.com/search?x=1 -d q=rust").unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.url, "example.com/search?x=1&q=rust");
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn user_becomes_basic_auth() {
+        let request = parse("curl -u alice:secret example.com").unwrap();
+        assert_eq!(
+            header(&request, "Authorization"),
+            Some("Basic YWxpY2U6c2VjcmV0")
+        );
+    }
+
+    #[test]
+    fn form_builds_multipart_body() {
+        let request = parse("curl -F name=httrs -F file=@logo.png example.com").unwrap();
+        assert_eq!(request.method, Method::POST);
+        assert!(header(&request, "Content-Type")
+            .unwrap()
+            .starts_with("multipart/form-data; boundary="));
+        assert!(request
+            .body
+            .contains("Content-Disposition: form-data; name=\"name\"\r\n\r\nhttrs\r\n"));
+        assert!(request.body.contains("filename=\"logo.png\""));
+    }
+
+    #[test]
+    fn head_and_ignored_options() {
+        let request = parse("curl -I --compressed -o out.txt -m 5 example.com").unwrap();
+        assert_eq!(request.method, Method::HEAD);
+        assert_eq!(request.url, "example.com");
+    }
+
+    #[test]
+    fn requires_a_url() {
+        assert!(parse("curl -H 'A: b'").is_err());
+        assert!(parse("curl -H").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::error::AppError;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// One row of a dataset, its columns as variables for the requests.
+pub type Row = BTreeMap<String, String>;
+
+/// Reads the rows to run requests with: an array of JSON objects from a
+/// `.json` file, otherwise CSV with the column names on its first line.
+pub fn load(path: &Path) -> Result<Vec<Row>, AppError> {
+    let contents = fs::read_to_string(path)?;
+    let json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let rows = if json {
+        parse_json(&contents)
+    } else {
+        parse_csv(&contents)
+    };
+    rows.map_err(|e| AppError::Parse(format!("{}: {}", path.display(), e)))
+}
+
+fn parse_json(contents: &str) -> Result<Vec<Row>, String> {
+    let value: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let items = value
+        .as_array()
+        .ok_or_else(|| String::from("expected an array of objects"))?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let object = item
+                .as_object()
+                .ok_or_else(|| format!("item {} isn't an object", i + 1))?;
+            Ok(object
+                .iter()
+                .map(|(key, value)| {
+                    let text = match value {
+                        Value::String(s) => s.clone(),
+                        Value::Null => String::new(),
+                        // Numbers, booleans and nested values as JSON, to
+                        // drop straight into a body
+                        other => other.to_string(),
+                    };
+                    (key.clone(), text)
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// Splits CSV into records of fields. Fields may be quoted, with `""` for
+/// a quote, to hold commas and line breaks.
+fn records(contents: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(String::from("unclosed quote"));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    // Blank lines, e.g. at the end of the file, aren't rows
+    records.retain(|record| record.iter().any(|field| !field.is_empty()));
+    Ok(records)
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<Row>, String> {
+    let mut records = records(contents)?.into_iter();
+    let Some(columns) = records.next() else {
+        return Ok(Vec::new());
+    };
+    let columns: Vec<String> = columns.iter().map(|name| name.trim().to_string()).collect();
+    records
+        .enumerate()
+        .map(|(i, record)| {
+            if record.len() != columns.len() {
+                return Err(format!(
+                    "row {} has {} fields, but there are {} columns",
+                    i + 1,
+                    record.len(),
+                    columns.len()
+                ));
+            }
+            Ok(columns.iter().cloned().zip(record).collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pairs: &[(&str, &str)]) -> Row {
+        pairs
+            .iter()
+            .map(|(k, v)| (String::from(*k), String::from(*v)))
+            .collect()
+    }
+
+    #[test]
+    fn reads_csv_rows_by_column() {
+        let rows = parse_csv("name, id\nAda,1\nGrace,2\n\n").unwrap();
+        assert_eq!(
+            rows,
+            [
+                row(&[("name", "Ada"), ("id", "1")]),
+                row(&[("name", "Grace"), ("id", "2")])
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_quoted_fields() {
+        let rows = parse_csv(
+            "name,note\r\n\"Lovelace, Ada\",\"said \"\"hi\"\"\nthen left\"\r\n\"\",last\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            [
+                row(&[
+                    ("name", "Lovelace, Ada"),
+                    ("note", "said \"hi\"\nthen left")
+                ]),
+                row(&[("name", ""), ("note", "last")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_a_last_row_without_a_line_break() {
+        let rows = parse_csv("a,b\n1,2").unwrap();
+        assert_eq!(rows, [row(&[("a", "1"), ("b", "2")])]);
+        assert!(parse_csv("").unwrap().is_empty());
+        assert!(parse_csv("a,b\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_csv() {
+        assert_eq!(
+            parse_csv("a,b\n\"open,1\n"),
+            Err(String::from("unclosed quote"))
+        );
+        assert_eq!(
+            parse_csv("a,b\n1\n"),
+            Err(String::from("row 1 has 1 fields, but there are 2 columns"))
+        );
+        assert_eq!(
+            parse_csv("a,b\n1,2\n1,2,3\n"),
+            Err(String::from("row 2 has 3 fields, but there are 2 columns"))
+        );
+    }
+
+    #[test]
+    fn reads_json_objects_with_values_as_text() {
+        let rows =
+            parse_json(r#"[{"name": "Ada", "id": 1, "admin": true, "tags": ["a"], "note": null}]"#)
+                .unwrap();
+        assert_eq!(
+            rows,
+            [row(&[
+                ("name", "Ada"),
+                ("id", "1"),
+                ("admin", "true"),
+                ("tags", "[\"a\"]"),
+                ("note", "")
+            ])]
+        );
+    }
+
+    #[test]
+    fn rejects_json_that_isnt_an_array_of_objects() {
+        assert!(parse_json(r#"{"name": "Ada"}"#).is_err());
+        assert_eq!(
+            parse_json(r#"[{"name": "Ada"}, 2]"#),
+            Err(String::from("item 2 isn't an object"))
+        );
+        assert!(parse_json("[{").is_err());
+    }
+}
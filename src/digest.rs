@@ -0,0 +1,231 @@
+use crate::auth::Auth;
+use crate::crypto::hex;
+use crate::error::AppError;
+use crate::request::{self, Request};
+use crate::response::ResponseData;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use md5::Md5;
+use reqwest::{header, Method, StatusCode};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// The parameters of a `WWW-Authenticate: Digest ...` challenge, keyed by
+/// lowercased name. `None` if it isn't one, is malformed, or lacks the
+/// `realm` and `nonce` every answer needs.
+pub fn parse_challenge(header: &str) -> Option<BTreeMap<String, String>> {
+    let (scheme, rest) = header.trim().split_once(char::is_whitespace)?;
+    if !scheme.eq_ignore_ascii_case("digest") {
+        return None;
+    }
+    let mut params = BTreeMap::new();
+    let mut rest = rest.trim_start();
+    while !rest.is_empty() {
+        let (name, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        // Quoted values may contain commas, as in `qop="auth,auth-int"`
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => {
+                let end = after.find(',').unwrap_or(after.len());
+                (after[..end].trim_end(), &after[end..])
+            }
+        };
+        params.insert(name.trim().to_ascii_lowercase(), String::from(value));
+        rest = after.trim_start().trim_start_matches(',').trim_start();
+    }
+    (params.contains_key("realm") && params.contains_key("nonce")).then_some(params)
+}
+
+/// Hashes with the challenge's algorithm; MD5 unless it asked for SHA-256.
+fn hash(algorithm: &str, data: &str) -> String {
+    if algorithm.to_ascii_uppercase().starts_with("SHA-256") {
+        hex(&Sha256::digest(data.as_bytes()))
+    } else {
+        hex(&Md5::digest(data.as_bytes()))
+    }
+}
+
+/// The `Authorization` value answering `challenge` (RFC 7616) for a request
+/// to `uri`, the path and query of the URL.
+pub fn authorization(
+    challenge: &BTreeMap<String, String>,
+    auth: &Auth,
+    method: &Method,
+    uri: &str,
+    cnonce: &str,
+) -> String {
+    let get = |name: &str| challenge.get(name).map_or("", String::as_str);
+    let (username, password) = (auth.value("username"), auth.value("password"));
+    let algorithm = match get("algorithm") {
+        "" => "MD5",
+        algorithm => algorithm,
+    };
+    let (realm, nonce) = (get("realm"), get("nonce"));
+    let nc = "00000001";
+
+    let mut ha1 = hash(algorithm, &format!("{}:{}:{}", username, realm, password));
+    if algorithm.to_ascii_lowercase().ends_with("-sess") {
+        ha1 = hash(algorithm, &format!("{}:{}:{}", ha1, nonce, cnonce));
+    }
+    let ha2 = hash(algorithm, &format!("{}:{}", method, uri));
+    // Only `auth` is supported; `auth-int` would need a hash of the body
+    let qop = get("qop").split(',').map(str::trim).find(|q| *q == "auth");
+    let response = match qop {
+        Some(qop) => hash(
+            algorithm,
+            &format!("{}:{}:{}:{}:{}:{}", ha1, nonce, nc, cnonce, qop, ha2),
+        ),
+        None => hash(algorithm, &format!("{}:{}:{}", ha1, nonce, ha2)),
+    };
+
+    let mut value = format!(
+        "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+        username, realm, nonce, uri, algorithm, response
+    );
+    if let Some(qop) = qop {
+        value.push_str(&format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nc, cnonce));
+    }
+    if !get("opaque").is_empty() {
+        value.push_str(&format!(", opaque=\"{}\"", get("opaque")));
+    }
+    value
+}
+
+/// Sends `request` once to get the server's challenge, then again with the
+/// computed digest if it answered 401.
+pub async fn send(mut request: Request) -> Result<ResponseData, AppError> {
+    let first = request::send(&request).await?;
+    if first.status != StatusCode::UNAUTHORIZED {
+        return Ok(first);
+    }
+    let challenge = first
+        .headers
+        .get_all(header::WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(parse_challenge);
+    let challenge = match challenge {
+        Some(challenge) => challenge,
+        // Not a Digest challenge; show the 401 as it is
+        None => return Ok(first),
+    };
+    let url = url::Url::parse(&request.full_url())
+        .map_err(|e| AppError::Auth(format!("digest: {}", e)))?;
+    let uri = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => String::from(url.path()),
+    };
+    let cnonce = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 12]>());
+    let value = authorization(&challenge, &request.auth, &request.method, &uri, &cnonce);
+    request.auth.set("digest_header", &value);
+    request::send(&request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthKind;
+
+    /// The credentials, request and client nonce of RFC 7616 section 3.9.1.
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn auth() -> Auth {
+        Auth {
+            kind: AuthKind::Digest,
+            values: BTreeMap::from([
+                (String::from("username"), String::from("Mufasa")),
+                (String::from("password"), String::from("Circle of Life")),
+            ]),
+            selected: 0,
+        }
+    }
+
+    fn challenge(algorithm: &str) -> String {
+        format!(
+            "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", \
+             algorithm={}, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"",
+            algorithm
+        )
+    }
+
+    fn answer(challenge: &str, uri: &str) -> String {
+        let challenge = parse_challenge(challenge).unwrap();
+        authorization(&challenge, &auth(), &Method::GET, uri, CNONCE)
+    }
+
+    /// The `response=` parameter of an `Authorization` value.
+    fn response(authorization: &str) -> &str {
+        let start = authorization.find("response=\"").unwrap() + "response=\"".len();
+        let rest = &authorization[start..];
+        &rest[..rest.find('"').unwrap()]
+    }
+
+    #[test]
+    fn answers_the_rfc_md5_example() {
+        let value = answer(&challenge("MD5"), "/dir/index.html");
+        assert_eq!(response(&value), "8ca523f5e9506fed4657c9700eebdbec");
+        assert_eq!(
+            value,
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", uri=\"/dir/index.html\", \
+             algorithm=MD5, response=\"8ca523f5e9506fed4657c9700eebdbec\", qop=auth, \
+             nc=00000001, cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        );
+    }
+
+    #[test]
+    fn answers_the_rfc_sha256_example() {
+        let value = answer(&challenge("SHA-256"), "/dir/index.html");
+        assert_eq!(
+            response(&value),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+    }
+
+    #[test]
+    fn hashes_the_session_key_for_sess() {
+        let value = answer(&challenge("MD5-sess"), "/dir/index.html");
+        assert_eq!(response(&value), "e783283f46242139c486a698fec7211d");
+        assert!(value.contains("algorithm=MD5-sess"));
+    }
+
+    #[test]
+    fn answers_without_qop() {
+        // Without a qop the response leaves out the nonce count and cnonce
+        let value = answer(r#"Digest realm="a, b", nonce=abc"#, "/");
+        assert_eq!(response(&value), "fbc420539398961f131f94e7fa3b83fa");
+        assert!(!value.contains("cnonce"));
+        assert!(value.contains("algorithm=MD5"));
+    }
+
+    #[test]
+    fn reads_quoted_and_bare_parameters() {
+        let challenge =
+            parse_challenge(r#"digest Realm="a, b",nonce=xyz , qop="auth,auth-int", stale=FALSE"#)
+                .unwrap();
+        assert_eq!(challenge["realm"], "a, b");
+        assert_eq!(challenge["nonce"], "xyz");
+        assert_eq!(challenge["qop"], "auth,auth-int");
+        assert_eq!(challenge["stale"], "FALSE");
+    }
+
+    #[test]
+    fn rejects_malformed_challenges() {
+        for header in [
+            "",
+            "Digest",
+            r#"Basic realm="a""#,
+            r#"Digest realm="a""#,
+            r#"Digest nonce="n""#,
+            r#"Digest realm="a, nonce="n""#,
+            r#"Digest realm="a" nonce"#,
+        ] {
+            assert!(parse_challenge(header).is_none(), "{:?}", header);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::encoding;
+use crate::error::AppError;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+/// A response body being streamed to a file rather than kept in memory.
+/// The task receiving it updates the progress, which the UI reads each frame.
+#[derive(Debug)]
+pub struct Download {
+    pub path: PathBuf,
+    progress: Mutex<Progress>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    pub received: u64,
+    /// From `Content-Length`, when the server sent one.
+    pub total: Option<u64>,
+    /// When the response headers arrived; unset until then.
+    pub started: Option<Instant>,
+}
+
+impl Download {
+    pub fn new(path: PathBuf) -> Download {
+        Download {
+            path,
+            progress: Mutex::new(Progress {
+                received: 0,
+                total: None,
+                started: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Progress> {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn progress(&self) -> Progress {
+        *self.lock()
+    }
+
+    /// Creates the file, truncating what an earlier attempt wrote, once the
+    /// response headers have said how big the body is.
+    pub async fn begin(&self, total: Option<u64>) -> Result<File, AppError> {
+        *self.lock() = Progress {
+            received: 0,
+            total,
+            started: Some(Instant::now()),
+        };
+        File::create(&self.path).await.map_err(|e| {
+            AppError::Io(io::Error::new(
+                e.kind(),
+                format!("{}: {}", self.path.display(), e),
+            ))
+        })
+    }
+
+    pub async fn write(&self, file: &mut File, chunk: &[u8]) -> Result<(), AppError> {
+        file.write_all(chunk).await?;
+        self.lock().received += chunk.len() as u64;
+        Ok(())
+    }
+
+    /// Waits for the last write to land, returning the bytes written.
+    pub async fn finish(&self, mut file: File) -> Result<u64, AppError> {
+        file.flush().await?;
+        Ok(self.lock().received)
+    }
+}
+
+impl Progress {
+    /// How far along the download is, from 0 to 1; 0 while the size is unknown.
+    pub fn ratio(&self) -> f64 {
+        match self.total {
+            Some(total) if total > 0 => (self.received as f64 / total as f64).min(1.0),
+            _ => 0.0,
+        }
+    }
+
+    /// e.g. `1.2 MB / 4.8 MB (25%)  640.0 KB/s`
+    pub fn label(&self) -> String {
+        let elapsed = self.started.map_or(Duration::ZERO, |at| at.elapsed());
+        let per_second = if elapsed.as_secs_f64() > 0.0 {
+            (self.received as f64 / elapsed.as_secs_f64()) as usize
+        } else {
+            0
+        };
+        let received = encoding::format_size(self.received as usize);
+        let amount = match self.total {
+            Some(total) => format!(
+                "{} / {} ({:.0}%)",
+                received,
+                encoding::format_size(total as usize),
+                self.ratio() * 100.0
+            ),
+            None => received,
+        };
+        format!("{}  {}/s", amount, encoding::format_size(per_second))
+    }
+}
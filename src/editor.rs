@@ -0,0 +1,235 @@
+use crate::input::{self, Edit};
+use crate::undo::{self, Undo};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{env, fs, io, process::Command};
+use unicode_width::UnicodeWidthChar;
+
+/// Spaces typed for Tab.
+const INDENT: &str = "  ";
+
+/// The cursor and scroll position of a multi-line text being edited, such
+/// as the body. The text itself stays with the request it belongs to.
+#[derive(Debug, Default)]
+pub struct Editor {
+    /// Byte offset into the text, on a char boundary.
+    pub cursor: usize,
+    /// First row shown.
+    pub scroll: usize,
+    /// Columns and rows available, as of the last draw.
+    width: usize,
+    height: usize,
+    /// Earlier texts and cursors, for Ctrl+Z.
+    undo: Undo<(String, usize)>,
+}
+
+/// A screen row of text, as a byte range. Long lines are wrapped onto as
+/// many rows as they need.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Row {
+    pub start: usize,
+    pub end: usize,
+    /// Whether the next row carries on the same line.
+    pub wrapped: bool,
+}
+
+/// Splits `text` into rows at most `width` columns wide.
+pub fn rows(text: &str, width: usize) -> Vec<Row> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut offset = 0;
+    for line in text.split('\n') {
+        let mut start = offset;
+        let mut columns = 0;
+        for (i, c) in line.char_indices() {
+            let w = c.width().unwrap_or(0);
+            if columns + w > width && columns > 0 {
+                rows.push(Row {
+                    start,
+                    end: offset + i,
+                    wrapped: true,
+                });
+                start = offset + i;
+                columns = 0;
+            }
+            columns += w;
+        }
+        rows.push(Row {
+            start,
+            end: offset + line.len(),
+            wrapped: false,
+        });
+        offset += line.len() + 1;
+    }
+    rows
+}
+
+fn columns(text: &str) -> usize {
+    text.chars().map(|c| c.width().unwrap_or(0)).sum()
+}
+
+impl Editor {
+    /// Puts the cursor after the last character, for when editing starts.
+    pub fn move_to_end(&mut self, text: &str) {
+        self.cursor = text.len();
+    }
+
+    /// The row and column the cursor is drawn at. At the end of a wrapped
+    /// row it's shown at the start of the next.
+    pub fn position(&self, rows: &[Row], text: &str) -> (usize, usize) {
+        let cursor = self.cursor.min(text.len());
+        let row = rows
+            .iter()
+            .position(|row| cursor < row.end || (cursor == row.end && !row.wrapped))
+            .unwrap_or(rows.len().saturating_sub(1));
+        let start = rows.get(row).map_or(0, |row| row.start);
+        (row, columns(&text[start..cursor]))
+    }
+
+    /// The rows that fit in a `width` by `height` area, scrolling to keep
+    /// the cursor in view, along with the cursor's row and column among them.
+    pub fn layout(
+        &mut self,
+        text: &str,
+        width: usize,
+        height: usize,
+    ) -> (Vec<Row>, (usize, usize)) {
+        self.width = width;
+        self.height = height;
+        let rows = rows(text, width);
+        let (row, column) = self.position(&rows, text);
+        if row < self.scroll {
+            self.scroll = row;
+        } else if height > 0 && row >= self.scroll + height {
+            self.scroll = row + 1 - height;
+        }
+        self.scroll = self.scroll.min(rows.len().saturating_sub(1));
+        let visible = rows
+            .iter()
+            .skip(self.scroll)
+            .take(height)
+            .copied()
+            .collect();
+        (visible, (row - self.scroll.min(row), column))
+    }
+
+    /// Applies an editing key to `text`. Enter starts a new line, Up/Down
+    /// and PageUp/PageDown move by row, Home/End go to the ends of the line,
+    /// and Ctrl+Home/End to the ends of the text. The rest, undo included,
+    /// edit as the URL field does.
+    pub fn edit(&mut self, key: &KeyEvent, text: &mut String) -> Edit {
+        let mut undo = std::mem::take(&mut self.undo);
+        let mut cursor = self.cursor;
+        let edit = undo::edit_text(key, text, &mut cursor, &mut undo, |text, cursor| {
+            self.cursor = *cursor;
+            let edit = self.apply(key, text);
+            *cursor = self.cursor;
+            edit
+        });
+        self.cursor = cursor;
+        self.undo = undo;
+        edit
+    }
+
+    fn apply(&mut self, key: &KeyEvent, text: &mut String) -> Edit {
+        self.cursor = self.cursor.min(text.len());
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let line_start = text[..self.cursor].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[self.cursor..]
+            .find('\n')
+            .map_or(text.len(), |i| self.cursor + i);
+        let before = self.cursor;
+        match key.code {
+            KeyCode::Enter => {
+                input::insert(text, &mut self.cursor, "\n");
+                return Edit::Changed;
+            }
+            KeyCode::Tab => {
+                input::insert(text, &mut self.cursor, INDENT);
+                return Edit::Changed;
+            }
+            KeyCode::Up => self.move_rows(text, -1),
+            KeyCode::Down => self.move_rows(text, 1),
+            KeyCode::PageUp => self.move_rows(text, -(self.height.max(1) as isize)),
+            KeyCode::PageDown => self.move_rows(text, self.height.max(1) as isize),
+            KeyCode::Home if ctrl => self.cursor = 0,
+            KeyCode::End if ctrl => self.cursor = text.len(),
+            KeyCode::Home => self.cursor = line_start,
+            KeyCode::End => self.cursor = line_end,
+            KeyCode::Char('a') if ctrl => self.cursor = line_start,
+            KeyCode::Char('e') if ctrl => self.cursor = line_end,
+            _ => return input::edit(key, text, &mut self.cursor),
+        }
+        if self.cursor == before {
+            Edit::Ignored
+        } else {
+            Edit::Moved
+        }
+    }
+
+    /// Inserts pasted text at the cursor.
+    pub fn insert(&mut self, text: &mut String, s: &str) {
+        self.undo.record((text.clone(), self.cursor), None);
+        input::insert(text, &mut self.cursor, s);
+    }
+
+    /// Moves the cursor `step` rows, keeping its column where the row is long enough.
+    fn move_rows(&mut self, text: &str, step: isize) {
+        let rows = rows(text, self.width.max(1));
+        let (row, column) = self.position(&rows, text);
+        let target = row
+            .saturating_add_signed(step)
+            .min(rows.len().saturating_sub(1));
+        if target == row {
+            // Past the first or last row, go to the very start or end
+            self.cursor = if step < 0 { 0 } else { text.len() };
+            return;
+        }
+        let row = rows[target];
+        let mut offset = row.start;
+        let mut width = 0;
+        for (i, c) in text[row.start..row.end].char_indices() {
+            let w = c.width().unwrap_or(0);
+            if width + w > column {
+                break;
+            }
+            width += w;
+            offset = row.start + i + c.len_utf8();
+        }
+        // The end of a wrapped row is drawn at the start of the next, so stop before it
+        if row.wrapped && offset == row.end {
+            offset = text[row.start..row.end]
+                .char_indices()
+                .next_back()
+                .map_or(row.start, |(i, _)| row.start + i);
+        }
+        self.cursor = offset;
+    }
+}
+
+/// Opens `text` in the user's `$VISUAL` or `$EDITOR`, falling back to `vi`,
+/// and returns what was saved once it exits. The file is named with
+/// `extension` so the editor can pick its syntax. The terminal should be
+/// handed over first.
+pub fn edit_externally(text: &str, extension: &str) -> io::Result<String> {
+    let command = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    // Allows for arguments, e.g. `code --wait`
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "$EDITOR is empty"))?;
+    let path = env::temp_dir().join(format!("heade-rs-{}.{}", std::process::id(), extension));
+    fs::write(&path, text)?;
+    let status = Command::new(program).args(words).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )));
+    }
+    edited
+}
@@ -0,0 +1,57 @@
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::io::{self, Read};
+
+/// What `Request::build` advertises in `Accept-Encoding`, all of which `decode` undoes.
+pub const ACCEPTED: &str = "gzip, deflate, br";
+
+/// Undoes the codings listed in a `Content-Encoding` header, last applied
+/// first. Unknown codings are an error, as the bytes can't be made sense of.
+pub fn decode(content_encoding: &str, bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut body = bytes.to_vec();
+    for coding in content_encoding.rsplit(',').map(str::trim) {
+        let mut out = Vec::new();
+        match coding.to_ascii_lowercase().as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => {
+                GzDecoder::new(body.as_slice()).read_to_end(&mut out)?;
+            }
+            // Meant to be zlib-wrapped, but some servers send raw deflate
+            "deflate" => {
+                if ZlibDecoder::new(body.as_slice())
+                    .read_to_end(&mut out)
+                    .is_err()
+                {
+                    out.clear();
+                    DeflateDecoder::new(body.as_slice()).read_to_end(&mut out)?;
+                }
+            }
+            "br" => {
+                brotli_decompressor::Decompressor::new(body.as_slice(), 4096)
+                    .read_to_end(&mut out)?;
+            }
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported content encoding {}", other),
+                ))
+            }
+        }
+        body = out;
+    }
+    Ok(body)
+}
+
+/// A byte count for display, e.g. `512 B`, `1.2 KB` or `3.4 MB`.
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -0,0 +1,123 @@
+use crate::error::AppError;
+use crate::settings::Settings;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// A named set of variables, e.g. `local`, `staging` or `prod`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Environment {
+    pub name: String,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Connection settings overriding `config.json` while this environment is active.
+    #[serde(default, skip_serializing_if = "Settings::is_empty")]
+    pub settings: Settings,
+}
+
+/// The environments defined in `environments.json` and which one is in use.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Environments {
+    pub environments: Vec<Environment>,
+    /// Name of the active environment; no substitution happens without one.
+    pub active: Option<String>,
+}
+
+impl Environments {
+    fn path() -> PathBuf {
+        storage::config_dir().join("environments.json")
+    }
+
+    pub fn load() -> Result<Environments, AppError> {
+        storage::load_json(&Environments::path())
+    }
+
+    pub fn active(&self) -> Option<&Environment> {
+        let name = self.active.as_deref()?;
+        self.environments.iter().find(|env| env.name == name)
+    }
+
+    /// The active environment alone, with `variables` set over its own, e.g.
+    /// for a row of a dataset. Nothing is saved.
+    pub fn with_variables(&self, variables: &BTreeMap<String, String>) -> Environments {
+        let mut environment = self.active().cloned().unwrap_or_default();
+        environment.variables.extend(variables.clone());
+        Environments {
+            active: Some(environment.name.clone()),
+            environments: vec![environment],
+        }
+    }
+
+    /// Adds an imported environment, replacing any existing one of the same name.
+    pub fn add(&mut self, environment: Environment) -> Result<(), AppError> {
+        match self
+            .environments
+            .iter_mut()
+            .find(|env| env.name == environment.name)
+        {
+            Some(existing) => *existing = environment,
+            None => self.environments.push(environment),
+        }
+        storage::save_json(&Environments::path(), self)
+    }
+
+    /// Sets a variable of the active environment and saves the change.
+    pub fn set_variable(&mut self, name: &str, value: &str) -> Result<(), AppError> {
+        let active = self.active.clone();
+        let env = self
+            .environments
+            .iter_mut()
+            .find(|env| Some(&env.name) == active.as_ref())
+            .ok_or_else(|| AppError::Parse(String::from("environments: none is active")))?;
+        env.variables
+            .insert(String::from(name), String::from(value));
+        storage::save_json(&Environments::path(), self)
+    }
+
+    /// Switches to the next environment, going back to none after the last.
+    pub fn cycle(&mut self) -> Result<(), AppError> {
+        let next = match self.active() {
+            Some(active) => self
+                .environments
+                .iter()
+                .position(|env| env.name == active.name)
+                .map_or(0, |i| i + 1),
+            None => 0,
+        };
+        self.active = self.environments.get(next).map(|env| env.name.clone());
+        storage::save_json(&Environments::path(), self)
+    }
+
+    /// Makes the environment called `name` active, or none, and saves the choice.
+    pub fn select(&mut self, name: Option<&str>) -> Result<(), AppError> {
+        self.active = name.map(String::from);
+        storage::save_json(&Environments::path(), self)
+    }
+
+    /// Replaces each `{{name}}` in `text` with the active environment's value
+    /// for it. Unknown variables are left as written.
+    pub fn interpolate(&self, text: &str) -> String {
+        let variables = match self.active() {
+            Some(env) => &env.variables,
+            None => return String::from(text),
+        };
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let end = match rest[start + 2..].find("}}") {
+                Some(end) => start + 2 + end,
+                None => break,
+            };
+            out.push_str(&rest[..start]);
+            match variables.get(rest[start + 2..end].trim()) {
+                Some(value) => out.push_str(value),
+                None => out.push_str(&rest[start..end + 2]),
+            }
+            rest = &rest[end + 2..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
@@ -0,0 +1,82 @@
+use std::{error::Error, fmt, io};
+
+/// Failures from user-triggered actions. These are shown in the UI rather
+/// than aborting the app.
+#[derive(Debug)]
+pub enum AppError {
+    /// The request could not be built, sent, or its body read.
+    Http(reqwest::Error),
+    Io(io::Error),
+    /// A config or data file exists but could not be parsed.
+    Parse(String),
+    Clipboard(arboard::Error),
+    /// Getting or refreshing credentials failed.
+    Auth(String),
+    /// A client certificate or other TLS setting could not be loaded.
+    Tls(String),
+    /// A connect, read or total timeout from the settings ran out.
+    Timeout(String),
+    /// A pre-request or post-response script failed to run.
+    Script(String),
+    /// A request to send first couldn't be found or didn't succeed.
+    Prerequisite(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Http(e) => {
+                write!(f, "Request failed: {}", e)?;
+                // reqwest keeps the useful detail (DNS, TLS, ...) in the source chain
+                let mut source = e.source();
+                while let Some(cause) = source {
+                    write!(f, ": {}", cause)?;
+                    source = cause.source();
+                }
+                Ok(())
+            }
+            AppError::Io(e) => write!(f, "I/O error: {}", e),
+            AppError::Parse(e) => write!(f, "Could not parse {}", e),
+            AppError::Clipboard(e) => write!(f, "Clipboard error: {}", e),
+            AppError::Auth(e) => write!(f, "Authentication failed: {}", e),
+            AppError::Tls(e) => write!(f, "TLS setup failed: {}", e),
+            AppError::Timeout(e) => write!(f, "Timed out: {}", e),
+            AppError::Script(e) => write!(f, "Script failed: {}", e),
+            AppError::Prerequisite(e) => write!(f, "Prerequisite failed: {}", e),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::Http(e) => Some(e),
+            AppError::Parse(_)
+            | AppError::Auth(_)
+            | AppError::Tls(_)
+            | AppError::Timeout(_)
+            | AppError::Script(_)
+            | AppError::Prerequisite(_) => None,
+            AppError::Io(e) => Some(e),
+            AppError::Clipboard(e) => Some(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> AppError {
+        AppError::Http(e)
+    }
+}
+
+impl From<arboard::Error> for AppError {
+    fn from(e: arboard::Error) -> AppError {
+        AppError::Clipboard(e)
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(e: io::Error) -> AppError {
+        AppError::Io(e)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::collections::{Collections, Row};
+use crate::fuzzy;
+use crate::history::History;
+
+/// Something the Ctrl+F finder can load.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    /// The sidebar row of a saved request.
+    Saved(Row),
+    /// A history entry, counted from the newest as in the history popup.
+    History(usize),
+}
+
+/// The Ctrl+F popup for jumping to a saved request or a past one.
+#[derive(Debug, Default)]
+pub struct Finder {
+    pub query: String,
+    /// Index into `matches()` of the highlighted entry.
+    pub selected: usize,
+}
+
+impl Finder {
+    /// Every saved request, as `name  (collection)  METHOD url`, then each
+    /// distinct method and URL in the history, newest first.
+    pub fn entries(collections: &Collections, history: &History) -> Vec<(Target, String)> {
+        let mut entries = Vec::new();
+        for (c, collection) in collections.collections.iter().enumerate() {
+            for (r, saved) in collection.requests.iter().enumerate() {
+                entries.push((
+                    Target::Saved(Row::Request(c, r)),
+                    format!(
+                        "{}  ({})  {} {}",
+                        saved.name, collection.name, saved.method, saved.url
+                    ),
+                ));
+            }
+        }
+        let mut seen: Vec<(&str, &str)> = Vec::new();
+        for (i, entry) in history.entries.iter().rev().enumerate() {
+            let key = (entry.method.as_str(), entry.url.as_str());
+            if seen.contains(&key) {
+                continue;
+            }
+            seen.push(key);
+            entries.push((
+                Target::History(i),
+                format!("history  {} {}", entry.method, entry.url),
+            ));
+        }
+        entries
+    }
+
+    /// The entries matching the query, best first.
+    pub fn matches(&self, collections: &Collections, history: &History) -> Vec<(Target, String)> {
+        let entries = Finder::entries(collections, history);
+        fuzzy::rank(&self.query, entries.iter().map(|(_, label)| label.as_str()))
+            .into_iter()
+            .map(|i| entries[i].clone())
+            .collect()
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    /// Moves the highlight `step` entries, within the `count` matching.
+    pub fn select(&mut self, step: isize, count: usize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(step)
+            .min(count.saturating_sub(1));
+    }
+}
@@ -0,0 +1,58 @@
+/// The pane keys go to in Normal mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Focus {
+    Method,
+    #[default]
+    Url,
+    Params,
+    Headers,
+    Auth,
+    Body,
+    Response,
+    Logs,
+    Collections,
+}
+
+impl Focus {
+    /// Every pane, in Tab order.
+    pub const ALL: [Focus; 9] = [
+        Focus::Method,
+        Focus::Url,
+        Focus::Params,
+        Focus::Headers,
+        Focus::Auth,
+        Focus::Body,
+        Focus::Response,
+        Focus::Logs,
+        Focus::Collections,
+    ];
+
+    /// e.g. `Response`, for the status bar.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Focus::Method => "Method",
+            Focus::Url => "URL",
+            Focus::Params => "Params",
+            Focus::Headers => "Headers",
+            Focus::Auth => "Auth",
+            Focus::Body => "Body",
+            Focus::Response => "Response",
+            Focus::Logs => "Logs",
+            Focus::Collections => "Collections",
+        }
+    }
+
+    /// The pane `step` places on in Tab order, wrapping around, skipping
+    /// those `can_focus` rules out. Stays put if it rules them all out.
+    pub fn cycle(self, step: isize, can_focus: impl Fn(Focus) -> bool) -> Focus {
+        let count = Focus::ALL.len() as isize;
+        let mut i = Focus::ALL.iter().position(|f| *f == self).unwrap_or(0) as isize;
+        for _ in 0..count {
+            i = (i + step).rem_euclid(count);
+            if can_focus(Focus::ALL[i as usize]) {
+                return Focus::ALL[i as usize];
+            }
+        }
+        self
+    }
+}
@@ -0,0 +1,184 @@
+/// How a response body is formatted for display.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BodyKind {
+    Json,
+    Xml,
+    Html,
+    Text,
+    /// Anything else, which is displayed exactly as received.
+    Other,
+}
+
+/// HTML elements that never have a closing tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// HTML elements whose contents are not markup.
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+const INDENT: &str = "  ";
+
+impl BodyKind {
+    /// File extension used when saving a body of this kind.
+    pub fn extension(self) -> &'static str {
+        match self {
+            BodyKind::Json => "json",
+            BodyKind::Xml => "xml",
+            BodyKind::Html => "html",
+            BodyKind::Text | BodyKind::Other => "txt",
+        }
+    }
+
+    /// Picks a kind from the Content-Type, sniffing the body when there isn't one.
+    pub fn detect(content_type: Option<&str>, body: &str) -> BodyKind {
+        match content_type {
+            Some(content_type) => {
+                let mime = content_type
+                    .split(';')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_ascii_lowercase();
+                if mime.ends_with("json") {
+                    BodyKind::Json
+                } else if mime == "text/html" || mime == "application/xhtml+xml" {
+                    BodyKind::Html
+                } else if mime.ends_with("xml") {
+                    BodyKind::Xml
+                } else if mime.starts_with("text/") {
+                    BodyKind::Text
+                } else {
+                    BodyKind::Other
+                }
+            }
+            None => {
+                let start = body.trim_start();
+                let lower = start.get(..15).unwrap_or(start).to_ascii_lowercase();
+                if start.starts_with('{') || start.starts_with('[') {
+                    BodyKind::Json
+                } else if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+                    BodyKind::Html
+                } else if start.starts_with('<') {
+                    BodyKind::Xml
+                } else {
+                    BodyKind::Other
+                }
+            }
+        }
+    }
+}
+
+/// Formats `body` for display. Bodies that don't parse as their kind are
+/// returned untouched rather than treated as errors.
+pub fn format_body(kind: BodyKind, body: &str) -> String {
+    match kind {
+        BodyKind::Json => {
+            if serde_json::from_str::<serde_json::Value>(body).is_err() {
+                return String::from(body);
+            }
+            jsonxf::pretty_print(body).unwrap_or_else(|_| String::from(body))
+        }
+        BodyKind::Xml => indent_markup(body, false),
+        BodyKind::Html => indent_markup(body, true),
+        BodyKind::Text | BodyKind::Other => String::from(body),
+    }
+}
+
+/// The element name of a tag like `<a href="..">` or `</a>`, lowercased.
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// Puts each tag of an XML or HTML document on its own line, indented by
+/// nesting depth. Elements holding only text stay on one line.
+fn indent_markup(body: &str, html: bool) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut depth: usize = 0;
+    let mut rest = body;
+
+    let push = |lines: &mut Vec<String>, depth: usize, s: &str| {
+        lines.push(format!("{}{}", INDENT.repeat(depth), s));
+    };
+
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            // Text up to the next tag
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = rest[..end].trim();
+            if !text.is_empty() {
+                push(&mut lines, depth, text);
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        // Comments, CDATA and declarations end with their own terminators
+        let terminator = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<![CDATA[") {
+            "]]>"
+        } else if rest.starts_with("<?") {
+            "?>"
+        } else {
+            ">"
+        };
+        let end = match rest.find(terminator) {
+            Some(i) => i + terminator.len(),
+            None => {
+                // Unterminated tag; show the remainder as is
+                push(&mut lines, depth, rest.trim());
+                break;
+            }
+        };
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        let name = tag_name(tag);
+
+        if tag.starts_with("</") {
+            depth = depth.saturating_sub(1);
+            push(&mut lines, depth, tag);
+        } else if tag.starts_with("<!")
+            || tag.starts_with("<?")
+            || tag.ends_with("/>")
+            || (html && VOID_ELEMENTS.contains(&name.as_str()))
+        {
+            push(&mut lines, depth, tag);
+        } else if html && RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            // Script and style contents aren't markup, so keep their lines as they are
+            // up to the closing tag, which is then handled like any other
+            let close = format!("</{}", name);
+            let end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+            push(&mut lines, depth, tag);
+            depth += 1;
+            for line in rest[..end].lines().filter(|l| !l.trim().is_empty()) {
+                push(&mut lines, depth, line.trim_end());
+            }
+            rest = &rest[end..];
+        } else {
+            // `<a>text</a>` stays on one line
+            let text_end = rest.find('<').unwrap_or(rest.len());
+            let closing = format!("</{}>", name);
+            let close_end = text_end + closing.len();
+            let closes_here = rest
+                .get(text_end..close_end)
+                .is_some_and(|s| s.eq_ignore_ascii_case(&closing));
+            if closes_here {
+                let text = rest[..text_end].trim();
+                let line = format!("{}{}{}", tag, text, &rest[text_end..close_end]);
+                push(&mut lines, depth, &line);
+                rest = &rest[close_end..];
+            } else {
+                push(&mut lines, depth, tag);
+                depth += 1;
+            }
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,41 @@
+/// How well `query` fuzzily matches `candidate`, higher being better, or
+/// `None` if its characters don't all appear in order. Case is ignored.
+/// Runs of consecutive characters, and characters starting a word, score more.
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let mut query = query.chars().filter(|c| !c.is_whitespace()).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut run = 0;
+    for c in candidate.chars() {
+        let Some(wanted) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(wanted.to_lowercase()) {
+            let word_start = previous.is_none_or(|p| !p.is_alphanumeric());
+            run += 1;
+            score += 1 + run + if word_start { 8 } else { 0 };
+            query.next();
+        } else {
+            run = 0;
+        }
+        previous = Some(c);
+    }
+    if query.peek().is_some() {
+        return None;
+    }
+    // Shorter candidates win ties
+    Some(score * 100 - candidate.chars().count() as i64)
+}
+
+/// The indexes of `candidates` matching `query`, best first. An empty query
+/// keeps them all in their original order.
+pub fn rank<'a>(query: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<usize> {
+    let mut scored: Vec<(usize, i64)> = candidates
+        .enumerate()
+        .filter_map(|(i, candidate)| score(query, candidate).map(|score| (i, score)))
+        .collect();
+    if !query.trim().is_empty() {
+        scored.sort_by_key(|(i, score)| (-score, *i));
+    }
+    scored.into_iter().map(|(i, _)| i).collect()
+}
@@ -0,0 +1,491 @@
+use crate::body::{self, BodyMode, FieldValue};
+use crate::collections::{Collection, SavedRequest};
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::request::{self, Request};
+use crate::response::ResponseData;
+use crate::storage;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Headers a browser records that shouldn't be replayed as typed headers.
+const SKIPPED_HEADERS: [&str; 3] = ["content-length", "host", "connection"];
+
+/// The top level of a HAR 1.2 file. Fields missing from imported files are
+/// defaulted, as browsers differ in what they fill in.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Har {
+    pub log: Log,
+}
+
+impl Har {
+    /// An empty log for recording this session's exchanges.
+    pub fn session() -> Har {
+        Har {
+            log: Log {
+                version: String::from("1.2"),
+                creator: Creator {
+                    name: String::from(env!("CARGO_PKG_NAME")),
+                    version: String::from(env!("CARGO_PKG_VERSION")),
+                },
+                entries: Vec::new(),
+            },
+        }
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), AppError> {
+        storage::save_json(path, self)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+/// One request/response exchange.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Entry {
+    pub started_date_time: String,
+    /// Total time in milliseconds.
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: serde_json::Value,
+    pub timings: Timings,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub headers: Vec<NameValue>,
+    pub query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub headers: Vec<NameValue>,
+    pub content: Content,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PostData {
+    pub mime_type: String,
+    pub text: String,
+    /// The fields of a multipart body, which has no `text`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<Param>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Param {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+impl Param {
+    fn new(field: &KeyValue) -> Param {
+        match FieldValue::parse(&field.value) {
+            FieldValue::Text(text) => Param {
+                name: field.key.clone(),
+                value: String::from(text),
+                file_name: None,
+                content_type: None,
+            },
+            FieldValue::File { path, content_type } => Param {
+                name: field.key.clone(),
+                value: String::new(),
+                file_name: Some(String::from(path)),
+                content_type: content_type.map(String::from),
+            },
+        }
+    }
+
+    /// The multipart field this param was recorded from.
+    fn field(&self) -> KeyValue {
+        let value = match (&self.file_name, &self.content_type) {
+            (Some(file), Some(content_type)) => format!("@{};type={}", file, content_type),
+            (Some(file), None) => format!("@{}", file),
+            (None, _) => self.value.clone(),
+        };
+        KeyValue::new(&self.name, &value)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Content {
+    pub size: i64,
+    pub mime_type: String,
+    pub text: String,
+}
+
+/// Only the waiting time is measured; the rest are recorded as unknown.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Timings {
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+fn name_values(pairs: &[KeyValue]) -> Vec<NameValue> {
+    pairs
+        .iter()
+        .map(|kv| NameValue {
+            name: kv.key.clone(),
+            value: kv.value.clone(),
+        })
+        .collect()
+}
+
+impl Entry {
+    /// Records a completed exchange. `request` should be the request as sent,
+    /// with variables already filled in.
+    pub fn new(request: &Request, response: &ResponseData) -> Entry {
+        let millis = response.elapsed.as_secs_f64() * 1000.0;
+        let started = Utc::now() - response.elapsed;
+        let content_type = |headers: &[NameValue]| {
+            headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("content-type"))
+                .map(|h| h.value.clone())
+                .unwrap_or_default()
+        };
+        let request_headers = name_values(&request.headers);
+        let response_headers: Vec<NameValue> = response
+            .headers
+            .iter()
+            .map(|(name, value)| NameValue {
+                name: name.to_string(),
+                value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            })
+            .collect();
+        let post_data = match request.body_mode {
+            _ if !request::method_has_body(&request.method) => None,
+            BodyMode::Raw if request.body.is_empty() => None,
+            BodyMode::Raw => Some(PostData {
+                mime_type: content_type(&request_headers),
+                text: request.body.clone(),
+                params: Vec::new(),
+            }),
+            BodyMode::Form => Some(PostData {
+                mime_type: String::from("application/x-www-form-urlencoded"),
+                text: body::form_urlencoded(&request.fields),
+                params: request.fields.iter().map(Param::new).collect(),
+            }),
+            BodyMode::GraphQl => Some(PostData {
+                mime_type: String::from("application/json"),
+                text: body::graphql(&request.body, &request.variables).unwrap_or_default(),
+                params: Vec::new(),
+            }),
+            BodyMode::Multipart => Some(PostData {
+                mime_type: String::from("multipart/form-data"),
+                text: String::new(),
+                params: request.fields.iter().map(Param::new).collect(),
+            }),
+        };
+        let body = response.raw_body.as_str();
+        Entry {
+            started_date_time: started.to_rfc3339(),
+            time: millis,
+            request: HarRequest {
+                method: request.method.to_string(),
+                url: request.full_url(),
+                http_version: format!("{:?}", response.version),
+                query_string: name_values(&request.params),
+                body_size: post_data.as_ref().map_or(0, |p| p.text.len() as i64),
+                post_data,
+                headers: request_headers,
+                headers_size: -1,
+            },
+            response: HarResponse {
+                status: response.status.as_u16(),
+                status_text: response.status.canonical_reason().unwrap_or("").to_string(),
+                http_version: format!("{:?}", response.version),
+                content: Content {
+                    size: body.len() as i64,
+                    mime_type: content_type(&response_headers),
+                    text: String::from(body),
+                },
+                headers: response_headers,
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: body.len() as i64,
+            },
+            cache: serde_json::json!({}),
+            timings: Timings {
+                send: 0.0,
+                wait: millis,
+                receive: 0.0,
+            },
+        }
+    }
+}
+
+/// Whether `json` looks like a HAR file.
+pub fn detect(json: &serde_json::Value) -> bool {
+    json.pointer("/log/entries").is_some_and(|e| e.is_array())
+}
+
+/// Turns the requests of a HAR file into a collection called `name`, so
+/// captured traffic can be replayed.
+pub fn import(json: serde_json::Value, name: &str) -> Result<Collection, AppError> {
+    let har: Har =
+        serde_json::from_value(json).map_err(|e| AppError::Parse(format!("HAR file: {}", e)))?;
+    let requests = har
+        .log
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let request = entry.request;
+            let headers = request
+                .headers
+                .iter()
+                // HTTP/2 pseudo-headers like `:authority` aren't real headers
+                .filter(|h| !h.name.starts_with(':'))
+                .filter(|h| !SKIPPED_HEADERS.contains(&h.name.to_ascii_lowercase().as_str()))
+                .map(|h| KeyValue::new(&h.name, &h.value))
+                .collect();
+            let post_data = request.post_data.unwrap_or_default();
+            let body_mode = if post_data.params.is_empty() {
+                BodyMode::Raw
+            } else if post_data.mime_type.starts_with("multipart/") {
+                BodyMode::Multipart
+            } else {
+                BodyMode::Form
+            };
+            let fields = match body_mode {
+                BodyMode::Raw => Vec::new(),
+                _ => post_data.params.iter().map(Param::field).collect(),
+            };
+            SavedRequest {
+                name: format!("{} {}", request.method, request.url),
+                method: request.method,
+                url: request.url,
+                headers,
+                body: post_data.text,
+                body_mode,
+                fields,
+                ..Default::default()
+            }
+        })
+        .collect();
+    Ok(Collection {
+        name: String::from(name),
+        requests,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderMap;
+    use reqwest::{Method, StatusCode, Version};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn har() -> serde_json::Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "entries": [
+                    {
+                        "request": {
+                            "method": "GET",
+                            "url": "https://shop.test/items?page=2",
+                            "headers": [
+                                {"name": ":authority", "value": "shop.test"},
+                                {"name": "Host", "value": "shop.test"},
+                                {"name": "Accept", "value": "application/json"},
+                                {"name": "Connection", "value": "keep-alive"}
+                            ]
+                        }
+                    },
+                    {
+                        "request": {
+                            "method": "POST",
+                            "url": "https://shop.test/items",
+                            "headers": [{"name": "Content-Length", "value": "9"}],
+                            "postData": {"mimeType": "application/json", "text": "{\"a\": 1}"}
+                        }
+                    },
+                    {
+                        "request": {
+                            "method": "POST",
+                            "url": "https://shop.test/login",
+                            "postData": {
+                                "mimeType": "application/x-www-form-urlencoded",
+                                "text": "user=ann",
+                                "params": [{"name": "user", "value": "ann"}]
+                            }
+                        }
+                    },
+                    {
+                        "request": {
+                            "method": "PUT",
+                            "url": "https://shop.test/files",
+                            "postData": {
+                                "mimeType": "multipart/form-data; boundary=x",
+                                "params": [
+                                    {"name": "note", "value": "hi"},
+                                    {"name": "file", "fileName": "a.png", "contentType": "image/png"}
+                                ]
+                            }
+                        }
+                    }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn detects_har_files() {
+        assert!(detect(&har()));
+        assert!(!detect(&json!({"log": {}})));
+    }
+
+    #[test]
+    fn imports_each_entry_as_a_request() {
+        let collection = import(har(), "Captured").unwrap();
+        assert_eq!(collection.name, "Captured");
+        let names: Vec<&str> = collection
+            .requests
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "GET https://shop.test/items?page=2",
+                "POST https://shop.test/items",
+                "POST https://shop.test/login",
+                "PUT https://shop.test/files"
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_headers_that_are_not_replayed() {
+        let collection = import(har(), "Captured").unwrap();
+        let get = &collection.requests[0];
+        assert_eq!(get.method, "GET");
+        assert_eq!(get.url, "https://shop.test/items?page=2");
+        assert_eq!(get.headers, [KeyValue::new("Accept", "application/json")]);
+        assert!(collection.requests[1].headers.is_empty());
+    }
+
+    #[test]
+    fn imports_bodies_in_their_mode() {
+        let collection = import(har(), "Captured").unwrap();
+        let raw = &collection.requests[1];
+        assert_eq!(raw.body_mode, BodyMode::Raw);
+        assert_eq!(raw.body, r#"{"a": 1}"#);
+        assert!(raw.fields.is_empty());
+
+        let form = &collection.requests[2];
+        assert_eq!(form.body_mode, BodyMode::Form);
+        assert_eq!(form.fields, [KeyValue::new("user", "ann")]);
+
+        let multipart = &collection.requests[3];
+        assert_eq!(multipart.body_mode, BodyMode::Multipart);
+        assert_eq!(
+            multipart.fields,
+            [
+                KeyValue::new("note", "hi"),
+                KeyValue::new("file", "@a.png;type=image/png")
+            ]
+        );
+        assert!(multipart.body.is_empty());
+    }
+
+    #[test]
+    fn leaves_what_a_har_file_has_no_equivalent_for_unset() {
+        let collection = import(har(), "Captured").unwrap();
+        for request in &collection.requests {
+            assert_eq!(request.auth.kind, crate::auth::AuthKind::None);
+            assert!(request.settings.values.is_empty());
+            assert!(request.assertions.is_empty());
+            assert!(request.pre_request.is_empty());
+            assert!(request.requires.is_empty());
+        }
+    }
+
+    #[test]
+    fn imports_an_exported_entry_back() {
+        let request = Request {
+            method: Method::POST,
+            url: String::from("https://shop.test/files"),
+            headers: vec![KeyValue::new("X-Id", "7")],
+            body_mode: BodyMode::Multipart,
+            fields: vec![
+                KeyValue::new("note", "hi"),
+                KeyValue::new("file", "@/tmp/a.png;type=image/png"),
+            ],
+            ..Default::default()
+        };
+        let response = ResponseData::new(
+            StatusCode::OK,
+            Version::HTTP_11,
+            HeaderMap::new(),
+            Duration::from_millis(5),
+            b"ok",
+            true,
+        );
+        let mut har = Har::session();
+        har.log.entries.push(Entry::new(&request, &response));
+        let collection = import(serde_json::to_value(&har).unwrap(), "Session").unwrap();
+        let imported = &collection.requests[0];
+        assert_eq!(imported.method, "POST");
+        assert_eq!(imported.url, "https://shop.test/files");
+        assert_eq!(imported.headers, request.headers);
+        assert_eq!(imported.body_mode, BodyMode::Multipart);
+        assert_eq!(imported.fields, request.fields);
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_har_file() {
+        assert!(import(json!({"log": {"entries": 1}}), "Bad").is_err());
+    }
+}
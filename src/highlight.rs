@@ -0,0 +1,94 @@
+use crate::theme::Theme;
+use tui::style::Style;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Key,
+    String,
+    Number,
+    /// `true`, `false` and `null`.
+    Literal,
+    Punctuation,
+    Whitespace,
+}
+
+impl TokenKind {
+    pub fn style(self, theme: &Theme) -> Style {
+        match self {
+            TokenKind::Key => Style::default().fg(theme.key),
+            TokenKind::String => Style::default().fg(theme.string),
+            TokenKind::Number => Style::default().fg(theme.number),
+            TokenKind::Literal => Style::default().fg(theme.literal),
+            TokenKind::Punctuation => Style::default().fg(theme.punctuation),
+            TokenKind::Whitespace => Style::default(),
+        }
+    }
+}
+
+/// A token as a byte range of the line it was found in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+/// Splits one line of pretty-printed JSON into tokens covering the whole line.
+///
+/// This works line by line because jsonxf never breaks a string across lines.
+/// Anything unexpected is tokenized as punctuation rather than rejected.
+pub fn json_line_tokens(line: &str) -> Vec<Token> {
+    let bytes = line.as_bytes();
+    let mut tokens = Vec::new();
+    let mut n = 0;
+    while n < bytes.len() {
+        let start = n;
+        let kind = match bytes[n] {
+            b'"' => {
+                n += 1;
+                while n < bytes.len() && bytes[n] != b'"' {
+                    n += if bytes[n] == b'\\' { 2 } else { 1 };
+                }
+                n = (n + 1).min(bytes.len());
+                // A string followed by a colon is an object key
+                let rest = line[n..].trim_start();
+                if rest.starts_with(':') {
+                    TokenKind::Key
+                } else {
+                    TokenKind::String
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                while n < bytes.len()
+                    && matches!(bytes[n], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                {
+                    n += 1;
+                }
+                TokenKind::Number
+            }
+            b'a'..=b'z' => {
+                while n < bytes.len() && bytes[n].is_ascii_alphabetic() {
+                    n += 1;
+                }
+                TokenKind::Literal
+            }
+            b' ' | b'\t' | b'\r' => {
+                while n < bytes.len() && matches!(bytes[n], b' ' | b'\t' | b'\r') {
+                    n += 1;
+                }
+                TokenKind::Whitespace
+            }
+            _ => {
+                // Step over a whole character so ranges stay on char boundaries
+                n += line[n..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Punctuation
+            }
+        };
+        tokens.push(Token {
+            start,
+            end: n,
+            kind,
+        });
+    }
+    tokens
+}
@@ -0,0 +1,136 @@
+use crate::body::BodyMode;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::request::Request;
+use crate::storage;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Oldest entries are dropped once the history grows past this.
+const MAX_ENTRIES: usize = 500;
+
+/// One sent request, as stored in `history.json`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: Vec<KeyValue>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub body_mode: BodyMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<KeyValue>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub variables: String,
+    /// Missing when the request failed before a response arrived.
+    pub status: Option<u16>,
+    /// How long the response took, missing too for entries from before
+    /// it was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl HistoryEntry {
+    pub fn new(request: &Request, status: Option<u16>) -> HistoryEntry {
+        HistoryEntry {
+            method: request.method.to_string(),
+            url: request.url.clone(),
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+            body_mode: request.body_mode,
+            fields: request.fields.clone(),
+            variables: request.variables.clone(),
+            status,
+            elapsed_ms: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// e.g. `2024-05-01 13:37:00  GET https://example.com  200`
+    pub fn summary(&self) -> String {
+        let status = self
+            .status
+            .map_or_else(|| String::from("failed"), |s| s.to_string());
+        format!(
+            "{}  {} {}  {}",
+            self.timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            self.method,
+            self.url,
+            status
+        )
+    }
+}
+
+/// Every request sent, oldest first, persisted to the data directory.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    fn path() -> PathBuf {
+        storage::data_dir().join("history.json")
+    }
+
+    pub fn load() -> Result<History, AppError> {
+        storage::load_json(&History::path())
+    }
+
+    /// Appends `entry` and writes the whole history back to disk.
+    pub fn push(&mut self, entry: HistoryEntry) -> Result<(), AppError> {
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            let excess = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..excess);
+        }
+        storage::save_json(&History::path(), self)
+    }
+
+    /// Distinct URLs sent before that contain `text`, ignoring case, at most
+    /// `limit` of them. Those starting with it come first, then the newest.
+    pub fn urls_matching(&self, text: &str, limit: usize) -> Vec<String> {
+        let text = text.to_lowercase();
+        let mut starting: Vec<String> = Vec::new();
+        let mut containing: Vec<String> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            let url = entry.url.to_lowercase();
+            if url == text || starting.contains(&entry.url) || containing.contains(&entry.url) {
+                continue;
+            }
+            if url.starts_with(&text) {
+                starting.push(entry.url.clone());
+            } else if url.contains(&text) {
+                containing.push(entry.url.clone());
+            }
+        }
+        starting.extend(containing);
+        starting.truncate(limit);
+        starting
+    }
+
+    /// The last `limit` distinct URLs sent, newest first.
+    pub fn recent_urls(&self, limit: usize) -> Vec<String> {
+        self.urls_matching("", limit)
+    }
+
+    /// When each response to `method` on `url`, as written, came and how
+    /// many milliseconds it took, oldest first.
+    pub fn response_times(&self, method: &str, url: &str) -> Vec<(DateTime<Utc>, u64)> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.method == method && entry.url == url)
+            .filter_map(|entry| Some((entry.timestamp, entry.elapsed_ms?)))
+            .collect()
+    }
+
+    /// The entry `index` places from the newest, as listed in the history popup.
+    pub fn newest(&self, index: usize) -> Option<&HistoryEntry> {
+        self.entries.iter().rev().nth(index)
+    }
+}
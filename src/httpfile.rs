@@ -0,0 +1,308 @@
+use crate::collections::{Collection, SavedRequest};
+use crate::environment::Environment;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::settings::Settings;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Whether `path` is named like a VS Code REST Client or JetBrains HTTP
+/// Client request file.
+pub fn detect(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("http") || ext.eq_ignore_ascii_case("rest"))
+}
+
+/// The requests in a `.http` file, and the `@name = value` variables it
+/// defines for their `{{name}}`s.
+#[derive(Debug, Default)]
+pub struct HttpFile {
+    pub variables: BTreeMap<String, String>,
+    pub requests: Vec<SavedRequest>,
+}
+
+impl HttpFile {
+    pub fn load(path: &Path) -> Result<HttpFile, AppError> {
+        let contents = fs::read_to_string(path)?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        parse(&contents, dir).map_err(|e| AppError::Parse(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The requests as a collection named `name`, and the variables as an
+    /// environment of the same name if there are any.
+    pub fn import(self, name: &str) -> (Collection, Option<Environment>) {
+        let environment = (!self.variables.is_empty()).then(|| Environment {
+            name: String::from(name),
+            variables: self.variables,
+            settings: Settings::default(),
+        });
+        let collection = Collection {
+            name: String::from(name),
+            requests: self.requests,
+        };
+        (collection, environment)
+    }
+}
+
+/// Where the parser is within the current request.
+#[derive(PartialEq)]
+enum Part {
+    /// Before the request line, where comments and variables go.
+    Start,
+    Headers,
+    Body,
+}
+
+/// Reads requests separated by `###` lines. Each has a request line such as
+/// `POST https://example.com/users HTTP/1.1` or just a URL, then headers, a
+/// blank line and the body. A body of `< path` is read from that file,
+/// relative to `dir`. `# @name` comments name the request, and response
+/// handlers (`> ...`) are skipped.
+fn parse(contents: &str, dir: &Path) -> Result<HttpFile, String> {
+    let mut file = HttpFile::default();
+    let mut current: Option<SavedRequest> = None;
+    let mut name = String::new();
+    let mut body: Vec<&str> = Vec::new();
+    let mut part = Part::Start;
+    let mut in_handler = false;
+    let finish = |file: &mut HttpFile,
+                  current: &mut Option<SavedRequest>,
+                  body: &mut Vec<&str>|
+     -> Result<(), String> {
+        if let Some(mut request) = current.take() {
+            while body.last().is_some_and(|line| line.trim().is_empty()) {
+                body.pop();
+            }
+            request.body = match body.as_slice() {
+                [line] if line.starts_with("< ") => {
+                    let path = dir.join(line[2..].trim());
+                    fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?
+                }
+                lines => lines.join("\n"),
+            };
+            file.requests.push(request);
+        }
+        body.clear();
+        Ok(())
+    };
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if let Some(separator) = trimmed.strip_prefix("###") {
+            finish(&mut file, &mut current, &mut body)?;
+            name = String::from(separator.trim());
+            part = Part::Start;
+            in_handler = false;
+            continue;
+        }
+        match part {
+            Part::Start => {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                if let Some(comment) = trimmed
+                    .strip_prefix('#')
+                    .or_else(|| trimmed.strip_prefix("//"))
+                {
+                    if let Some(named) = comment.trim().strip_prefix("@name") {
+                        name = String::from(named.trim_start_matches([' ', '=']).trim());
+                    }
+                    continue;
+                }
+                if let Some(variable) = trimmed.strip_prefix('@') {
+                    let (key, value) = variable
+                        .split_once('=')
+                        .ok_or_else(|| format!("malformed variable {}", trimmed))?;
+                    // Variables can be built from ones defined above them
+                    let mut value = String::from(value.trim());
+                    for (earlier, earlier_value) in &file.variables {
+                        value = value.replace(&format!("{{{{{}}}}}", earlier), earlier_value);
+                    }
+                    file.variables.insert(String::from(key.trim()), value);
+                    continue;
+                }
+                let mut words = trimmed.split_whitespace();
+                let (method, url) = match (words.next(), words.next()) {
+                    (Some(method), Some(url)) if !method.contains("://") => {
+                        (method.to_ascii_uppercase(), url)
+                    }
+                    (Some(url), _) => (String::from("GET"), url),
+                    _ => continue,
+                };
+                let label = if name.is_empty() {
+                    format!("{} {}", method, url)
+                } else {
+                    std::mem::take(&mut name)
+                };
+                current = Some(SavedRequest {
+                    name: label,
+                    method,
+                    url: String::from(url),
+                    ..Default::default()
+                });
+                part = Part::Headers;
+            }
+            Part::Headers => {
+                let request = current.as_mut().expect("a request line came first");
+                if trimmed.is_empty() {
+                    part = Part::Body;
+                } else if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                    continue;
+                } else if line.starts_with('>') {
+                    // A handler can follow the headers of a request without a body
+                    part = Part::Body;
+                    in_handler = line.contains("{%") && !trimmed.ends_with("%}");
+                } else if trimmed.starts_with('?') || trimmed.starts_with('&') {
+                    // A long query string continued over several lines
+                    request.url.push_str(trimmed);
+                } else {
+                    let (key, value) = trimmed
+                        .split_once(':')
+                        .ok_or_else(|| format!("malformed header {}", trimmed))?;
+                    request
+                        .headers
+                        .push(KeyValue::new(key.trim(), value.trim()));
+                }
+            }
+            Part::Body if in_handler => in_handler = !trimmed.ends_with("%}"),
+            Part::Body => {
+                if line.starts_with('>') {
+                    // A `> {% ... %}` script may go on over several lines
+                    in_handler = line.contains("{%") && !trimmed.ends_with("%}");
+                } else {
+                    body.push(line);
+                }
+            }
+        }
+    }
+    finish(&mut file, &mut current, &mut body)?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<HttpFile, String> {
+        super::parse(contents, Path::new("."))
+    }
+
+    fn names(file: &HttpFile) -> Vec<&str> {
+        file.requests.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_requests_between_separators() {
+        let file = parse(
+            "### List users
+GET https://example.com/users HTTP/1.1
+Accept: application/json
+
+### 
+# @name create
+POST https://example.com/users
+Content-Type: application/json
+
+{\"name\": \"Ada\"}
+
+###
+// @name = remove
+delete https://example.com/users/1
+",
+        )
+        .unwrap();
+        assert_eq!(names(&file), ["List users", "create", "remove"]);
+        let methods: Vec<&str> = file.requests.iter().map(|r| r.method.as_str()).collect();
+        assert_eq!(methods, ["GET", "POST", "DELETE"]);
+        assert_eq!(file.requests[0].headers[0].key, "Accept");
+        assert_eq!(file.requests[0].headers[0].value, "application/json");
+        assert_eq!(file.requests[0].body, "");
+        assert_eq!(file.requests[1].body, "{\"name\": \"Ada\"}");
+    }
+
+    #[test]
+    fn reads_variables_built_from_earlier_ones() {
+        let file = parse(
+            "@host = example.com
+@base = https://{{host}}/v1
+@token=abc
+
+GET {{base}}/me
+",
+        )
+        .unwrap();
+        assert_eq!(file.variables["host"], "example.com");
+        assert_eq!(file.variables["base"], "https://example.com/v1");
+        assert_eq!(file.variables["token"], "abc");
+        assert_eq!(file.requests[0].url, "{{base}}/me");
+    }
+
+    #[test]
+    fn reads_a_url_without_a_method() {
+        let file = parse("https://example.com/health\n").unwrap();
+        assert_eq!(file.requests[0].method, "GET");
+        assert_eq!(file.requests[0].url, "https://example.com/health");
+        assert_eq!(names(&file), ["GET https://example.com/health"]);
+    }
+
+    #[test]
+    fn joins_query_continuation_lines() {
+        let file = parse(
+            "GET https://example.com/search
+    ?q=rust
+    &page=2
+Accept: */*
+",
+        )
+        .unwrap();
+        assert_eq!(
+            file.requests[0].url,
+            "https://example.com/search?q=rust&page=2"
+        );
+        assert_eq!(file.requests[0].headers.len(), 1);
+    }
+
+    #[test]
+    fn trims_trailing_blank_lines_and_skips_handlers() {
+        let file = parse(
+            "POST https://example.com
+
+line one
+
+line three
+
+
+> {%
+    client.global.set(\"token\", response.body.token);
+%}
+
+### 
+POST https://example.com
+> {% client.log(\"one line\"); %}
+",
+        )
+        .unwrap();
+        assert_eq!(file.requests[0].body, "line one\n\nline three");
+        assert_eq!(file.requests.len(), 2);
+        assert_eq!(file.requests[1].body, "");
+    }
+
+    #[test]
+    fn reads_bodies_from_files() {
+        let dir = std::env::temp_dir().join(format!("httpfile-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("body.json"), "{\"id\": 1}").unwrap();
+        let file = super::parse("POST https://example.com\n\n< body.json\n\n", &dir);
+        let missing = super::parse("POST https://example.com\n\n< missing.json\n", &dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(file.unwrap().requests[0].body, "{\"id\": 1}");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(parse("@host example.com\nGET https://{{host}}\n").is_err());
+        assert!(parse("GET https://example.com\nAccept application/json\n").is_err());
+    }
+}
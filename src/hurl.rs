@@ -0,0 +1,480 @@
+use crate::assertion::{Assertion, Query};
+use crate::body::BodyMode;
+use crate::collections::SavedRequest;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use url::form_urlencoded;
+
+/// One request of a Hurl file, with what's expected of its response as the
+/// request's assertions.
+#[derive(Debug)]
+pub struct Entry {
+    pub request: SavedRequest,
+    /// Variables set from the response for later entries, as
+    /// `(name, query)`.
+    pub captures: Vec<(String, String)>,
+}
+
+pub fn detect(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("hurl"))
+}
+
+pub fn load(path: &Path) -> Result<Vec<Entry>, AppError> {
+    let contents = fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    parse(&contents, dir).map_err(|e| AppError::Parse(format!("{}: {}", path.display(), e)))
+}
+
+/// Where the parser is within the current entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Part {
+    Headers,
+    Query,
+    Form,
+    Multipart,
+    BasicAuth,
+    Cookies,
+    /// A section with nothing needed from it, e.g. `[Options]`.
+    Ignored,
+    /// A JSON or XML body, going on until the response line.
+    Body,
+    /// A ```` ``` ```` body, going on until the closing fence.
+    Fenced,
+    ResponseHeaders,
+    Captures,
+    Asserts,
+}
+
+/// The sections an entry can have, e.g. `[Asserts]`. Other bracketed
+/// lines, such as a JSON body's `[true]`, aren't sections.
+const SECTIONS: [&str; 11] = [
+    "QueryStringParams",
+    "Query",
+    "FormParams",
+    "Form",
+    "MultipartFormData",
+    "Multipart",
+    "BasicAuth",
+    "Cookies",
+    "Options",
+    "Captures",
+    "Asserts",
+];
+
+/// `METHOD url`, if `line` starts an entry.
+fn request_line(line: &str) -> Option<(&str, &str)> {
+    let (method, url) = line.split_once(char::is_whitespace)?;
+    let is_method = !method.is_empty() && method.bytes().all(|b| b.is_ascii_uppercase());
+    (is_method && method != "HTTP").then_some((method, url.trim()))
+}
+
+fn pair(line: &str) -> Result<(&str, &str), String> {
+    line.split_once(':')
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .ok_or_else(|| format!("expected name: value, got {}", line))
+}
+
+/// A `file,path;` value as a multipart field's `@path`, relative to `dir`,
+/// with any content type after the `;`.
+fn file_field(value: &str, dir: &Path) -> String {
+    let Some(rest) = value.strip_prefix("file,") else {
+        return String::from(value);
+    };
+    let (path, content_type) = rest.split_once(';').unwrap_or((rest, ""));
+    let path = dir.join(path.trim()).display().to_string();
+    match content_type.trim() {
+        "" => format!("@{}", path),
+        content_type => format!("@{};type={}", path, content_type),
+    }
+}
+
+/// Reads a Hurl file: entries of a request line, headers, sections such as
+/// `[QueryStringParams]` or `[FormParams]`, a body, then an optional
+/// `HTTP 200` response line with headers, `[Captures]` and `[Asserts]`.
+/// Bodies may be JSON, XML, fenced with ```` ``` ````, a `` `one line` ``
+/// or `file,path;`.
+fn parse(contents: &str, dir: &Path) -> Result<Vec<Entry>, String> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut part = Part::Headers;
+    let mut body: Vec<&str> = Vec::new();
+    let finish_body = |entries: &mut Vec<Entry>, body: &mut Vec<&str>| {
+        if let Some(entry) = entries.last_mut() {
+            if !body.is_empty() {
+                entry.request.body = body.join("\n");
+                body.clear();
+            }
+        }
+    };
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if part == Part::Fenced {
+            if trimmed == "```" {
+                finish_body(&mut entries, &mut body);
+                part = Part::Headers;
+            } else {
+                body.push(line);
+            }
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some((method, url)) = request_line(trimmed) {
+            finish_body(&mut entries, &mut body);
+            entries.push(Entry {
+                request: SavedRequest {
+                    name: format!("{} {}", method, url),
+                    method: String::from(method),
+                    url: String::from(url),
+                    ..Default::default()
+                },
+                captures: Vec::new(),
+            });
+            part = Part::Headers;
+            continue;
+        }
+        let entry = entries
+            .last_mut()
+            .ok_or_else(|| format!("expected a request line, got {}", trimmed))?;
+        if let Some(status) = trimmed.strip_prefix("HTTP") {
+            finish_body(&mut entries, &mut body);
+            let entry = entries.last_mut().expect("a request line came first");
+            // `HTTP/1.1 200`, `HTTP 200` or `HTTP *` for any status
+            let status = status.trim_start_matches(|c| c != ' ').trim();
+            if status != "*" {
+                entry
+                    .request
+                    .assertions
+                    .push(format!("status == {}", status));
+            }
+            part = Part::ResponseHeaders;
+            continue;
+        }
+        let request = &mut entry.request;
+        let responding = matches!(part, Part::ResponseHeaders | Part::Captures | Part::Asserts);
+        let section = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+            .filter(|name| SECTIONS.contains(name));
+        if let Some(section) = section {
+            part = match (section, responding) {
+                ("Captures", true) => Part::Captures,
+                ("Asserts", true) => Part::Asserts,
+                ("QueryStringParams" | "Query", false) => Part::Query,
+                ("FormParams" | "Form", false) => {
+                    request.body_mode = BodyMode::Form;
+                    Part::Form
+                }
+                ("MultipartFormData" | "Multipart", false) => {
+                    request.body_mode = BodyMode::Multipart;
+                    Part::Multipart
+                }
+                ("BasicAuth", false) => Part::BasicAuth,
+                ("Cookies", false) => Part::Cookies,
+                (_, false) => Part::Ignored,
+                (section, true) => return Err(format!("unsupported section [{}]", section)),
+            };
+            continue;
+        }
+        if part == Part::Body {
+            body.push(line);
+            continue;
+        }
+        if !responding {
+            if let Some(fence) = trimmed.strip_prefix("```") {
+                // A one-line fence, or the start of one with its language
+                if let Some(text) = fence.strip_suffix("```") {
+                    request.body = String::from(text);
+                } else {
+                    part = Part::Fenced;
+                }
+                continue;
+            }
+            if trimmed.starts_with('{') || trimmed.starts_with('[') || trimmed.starts_with('<') {
+                body.push(line);
+                part = Part::Body;
+                continue;
+            }
+            if let Some(text) = trimmed.strip_prefix('`').and_then(|t| t.strip_suffix('`')) {
+                request.body = String::from(text);
+                continue;
+            }
+            if let Some(file) = trimmed.strip_prefix("file,") {
+                let path = dir.join(file.trim_end_matches(';').trim());
+                request.body =
+                    fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                continue;
+            }
+        }
+        match part {
+            Part::Headers => {
+                let (key, value) = pair(trimmed)?;
+                request.headers.push(KeyValue::new(key, value));
+            }
+            Part::Query => {
+                let (key, value) = pair(trimmed)?;
+                let separator = if request.url.contains('?') { '&' } else { '?' };
+                let encode = |text: &str| {
+                    form_urlencoded::byte_serialize(text.as_bytes()).collect::<String>()
+                };
+                request.url += &format!("{}{}={}", separator, encode(key), encode(value));
+            }
+            Part::Form => {
+                let (key, value) = pair(trimmed)?;
+                request.fields.push(KeyValue::new(key, value));
+            }
+            Part::Multipart => {
+                let (key, value) = pair(trimmed)?;
+                request
+                    .fields
+                    .push(KeyValue::new(key, &file_field(value, dir)));
+            }
+            Part::BasicAuth => {
+                let (user, password) = pair(trimmed)?;
+                let credentials = STANDARD.encode(format!("{}:{}", user, password));
+                request.headers.push(KeyValue::new(
+                    "Authorization",
+                    &format!("Basic {}", credentials),
+                ));
+            }
+            Part::Cookies => {
+                let (key, value) = pair(trimmed)?;
+                let cookie = format!("{}={}", key, value);
+                match request
+                    .headers
+                    .iter_mut()
+                    .find(|kv| kv.key.eq_ignore_ascii_case("Cookie"))
+                {
+                    Some(header) => header.value += &format!("; {}", cookie),
+                    None => request.headers.push(KeyValue::new("Cookie", &cookie)),
+                }
+            }
+            Part::Ignored | Part::Body | Part::Fenced => {}
+            Part::ResponseHeaders => {
+                let (key, value) = pair(trimmed)?;
+                request.assertions.push(format!(
+                    "header {} == {}",
+                    Value::from(key),
+                    Value::from(value)
+                ));
+            }
+            Part::Captures => {
+                let (name, query) = pair(trimmed)?;
+                Query::parse(query)?;
+                entry
+                    .captures
+                    .push((String::from(name), String::from(query)));
+            }
+            Part::Asserts => {
+                Assertion::parse(trimmed)?;
+                request.assertions.push(String::from(trimmed));
+            }
+        }
+    }
+    if part == Part::Fenced {
+        return Err(String::from("unclosed ``` body"));
+    }
+    finish_body(&mut entries, &mut body);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<Vec<Entry>, String> {
+        super::parse(contents, Path::new("/api"))
+    }
+
+    fn header<'a>(request: &'a SavedRequest, name: &str) -> Option<&'a str> {
+        request
+            .headers
+            .iter()
+            .find(|kv| kv.key == name)
+            .map(|kv| kv.value.as_str())
+    }
+
+    fn fields(request: &SavedRequest) -> Vec<(&str, &str)> {
+        request
+            .fields
+            .iter()
+            .map(|kv| (kv.key.as_str(), kv.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_request_sections() {
+        let entries = parse(
+            "# Search
+GET https://example.com/search
+Accept: application/json
+[QueryStringParams]
+q: a b&c
+page: 2
+[BasicAuth]
+ada: secret
+[Cookies]
+theme: dark
+lang: en
+[Options]
+insecure: true
+",
+        )
+        .unwrap();
+        let request = &entries[0].request;
+        assert_eq!(request.name, "GET https://example.com/search");
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://example.com/search?q=a+b%26c&page=2");
+        assert_eq!(header(request, "Accept"), Some("application/json"));
+        assert_eq!(
+            header(request, "Authorization"),
+            Some("Basic YWRhOnNlY3JldA==")
+        );
+        assert_eq!(header(request, "Cookie"), Some("theme=dark; lang=en"));
+        assert_eq!(request.headers.len(), 3);
+    }
+
+    #[test]
+    fn reads_form_and_multipart_fields() {
+        let entries = parse(
+            "POST https://example.com/login
+[FormParams]
+user: ada
+POST https://example.com/upload
+[MultipartFormData]
+title: Notes
+file: file,notes.txt; text/plain
+",
+        )
+        .unwrap();
+        assert_eq!(entries[0].request.body_mode, BodyMode::Form);
+        assert_eq!(fields(&entries[0].request), [("user", "ada")]);
+        assert_eq!(entries[1].request.body_mode, BodyMode::Multipart);
+        assert_eq!(
+            fields(&entries[1].request),
+            [
+                ("title", "Notes"),
+                ("file", "@/api/notes.txt;type=text/plain")
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_bodies() {
+        let entries = parse(
+            r#"POST https://example.com/json
+{
+  "name": "Ada",
+  "tags": [true]
+}
+HTTP 201
+POST https://example.com/array
+[true]
+POST https://example.com/xml
+<user>
+  <name>Ada</name>
+</user>
+POST https://example.com/fenced
+```json
+line one
+
+line three
+```
+POST https://example.com/inline
+```inline```
+POST https://example.com/backtick
+`one line`
+"#,
+        )
+        .unwrap();
+        let bodies: Vec<&str> = entries.iter().map(|e| e.request.body.as_str()).collect();
+        assert_eq!(
+            bodies,
+            [
+                "{\n  \"name\": \"Ada\",\n  \"tags\": [true]\n}",
+                "[true]",
+                "<user>\n  <name>Ada</name>\n</user>",
+                "line one\n\nline three",
+                "inline",
+                "one line",
+            ]
+        );
+        assert_eq!(entries[0].request.assertions, ["status == 201"]);
+    }
+
+    #[test]
+    fn reads_file_bodies() {
+        let dir = std::env::temp_dir().join(format!("hurl-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("body.json"), "{\"id\": 1}").unwrap();
+        let entries = super::parse("POST https://example.com\nfile,body.json;\n", &dir);
+        let missing = super::parse("POST https://example.com\nfile,missing.json;\n", &dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(entries.unwrap()[0].request.body, "{\"id\": 1}");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn reads_responses() {
+        let entries = parse(
+            r#"POST https://example.com/login
+HTTP/1.1 200
+Content-Type: application/json
+[Captures]
+token: jsonpath "$.token"
+[Asserts]
+jsonpath "$.user" exists
+duration < 1000
+
+GET https://example.com/me
+Authorization: Bearer {{token}}
+HTTP *
+[Asserts]
+status < 500
+"#,
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].request.assertions,
+            [
+                "status == 200",
+                r#"header "Content-Type" == "application/json""#,
+                r#"jsonpath "$.user" exists"#,
+                "duration < 1000",
+            ]
+        );
+        assert_eq!(
+            entries[0].captures,
+            [(String::from("token"), String::from(r#"jsonpath "$.token""#))]
+        );
+        assert_eq!(
+            header(&entries[1].request, "Authorization"),
+            Some("Bearer {{token}}")
+        );
+        assert_eq!(entries[1].request.assertions, ["status < 500"]);
+        assert!(entries[1].captures.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        for contents in [
+            "Accept: text/html\n",
+            "GET https://example.com\n```\nnever closed\n",
+            "GET https://example.com\nAccept text/html\n",
+            "GET https://example.com\n[QueryStringParams]\nq\n",
+            "GET https://example.com\nHTTP 200\n[Options]\n",
+            "GET https://example.com\nHTTP 200\n[Unknown]\n",
+            "GET https://example.com\nHTTP 200\n[Asserts]\nstatus is 200\n",
+            "GET https://example.com\nHTTP 200\n[Captures]\ntoken: nothing\n",
+        ] {
+            assert!(parse(contents).is_err(), "{:?}", contents);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What a key did to a single-line field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edit {
+    /// The text changed.
+    Changed,
+    /// Only the cursor moved.
+    Moved,
+    /// The key isn't an editing key.
+    Ignored,
+}
+
+/// Applies an editing key to `text`, whose cursor is the byte offset
+/// `cursor`: typing inserts at the cursor, Left/Right and Home/End move it,
+/// Ctrl (or Alt+b/f) moves by word, and Ctrl+W deletes the word before it.
+pub fn edit(key: &KeyEvent, text: &mut String, cursor: &mut usize) -> Edit {
+    *cursor = clamp(text, *cursor);
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let before = *cursor;
+    match key.code {
+        KeyCode::Left if ctrl => *cursor = word_start(text, *cursor),
+        KeyCode::Right if ctrl => *cursor = word_end(text, *cursor),
+        KeyCode::Char('b') if alt => *cursor = word_start(text, *cursor),
+        KeyCode::Char('f') if alt => *cursor = word_end(text, *cursor),
+        KeyCode::Left => *cursor = previous(text, *cursor),
+        KeyCode::Right => *cursor = next(text, *cursor),
+        KeyCode::Home => *cursor = 0,
+        KeyCode::End => *cursor = text.len(),
+        KeyCode::Char('a') if ctrl => *cursor = 0,
+        KeyCode::Char('e') if ctrl => *cursor = text.len(),
+        KeyCode::Char('w') if ctrl => {
+            let start = word_start(text, *cursor);
+            text.replace_range(start..*cursor, "");
+            *cursor = start;
+            return Edit::Changed;
+        }
+        KeyCode::Backspace if *cursor > 0 => {
+            let start = previous(text, *cursor);
+            text.replace_range(start..*cursor, "");
+            *cursor = start;
+            return Edit::Changed;
+        }
+        KeyCode::Delete if *cursor < text.len() => {
+            let end = next(text, *cursor);
+            text.replace_range(*cursor..end, "");
+            return Edit::Changed;
+        }
+        KeyCode::Char(c) if !ctrl && !alt => {
+            text.insert(*cursor, c);
+            *cursor += c.len_utf8();
+            return Edit::Changed;
+        }
+        _ => return Edit::Ignored,
+    }
+    if *cursor == before {
+        Edit::Ignored
+    } else {
+        Edit::Moved
+    }
+}
+
+/// Inserts `s` at the cursor, leaving the cursor after it.
+pub fn insert(text: &mut String, cursor: &mut usize, s: &str) {
+    *cursor = clamp(text, *cursor);
+    text.insert_str(*cursor, s);
+    *cursor += s.len();
+}
+
+/// `cursor` moved back onto a char boundary within `text`.
+fn clamp(text: &str, cursor: usize) -> usize {
+    let mut cursor = cursor.min(text.len());
+    while !text.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    cursor
+}
+
+fn previous(text: &str, cursor: usize) -> usize {
+    text[..cursor]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+fn next(text: &str, cursor: usize) -> usize {
+    text[cursor..]
+        .chars()
+        .next()
+        .map_or(cursor, |c| cursor + c.len_utf8())
+}
+
+/// Words are runs of letters and digits, so a jump stops at each part of a
+/// URL rather than only at spaces.
+fn word_start(text: &str, cursor: usize) -> usize {
+    let chars: Vec<(usize, char)> = text[..cursor].char_indices().collect();
+    let mut i = chars.len();
+    while i > 0 && !chars[i - 1].1.is_alphanumeric() {
+        i -= 1;
+    }
+    while i > 0 && chars[i - 1].1.is_alphanumeric() {
+        i -= 1;
+    }
+    chars.get(i).map_or(cursor, |(offset, _)| *offset)
+}
+
+fn word_end(text: &str, cursor: usize) -> usize {
+    let mut chars = text[cursor..].char_indices().peekable();
+    while chars.next_if(|(_, c)| !c.is_alphanumeric()).is_some() {}
+    while chars.next_if(|(_, c)| c.is_alphanumeric()).is_some() {}
+    chars
+        .peek()
+        .map_or(text.len(), |(offset, _)| cursor + offset)
+}
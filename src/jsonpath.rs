@@ -0,0 +1,290 @@
+use serde_json::Value;
+
+/// One step of a path.
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    /// `.name` or `['name']`
+    Key(String),
+    /// `[0]`, or `[-1]` counting from the end.
+    Index(i64),
+    /// `.*` or `[*]`
+    Wildcard,
+    /// `..`, the value and every one inside it, at any depth, for the step
+    /// after it to look in, e.g. `..name` or `..[0]`.
+    Descendant,
+}
+
+/// A parsed JSONPath such as `$.items[0].name`. Covers keys, indexes,
+/// wildcards and `..` descent, but not filters or slices. Quoted keys may
+/// escape a quote or backslash with a backslash, e.g. `['it\'s']`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    steps: Vec<Step>,
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<JsonPath, String> {
+        let error = |reason: &str| format!("JSONPath {}: {}", path, reason);
+        let rest = path
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| error("should start with $"))?;
+        let mut steps = Vec::new();
+        let mut chars = rest.chars().peekable();
+        let name = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == '.' || c == '[' {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            name
+        };
+        while let Some(c) = chars.next() {
+            match c {
+                '.' if chars.peek() == Some(&'.') => {
+                    chars.next();
+                    steps.push(Step::Descendant);
+                    // A bracket is read as after any other step
+                    if chars.peek() != Some(&'[') {
+                        match name(&mut chars).as_str() {
+                            "" => return Err(error("a name or [ should follow ..")),
+                            "*" => steps.push(Step::Wildcard),
+                            key => steps.push(Step::Key(String::from(key))),
+                        }
+                    }
+                }
+                '.' => match name(&mut chars).as_str() {
+                    "" => return Err(error("a name should follow .")),
+                    "*" => steps.push(Step::Wildcard),
+                    key => steps.push(Step::Key(String::from(key))),
+                },
+                '[' => {
+                    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                    let quote = chars.next_if(|c| *c == '\'' || *c == '"');
+                    let mut key = String::new();
+                    if let Some(quote) = quote {
+                        loop {
+                            match chars.next() {
+                                Some('\\') => key.extend(chars.next()),
+                                Some(c) if c == quote => break,
+                                Some(c) => key.push(c),
+                                None => return Err(error("unclosed quote")),
+                            }
+                        }
+                    }
+                    let mut inside = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(c) => inside.push(c),
+                            None => return Err(error("unclosed [")),
+                        }
+                    }
+                    let inside = inside.trim();
+                    if quote.is_some() {
+                        if !inside.is_empty() {
+                            return Err(error(&format!("unexpected {} after the key", inside)));
+                        }
+                        steps.push(Step::Key(key));
+                    } else if inside == "*" {
+                        steps.push(Step::Wildcard);
+                    } else {
+                        let index = inside
+                            .parse()
+                            .map_err(|_| error(&format!("unsupported [{}]", inside)))?;
+                        steps.push(Step::Index(index));
+                    }
+                }
+                _ => return Err(error(&format!("unexpected {}", c))),
+            }
+        }
+        Ok(JsonPath { steps })
+    }
+
+    /// Every value the path leads to in `root`, in document order.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for value in current {
+                match step {
+                    Step::Key(key) => next.extend(value.get(key)),
+                    Step::Index(i) => {
+                        if let Some(items) = value.as_array() {
+                            let i = if *i < 0 { items.len() as i64 + i } else { *i };
+                            next.extend(usize::try_from(i).ok().and_then(|i| items.get(i)));
+                        }
+                    }
+                    Step::Wildcard => match value {
+                        Value::Array(items) => next.extend(items),
+                        Value::Object(map) => next.extend(map.values()),
+                        _ => {}
+                    },
+                    Step::Descendant => descendants(value, &mut next),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+
+    /// Sets every value the path leads to in `root` to `with`.
+    pub fn replace(&self, root: &mut Value, with: &Value) {
+        replace(root, &self.steps, with);
+    }
+
+    /// Whether the path can only lead to one value, so a match is that
+    /// value rather than a list of them.
+    pub fn is_single(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|step| matches!(step, Step::Key(_) | Step::Index(_)))
+    }
+}
+
+/// Adds `value` and every value inside it to `found`, in document order.
+fn descendants<'a>(value: &'a Value, found: &mut Vec<&'a Value>) {
+    found.push(value);
+    match value {
+        Value::Object(map) => map.values().for_each(|child| descendants(child, found)),
+        Value::Array(items) => items.iter().for_each(|child| descendants(child, found)),
+        _ => {}
+    }
+}
+
+fn replace(value: &mut Value, steps: &[Step], with: &Value) {
+    let Some((step, rest)) = steps.split_first() else {
+        *value = with.clone();
+        return;
+    };
+    match step {
+        Step::Key(key) => {
+            if let Some(child) = value.get_mut(key) {
+                replace(child, rest, with);
+            }
+        }
+        Step::Index(i) => {
+            if let Some(items) = value.as_array_mut() {
+                let i = if *i < 0 { items.len() as i64 + i } else { *i };
+                if let Some(child) = usize::try_from(i).ok().and_then(|i| items.get_mut(i)) {
+                    replace(child, rest, with);
+                }
+            }
+        }
+        Step::Wildcard => match value {
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|child| replace(child, rest, with)),
+            Value::Object(map) => map
+                .values_mut()
+                .for_each(|child| replace(child, rest, with)),
+            _ => {}
+        },
+        Step::Descendant => replace_descendants(value, rest, with),
+    }
+}
+
+/// As `replace` with `rest` from `value` and every value inside it. Those
+/// inside go first, so nothing is looked for in what's been put in.
+fn replace_descendants(value: &mut Value, rest: &[Step], with: &Value) {
+    match value {
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|child| replace_descendants(child, rest, with)),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|child| replace_descendants(child, rest, with)),
+        _ => {}
+    }
+    replace(value, rest, with);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(path: &str, root: &Value) -> Vec<Value> {
+        let path = JsonPath::parse(path).unwrap();
+        path.select(root).into_iter().cloned().collect()
+    }
+
+    fn document() -> Value {
+        json!({ "a": { "id": 1, "b": [{ "id": 2 }, { "id": 3 }] }, "id": 0 })
+    }
+
+    #[test]
+    fn selects_keys_indexes_and_wildcards() {
+        let root = document();
+        assert_eq!(select("$.a.id", &root), [json!(1)]);
+        assert_eq!(select("$['a'][\"b\"][1].id", &root), [json!(3)]);
+        assert_eq!(select("$.a.b[-1].id", &root), [json!(3)]);
+        assert_eq!(select("$.a.b[*].id", &root), [json!(2), json!(3)]);
+        assert_eq!(select("$.a.b.*.id", &root), [json!(2), json!(3)]);
+        assert_eq!(select("$", &root), vec![root.clone()]);
+        assert!(select("$.missing", &root).is_empty());
+    }
+
+    #[test]
+    fn descends_to_any_depth() {
+        let root = document();
+        assert_eq!(
+            select("$..id", &root),
+            [json!(0), json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(
+            select("$..['id']", &root),
+            [json!(0), json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(select("$..[0]", &root), [json!({ "id": 2 })]);
+        assert_eq!(select("$..b[1].id", &root), [json!(3)]);
+        assert_eq!(select("$..*", &root).len(), 8);
+        assert_eq!(select("$..[*]", &root).len(), 8);
+        assert!(select("$..*", &json!(1)).is_empty());
+    }
+
+    #[test]
+    fn reads_escaped_quotes_and_brackets_in_keys() {
+        let root = json!({ "it's \"x\"": 1, "a]b": 2, "back\\slash": 3 });
+        assert_eq!(select(r#"$['it\'s "x"']"#, &root), [json!(1)]);
+        assert_eq!(select(r#"$["it's \"x\""]"#, &root), [json!(1)]);
+        assert_eq!(select("$['a]b']", &root), [json!(2)]);
+        assert_eq!(select(r"$['back\\slash']", &root), [json!(3)]);
+    }
+
+    #[test]
+    fn replaces_what_it_selects() {
+        let mut root = document();
+        JsonPath::parse("$..id")
+            .unwrap()
+            .replace(&mut root, &json!(9));
+        assert_eq!(
+            select("$..id", &root),
+            [json!(9), json!(9), json!(9), json!(9)]
+        );
+        let mut root = document();
+        JsonPath::parse("$..[0]")
+            .unwrap()
+            .replace(&mut root, &json!(null));
+        assert_eq!(root["a"]["b"], json!([null, { "id": 3 }]));
+    }
+
+    #[test]
+    fn is_single_only_for_keys_and_indexes() {
+        assert!(JsonPath::parse("$.a[0]['b']").unwrap().is_single());
+        assert!(!JsonPath::parse("$.a[*]").unwrap().is_single());
+        assert!(!JsonPath::parse("$..a").unwrap().is_single());
+    }
+
+    #[test]
+    fn rejects_unsupported_paths() {
+        for path in [
+            "a", "$.", "$..", "$...a", "$[", "$['a'", "$['a' b]", "$[a]", "$[1:2]", "$x",
+        ] {
+            assert!(JsonPath::parse(path).is_err(), "{:?}", path);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A JSON array of objects shown as a table, a row per object and a column
+/// per key, sorted by whichever column is picked. Nested objects are
+/// flattened into a column per key, e.g. `address.city`.
+#[derive(Debug)]
+pub struct JsonTable {
+    /// Every key found in the flattened objects, in the order first seen.
+    pub columns: Vec<String>,
+    /// Each object's values, in column order, `None` where it lacks the key.
+    rows: Vec<Vec<Option<Value>>>,
+    pub selected: usize,
+    pub column: usize,
+    /// The first row in view.
+    pub scroll: usize,
+    /// The column sorted by, and whether it's sorted from the largest.
+    pub sorted: Option<(usize, bool)>,
+}
+
+impl JsonTable {
+    /// The table of `value`, unless it isn't a non-empty array of objects.
+    pub fn new(value: &Value) -> Option<JsonTable> {
+        let objects = value
+            .as_array()
+            .filter(|items| !items.is_empty())?
+            .iter()
+            .map(Value::as_object)
+            .collect::<Option<Vec<_>>>()?;
+        let flattened: Vec<Vec<(String, Value)>> = objects
+            .into_iter()
+            .map(|object| {
+                let mut flat = Vec::new();
+                flatten("", object, &mut flat);
+                flat
+            })
+            .collect();
+        let mut columns: Vec<String> = Vec::new();
+        for (key, _) in flattened.iter().flatten() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        let rows = flattened
+            .into_iter()
+            .map(|flat| {
+                let mut values: HashMap<String, Value> = flat.into_iter().collect();
+                columns.iter().map(|key| values.remove(key)).collect()
+            })
+            .collect();
+        Some(JsonTable {
+            columns,
+            rows,
+            selected: 0,
+            column: 0,
+            scroll: 0,
+            sorted: None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Row `row`'s value in `column` as shown: strings without their
+    /// quotes, anything else as JSON.
+    pub fn cell(&self, row: usize, column: usize) -> String {
+        match &self.rows[row][column] {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        }
+    }
+
+    pub fn select(&mut self, step: isize) {
+        let last = self.rows.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(step).min(last);
+    }
+
+    pub fn select_column(&mut self, step: isize) {
+        let last = self.columns.len().saturating_sub(1);
+        self.column = self.column.saturating_add_signed(step).min(last);
+    }
+
+    /// Sorts by the selected column, from the smallest first, or the other
+    /// way round if it's sorted so already. Rows without the key go last.
+    pub fn sort(&mut self) {
+        let column = self.column;
+        let descending = self.sorted == Some((column, false));
+        self.rows.sort_by(|a, b| match (&a[column], &b[column]) {
+            (Some(a), Some(b)) if descending => compare(b, a),
+            (Some(a), Some(b)) => compare(a, b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        });
+        self.sorted = Some((column, descending));
+        self.selected = 0;
+    }
+
+    /// The table as CSV, a header row of the column names and then the rows
+    /// in the order shown.
+    pub fn to_csv(&self) -> String {
+        let line = |cells: Vec<String>| {
+            let fields: Vec<String> = cells.iter().map(|cell| csv_field(cell)).collect();
+            fields.join(",") + "\n"
+        };
+        let mut csv = line(self.columns.clone());
+        for row in 0..self.len() {
+            csv += &line((0..self.columns.len()).map(|i| self.cell(row, i)).collect());
+        }
+        csv
+    }
+
+    /// Scrolls so the selected row is among the `height` in view.
+    pub fn scroll_into_view(&mut self, height: usize) {
+        let height = height.max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+    }
+}
+
+/// Adds `object`'s values to `flat`, keyed by their names after `prefix`,
+/// with those of objects inside it added in turn.
+fn flatten(prefix: &str, object: &Map<String, Value>, flat: &mut Vec<(String, Value)>) {
+    for (key, value) in object {
+        let key = match prefix {
+            "" => key.clone(),
+            _ => format!("{}.{}", prefix, key),
+        };
+        match value {
+            Value::Object(inner) if !inner.is_empty() => flatten(&key, inner, flat),
+            _ => flat.push((key, value.clone())),
+        }
+    }
+}
+
+/// `cell` quoted for CSV if it needs to be.
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        String::from(cell)
+    }
+}
+
+/// Numbers by size, anything else by its text.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
@@ -0,0 +1,349 @@
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// One step from a value to one inside it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// A line of the tree as drawn: a value, with where it sits.
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub path: Vec<Segment>,
+    pub depth: usize,
+    /// e.g. `"name": ` or `0: `, empty for the root.
+    pub label: String,
+    /// The value itself, or how many it holds for an object or array.
+    pub value: String,
+    /// Set for an object or array that isn't empty, to whether it's expanded.
+    pub expanded: Option<bool>,
+}
+
+/// A JSON body browsed as a tree of values, with objects and arrays
+/// expanded and collapsed in place.
+#[derive(Debug)]
+pub struct JsonTree {
+    root: Value,
+    /// Paths of the objects and arrays whose contents are shown.
+    expanded: HashSet<Vec<Segment>>,
+    /// Those shown, in document order.
+    nodes: Vec<Node>,
+    pub selected: usize,
+    /// The first node in view.
+    pub scroll: usize,
+}
+
+/// Levels expanded when the tree is first shown; deeper ones start collapsed.
+const OPEN_DEPTH: usize = 2;
+
+impl JsonTree {
+    pub fn parse(text: &str) -> Result<JsonTree, String> {
+        let root: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let mut expanded = HashSet::new();
+        expand_to(&root, &mut Vec::new(), OPEN_DEPTH, &mut expanded);
+        let mut tree = JsonTree {
+            root,
+            expanded,
+            nodes: Vec::new(),
+            selected: 0,
+            scroll: 0,
+        };
+        tree.rebuild();
+        Ok(tree)
+    }
+
+    fn rebuild(&mut self) {
+        self.nodes.clear();
+        walk(
+            &self.root,
+            &mut Vec::new(),
+            String::new(),
+            &self.expanded,
+            &mut self.nodes,
+        );
+        self.selected = self.selected.min(self.nodes.len().saturating_sub(1));
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn selected_node(&self) -> Option<&Node> {
+        self.nodes.get(self.selected)
+    }
+
+    /// The value the selected node stands for.
+    pub fn selected_value(&self) -> Option<&Value> {
+        let node = self.selected_node()?;
+        node.path
+            .iter()
+            .try_fold(&self.root, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(i) => value.get(i),
+            })
+    }
+
+    pub fn select(&mut self, step: isize) {
+        let last = self.nodes.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(step).min(last);
+    }
+
+    /// Expands or collapses the selected object or array.
+    pub fn toggle(&mut self) {
+        match self.selected_node().and_then(|node| node.expanded) {
+            Some(true) => self.collapse(),
+            Some(false) => self.expand(),
+            None => {}
+        }
+    }
+
+    /// Expands the selected object or array, or moves into it if it
+    /// already is.
+    pub fn expand(&mut self) {
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        match node.expanded {
+            Some(false) => {
+                self.expanded.insert(node.path.clone());
+                self.rebuild();
+            }
+            Some(true) => self.select(1),
+            None => {}
+        }
+    }
+
+    /// Collapses the selected object or array, or moves out to the one
+    /// holding it if it already is or has nothing to collapse.
+    pub fn collapse(&mut self) {
+        let Some(node) = self.nodes.get(self.selected) else {
+            return;
+        };
+        if node.expanded == Some(true) {
+            self.expanded.remove(&node.path);
+            self.rebuild();
+            return;
+        }
+        let parent = &node.path[..node.path.len().saturating_sub(1)];
+        if let Some(i) = self.nodes[..self.selected]
+            .iter()
+            .rposition(|node| node.path == parent)
+        {
+            self.selected = i;
+        }
+    }
+
+    /// The JSONPath of the selected node, e.g. `$.items[0].name`.
+    pub fn path(&self) -> String {
+        path(self.selected_node().map_or(&[], |node| &node.path))
+    }
+
+    /// The selected node's path as jq writes it, e.g. `.items[0].name`.
+    pub fn jq_path(&self) -> String {
+        jq_path(self.selected_node().map_or(&[], |node| &node.path))
+    }
+
+    /// Scrolls so the selected node is among the `height` in view.
+    pub fn scroll_into_view(&mut self, height: usize) {
+        let height = height.max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+    }
+}
+
+fn expand_to(
+    value: &Value,
+    path: &mut Vec<Segment>,
+    depth: usize,
+    expanded: &mut HashSet<Vec<Segment>>,
+) {
+    if depth == 0 {
+        return;
+    }
+    let children: Vec<(Segment, &Value)> = match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, child)| (Segment::Key(key.clone()), child))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (Segment::Index(i), child))
+            .collect(),
+        _ => return,
+    };
+    expanded.insert(path.clone());
+    for (segment, child) in children {
+        path.push(segment);
+        expand_to(child, path, depth - 1, expanded);
+        path.pop();
+    }
+}
+
+fn walk(
+    value: &Value,
+    path: &mut Vec<Segment>,
+    label: String,
+    expanded: &HashSet<Vec<Segment>>,
+    nodes: &mut Vec<Node>,
+) {
+    let (summary, len) = match value {
+        Value::Object(map) => (count(map.len(), "key", "{}"), map.len()),
+        Value::Array(items) => (count(items.len(), "item", "[]"), items.len()),
+        leaf => (leaf.to_string(), 0),
+    };
+    let open = len > 0 && expanded.contains(path);
+    nodes.push(Node {
+        path: path.clone(),
+        depth: path.len(),
+        label,
+        value: summary,
+        expanded: (len > 0).then_some(open),
+    });
+    if !open {
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                path.push(Segment::Key(key.clone()));
+                walk(
+                    child,
+                    path,
+                    format!("{}: ", Value::from(key.as_str())),
+                    expanded,
+                    nodes,
+                );
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                path.push(Segment::Index(i));
+                walk(child, path, format!("{}: ", i), expanded, nodes);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// e.g. `{3 keys}`, or `{}` when empty.
+fn count(n: usize, noun: &str, brackets: &str) -> String {
+    let (open, close) = brackets.split_at(1);
+    match n {
+        0 => String::from(brackets),
+        1 => format!("{}1 {}{}", open, noun, close),
+        n => format!("{}{} {}s{}", open, n, noun, close),
+    }
+}
+
+/// Whether `key` can follow a `.` in a path without quotes.
+fn is_plain(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// `segments` as a JSONPath, quoting keys that aren't plain names.
+fn path(segments: &[Segment]) -> String {
+    let mut path = String::from("$");
+    for segment in segments {
+        match segment {
+            Segment::Key(key) if is_plain(key) => path += &format!(".{}", key),
+            Segment::Key(key) => path += &format!("[{}]", quoted(key)),
+            Segment::Index(i) => path += &format!("[{}]", i),
+        }
+    }
+    path
+}
+
+/// `key` in single quotes, or double if it has only single ones in it,
+/// with the quote and any backslashes escaped.
+fn quoted(key: &str) -> String {
+    let quote = if key.contains('\'') && !key.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut quoted = String::from(quote);
+    for c in key.chars() {
+        if c == quote || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push(quote);
+    quoted
+}
+
+/// `segments` as a jq path, `.` for the root.
+fn jq_path(segments: &[Segment]) -> String {
+    let path: String = segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Key(key) if is_plain(key) => format!(".{}", key),
+            Segment::Key(key) => format!(".{}", Value::from(key.as_str())),
+            Segment::Index(i) => format!("[{}]", i),
+        })
+        .collect();
+    match path.as_str() {
+        "" => String::from("."),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonpath::JsonPath;
+    use serde_json::json;
+
+    #[test]
+    fn paths_parse_back_to_the_value() {
+        let root = json!({
+            "plain": { "items": [1, { "it's": 2 }] },
+            "a b": { "say \"hi\"": 3, "both ' \"": 4, "back\\slash]": 5 },
+        });
+        let mut tree = JsonTree::parse(&root.to_string()).unwrap();
+        // Expand everything, so every value has a node
+        while tree.nodes().iter().any(|node| node.expanded == Some(false)) {
+            tree.selected = tree
+                .nodes()
+                .iter()
+                .position(|node| node.expanded == Some(false))
+                .unwrap();
+            tree.expand();
+        }
+        for i in 0..tree.nodes().len() {
+            tree.selected = i;
+            let path = JsonPath::parse(&tree.path()).unwrap();
+            let value = tree.selected_value().unwrap();
+            assert_eq!(path.select(&root), [value], "{}", tree.path());
+        }
+    }
+
+    #[test]
+    fn writes_paths() {
+        let segments = |keys: &[&str]| -> Vec<Segment> {
+            keys.iter()
+                .map(|key| Segment::Key(String::from(*key)))
+                .collect()
+        };
+        assert_eq!(path(&[]), "$");
+        assert_eq!(
+            path(&[Segment::Key(String::from("a")), Segment::Index(0)]),
+            "$.a[0]"
+        );
+        assert_eq!(path(&segments(&["a b"])), "$['a b']");
+        assert_eq!(path(&segments(&["it's"])), "$[\"it's\"]");
+        assert_eq!(path(&segments(&["it's \"x\""])), r#"$['it\'s "x"']"#);
+        assert_eq!(jq_path(&segments(&["a", "b c"])), ".a.\"b c\"");
+        assert_eq!(jq_path(&[]), ".");
+    }
+}
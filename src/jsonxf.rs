@@ -182,7 +182,7 @@ impl Formatter {
     /// fmt.trailing_output = String::from("\n");
     /// match fmt.format_stream(&mut std::io::stdin(), &mut std::io::stdout()) {
     ///     Ok(_) => { /* YAY */ },
-    ///     Err(e) => { panic!(e.to_string()); }
+    ///     Err(e) => { panic!("{}", e); }
     /// }
     /// ```
     pub fn format_stream(
@@ -402,7 +402,7 @@ pub fn pretty_print(json_string: &str) -> Result<String, String> {
 /// ```no_run
 /// match jsonxf::pretty_print_stream(&mut std::io::stdin(), &mut std::io::stdout()) {
 ///     Ok(_) => { /* YAY */ },
-///     Err(e) => { panic!(e.to_string()) }
+///     Err(e) => { panic!("{}", e) }
 /// };
 /// ```
 ///
@@ -451,7 +451,7 @@ pub fn minimize(json_string: &str) -> Result<String, String> {
 /// ```no_run
 /// match jsonxf::minimize_stream(&mut std::io::stdin(), &mut std::io::stdout()) {
 ///     Ok(_) => { /* YAY */ },
-///     Err(e) => { panic!(e.to_string()) }
+///     Err(e) => { panic!("{}", e) }
 /// };
 /// ```
 ///
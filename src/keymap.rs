@@ -0,0 +1,1162 @@
+use crate::app::App;
+use crate::body::BodyMode;
+use crate::focus::Focus;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something a key does in Normal mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    FocusLeft,
+    FocusDown,
+    FocusUp,
+    FocusRight,
+    NextPane,
+    PreviousPane,
+    CommandLine,
+    Palette,
+    Finder,
+    Help,
+    Visual,
+    JsonTree,
+    JsonTable,
+    /// Needs pressing twice, as with vim's `gg`.
+    ScrollTop,
+    ScrollBottom,
+    ScrollDown,
+    ScrollUp,
+    PageDown,
+    PageUp,
+    CopyBody,
+    CopyHeaders,
+    CopyMatch,
+    SaveResponse,
+    Filter,
+    Unfilter,
+    ToggleRaw,
+    ToggleMetadata,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    Authorize,
+    EditAuthField,
+    CycleAuthKind,
+    NextAuthField,
+    PreviousAuthField,
+    CycleBodyMode,
+    EditVariables,
+    AttachFile,
+    OpenInEditor,
+    BodyFromStdin,
+    Edit,
+    AddRow,
+    RemoveRow,
+    ToggleRow,
+    PreviousRow,
+    NextRow,
+    PreviousMethod,
+    NextMethod,
+    RecentUrls,
+    Send,
+    Cancel,
+    Disconnect,
+    Download,
+    History,
+    ImportCurl,
+    ImportFile,
+    ExportHar,
+    ExportLogs,
+    ExportCode,
+    Monitor,
+    Bench,
+    ResponseTimes,
+    Cookies,
+    Assertions,
+    PreRequestScript,
+    PostResponseScript,
+    Settings,
+    BearerToken,
+    CycleEnvironment,
+    SaveRequest,
+    CycleLayout,
+    CycleTheme,
+    NextCollection,
+    PreviousCollection,
+    RemoveCollection,
+    LoadCollection,
+    RunCollection,
+    MarkRequest,
+    Quit,
+}
+
+impl Action {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::NewTab => "Open a new tab",
+            Action::CloseTab => "Close the tab",
+            Action::NextTab => "Next tab",
+            Action::PreviousTab => "Previous tab",
+            Action::FocusLeft => "Focus the pane to the left",
+            Action::FocusDown => "Focus the pane below",
+            Action::FocusUp => "Focus the pane above",
+            Action::FocusRight => "Focus the pane to the right",
+            Action::NextPane => "Focus the next pane",
+            Action::PreviousPane => "Focus the previous pane",
+            Action::CommandLine => "Type a command (:send, :save, :env, :q...)",
+            Action::Palette => "Find any action by name",
+            Action::Finder => "Find a saved or past request",
+            Action::Help => "Show this help",
+            Action::Visual => "Select lines to copy",
+            Action::JsonTree => "Browse the JSON as a tree, expanding and collapsing",
+            Action::JsonTable => "Show a JSON array of objects as a table",
+            Action::ScrollTop => "Scroll to the top",
+            Action::ScrollBottom => "Scroll to the bottom",
+            Action::ScrollDown => "Scroll down",
+            Action::ScrollUp => "Scroll up",
+            Action::PageDown => "Scroll down a page",
+            Action::PageUp => "Scroll up a page",
+            Action::CopyBody => "Copy the body",
+            Action::CopyHeaders => "Copy the status and headers",
+            Action::CopyMatch => "Copy the search match",
+            Action::SaveResponse => "Save the body to a file",
+            Action::Filter => {
+                "Show only part of the JSON or XML, picked by a path like .items[0].name or //item"
+            }
+            Action::Unfilter => "Show the whole body again after filtering",
+            Action::ToggleRaw => "Toggle the raw body",
+            Action::ToggleMetadata => "Expand or collapse the headers",
+            Action::Search => "Search the body",
+            Action::NextMatch => "Next search match",
+            Action::PreviousMatch => "Previous search match",
+            Action::Authorize => "Fetch an OAuth2 token",
+            Action::EditAuthField => "Edit the selected field",
+            Action::CycleAuthKind => "Change the auth type",
+            Action::NextAuthField => "Select the next field",
+            Action::PreviousAuthField => "Select the previous field",
+            Action::CycleBodyMode => "Change the body mode",
+            Action::EditVariables => "Edit the GraphQL variables",
+            Action::AttachFile => "Attach a file to a multipart body",
+            Action::OpenInEditor => "Open the body in $EDITOR",
+            Action::BodyFromStdin => "Replace the body with what was piped to stdin",
+            Action::Edit => "Edit the focused pane",
+            Action::AddRow => "Add a row",
+            Action::RemoveRow => "Remove the selected row",
+            Action::ToggleRow => "Enable or disable the selected row",
+            Action::PreviousRow => "Select the previous row",
+            Action::NextRow => "Select the next row",
+            Action::PreviousMethod => "Previous method",
+            Action::NextMethod => "Next method",
+            Action::RecentUrls => "Pick from the URLs sent most recently",
+            Action::Send => "Send the request",
+            Action::Cancel => "Cancel the request",
+            Action::Disconnect => "Disconnect from an event stream",
+            Action::Download => "Send the request, saving the body to a file",
+            Action::History => "Browse the request history",
+            Action::ImportCurl => "Import a curl command",
+            Action::ImportFile => "Import a collection file",
+            Action::ExportHar => "Export the session as HAR",
+            Action::ExportLogs => "Export the logs to a file",
+            Action::ExportCode => "Show the request as curl, Python, JavaScript, Go or Rust code",
+            Action::Monitor => "Send the request every few seconds, watching how it goes",
+            Action::Bench => "Benchmark the request, sending it many times at once",
+            Action::ResponseTimes => "Chart the request's response times from the history",
+            Action::Cookies => "Browse the cookie jar",
+            Action::Assertions => "Edit the checks on the response",
+            Action::PreRequestScript => "Edit the pre-request script in $EDITOR",
+            Action::PostResponseScript => "Edit the post-response script in $EDITOR",
+            Action::Settings => "Edit the connection settings",
+            Action::BearerToken => "Set the bearer token",
+            Action::CycleEnvironment => "Switch environment",
+            Action::SaveRequest => "Save the request to a collection",
+            Action::CycleLayout => "Switch layout",
+            Action::CycleTheme => "Switch color theme",
+            Action::NextCollection => "Select the next request",
+            Action::PreviousCollection => "Select the previous request",
+            Action::RemoveCollection => "Delete the selected request",
+            Action::LoadCollection => "Load the selected request",
+            Action::RunCollection => "Run the selected collection, or its marked requests at once",
+            Action::MarkRequest => "Mark the request to run with others at once",
+            Action::Quit => "Quit",
+        }
+    }
+}
+
+/// Where a binding applies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scope {
+    Anywhere,
+    /// While this pane is focused.
+    Pane(Focus),
+    /// While a key/value table is focused: params, headers or form fields.
+    Table,
+    /// While the Body pane is focused in GraphQL mode.
+    GraphQl,
+}
+
+impl Scope {
+    /// The order scopes are listed in the help.
+    pub const ALL: [Scope; 10] = [
+        Scope::Anywhere,
+        Scope::Pane(Focus::Method),
+        Scope::Pane(Focus::Url),
+        Scope::Table,
+        Scope::Pane(Focus::Auth),
+        Scope::Pane(Focus::Body),
+        Scope::GraphQl,
+        Scope::Pane(Focus::Response),
+        Scope::Pane(Focus::Logs),
+        Scope::Pane(Focus::Collections),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scope::Anywhere => "Anywhere",
+            Scope::Pane(Focus::Method) => "Method",
+            Scope::Pane(Focus::Url) => "URL",
+            Scope::Pane(Focus::Auth) => "Auth",
+            Scope::Pane(Focus::Body) => "Body",
+            Scope::Pane(Focus::Response) => "Response",
+            Scope::Pane(Focus::Logs) => "Logs",
+            Scope::Pane(Focus::Collections) => "Collections",
+            Scope::Pane(_) => "Other panes",
+            Scope::Table => "Params, headers and form fields",
+            Scope::GraphQl => "GraphQL body",
+        }
+    }
+
+    pub fn applies(&self, app: &mut App) -> bool {
+        match self {
+            Scope::Anywhere => true,
+            Scope::Pane(window) => app.focus == *window,
+            Scope::Table => app.focused_table().is_some(),
+            Scope::GraphQl => app.focus == Focus::Body && app.body_mode == BodyMode::GraphQl,
+        }
+    }
+}
+
+/// A key, or keys, bound to an action.
+pub struct Binding {
+    pub keys: &'static [KeyCode],
+    /// Whether Ctrl is held. Bindings without it only match without it.
+    pub ctrl: bool,
+    pub scope: Scope,
+    pub action: Action,
+}
+
+const fn bind(keys: &'static [KeyCode], scope: Scope, action: Action) -> Binding {
+    Binding {
+        keys,
+        ctrl: false,
+        scope,
+        action,
+    }
+}
+
+const fn ctrl(keys: &'static [KeyCode], scope: Scope, action: Action) -> Binding {
+    Binding {
+        keys,
+        ctrl: true,
+        scope,
+        action,
+    }
+}
+
+const RESPONSE: Scope = Scope::Pane(Focus::Response);
+const AUTH: Scope = Scope::Pane(Focus::Auth);
+const BODY: Scope = Scope::Pane(Focus::Body);
+const METHOD: Scope = Scope::Pane(Focus::Method);
+const URL: Scope = Scope::Pane(Focus::Url);
+const LOGS: Scope = Scope::Pane(Focus::Logs);
+const COLLECTIONS: Scope = Scope::Pane(Focus::Collections);
+
+/// Normal mode's bindings. The first that matches a key wins, so ones
+/// scoped to a pane come before those that apply anywhere.
+pub const BINDINGS: &[Binding] = &[
+    ctrl(&[KeyCode::Char('n')], Scope::Anywhere, Action::NewTab),
+    ctrl(&[KeyCode::Char('w')], Scope::Anywhere, Action::CloseTab),
+    bind(&[KeyCode::Char(']')], Scope::Anywhere, Action::NextTab),
+    bind(&[KeyCode::Char('[')], Scope::Anywhere, Action::PreviousTab),
+    // Ctrl+h/j/k/l move between panes even where j and k move through a list
+    ctrl(&[KeyCode::Char('h')], Scope::Anywhere, Action::FocusLeft),
+    ctrl(&[KeyCode::Char('j')], Scope::Anywhere, Action::FocusDown),
+    ctrl(&[KeyCode::Char('k')], Scope::Anywhere, Action::FocusUp),
+    ctrl(&[KeyCode::Char('l')], Scope::Anywhere, Action::FocusRight),
+    bind(&[KeyCode::Char('h')], Scope::Anywhere, Action::FocusLeft),
+    bind(&[KeyCode::Char('l')], Scope::Anywhere, Action::FocusRight),
+    bind(&[KeyCode::Char(':')], Scope::Anywhere, Action::CommandLine),
+    ctrl(&[KeyCode::Char('p')], Scope::Anywhere, Action::Palette),
+    ctrl(&[KeyCode::Char('f')], Scope::Anywhere, Action::Finder),
+    bind(&[KeyCode::Char('?')], Scope::Anywhere, Action::Help),
+    bind(&[KeyCode::Char('v')], RESPONSE, Action::Visual),
+    bind(&[KeyCode::Char('t')], RESPONSE, Action::JsonTree),
+    bind(&[KeyCode::Char('b')], RESPONSE, Action::JsonTable),
+    bind(&[KeyCode::Char('g')], RESPONSE, Action::ScrollTop),
+    bind(&[KeyCode::Char('G')], RESPONSE, Action::ScrollBottom),
+    bind(
+        &[KeyCode::Char('j'), KeyCode::Down],
+        RESPONSE,
+        Action::ScrollDown,
+    ),
+    bind(
+        &[KeyCode::Char('k'), KeyCode::Up],
+        RESPONSE,
+        Action::ScrollUp,
+    ),
+    bind(&[KeyCode::PageDown], RESPONSE, Action::PageDown),
+    bind(&[KeyCode::PageUp], RESPONSE, Action::PageUp),
+    bind(&[KeyCode::Char('g')], LOGS, Action::ScrollTop),
+    bind(&[KeyCode::Char('G')], LOGS, Action::ScrollBottom),
+    bind(
+        &[KeyCode::Char('j'), KeyCode::Down],
+        LOGS,
+        Action::ScrollDown,
+    ),
+    bind(&[KeyCode::Char('k'), KeyCode::Up], LOGS, Action::ScrollUp),
+    bind(&[KeyCode::PageDown], LOGS, Action::PageDown),
+    bind(&[KeyCode::PageUp], LOGS, Action::PageUp),
+    bind(&[KeyCode::Char('s')], LOGS, Action::ExportLogs),
+    bind(&[KeyCode::Char('y')], RESPONSE, Action::CopyBody),
+    bind(&[KeyCode::Char('Y')], RESPONSE, Action::CopyHeaders),
+    bind(&[KeyCode::Char('c')], RESPONSE, Action::CopyMatch),
+    bind(&[KeyCode::Char('s')], RESPONSE, Action::SaveResponse),
+    bind(&[KeyCode::Char('f')], RESPONSE, Action::Filter),
+    bind(&[KeyCode::Char('F')], RESPONSE, Action::Unfilter),
+    bind(&[KeyCode::Char('r')], RESPONSE, Action::ToggleRaw),
+    bind(&[KeyCode::Char('H')], RESPONSE, Action::ToggleMetadata),
+    bind(&[KeyCode::Char('/')], RESPONSE, Action::Search),
+    bind(&[KeyCode::Char('n')], RESPONSE, Action::NextMatch),
+    bind(&[KeyCode::Char('N')], RESPONSE, Action::PreviousMatch),
+    bind(&[KeyCode::Char('a')], AUTH, Action::Authorize),
+    bind(&[KeyCode::Char('i')], AUTH, Action::EditAuthField),
+    bind(&[KeyCode::Char('t')], AUTH, Action::CycleAuthKind),
+    bind(
+        &[KeyCode::Char('j'), KeyCode::Down],
+        AUTH,
+        Action::NextAuthField,
+    ),
+    bind(
+        &[KeyCode::Char('k'), KeyCode::Up],
+        AUTH,
+        Action::PreviousAuthField,
+    ),
+    bind(&[KeyCode::Char('t')], BODY, Action::CycleBodyMode),
+    bind(&[KeyCode::Char('v')], Scope::GraphQl, Action::EditVariables),
+    bind(&[KeyCode::Char('f')], BODY, Action::AttachFile),
+    bind(&[KeyCode::Char('o')], BODY, Action::OpenInEditor),
+    bind(&[KeyCode::Char('p')], BODY, Action::BodyFromStdin),
+    bind(&[KeyCode::Char('i')], Scope::Anywhere, Action::Edit),
+    bind(&[KeyCode::Char('a')], Scope::Table, Action::AddRow),
+    bind(&[KeyCode::Char('d')], Scope::Table, Action::RemoveRow),
+    bind(&[KeyCode::Char(' ')], Scope::Table, Action::ToggleRow),
+    bind(
+        &[KeyCode::Char('k'), KeyCode::Up],
+        Scope::Table,
+        Action::PreviousRow,
+    ),
+    bind(
+        &[KeyCode::Char('j'), KeyCode::Down],
+        Scope::Table,
+        Action::NextRow,
+    ),
+    bind(&[KeyCode::Up], METHOD, Action::PreviousMethod),
+    bind(&[KeyCode::Down], METHOD, Action::NextMethod),
+    bind(&[KeyCode::Down], URL, Action::RecentUrls),
+    bind(
+        &[KeyCode::Char('j'), KeyCode::Down],
+        COLLECTIONS,
+        Action::NextCollection,
+    ),
+    bind(
+        &[KeyCode::Char('k'), KeyCode::Up],
+        COLLECTIONS,
+        Action::PreviousCollection,
+    ),
+    bind(&[KeyCode::Char('d')], COLLECTIONS, Action::RemoveCollection),
+    bind(&[KeyCode::Enter], COLLECTIONS, Action::LoadCollection),
+    bind(&[KeyCode::Char('R')], COLLECTIONS, Action::RunCollection),
+    bind(&[KeyCode::Char(' ')], COLLECTIONS, Action::MarkRequest),
+    bind(&[KeyCode::Enter], Scope::Anywhere, Action::Send),
+    bind(&[KeyCode::Esc], Scope::Anywhere, Action::Cancel),
+    ctrl(&[KeyCode::Char('c')], Scope::Anywhere, Action::Cancel),
+    bind(&[KeyCode::Char('x')], Scope::Anywhere, Action::Disconnect),
+    bind(&[KeyCode::Char('D')], Scope::Anywhere, Action::Download),
+    ctrl(&[KeyCode::Char('r')], Scope::Anywhere, Action::History),
+    bind(&[KeyCode::Char('I')], Scope::Anywhere, Action::ImportCurl),
+    ctrl(&[KeyCode::Char('o')], Scope::Anywhere, Action::ImportFile),
+    bind(&[KeyCode::Char('E')], Scope::Anywhere, Action::ExportHar),
+    bind(&[KeyCode::Char('X')], Scope::Anywhere, Action::ExportCode),
+    bind(&[KeyCode::Char('M')], Scope::Anywhere, Action::Monitor),
+    bind(&[KeyCode::Char('B')], Scope::Anywhere, Action::Bench),
+    bind(
+        &[KeyCode::Char('K')],
+        Scope::Anywhere,
+        Action::ResponseTimes,
+    ),
+    bind(&[KeyCode::Char('C')], Scope::Anywhere, Action::Cookies),
+    bind(&[KeyCode::Char('A')], Scope::Anywhere, Action::Assertions),
+    bind(
+        &[KeyCode::Char('P')],
+        Scope::Anywhere,
+        Action::PreRequestScript,
+    ),
+    bind(
+        &[KeyCode::Char('O')],
+        Scope::Anywhere,
+        Action::PostResponseScript,
+    ),
+    bind(&[KeyCode::Char('S')], Scope::Anywhere, Action::Settings),
+    ctrl(&[KeyCode::Char('t')], Scope::Anywhere, Action::BearerToken),
+    ctrl(
+        &[KeyCode::Char('e')],
+        Scope::Anywhere,
+        Action::CycleEnvironment,
+    ),
+    ctrl(&[KeyCode::Char('s')], Scope::Anywhere, Action::SaveRequest),
+    bind(&[KeyCode::Char('L')], Scope::Anywhere, Action::CycleLayout),
+    bind(&[KeyCode::Char('T')], Scope::Anywhere, Action::CycleTheme),
+    bind(&[KeyCode::Tab], Scope::Anywhere, Action::NextPane),
+    bind(&[KeyCode::BackTab], Scope::Anywhere, Action::PreviousPane),
+    bind(&[KeyCode::Char('j')], Scope::Anywhere, Action::FocusDown),
+    bind(&[KeyCode::Char('k')], Scope::Anywhere, Action::FocusUp),
+    bind(&[KeyCode::Char('q')], Scope::Anywhere, Action::Quit),
+];
+
+/// Something a key does in one of the other input modes. Each mode's
+/// dispatcher reads these its own way, e.g. `Down` selects the next row of
+/// a list but scrolls the help.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModeAction {
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    Toggle,
+    Copy,
+    CopyJq,
+    Table,
+    ExportCsv,
+    Sort,
+    SwitchColumn,
+    Add,
+    Edit,
+    Remove,
+    UpdateSnapshots,
+    Stop,
+    Complete,
+    Accept,
+    Close,
+}
+
+/// A key, or keys, bound in one of the other input modes.
+pub struct ModeBinding {
+    pub keys: &'static [KeyCode],
+    /// Whether Ctrl is held. Bindings without it only match without it.
+    pub ctrl: bool,
+    pub action: ModeAction,
+    pub description: &'static str,
+}
+
+const fn key(
+    keys: &'static [KeyCode],
+    action: ModeAction,
+    description: &'static str,
+) -> ModeBinding {
+    ModeBinding {
+        keys,
+        ctrl: false,
+        action,
+        description,
+    }
+}
+
+const fn ctrl_key(
+    keys: &'static [KeyCode],
+    action: ModeAction,
+    description: &'static str,
+) -> ModeBinding {
+    ModeBinding {
+        keys,
+        ctrl: true,
+        action,
+        description,
+    }
+}
+
+/// An input mode's bindings, read by both its dispatcher and the help.
+pub struct Mode {
+    pub name: &'static str,
+    pub bindings: &'static [ModeBinding],
+    /// What the keys without a binding do, if anything.
+    pub other_keys: Option<&'static str>,
+}
+
+impl Mode {
+    /// What `key` does in this mode, if it's bound.
+    pub fn lookup(&self, key: &KeyEvent) -> Option<ModeAction> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        self.bindings
+            .iter()
+            .find(|binding| binding.ctrl == ctrl && binding.keys.contains(&key.code))
+            .map(|binding| binding.action)
+    }
+}
+
+pub const EDITING: Mode = Mode {
+    name: "Editing",
+    bindings: &[
+        key(&[KeyCode::Esc], ModeAction::Close, "Stop editing"),
+        key(
+            &[KeyCode::Down],
+            ModeAction::Down,
+            "Select the next URL suggestion",
+        ),
+        key(
+            &[KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous URL suggestion",
+        ),
+        key(
+            &[KeyCode::Tab],
+            ModeAction::Complete,
+            "Take the selected URL suggestion, or the first",
+        ),
+        key(
+            &[KeyCode::Enter],
+            ModeAction::Accept,
+            "Take the selected URL suggestion",
+        ),
+    ],
+    other_keys: Some(
+        "Type into the focused pane, arrows move the cursor, Ctrl+Z/Ctrl+R undo and redo",
+    ),
+};
+
+pub const TABLE: Mode = Mode {
+    name: "Editing a table",
+    bindings: &[
+        key(
+            &[KeyCode::Enter, KeyCode::Esc],
+            ModeAction::Close,
+            "Stop editing",
+        ),
+        key(
+            &[KeyCode::Tab],
+            ModeAction::SwitchColumn,
+            "Switch between key and value",
+        ),
+        key(&[KeyCode::Down], ModeAction::Down, "Edit the next row"),
+        key(&[KeyCode::Up], ModeAction::Up, "Edit the previous row"),
+    ],
+    other_keys: Some("Type into the cell, Ctrl+Z/Ctrl+R undo and redo"),
+};
+
+pub const SEARCH: Mode = Mode {
+    name: "Searching",
+    bindings: &[
+        key(&[KeyCode::Enter], ModeAction::Accept, "Keep the search"),
+        key(&[KeyCode::Esc], ModeAction::Close, "Clear the search"),
+    ],
+    other_keys: Some("Type the query"),
+};
+
+pub const VISUAL: Mode = Mode {
+    name: "Selecting lines",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('v')],
+            ModeAction::Close,
+            "Stop selecting",
+        ),
+        key(
+            &[KeyCode::Char('y')],
+            ModeAction::Copy,
+            "Copy the selection",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Extend the selection down",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Extend the selection up",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const TREE: Mode = Mode {
+    name: "JSON tree",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('t')],
+            ModeAction::Close,
+            "Close the tree",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next node",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous node",
+        ),
+        key(
+            &[KeyCode::PageDown],
+            ModeAction::PageDown,
+            "Move down a page",
+        ),
+        key(&[KeyCode::PageUp], ModeAction::PageUp, "Move up a page"),
+        key(
+            &[KeyCode::Char('g'), KeyCode::Home],
+            ModeAction::Top,
+            "Select the first node",
+        ),
+        key(
+            &[KeyCode::Char('G'), KeyCode::End],
+            ModeAction::Bottom,
+            "Select the last node",
+        ),
+        key(
+            &[KeyCode::Char('l'), KeyCode::Right],
+            ModeAction::Right,
+            "Expand the node",
+        ),
+        key(
+            &[KeyCode::Char('h'), KeyCode::Left],
+            ModeAction::Left,
+            "Collapse the node",
+        ),
+        key(
+            &[KeyCode::Enter, KeyCode::Char(' ')],
+            ModeAction::Toggle,
+            "Expand or collapse the node",
+        ),
+        key(
+            &[KeyCode::Char('y')],
+            ModeAction::Copy,
+            "Copy the node's path as JSONPath",
+        ),
+        key(
+            &[KeyCode::Char('Y')],
+            ModeAction::CopyJq,
+            "Copy the node's path as jq",
+        ),
+        key(
+            &[KeyCode::Char('b')],
+            ModeAction::Table,
+            "Show the node as a table",
+        ),
+        key(
+            &[KeyCode::Char('e')],
+            ModeAction::ExportCsv,
+            "Export the node as CSV",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const JSON_TABLE: Mode = Mode {
+    name: "JSON table",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('b')],
+            ModeAction::Close,
+            "Close the table",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next row",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous row",
+        ),
+        key(
+            &[KeyCode::PageDown],
+            ModeAction::PageDown,
+            "Move down a page",
+        ),
+        key(&[KeyCode::PageUp], ModeAction::PageUp, "Move up a page"),
+        key(
+            &[KeyCode::Char('g'), KeyCode::Home],
+            ModeAction::Top,
+            "Select the first row",
+        ),
+        key(
+            &[KeyCode::Char('G'), KeyCode::End],
+            ModeAction::Bottom,
+            "Select the last row",
+        ),
+        key(
+            &[KeyCode::Char('l'), KeyCode::Right],
+            ModeAction::Right,
+            "Select the next column",
+        ),
+        key(
+            &[KeyCode::Char('h'), KeyCode::Left],
+            ModeAction::Left,
+            "Select the previous column",
+        ),
+        key(
+            &[KeyCode::Char('s')],
+            ModeAction::Sort,
+            "Sort by the selected column",
+        ),
+        key(
+            &[KeyCode::Char('e')],
+            ModeAction::ExportCsv,
+            "Export the table as CSV",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const PROMPT: Mode = Mode {
+    name: "Prompt",
+    bindings: &[
+        key(&[KeyCode::Enter], ModeAction::Accept, "Accept the answer"),
+        key(&[KeyCode::Esc], ModeAction::Close, "Cancel"),
+    ],
+    other_keys: Some("Type the answer, or a command after :"),
+};
+
+pub const HISTORY: Mode = Mode {
+    name: "History",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the history",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next request",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous request",
+        ),
+        key(
+            &[KeyCode::Enter],
+            ModeAction::Accept,
+            "Load the selected request",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const SNIPPETS: Mode = Mode {
+    name: "Code snippets",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the snippets",
+        ),
+        key(
+            &[KeyCode::Char('l'), KeyCode::Right, KeyCode::Tab],
+            ModeAction::Right,
+            "Next language",
+        ),
+        key(
+            &[KeyCode::Char('h'), KeyCode::Left, KeyCode::BackTab],
+            ModeAction::Left,
+            "Previous language",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Scroll down",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Scroll up",
+        ),
+        key(
+            &[KeyCode::Char('y'), KeyCode::Enter],
+            ModeAction::Copy,
+            "Copy the snippet",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const COOKIES: Mode = Mode {
+    name: "Cookie jar",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the cookie jar",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next cookie",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous cookie",
+        ),
+        key(
+            &[KeyCode::Char('i'), KeyCode::Enter],
+            ModeAction::Edit,
+            "Edit the selected cookie",
+        ),
+        key(
+            &[KeyCode::Char('d')],
+            ModeAction::Remove,
+            "Delete the selected cookie",
+        ),
+        key(
+            &[KeyCode::Char('t')],
+            ModeAction::Toggle,
+            "Turn cookies off or on for this request",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const ASSERTIONS: Mode = Mode {
+    name: "Checks",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the checks",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next check",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous check",
+        ),
+        key(&[KeyCode::Char('a')], ModeAction::Add, "Add a check"),
+        key(
+            &[KeyCode::Char('i'), KeyCode::Enter],
+            ModeAction::Edit,
+            "Edit the selected check",
+        ),
+        key(
+            &[KeyCode::Char('d')],
+            ModeAction::Remove,
+            "Delete the selected check",
+        ),
+        key(
+            &[KeyCode::Char('u')],
+            ModeAction::UpdateSnapshots,
+            "Update the snapshots to the last response",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const RUNNER: Mode = Mode {
+    name: "Collection run",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the run",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next request",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous request",
+        ),
+        key(
+            &[KeyCode::Enter],
+            ModeAction::Accept,
+            "Load the selected request",
+        ),
+        key(&[KeyCode::Char('x')], ModeAction::Stop, "Stop the run"),
+    ],
+    other_keys: None,
+};
+
+pub const MONITOR: Mode = Mode {
+    name: "Monitor",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the monitor",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next sample",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous sample",
+        ),
+        key(&[KeyCode::Char('x')], ModeAction::Stop, "Stop monitoring"),
+    ],
+    other_keys: None,
+};
+
+pub const BENCH: Mode = Mode {
+    name: "Benchmark",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the benchmark",
+        ),
+        key(
+            &[KeyCode::Char('x')],
+            ModeAction::Stop,
+            "Stop the benchmark",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const TREND: Mode = Mode {
+    name: "Response times",
+    bindings: &[key(
+        &[KeyCode::Esc, KeyCode::Char('q')],
+        ModeAction::Close,
+        "Close the chart",
+    )],
+    other_keys: None,
+};
+
+pub const SETTINGS: Mode = Mode {
+    name: "Settings",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the settings",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Select the next setting",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous setting",
+        ),
+        key(
+            &[KeyCode::Char('i'), KeyCode::Enter],
+            ModeAction::Edit,
+            "Edit the selected setting",
+        ),
+        key(
+            &[KeyCode::Char('d')],
+            ModeAction::Remove,
+            "Clear the selected setting",
+        ),
+    ],
+    other_keys: None,
+};
+
+pub const PALETTE: Mode = Mode {
+    name: "Command palette",
+    bindings: &[
+        key(&[KeyCode::Esc], ModeAction::Close, "Close the palette"),
+        key(
+            &[KeyCode::Enter],
+            ModeAction::Accept,
+            "Run the selected action",
+        ),
+        key(&[KeyCode::Down], ModeAction::Down, "Select the next action"),
+        ctrl_key(
+            &[KeyCode::Char('n'), KeyCode::Char('j')],
+            ModeAction::Down,
+            "Select the next action",
+        ),
+        key(&[KeyCode::Up], ModeAction::Up, "Select the previous action"),
+        ctrl_key(
+            &[KeyCode::Char('p'), KeyCode::Char('k')],
+            ModeAction::Up,
+            "Select the previous action",
+        ),
+    ],
+    other_keys: Some("Type to filter the actions"),
+};
+
+pub const FINDER: Mode = Mode {
+    name: "Finder",
+    bindings: &[
+        key(&[KeyCode::Esc], ModeAction::Close, "Close the finder"),
+        key(
+            &[KeyCode::Enter],
+            ModeAction::Accept,
+            "Load the selected request",
+        ),
+        key(
+            &[KeyCode::Down],
+            ModeAction::Down,
+            "Select the next request",
+        ),
+        ctrl_key(
+            &[KeyCode::Char('n'), KeyCode::Char('j')],
+            ModeAction::Down,
+            "Select the next request",
+        ),
+        key(
+            &[KeyCode::Up],
+            ModeAction::Up,
+            "Select the previous request",
+        ),
+        ctrl_key(
+            &[KeyCode::Char('p'), KeyCode::Char('k')],
+            ModeAction::Up,
+            "Select the previous request",
+        ),
+    ],
+    other_keys: Some("Type to filter the requests"),
+};
+
+pub const HELP: Mode = Mode {
+    name: "Help",
+    bindings: &[
+        key(
+            &[KeyCode::Esc, KeyCode::Char('?'), KeyCode::Char('q')],
+            ModeAction::Close,
+            "Close the help",
+        ),
+        key(
+            &[KeyCode::Char('j'), KeyCode::Down],
+            ModeAction::Down,
+            "Scroll down",
+        ),
+        key(
+            &[KeyCode::Char('k'), KeyCode::Up],
+            ModeAction::Up,
+            "Scroll up",
+        ),
+    ],
+    other_keys: None,
+};
+
+/// The other input modes, in the order the help lists them.
+pub const MODES: [&Mode; 19] = [
+    &EDITING,
+    &TABLE,
+    &SEARCH,
+    &VISUAL,
+    &TREE,
+    &JSON_TABLE,
+    &PROMPT,
+    &HISTORY,
+    &SNIPPETS,
+    &COOKIES,
+    &ASSERTIONS,
+    &RUNNER,
+    &MONITOR,
+    &BENCH,
+    &TREND,
+    &SETTINGS,
+    &PALETTE,
+    &FINDER,
+    &HELP,
+];
+
+/// The action Normal mode runs for `key`, if any.
+pub fn lookup(key: &KeyEvent, app: &mut App) -> Option<Action> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    BINDINGS
+        .iter()
+        .find(|binding| {
+            binding.ctrl == ctrl && binding.keys.contains(&key.code) && binding.scope.applies(app)
+        })
+        .map(|binding| binding.action)
+}
+
+/// e.g. `Ctrl+S` or `j/Down`
+pub fn keys_label(binding: &Binding) -> String {
+    label(binding.keys, binding.ctrl)
+}
+
+/// As `keys_label`, for a binding in one of the other modes.
+pub fn mode_keys_label(binding: &ModeBinding) -> String {
+    label(binding.keys, binding.ctrl)
+}
+
+fn label(keys: &[KeyCode], ctrl: bool) -> String {
+    let keys: Vec<String> = keys
+        .iter()
+        .map(|key| {
+            let name = match key {
+                KeyCode::Char(' ') => String::from("Space"),
+                KeyCode::Char(c) if ctrl => c.to_ascii_uppercase().to_string(),
+                KeyCode::Char(c) => c.to_string(),
+                KeyCode::PageDown => String::from("PgDn"),
+                KeyCode::PageUp => String::from("PgUp"),
+                KeyCode::BackTab => String::from("Shift+Tab"),
+                other => format!("{:?}", other),
+            };
+            if ctrl {
+                format!("Ctrl+{}", name)
+            } else {
+                name
+            }
+        })
+        .collect();
+    keys.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binds_each_key_once_per_mode() {
+        for mode in MODES {
+            let mut seen = Vec::new();
+            for binding in mode.bindings {
+                for key in binding.keys {
+                    assert!(
+                        !seen.contains(&(binding.ctrl, key)),
+                        "{} binds {} twice",
+                        mode.name,
+                        label(&[*key], binding.ctrl)
+                    );
+                    seen.push((binding.ctrl, key));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn looks_up_ctrl_bindings_only_with_ctrl() {
+        let down = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        assert_eq!(PALETTE.lookup(&down), Some(ModeAction::Down));
+        let typed = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(PALETTE.lookup(&typed), None);
+        assert_eq!(HELP.lookup(&typed), Some(ModeAction::Down));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::undo::{Run, Step, Undo};
+use serde::{Deserialize, Serialize};
+
+/// A single key/value row, e.g. a header or a query parameter.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct KeyValue {
+    pub key: String,
+    pub value: String,
+    /// Disabled rows are kept in the editor but left out of the request.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl KeyValue {
+    pub fn new(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: String::from(key),
+            value: String::from(value),
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Key,
+    Value,
+}
+
+/// Editable list of key/value rows with a selected row and column.
+#[derive(Clone, Debug)]
+pub struct KeyValueTable {
+    pub rows: Vec<KeyValue>,
+    pub selected: usize,
+    pub column: Column,
+    /// Earlier rows, and the row selected, for undoing cell edits.
+    undo: Undo<(Vec<KeyValue>, usize)>,
+}
+
+impl Default for KeyValueTable {
+    fn default() -> KeyValueTable {
+        KeyValueTable {
+            rows: Vec::new(),
+            selected: 0,
+            column: Column::Key,
+            undo: Undo::default(),
+        }
+    }
+}
+
+impl KeyValueTable {
+    /// Appends an empty row, selects it and puts the cursor on its key.
+    pub fn add_row(&mut self) {
+        self.rows.push(KeyValue::new("", ""));
+        self.selected = self.rows.len() - 1;
+        self.column = Column::Key;
+    }
+
+    pub fn remove_selected(&mut self) {
+        if self.selected < self.rows.len() {
+            self.rows.remove(self.selected);
+        }
+        if self.selected >= self.rows.len() && self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.undo.seal();
+        if self.selected + 1 < self.rows.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.undo.seal();
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn toggle_column(&mut self) {
+        self.undo.seal();
+        self.column = match self.column {
+            Column::Key => Column::Value,
+            Column::Value => Column::Key,
+        };
+    }
+
+    pub fn toggle_enabled(&mut self) {
+        if let Some(row) = self.rows.get_mut(self.selected) {
+            row.enabled = !row.enabled;
+        }
+    }
+
+    /// The cell under the cursor, if there is a selected row.
+    pub fn current_cell(&mut self) -> Option<&mut String> {
+        let column = self.column;
+        self.rows.get_mut(self.selected).map(|row| match column {
+            Column::Key => &mut row.key,
+            Column::Value => &mut row.value,
+        })
+    }
+
+    pub fn push(&mut self, c: char) {
+        let run = (!c.is_whitespace()).then_some(Run::Typing);
+        self.edit_cell(run, |cell| cell.push(c));
+    }
+
+    pub fn pop(&mut self) {
+        self.edit_cell(Some(Run::Deleting), |cell| {
+            cell.pop();
+        });
+    }
+
+    /// Adds pasted text to the cell under the cursor.
+    pub fn push_str(&mut self, s: &str) {
+        self.edit_cell(None, |cell| cell.push_str(s));
+    }
+
+    /// Changes the cell under the cursor, remembering the rows as they were
+    /// if it did change.
+    fn edit_cell(&mut self, run: Option<Run>, edit: impl FnOnce(&mut String)) {
+        let before = (self.rows.clone(), self.selected);
+        let Some(cell) = self.current_cell() else {
+            return;
+        };
+        let old = cell.clone();
+        edit(cell);
+        if *cell != old {
+            self.undo.record(before, run);
+        }
+    }
+
+    /// Undoes or redoes the last cell edit.
+    pub fn step(&mut self, step: Step) {
+        let mut state = (std::mem::take(&mut self.rows), self.selected);
+        self.undo.apply(step, &mut state);
+        (self.rows, self.selected) = state;
+    }
+
+    /// Rows that should be sent: enabled and with a non-empty key.
+    pub fn active(&self) -> impl Iterator<Item = &KeyValue> {
+        self.rows
+            .iter()
+            .filter(|kv| kv.enabled && !kv.key.is_empty())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::focus::Focus;
+use serde::{Deserialize, Serialize};
+use tui::layout::Rect;
+
+/// How the panes are arranged on screen. Set with `layout` in `config.json`,
+/// and cycled with `L`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    /// The collections sidebar, the request editors and the response side by side.
+    #[default]
+    SidebarLeft,
+    /// As `SidebarLeft`, with the collections sidebar on the right.
+    SidebarRight,
+    /// The request editors above the response rather than beside it.
+    Stacked,
+    /// Only the URL bar, the response and the logs.
+    ResponseMaximized,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [
+        Preset::SidebarLeft,
+        Preset::SidebarRight,
+        Preset::Stacked,
+        Preset::ResponseMaximized,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::SidebarLeft => "Sidebar left",
+            Preset::SidebarRight => "Sidebar right",
+            Preset::Stacked => "Stacked",
+            Preset::ResponseMaximized => "Response maximized",
+        }
+    }
+
+    pub fn next(self) -> Preset {
+        let i = Preset::ALL.iter().position(|p| *p == self).unwrap_or(0);
+        Preset::ALL[(i + 1) % Preset::ALL.len()]
+    }
+
+    /// Whether `window` is on screen, so it can be switched to.
+    pub fn shows(&self, window: Focus) -> bool {
+        match self {
+            Preset::ResponseMaximized => ![
+                Focus::Params,
+                Focus::Headers,
+                Focus::Auth,
+                Focus::Body,
+                Focus::Collections,
+            ]
+            .contains(&window),
+            _ => true,
+        }
+    }
+}
+
+/// A way to move focus between panes on screen, as with vim's `Ctrl-W h/j/k/l`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Towards {
+    Left,
+    Down,
+    Up,
+    Right,
+}
+
+/// The pane next to `from` in the direction `towards`: of those past its
+/// edge, one lined up with it if possible, then the closest.
+pub fn neighbour(panes: &[(Focus, Rect)], from: Focus, towards: Towards) -> Option<Focus> {
+    let (_, current) = panes.iter().find(|(window, _)| *window == from)?;
+    let middle = |start: u16, length: u16| start as i32 + length as i32 / 2;
+    panes
+        .iter()
+        .filter(|(window, area)| *window != from && area.area() > 0)
+        .filter_map(|(window, area)| {
+            // How far past the edge the pane is, whether it overlaps on the
+            // other axis, and how far apart their middles are on that axis
+            let (gap, overlaps, offset) = match towards {
+                Towards::Left | Towards::Right => (
+                    match towards {
+                        Towards::Left => current.left() as i32 - area.right() as i32,
+                        _ => area.left() as i32 - current.right() as i32,
+                    },
+                    area.top() < current.bottom() && current.top() < area.bottom(),
+                    middle(area.y, area.height) - middle(current.y, current.height),
+                ),
+                Towards::Up | Towards::Down => (
+                    match towards {
+                        Towards::Up => current.top() as i32 - area.bottom() as i32,
+                        _ => area.top() as i32 - current.bottom() as i32,
+                    },
+                    area.left() < current.right() && current.left() < area.right(),
+                    middle(area.x, area.width) - middle(current.x, current.width),
+                ),
+            };
+            (gap >= 0).then_some(((!overlaps, gap, offset.abs()), *window))
+        })
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, window)| window)
+}
@@ -0,0 +1,117 @@
+use crate::theme::Theme;
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use tui::style::Color;
+
+/// Oldest entries are dropped once the Logs pane holds more than this.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Info,
+    /// Something asked for that couldn't be done, e.g. copying with no response.
+    Warning,
+    /// Something that went wrong, e.g. a failed request or save.
+    Error,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Warning => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+
+    pub fn color(&self, theme: &Theme) -> Color {
+        match self {
+            Level::Info => theme.info,
+            Level::Warning => theme.warning,
+            Level::Error => theme.error,
+        }
+    }
+}
+
+/// One line of the Logs pane.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub level: Level,
+    pub timestamp: DateTime<Local>,
+    pub message: String,
+}
+
+impl Entry {
+    /// e.g. `2024-05-01 13:37:00 INFO  Done: HTTP/1.1 200 OK`, for exports.
+    pub fn line(&self) -> String {
+        format!(
+            "{} {:<5} {}",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            self.level.name(),
+            self.message
+        )
+    }
+}
+
+/// What the app has logged, newest last.
+#[derive(Debug, Default)]
+pub struct Logs {
+    entries: VecDeque<Entry>,
+    /// How many of the oldest entries have been dropped.
+    dropped: usize,
+}
+
+impl Logs {
+    pub fn push(&mut self, level: Level, message: impl Into<String>) {
+        self.entries.push_back(Entry {
+            level,
+            timestamp: Local::now(),
+            message: message.into(),
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Level::Info, message);
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.push(Level::Warning, message);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Level::Error, message);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    /// Writes every entry kept to `path`, one per line.
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        let mut text = String::new();
+        if self.dropped > 0 {
+            let _ = writeln!(text, "({} earlier lines were dropped)", self.dropped);
+        }
+        for entry in &self.entries {
+            let _ = writeln!(text, "{}", entry.line());
+        }
+        fs::write(path, text)
+    }
+}
+
+/// e.g. `heade-rs-20240501-133700.log`, so exports don't overwrite each other.
+pub fn export_name() -> String {
+    format!("heade-rs-{}.log", Local::now().format("%Y%m%d-%H%M%S"))
+}
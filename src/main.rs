@@ -1,57 +1,184 @@
+use app::{cycle_method, App, ExternalEdit, InputMode, Message};
+use body::BodyMode;
+use cli::Invocation;
+use collections::Collections;
+use config::Config;
+use cookies::CookieJar;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use regex::Regex;
-use std::{error::Error, fmt, io};
+use environment::Environments;
+use focus::Focus;
+use history::History;
+use input::Edit;
+use keymap::{Action, ModeAction};
+use kv::KeyValueTable;
+use layout::Towards;
+use oauth::TokenStore;
+use prompt::PromptAction;
+use state::State;
+use std::{env, error::Error, io, process, sync::Arc, time::Duration};
+use tokio::sync::mpsc::{self, UnboundedSender};
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans, Text},
-    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
-    Frame, Terminal,
+    Terminal,
 };
-use unicode_width::UnicodeWidthStr;
+use ui::ui;
+use undo::Step;
 extern crate jsonxf;
 
-enum InputMode {
-    Normal,
-    Editing,
-}
+mod app;
+mod assertion;
+mod auth;
+mod bench;
+mod body;
+mod cli;
+mod clipboard;
+mod collections;
+mod command;
+mod config;
+mod cookies;
+mod crypto;
+mod curl;
+mod dataset;
+mod digest;
+mod download;
+mod editor;
+mod encoding;
+mod environment;
+mod error;
+mod finder;
+mod focus;
+mod format;
+mod fuzzy;
+mod har;
+mod highlight;
+mod history;
+mod httpfile;
+mod hurl;
+mod input;
+mod jsonpath;
+mod jsontable;
+mod jsontree;
+mod keymap;
+mod kv;
+mod layout;
+mod logs;
+mod mock;
+mod monitor;
+mod notify;
+mod oauth;
+mod openapi;
+mod palette;
+mod postman;
+mod prompt;
+mod proxy;
+mod report;
+mod request;
+mod response;
+mod retry;
+mod runner;
+mod script;
+mod search;
+mod settings;
+mod sigv4;
+mod snapshot;
+mod snippet;
+mod sse;
+mod state;
+mod storage;
+mod tabs;
+mod theme;
+mod throttle;
+mod ui;
+mod undo;
+mod unix;
+mod xpath;
 
-struct App {
-    response: String,
-    input_mode: InputMode,
-    url: String,
-    logs: Vec<String>,
-    current_window: i32,
-}
+/// How long to wait for input before redrawing and checking for responses.
+const TICK_RATE: Duration = Duration::from_millis(100);
 
-impl Default for App {
-    fn default() -> App {
-        App {
-            response: String::new(),
-            input_mode: InputMode::Normal,
-            url: String::new(),
-            logs: Vec::new(),
-            current_window: 1,
-        }
-    }
-}
+/// Lines scrolled by one notch of the mouse wheel.
+const WHEEL_LINES: i32 = 3;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let invocation = match cli::parse(env::args().skip(1)) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            process::exit(cli::EXIT_USAGE);
+        }
+    };
+    let prefill = match invocation {
+        Invocation::Tui(prefill) => prefill,
+        Invocation::Help => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Invocation::Send(args) => {
+            if let Err(e) = cli::run(load_app(), args).await {
+                eprintln!("{}", e);
+                process::exit(cli::EXIT_ERROR);
+            }
+            return Ok(());
+        }
+        Invocation::Mock(source, args) => {
+            if let Err(e) = cli::mock(load_app(), &source, args).await {
+                eprintln!("{}", e);
+                process::exit(cli::EXIT_ERROR);
+            }
+            return Ok(());
+        }
+        Invocation::Bench(args) => match cli::bench(load_app(), args).await {
+            Ok(0) => return Ok(()),
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(cli::EXIT_ERROR);
+            }
+        },
+        Invocation::Run(path, args) => match cli::run_file(load_app(), &path, args).await {
+            Ok(0) => return Ok(()),
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(cli::EXIT_ERROR);
+            }
+        },
+    };
+
+    // Keys are still read from the terminal when stdin is piped, so it's
+    // drained before the UI starts
+    let stdin = cli::read_stdin()?;
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let app = App::default();
+    let mut app = load_app();
+    match State::load() {
+        Ok(state) => app.restore(state),
+        Err(e) => app.logs.error(e.to_string()),
+    }
+    if let Some(args) = prefill {
+        args.fill(&mut app);
+    }
+    app.stdin = (!stdin.is_empty()).then_some(stdin);
     let res = run_app(&mut terminal, app).await;
 
     // restore terminal
@@ -59,198 +186,593 @@ async fn main() -> Result<(), Box<dyn Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
-    if let Err(err) = res {
-        println!("{:?}", err)
+    match res {
+        Ok(app) => {
+            if let Err(e) = app.state().save() {
+                eprintln!("Could not save the session: {}", e)
+            }
+        }
+        Err(err) => println!("{:?}", err),
     }
 
     Ok(())
 }
 
-async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
-    loop {
-        terminal.draw(|f| ui(f, &app))?;
+/// The app with the config and everything saved on disk loaded, and any
+/// problem loading them logged.
+fn load_app() -> App {
+    let mut app = App::default();
+    match Config::load() {
+        Ok(config) => app.config = config,
+        Err(e) => app.logs.error(e.to_string()),
+    }
+    match History::load() {
+        Ok(history) => app.history = history,
+        Err(e) => app.logs.error(e.to_string()),
+    }
+    match Collections::load() {
+        Ok(collections) => app.collections = collections,
+        Err(e) => app.logs.error(e.to_string()),
+    }
+    match Environments::load() {
+        Ok(environments) => app.environments = environments,
+        Err(e) => app.logs.error(e.to_string()),
+    }
+    match TokenStore::load() {
+        Ok(tokens) => app.tokens = tokens,
+        Err(e) => app.logs.error(e.to_string()),
+    }
+    match CookieJar::load() {
+        Ok(cookies) => app.cookies = Arc::new(cookies),
+        Err(e) => app.logs.error(e.to_string()),
+    }
+    app
+}
+
+/// Runs until quit, handing back the app so its state can be saved.
+async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<App> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    while !app.quit {
+        terminal.draw(|f| ui(f, &mut app))?;
+
+        while let Ok(message) = rx.try_recv() {
+            app.handle_message(message);
+        }
+
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
+
+        let key = match event::read()? {
+            Event::Key(key) => key,
+            Event::Paste(text) => {
+                app.paste(&text);
+                continue;
+            }
+            Event::Mouse(mouse) => {
+                handle_mouse(&mut app, mouse);
+                continue;
+            }
+            _ => continue,
+        };
+
+        if key.code == KeyCode::Char('v')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+            && app.accepts_text()
+        {
+            app.paste_from_clipboard();
+            continue;
+        }
 
-        if let Event::Key(key) = event::read()? {
-            match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('i') => {
-                        app.input_mode = InputMode::Editing;
+        // `gg` only counts when the two presses are back to back
+        let pending_g = std::mem::take(&mut app.pending_g);
+        match app.input_mode {
+            InputMode::Normal => {
+                if let Some(action) = keymap::lookup(&key, &mut app) {
+                    run_action(&mut app, action, &tx, pending_g);
+                }
+            }
+            InputMode::Palette => match keymap::PALETTE.lookup(&key) {
+                Some(ModeAction::Down) => app.palette.select(1),
+                Some(ModeAction::Up) => app.palette.select(-1),
+                Some(ModeAction::Accept) => {
+                    app.input_mode = InputMode::Normal;
+                    if let Some(action) = app.palette.selected_action() {
+                        // There's no first `g` to wait for when picked by name
+                        run_action(&mut app, action, &tx, true);
                     }
-                    KeyCode::Char('q') => {
-                        return Ok(());
+                }
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => match key.code {
+                    KeyCode::Char(c) => app.palette.type_char(c),
+                    KeyCode::Backspace => app.palette.backspace(),
+                    _ => {}
+                },
+            },
+            InputMode::Finder => match keymap::FINDER.lookup(&key) {
+                Some(ModeAction::Down) => app.select_in_finder(1),
+                Some(ModeAction::Up) => app.select_in_finder(-1),
+                Some(ModeAction::Accept) => app.load_found(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => match key.code {
+                    KeyCode::Char(c) => app.finder.type_char(c),
+                    KeyCode::Backspace => app.finder.backspace(),
+                    _ => {}
+                },
+            },
+            InputMode::Help => match keymap::HELP.lookup(&key) {
+                Some(ModeAction::Down) => app.help_scroll = app.help_scroll.saturating_add(1),
+                Some(ModeAction::Up) => app.help_scroll = app.help_scroll.saturating_sub(1),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Visual => match keymap::VISUAL.lookup(&key) {
+                Some(ModeAction::Down) => app.move_visual(1),
+                Some(ModeAction::Up) => app.move_visual(-1),
+                Some(ModeAction::Copy) => app.copy_visual_selection(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Tree => match app.tree.as_mut() {
+                Some(tree) => match keymap::TREE.lookup(&key) {
+                    Some(ModeAction::Down) => tree.select(1),
+                    Some(ModeAction::Up) => tree.select(-1),
+                    Some(ModeAction::PageDown) => tree.select(app.response_height as isize),
+                    Some(ModeAction::PageUp) => tree.select(-(app.response_height as isize)),
+                    Some(ModeAction::Top) => tree.selected = 0,
+                    Some(ModeAction::Bottom) => tree.select(isize::MAX),
+                    Some(ModeAction::Right) => tree.expand(),
+                    Some(ModeAction::Left) => tree.collapse(),
+                    Some(ModeAction::Toggle) => tree.toggle(),
+                    Some(ModeAction::Copy) => app.copy_tree_path(false),
+                    Some(ModeAction::CopyJq) => app.copy_tree_path(true),
+                    Some(ModeAction::Table) => app.open_table(),
+                    Some(ModeAction::ExportCsv) => app.prompt_export_csv(),
+                    Some(ModeAction::Close) => app.close_tree(),
+                    _ => {}
+                },
+                None => app.close_tree(),
+            },
+            InputMode::JsonTable => match app.table.as_mut() {
+                Some(table) => match keymap::JSON_TABLE.lookup(&key) {
+                    Some(ModeAction::Down) => table.select(1),
+                    Some(ModeAction::Up) => table.select(-1),
+                    Some(ModeAction::PageDown) => table.select(app.response_height as isize),
+                    Some(ModeAction::PageUp) => table.select(-(app.response_height as isize)),
+                    Some(ModeAction::Top) => table.selected = 0,
+                    Some(ModeAction::Bottom) => table.select(isize::MAX),
+                    Some(ModeAction::Right) => table.select_column(1),
+                    Some(ModeAction::Left) => table.select_column(-1),
+                    Some(ModeAction::Sort) => table.sort(),
+                    Some(ModeAction::ExportCsv) => app.prompt_export_csv(),
+                    Some(ModeAction::Close) => app.close_table(),
+                    _ => {}
+                },
+                None => app.close_table(),
+            },
+            InputMode::Editing => match keymap::EDITING.lookup(&key) {
+                Some(ModeAction::Close) => {
+                    app.close_url_completions();
+                    app.input_mode = InputMode::Normal;
+                }
+                Some(ModeAction::Down) if !app.url_completions.is_empty() => {
+                    app.select_completion(1);
+                }
+                Some(ModeAction::Up) if !app.url_completions.is_empty() => {
+                    app.select_completion(-1);
+                }
+                Some(ModeAction::Complete) if !app.url_completions.is_empty() => {
+                    app.accept_completion();
+                }
+                Some(ModeAction::Accept) if app.completion_selected.is_some() => {
+                    app.accept_completion();
+                }
+                _ => {
+                    let edit = undo::edit_text(
+                        &key,
+                        &mut app.url,
+                        &mut app.url_cursor,
+                        &mut app.url_undo,
+                        |text, cursor| input::edit(&key, text, cursor),
+                    );
+                    if edit == Edit::Changed {
+                        app.sync_params_from_url();
+                        app.update_url_completions();
                     }
-                    KeyCode::Enter => {
-                        app.logs.push(String::from("Fetching results..."));
-                        let response = get_request(&app.url).await;
-                        app.response = match response {
-                            Ok(body) => body,
-                            Err(e) => panic!("Error: {:?}", e),
-                        };
-                        app.logs.push(String::from("Done"));
+                }
+            },
+            InputMode::EditingTable => {
+                if let Some(step) = Step::of(&key) {
+                    app.edit_focused_table(|table| table.step(step));
+                    continue;
+                }
+                match keymap::TABLE.lookup(&key) {
+                    Some(ModeAction::SwitchColumn) => {
+                        app.edit_focused_table(KeyValueTable::toggle_column);
                     }
-                    KeyCode::Tab => {
-                        app.current_window += 1;
-                        if app.current_window == 5 {
-                            app.current_window = 0
+                    Some(ModeAction::Up) => {
+                        if let Some(table) = app.focused_table() {
+                            table.previous();
                         }
                     }
-                    _ => {}
-                },
-                InputMode::Editing => match key.code {
+                    Some(ModeAction::Down) => {
+                        if let Some(table) = app.focused_table() {
+                            table.next();
+                        }
+                    }
+                    Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                    _ => match key.code {
+                        KeyCode::Char(c) => app.edit_focused_table(|table| table.push(c)),
+                        KeyCode::Backspace => app.edit_focused_table(KeyValueTable::pop),
+                        _ => {}
+                    },
+                }
+            }
+            InputMode::Search => match keymap::SEARCH.lookup(&key) {
+                Some(ModeAction::Accept) => app.input_mode = InputMode::Normal,
+                Some(ModeAction::Close) => {
+                    app.search.clear();
+                    app.input_mode = InputMode::Normal;
+                }
+                _ => match key.code {
                     KeyCode::Char(c) => {
-                        app.url.push(c);
+                        app.search.query.push(c);
+                        app.update_search();
                     }
                     KeyCode::Backspace => {
-                        app.url.pop();
-                    }
-                    KeyCode::Esc => {
-                        app.input_mode = InputMode::Normal;
+                        app.search.query.pop();
+                        app.update_search();
                     }
                     _ => {}
                 },
+            },
+            InputMode::Prompt => match keymap::PROMPT.lookup(&key) {
+                Some(ModeAction::Accept) => app.submit_prompt(&tx),
+                Some(ModeAction::Close) => app.close_prompt(),
+                _ => {
+                    if let Some(prompt) = app.prompt.as_mut() {
+                        match key.code {
+                            KeyCode::Char(c) => prompt.input.push(c),
+                            KeyCode::Backspace => {
+                                prompt.input.pop();
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            },
+            InputMode::History => match keymap::HISTORY.lookup(&key) {
+                Some(ModeAction::Down) => app.select_history(1),
+                Some(ModeAction::Up) => app.select_history(-1),
+                Some(ModeAction::Accept) => app.load_selected_history(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Snippets => match keymap::SNIPPETS.lookup(&key) {
+                Some(ModeAction::Right) => app.select_snippet(1),
+                Some(ModeAction::Left) => app.select_snippet(-1),
+                Some(ModeAction::Down) => app.scroll_snippet(1),
+                Some(ModeAction::Up) => app.scroll_snippet(-1),
+                Some(ModeAction::Copy) => app.copy_snippet(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Cookies => match keymap::COOKIES.lookup(&key) {
+                Some(ModeAction::Down) => app.select_cookie(1),
+                Some(ModeAction::Up) => app.select_cookie(-1),
+                Some(ModeAction::Edit) => app.edit_cookie(),
+                Some(ModeAction::Remove) => app.remove_cookie(),
+                Some(ModeAction::Toggle) => app.toggle_cookies(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Assertions => match keymap::ASSERTIONS.lookup(&key) {
+                Some(ModeAction::Down) => app.select_assertion(1),
+                Some(ModeAction::Up) => app.select_assertion(-1),
+                Some(ModeAction::Add) => app.prompt_add_assertion(),
+                Some(ModeAction::Edit) => app.prompt_edit_assertion(),
+                Some(ModeAction::Remove) => app.remove_assertion(),
+                Some(ModeAction::UpdateSnapshots) => app.update_snapshots(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Runner => match keymap::RUNNER.lookup(&key) {
+                Some(ModeAction::Down) => {
+                    if let Some(run) = app.run.as_mut() {
+                        run.select(1);
+                    }
+                }
+                Some(ModeAction::Up) => {
+                    if let Some(run) = app.run.as_mut() {
+                        run.select(-1);
+                    }
+                }
+                Some(ModeAction::Accept) => app.load_run_step(),
+                Some(ModeAction::Stop) => app.stop_run(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Monitor => match keymap::MONITOR.lookup(&key) {
+                Some(ModeAction::Down) => {
+                    if let Some(monitor) = app.monitor.as_mut() {
+                        monitor.select(1);
+                    }
+                }
+                Some(ModeAction::Up) => {
+                    if let Some(monitor) = app.monitor.as_mut() {
+                        monitor.select(-1);
+                    }
+                }
+                Some(ModeAction::Stop) => app.stop_monitor(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Trend => {
+                if keymap::TREND.lookup(&key) == Some(ModeAction::Close) {
+                    app.input_mode = InputMode::Normal;
+                }
+            }
+            InputMode::Bench => match keymap::BENCH.lookup(&key) {
+                Some(ModeAction::Stop) => app.stop_bench(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Settings => match keymap::SETTINGS.lookup(&key) {
+                Some(ModeAction::Down) => app.select_setting(1),
+                Some(ModeAction::Up) => app.select_setting(-1),
+                Some(ModeAction::Edit) => app.edit_setting(),
+                Some(ModeAction::Remove) => app.clear_setting(),
+                Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            // The completion keys only apply to the URL, so the rest go to the editor
+            InputMode::EditingBody | InputMode::EditingVariables => {
+                match keymap::EDITING.lookup(&key) {
+                    Some(ModeAction::Close) => app.input_mode = InputMode::Normal,
+                    _ => {
+                        if let Some((text, editor)) = app.edited_text() {
+                            editor.edit(&key, text);
+                        }
+                    }
+                }
             }
         }
+
+        if let Some(target) = app.external_edit.take() {
+            edit_externally(terminal, &mut app, target)?;
+        }
     }
+    Ok(app)
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([Constraint::Percentage(5), Constraint::Percentage(90)].as_ref())
-        .split(f.size());
-
-    // Top two inner blocks
-    let top_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(10), Constraint::Percentage(90)].as_ref())
-        .split(chunks[0]);
-
-    // Top left inner block with green background
-    let input = Paragraph::new("GET")
-        .style(if app.current_window == 0 {
-            match app.input_mode {
-                InputMode::Normal => Style::default().fg(Color::Cyan),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
-            }
-        } else {
-            Style::default()
-        })
-        .block(Block::default().borders(Borders::ALL).title("Method"));
-    f.render_widget(input, top_chunks[0]);
-
-    // Top right inner block with styled title aligned to the right
-    let input = Paragraph::new(app.url.as_ref())
-        .style(get_style(&app.current_window, 1, &app.input_mode))
-        .block(Block::default().borders(Borders::ALL).title("URL"));
-    f.render_widget(input, top_chunks[1]);
-    match app.input_mode {
-        InputMode::Normal =>
-            // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
-            {}
-
-        InputMode::Editing => {
-            // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
-            f.set_cursor(
-                // Put cursor past the end of the input text
-                top_chunks[1].x + app.url.width() as u16 + 1,
-                // Move one line down, from the border to the input line
-                top_chunks[1].y + 1,
-            )
+/// Leaves the TUI for `$EDITOR` to edit the body or a script, then comes
+/// back with what was saved.
+fn edit_externally<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    target: ExternalEdit,
+) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    let edited = match target {
+        ExternalEdit::Body => {
+            let extension = if app.body_mode == BodyMode::GraphQl {
+                "graphql"
+            } else if serde_json::from_str::<serde_json::Value>(&app.body).is_ok() {
+                "json"
+            } else {
+                "txt"
+            };
+            editor::edit_externally(&app.body, extension)
         }
+        ExternalEdit::PreRequestScript => editor::edit_externally(&app.pre_request, "rhai"),
+        ExternalEdit::PostResponseScript => editor::edit_externally(&app.post_response, "rhai"),
+    };
+    enable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+    match (target, edited) {
+        (ExternalEdit::Body, Ok(body)) => app.set_body_from_editor(body),
+        (ExternalEdit::Body, Err(e)) => app.logs.error(format!("Couldn't edit the body: {}", e)),
+        (target, Ok(script)) => app.set_script(target, script),
+        (_, Err(e)) => app.logs.error(format!("Couldn't edit the script: {}", e)),
     }
+    Ok(())
+}
 
-    // Bottom two inner blocks
-    let bottom_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
-        .split(chunks[1]);
-
-    let bottom_right_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(90), Constraint::Percentage(10)].as_ref())
-        .split(bottom_chunks[1]);
-
-    // Bottom left block with all default borders
-    let block = Block::default()
-        .style(if app.current_window == 2 {
-            match app.input_mode {
-                InputMode::Normal => Style::default().fg(Color::Cyan),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
+/// Does what a Normal mode key is bound to. `pending_g` is whether the key
+/// before was a first `g`.
+fn run_action(app: &mut App, action: Action, tx: &UnboundedSender<Message>, pending_g: bool) {
+    match action {
+        Action::NewTab => app.new_tab(),
+        Action::CloseTab => app.close_tab(),
+        Action::NextTab => app.switch_tab(1),
+        Action::PreviousTab => app.switch_tab(-1),
+        Action::FocusLeft => app.focus_towards(Towards::Left),
+        Action::FocusDown => app.focus_towards(Towards::Down),
+        Action::FocusUp => app.focus_towards(Towards::Up),
+        Action::FocusRight => app.focus_towards(Towards::Right),
+        Action::NextPane => app.cycle_focus(1),
+        Action::PreviousPane => app.cycle_focus(-1),
+        Action::CommandLine => app.open_command_line(),
+        Action::Palette => app.open_palette(),
+        Action::Finder => app.open_finder(),
+        Action::Help => app.open_help(),
+        Action::Visual => app.start_visual(),
+        Action::JsonTree => app.open_tree(),
+        Action::JsonTable => app.open_table(),
+        Action::ScrollTop if app.focus == Focus::Logs => {
+            if pending_g {
+                app.scroll_logs(i32::MIN);
+            } else {
+                app.pending_g = true;
             }
-        } else {
-            Style::default()
-        })
-        .title("Place Holder")
-        .borders(Borders::ALL);
-    f.render_widget(block, bottom_chunks[0]);
-
-    // Bottom right block with styled left and right border
-    let response = Paragraph::new(app.response.as_ref())
-        .style(if app.current_window == 3 {
-            match app.input_mode {
-                InputMode::Normal => Style::default().fg(Color::Cyan),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
+        }
+        Action::ScrollBottom if app.focus == Focus::Logs => app.scroll_logs(i32::MAX),
+        Action::ScrollDown if app.focus == Focus::Logs => app.scroll_logs(1),
+        Action::ScrollUp if app.focus == Focus::Logs => app.scroll_logs(-1),
+        Action::PageDown if app.focus == Focus::Logs => app.scroll_logs(app.logs_height as i32),
+        Action::PageUp if app.focus == Focus::Logs => app.scroll_logs(-(app.logs_height as i32)),
+        Action::ScrollTop => {
+            if pending_g {
+                app.scroll_response_to_top();
+            } else {
+                app.pending_g = true;
             }
-        } else {
-            Style::default()
-        })
-        .block(Block::default().borders(Borders::ALL).title("Response"));
-    f.render_widget(response, bottom_right_chunks[0]);
-
-    let logs: Vec<ListItem> = app
-        .logs
-        .iter()
-        .enumerate()
-        .map(|(i, m)| {
-            let content = vec![Spans::from(Span::raw(format!("{}: {}", i, m)))];
-            ListItem::new(content)
-        })
-        .collect();
-
-    let logs = List::new(logs)
-        .style(if app.current_window == 4 {
-            match app.input_mode {
-                InputMode::Normal => Style::default().fg(Color::Cyan),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
+        }
+        Action::ScrollBottom => app.scroll_response_to_bottom(),
+        Action::ScrollDown => app.scroll_response(1),
+        Action::ScrollUp => app.scroll_response(-1),
+        Action::PageDown => app.scroll_response(app.response_height as i32),
+        Action::PageUp => app.scroll_response(-(app.response_height as i32)),
+        Action::CopyBody => app.copy_response_body(),
+        Action::CopyHeaders => app.copy_response_headers(),
+        Action::CopyMatch => app.copy_search_match(),
+        Action::SaveResponse => app.prompt_save_response(),
+        Action::Filter => app.prompt_filter(),
+        Action::Unfilter => app.unfilter_response(),
+        Action::ToggleRaw => app.toggle_raw(),
+        Action::ToggleMetadata => app.show_metadata = !app.show_metadata,
+        Action::Search => {
+            app.search.clear();
+            app.input_mode = InputMode::Search;
+        }
+        Action::NextMatch => {
+            app.search.next();
+            app.jump_to_match();
+        }
+        Action::PreviousMatch => {
+            app.search.previous();
+            app.jump_to_match();
+        }
+        Action::Authorize => app.authorize(tx),
+        Action::EditAuthField => app.edit_auth_field(),
+        Action::CycleAuthKind => app.auth.cycle_kind(),
+        Action::NextAuthField => app.auth.next(),
+        Action::PreviousAuthField => app.auth.previous(),
+        Action::CycleBodyMode => app.cycle_body_mode(),
+        Action::EditVariables => app.start_editing_text(InputMode::EditingVariables),
+        Action::AttachFile => app.prompt_attach_file(),
+        Action::OpenInEditor => app.open_body_externally(),
+        Action::PreRequestScript => app.open_script(ExternalEdit::PreRequestScript),
+        Action::PostResponseScript => app.open_script(ExternalEdit::PostResponseScript),
+        Action::BodyFromStdin => app.body_from_stdin(),
+        Action::Edit => {
+            if app.focused_table().is_some() {
+                app.edit_focused_table(|table| {
+                    if table.rows.is_empty() {
+                        table.add_row();
+                    }
+                });
+                app.input_mode = InputMode::EditingTable;
+            } else if app.focus == Focus::Body {
+                app.start_editing_text(InputMode::EditingBody);
+            } else {
+                app.url_cursor = app.url.len();
+                app.input_mode = InputMode::Editing;
             }
-        } else {
-            Style::default()
-        })
-        .block(Block::default().borders(Borders::ALL).title("Logs"));
-    f.render_widget(logs, bottom_right_chunks[1]);
-}
-
-fn get_style(current_window: &i32, this_window: i32, input_mode: &InputMode) -> Style {
-    if *current_window == this_window {
-        match input_mode {
-            InputMode::Normal => Style::default().fg(Color::Cyan),
-            InputMode::Editing => Style::default().fg(Color::Yellow),
         }
-    } else {
-        Style::default()
+        Action::AddRow => {
+            app.edit_focused_table(KeyValueTable::add_row);
+            app.input_mode = InputMode::EditingTable;
+        }
+        Action::RemoveRow => app.edit_focused_table(KeyValueTable::remove_selected),
+        Action::ToggleRow => app.edit_focused_table(KeyValueTable::toggle_enabled),
+        Action::PreviousRow => {
+            if let Some(table) = app.focused_table() {
+                table.previous();
+            }
+        }
+        Action::NextRow => {
+            if let Some(table) = app.focused_table() {
+                table.next();
+            }
+        }
+        Action::PreviousMethod => app.method = cycle_method(&app.method, -1),
+        Action::NextMethod => app.method = cycle_method(&app.method, 1),
+        Action::RecentUrls => app.open_recent_urls(),
+        Action::Send => app.send(tx),
+        Action::Cancel => app.cancel_request(),
+        Action::Disconnect => app.disconnect_events(),
+        Action::Download => app.prompt_download(),
+        Action::History => app.open_history(),
+        Action::ImportCurl => app.open_prompt(
+            PromptAction::ImportCurl,
+            "Import curl command (or @file)",
+            "",
+        ),
+        Action::ImportFile => {
+            app.open_prompt(PromptAction::ImportFile, "Import collection from file", "")
+        }
+        Action::ExportHar => app.open_prompt(
+            PromptAction::ExportHar,
+            "Export session as HAR to",
+            "session.har",
+        ),
+        Action::ExportLogs => app.open_prompt(
+            PromptAction::ExportLogs,
+            "Export logs to",
+            &logs::export_name(),
+        ),
+        Action::ExportCode => app.open_snippets(),
+        Action::Monitor => app.open_monitor(tx),
+        Action::Bench => app.open_bench(tx),
+        Action::ResponseTimes => app.open_trend(),
+        Action::Cookies => app.open_cookies(),
+        Action::Assertions => app.open_assertions(),
+        Action::Settings => app.open_settings(),
+        Action::BearerToken => app.prompt_bearer_token(),
+        Action::CycleEnvironment => app.cycle_environment(),
+        Action::SaveRequest => app.prompt_save_request(),
+        Action::CycleLayout => app.cycle_layout(),
+        Action::CycleTheme => app.cycle_theme(),
+        Action::NextCollection => app.collections.next(),
+        Action::PreviousCollection => app.collections.previous(),
+        Action::RemoveCollection => app.remove_selected_collection(),
+        Action::LoadCollection => app.load_selected_collection(),
+        Action::RunCollection if app.collections.marked_requests().is_some() => app.run_marked(tx),
+        Action::RunCollection => app.run_collection(tx, None),
+        Action::MarkRequest => app.collections.toggle_mark(),
+        Action::Quit => app.quit = true,
     }
 }
 
-async fn get_request(url: &String) -> Result<String, Box<dyn Error>> {
-    let new_url;
-    if !url.starts_with("http") {
-        new_url = String::from("https://") + url;
-    } else {
-        new_url = String::from(url);
+/// Clicking a pane focuses it, and the wheel scrolls the Response and Logs
+/// panes. Ignored while typing or in a popup.
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    if !matches!(app.input_mode, InputMode::Normal) {
+        return;
     }
-
-    let mut res = reqwest::get(new_url).await?.text().await?;
-
-    let mut xf = jsonxf::Formatter::pretty_printer();
-    let formatted = match xf.format(&mut res) {
-        Ok(body) => body,
-        Err(e) => panic!("Error: {:?}", e),
+    let Some(window) = app.window_at(mouse.column, mouse.row) else {
+        return;
     };
-
-    Ok(formatted)
+    let delta = match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            app.focus = window;
+            return;
+        }
+        MouseEventKind::ScrollDown => WHEEL_LINES,
+        MouseEventKind::ScrollUp => -WHEEL_LINES,
+        _ => return,
+    };
+    match window {
+        Focus::Response => app.scroll_response(delta),
+        Focus::Logs => app.scroll_logs(delta),
+        _ => {}
+    }
 }
@@ -0,0 +1,226 @@
+use crate::app::Message;
+use crate::assertion;
+use crate::collections::Collection;
+use crate::error::AppError;
+use crate::logs::Level;
+use crate::openapi;
+use hyper::{header, server::conn::Http, service::service_fn, Body, Method, StatusCode};
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Port mocks listen on when none is given.
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// One part of a route's path.
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// `{id}`, `:id` or `{{id}}`, matching any one part.
+    Variable,
+}
+
+/// A response the mock gives to requests with its method and path.
+#[derive(Debug)]
+struct Route {
+    /// The saved request or operation it stands for, for the logs.
+    name: String,
+    method: Method,
+    path: Vec<Segment>,
+    status: u16,
+    content_type: String,
+    body: String,
+}
+
+impl Route {
+    /// How many of its parts are literals, if it answers `method` on
+    /// `path`, so the most specific route can be picked.
+    fn matches(&self, method: &Method, path: &[&str]) -> Option<usize> {
+        if self.method != method || self.path.len() != path.len() {
+            return None;
+        }
+        let mut literals = 0;
+        for (segment, part) in self.path.iter().zip(path) {
+            match segment {
+                Segment::Literal(literal) if literal == part => literals += 1,
+                Segment::Literal(_) => return None,
+                Segment::Variable => {}
+            }
+        }
+        Some(literals)
+    }
+}
+
+/// The parts of a URL's path as typed in a saved request, e.g.
+/// `{{base_url}}/users/{id}` or `https://api.example.com/users/:id`.
+fn segments(url: &str) -> Vec<Segment> {
+    let url = url.split(['?', '#']).next().unwrap_or("");
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |i| &rest[i..]),
+        // A variable standing for the scheme and host, as OpenAPI imports have
+        None if url.starts_with("{{") => url.find("}}").map_or("", |i| &url[i + 2..]),
+        None => url.find('/').map_or("", |i| &url[i..]),
+    };
+    path.split('/')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let braced = part.starts_with('{') && part.ends_with('}');
+            if part.starts_with(':') || braced || part.contains("{{") {
+                Segment::Variable
+            } else {
+                Segment::Literal(String::from(part))
+            }
+        })
+        .collect()
+}
+
+/// Example responses served over HTTP, one per request of a collection or
+/// operation of an OpenAPI spec.
+#[derive(Debug)]
+pub struct Mock {
+    pub name: String,
+    routes: Vec<Route>,
+}
+
+impl Mock {
+    /// Answers each saved request of `collection` with the response its
+    /// assertions describe, as for `assertion::example`, or an empty 200.
+    pub fn from_collection(collection: &Collection) -> Mock {
+        let routes = collection
+            .requests
+            .iter()
+            .filter_map(|saved| {
+                let (status, content_type, body) = assertion::example(&saved.assertions)
+                    .unwrap_or((200, String::new(), String::new()));
+                Some(Route {
+                    name: saved.name.clone(),
+                    method: Method::from_bytes(saved.method.as_bytes()).ok()?,
+                    path: segments(&saved.url),
+                    status,
+                    content_type,
+                    body,
+                })
+            })
+            .collect();
+        Mock {
+            name: collection.name.clone(),
+            routes,
+        }
+    }
+
+    /// Answers each operation of `spec` with its example response.
+    pub fn from_spec(spec: &Value) -> Mock {
+        let routes = openapi::operations(spec)
+            .into_iter()
+            .filter_map(|operation| {
+                let (method, path) = operation.split_once(' ')?;
+                let (status, content_type, body) = openapi::example(spec, &operation)?;
+                Some(Route {
+                    method: Method::from_bytes(method.as_bytes()).ok()?,
+                    path: segments(&format!("{{{{base_url}}}}{}", path)),
+                    status,
+                    content_type,
+                    body,
+                    name: operation,
+                })
+            })
+            .collect();
+        Mock {
+            name: String::from(openapi::title(spec)),
+            routes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// The route answering `method` on `path`, the most specific if several do.
+    fn route(&self, method: &Method, path: &str) -> Option<&Route> {
+        let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+        self.routes
+            .iter()
+            .filter_map(|route| Some((route.matches(method, &parts)?, route)))
+            .rev()
+            .max_by_key(|(literals, _)| *literals)
+            .map(|(_, route)| route)
+    }
+
+    /// The response to `request`, with a line for the logs saying what it was.
+    fn respond(&self, request: &hyper::Request<Body>) -> (hyper::Response<Body>, String) {
+        let (method, path) = (request.method(), request.uri().path());
+        let mut response = hyper::Response::builder()
+            // For frontends served from another origin
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+        let route = self.route(method, path);
+        let (status, body, answered) = match route {
+            Some(route) => {
+                if !route.content_type.is_empty() {
+                    response = response.header(header::CONTENT_TYPE, route.content_type.as_str());
+                }
+                let status = StatusCode::from_u16(route.status).unwrap_or(StatusCode::OK);
+                (status, route.body.clone(), route.name.as_str())
+            }
+            // Browsers ask before sending anything but simple requests across origins
+            None if method == Method::OPTIONS => {
+                response = response
+                    .header(header::ACCESS_CONTROL_ALLOW_METHODS, "*")
+                    .header(header::ACCESS_CONTROL_ALLOW_HEADERS, "*");
+                (StatusCode::NO_CONTENT, String::new(), "preflight")
+            }
+            None => (
+                StatusCode::NOT_FOUND,
+                format!("No request in {} for {} {}\n", self.name, method, path),
+                "no match",
+            ),
+        };
+        let line = format!(
+            "Mock: {} {} -> {} ({})",
+            method,
+            path,
+            status.as_u16(),
+            answered
+        );
+        let response = response
+            .status(status)
+            .body(Body::from(body))
+            .unwrap_or_default();
+        (response, line)
+    }
+}
+
+/// Serves `mock` on `port` of localhost until the task is dropped, logging
+/// each request on `tx`. Fails if the port can't be listened on.
+pub async fn serve(
+    mock: Arc<Mock>,
+    port: u16,
+    tx: UnboundedSender<Message>,
+) -> Result<(), AppError> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let _ = tx.send(Message::Log(
+        Level::Info,
+        format!(
+            "Mocking {} ({} requests) on http://127.0.0.1:{}",
+            mock.name,
+            mock.len(),
+            port
+        ),
+    ));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (mock, tx) = (mock.clone(), tx.clone());
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let (response, line) = mock.respond(&request);
+                let _ = tx.send(Message::Log(Level::Info, line));
+                async { Ok::<_, Infallible>(response) }
+            });
+            let _ = Http::new()
+                .http1_only(true)
+                .serve_connection(stream, service)
+                .await;
+        });
+    }
+}
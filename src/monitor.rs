@@ -0,0 +1,93 @@
+use crate::runner::Outcome;
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// How often a monitor sends when no interval is given.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Older samples are dropped once a monitor has this many.
+const MAX_SAMPLES: usize = 500;
+
+/// One send of a monitored request.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub at: DateTime<Local>,
+    pub outcome: Outcome,
+}
+
+/// A request sent again and again, to watch how it does over time.
+#[derive(Debug)]
+pub struct Monitor {
+    /// Told apart from samples of a monitor that has since been replaced.
+    pub id: u64,
+    /// e.g. `GET https://example.com/health`
+    pub label: String,
+    pub interval: Duration,
+    /// Whether the request has assertions, which then decide if it passed.
+    pub asserted: bool,
+    /// Newest first.
+    pub samples: VecDeque<Sample>,
+    /// The sample highlighted in the popup.
+    pub selected: usize,
+    pub task: Option<JoinHandle<()>>,
+}
+
+impl Monitor {
+    pub fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+
+    pub fn record(&mut self, outcome: Outcome) {
+        self.samples.push_front(Sample {
+            at: Local::now(),
+            outcome,
+        });
+        self.samples.truncate(MAX_SAMPLES);
+        // Keeps the same sample highlighted as new ones arrive above it
+        if self.selected > 0 {
+            self.selected = (self.selected + 1).min(self.samples.len() - 1);
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    pub fn select(&mut self, step: isize) {
+        let last = self.samples.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(step).min(last);
+    }
+
+    pub fn passed(&self) -> usize {
+        self.samples
+            .iter()
+            .filter(|sample| sample.outcome.passed(self.asserted))
+            .count()
+    }
+
+    /// How long each send that got a response took, newest first.
+    pub fn latencies(&self) -> Vec<Duration> {
+        self.samples
+            .iter()
+            .filter_map(|sample| match sample.outcome {
+                Outcome::Done { elapsed, .. } => Some(elapsed),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// e.g. `GET https://example.com/health every 5 s: 12 sent, 11 passed`
+    pub fn summary(&self) -> String {
+        format!(
+            "{} every {} s: {} sent, {} passed",
+            self.label,
+            self.interval.as_secs_f64(),
+            self.samples.len(),
+            self.passed()
+        )
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Deserialize;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// How to say a slow request has finished, for when the terminal isn't
+/// being watched.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Notification {
+    #[default]
+    None,
+    /// Rings the terminal bell.
+    Bell,
+    /// Shows a desktop notification.
+    Desktop,
+}
+
+/// Notifies that a request finished, with `summary` as the message.
+pub fn send(notification: Notification, summary: &str) -> io::Result<()> {
+    match notification {
+        Notification::None => Ok(()),
+        Notification::Bell => {
+            let mut stdout = io::stdout();
+            stdout.write_all(b"\x07")?;
+            stdout.flush()
+        }
+        Notification::Desktop => desktop(summary),
+    }
+}
+
+fn desktop(summary: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        let script = format!("display notification {:?} with title \"heade-rs\"", summary);
+        command.args(["-e", &script]);
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("msg");
+        command.args(["*", summary]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["heade-rs", summary]);
+        command
+    };
+    // Its output would otherwise be drawn over the UI
+    command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use crate::auth::{Auth, AuthKind};
+use crate::error::AppError;
+use crate::request::{self, Request};
+use crate::response::ResponseData;
+use crate::settings::Settings;
+use crate::storage;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Used when the Auth pane leaves the redirect URI empty.
+pub const DEFAULT_REDIRECT_URI: &str = "http://127.0.0.1:8765/callback";
+
+/// How long to wait for the browser to come back with a code.
+const REDIRECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Tokens are treated as expired this long before they actually are, so a
+/// request doesn't race the expiry.
+const EXPIRY_MARGIN_SECS: i64 = 30;
+
+const CALLBACK_PAGE: &str =
+    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: close\r\n\r\n\
+    <html><body><h1>httrs is authorized</h1><p>You can close this window.</p></body></html>";
+
+const NOT_FOUND_PAGE: &str = "HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n";
+
+/// A token from the token endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Token {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Token {
+    pub fn expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - Duration::seconds(EXPIRY_MARGIN_SECS) <= Utc::now())
+    }
+}
+
+/// The token endpoint's JSON reply, success or error.
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Tokens by environment and client, persisted to the data directory.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TokenStore {
+    pub tokens: BTreeMap<String, Token>,
+}
+
+impl TokenStore {
+    fn path() -> PathBuf {
+        storage::data_dir().join("tokens.json")
+    }
+
+    pub fn load() -> Result<TokenStore, AppError> {
+        storage::load_json(&TokenStore::path())
+    }
+
+    pub fn insert(&mut self, key: String, token: Token) -> Result<(), AppError> {
+        self.tokens.insert(key, token);
+        storage::save_json(&TokenStore::path(), self)
+    }
+}
+
+/// Where the token for `auth` is kept, so each environment has its own.
+pub fn token_key(environment: Option<&str>, auth: &Auth) -> String {
+    format!(
+        "{}|{}|{}",
+        environment.unwrap_or(""),
+        auth.value("token_url"),
+        auth.value("client_id")
+    )
+}
+
+fn redirect_uri(auth: &Auth) -> &str {
+    match auth.value("redirect_uri") {
+        "" => DEFAULT_REDIRECT_URI,
+        uri => uri,
+    }
+}
+
+/// An authorization-code flow waiting for the browser to redirect back.
+pub struct Pkce {
+    verifier: String,
+    state: String,
+    /// The authorization URL the user is sent to.
+    pub url: String,
+}
+
+impl Pkce {
+    pub fn new(auth: &Auth) -> Result<Pkce, AppError> {
+        let verifier = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+        let state = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 16]>());
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        let mut url = url::Url::parse(auth.value("auth_url"))
+            .map_err(|e| AppError::Auth(format!("authorization URL: {}", e)))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", auth.value("client_id"))
+            .append_pair("redirect_uri", redirect_uri(auth))
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256");
+        if !auth.value("scope").is_empty() {
+            url.query_pairs_mut()
+                .append_pair("scope", auth.value("scope"));
+        }
+        Ok(Pkce {
+            verifier,
+            state,
+            url: url.to_string(),
+        })
+    }
+
+    /// Waits for the redirect on a local listener and exchanges its code for a
+    /// token, sent with the request's `settings`.
+    pub async fn finish(self, auth: &Auth, settings: &Settings) -> Result<Token, AppError> {
+        let code = tokio::time::timeout(REDIRECT_TIMEOUT, self.receive_code(auth))
+            .await
+            .map_err(|_| AppError::Auth(String::from("timed out waiting for the browser")))??;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", redirect_uri(auth)),
+            ("client_id", auth.value("client_id")),
+            ("code_verifier", self.verifier.as_str()),
+        ];
+        if !auth.value("client_secret").is_empty() {
+            form.push(("client_secret", auth.value("client_secret")));
+        }
+        request_token(settings, auth.value("token_url"), &form).await
+    }
+
+    async fn receive_code(&self, auth: &Auth) -> Result<String, AppError> {
+        let redirect = url::Url::parse(redirect_uri(auth))
+            .map_err(|e| AppError::Auth(format!("redirect URI: {}", e)))?;
+        let host = redirect.host_str().unwrap_or("127.0.0.1");
+        let port = redirect.port_or_known_default().unwrap_or(80);
+        let listener = TcpListener::bind((host, port)).await?;
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let mut buf = vec![0; 8192];
+            let n = stream.read(&mut buf).await?;
+            let head = String::from_utf8_lossy(&buf[..n]);
+            // e.g. `GET /callback?code=..&state=.. HTTP/1.1`
+            let target = head.split_whitespace().nth(1).unwrap_or("");
+            let url = match redirect.join(target) {
+                Ok(url) if url.path() == redirect.path() => url,
+                // Browsers also ask for things like /favicon.ico
+                _ => {
+                    stream.write_all(NOT_FOUND_PAGE.as_bytes()).await?;
+                    continue;
+                }
+            };
+            stream.write_all(CALLBACK_PAGE.as_bytes()).await?;
+            let query: BTreeMap<String, String> = url.query_pairs().into_owned().collect();
+            if let Some(error) = query.get("error") {
+                return Err(AppError::Auth(format!("authorization denied: {}", error)));
+            }
+            if query.get("state") != Some(&self.state) {
+                return Err(AppError::Auth(String::from("redirect state did not match")));
+            }
+            return query
+                .get("code")
+                .cloned()
+                .ok_or_else(|| AppError::Auth(String::from("redirect had no code")));
+        }
+    }
+}
+
+/// Opens `url` in the default browser.
+pub fn open_browser(url: &str) -> Result<(), AppError> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    // The browser's output would otherwise be drawn over the UI
+    command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// Posts `form` to the token endpoint, through the same proxy, certificates
+/// and timeouts as the request itself.
+async fn request_token(
+    settings: &Settings,
+    token_url: &str,
+    form: &[(&str, &str)],
+) -> Result<Token, AppError> {
+    let client = settings.client(&Arc::new(Mutex::new(Vec::new())), None)?;
+    let response = client
+        .post(token_url)
+        .form(form)
+        .send()
+        .await
+        .map_err(|e| settings.timeout_error(e))?;
+    let status = response.status();
+    let body: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Auth(format!("token endpoint replied {}: {}", status, e)))?;
+    if let Some(error) = body.error {
+        let detail = body.error_description.unwrap_or_default();
+        return Err(AppError::Auth(
+            format!("{} {}", error, detail).trim().to_string(),
+        ));
+    }
+    let access_token = body
+        .access_token
+        .ok_or_else(|| AppError::Auth(String::from("token endpoint sent no access_token")))?;
+    Ok(Token {
+        access_token,
+        refresh_token: body.refresh_token,
+        expires_at: body.expires_in.map(|s| Utc::now() + Duration::seconds(s)),
+    })
+}
+
+/// Gets a new access token with a refresh token. Providers that don't rotate
+/// refresh tokens reply without one, so the old one is kept.
+pub async fn refresh(
+    auth: &Auth,
+    settings: &Settings,
+    refresh_token: &str,
+) -> Result<Token, AppError> {
+    let mut form = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+        ("client_id", auth.value("client_id")),
+    ];
+    if !auth.value("client_secret").is_empty() {
+        form.push(("client_secret", auth.value("client_secret")));
+    }
+    let mut token = request_token(settings, auth.value("token_url"), &form).await?;
+    if token.refresh_token.is_none() {
+        token.refresh_token = Some(String::from(refresh_token));
+    }
+    Ok(token)
+}
+
+/// Fetches a token with the client-credentials grant.
+pub async fn client_credentials(auth: &Auth, settings: &Settings) -> Result<Token, AppError> {
+    let mut form = vec![
+        ("grant_type", "client_credentials"),
+        ("client_id", auth.value("client_id")),
+        ("client_secret", auth.value("client_secret")),
+    ];
+    if !auth.value("scope").is_empty() {
+        form.push(("scope", auth.value("scope")));
+    }
+    request_token(settings, auth.value("token_url"), &form).await
+}
+
+/// A new token in place of `token`: client credentials are simply fetched
+/// again, while an authorization-code token needs its refresh token.
+async fn renew(
+    auth: &Auth,
+    settings: &Settings,
+    token: Option<&Token>,
+) -> Option<Result<Token, AppError>> {
+    if auth.kind == AuthKind::ClientCredentials {
+        return Some(client_credentials(auth, settings).await);
+    }
+    let refresh_token = token?.refresh_token.clone()?;
+    Some(refresh(auth, settings, &refresh_token).await)
+}
+
+/// Sends `request` with the stored token, renewing it first if it is missing
+/// or expired and again if the server answers 401. A renewed token is returned
+/// alongside the response so it can be stored.
+pub async fn send(
+    mut request: Request,
+    token: Option<Token>,
+) -> (Result<ResponseData, AppError>, Option<Token>) {
+    let mut renewed = false;
+    let mut token = match token {
+        Some(token) if !token.expired() => Some(token),
+        stale => match renew(&request.auth, &request.settings, stale.as_ref()).await {
+            Some(Ok(new)) => {
+                renewed = true;
+                Some(new)
+            }
+            Some(Err(e)) => return (Err(e), None),
+            None => stale,
+        },
+    };
+    let access_token = match &token {
+        Some(token) => token.access_token.clone(),
+        None => {
+            let e = AppError::Auth(String::from(
+                "no OAuth2 token yet, press a in the Auth pane to authorize",
+            ));
+            return (Err(e), None);
+        }
+    };
+    request.auth.set("access_token", &access_token);
+    let mut result = request::send(&request).await;
+    let unauthorized = matches!(&result, Ok(r) if r.status == StatusCode::UNAUTHORIZED);
+    if unauthorized && !renewed {
+        // On failure, show the 401 itself rather than the failed renewal
+        if let Some(Ok(new)) = renew(&request.auth, &request.settings, token.as_ref()).await {
+            request.auth.set("access_token", &new.access_token);
+            result = request::send(&request).await;
+            renewed = true;
+            token = Some(new);
+        }
+    }
+    (result, token.filter(|_| renewed))
+}
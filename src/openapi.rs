@@ -0,0 +1,957 @@
+use crate::collections::{Collection, SavedRequest};
+use crate::environment::Environment;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::response::ResponseData;
+use crate::settings::Settings;
+use crate::storage;
+use regex::Regex;
+use reqwest::header;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Operation keys of a path item, in the order they are listed.
+const METHODS: [&str; 8] = [
+    "get", "post", "put", "patch", "delete", "head", "options", "trace",
+];
+
+/// How deep `$ref`s and nested schemas are followed when generating examples,
+/// which also stops recursive schemas.
+const MAX_DEPTH: usize = 6;
+
+/// How deep schemas are followed when checking a response, which stops
+/// schemas made only of themselves.
+const MAX_CHECK_DEPTH: usize = 64;
+
+/// How many violations a failed check lists before counting the rest.
+const SHOWN: usize = 3;
+
+/// Specs already read, by name, since `validate` is run on every redraw.
+static SPECS: Mutex<BTreeMap<String, Arc<Value>>> = Mutex::new(BTreeMap::new());
+
+/// Whether `json` looks like an OpenAPI 3 or Swagger 2 document.
+pub fn detect(json: &Value) -> bool {
+    json.get("openapi").is_some() || json.get("swagger").is_some()
+}
+
+/// Creates a collection entry per operation, with URLs based on a
+/// `{{base_url}}` variable from an environment named after the API. Each
+/// asserts that its responses are as the spec documents, once it's kept
+/// with `save_spec`.
+pub fn import(spec: &Value) -> Result<(Collection, Option<Environment>), AppError> {
+    let paths = spec
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| AppError::Parse(String::from("OpenAPI document: no paths")))?;
+    let title = title(spec);
+
+    let mut requests = Vec::new();
+    for (path, item) in paths {
+        let item = resolve(spec, item);
+        let shared = item.get("parameters").and_then(Value::as_array);
+        for method in METHODS {
+            if let Some(operation) = item.get(method) {
+                requests.push(operation_request(
+                    spec, title, path, method, operation, shared,
+                ));
+            }
+        }
+    }
+
+    let mut variables = BTreeMap::new();
+    variables.insert(String::from("base_url"), base_url(spec));
+    let environment = Environment {
+        name: String::from(title),
+        variables,
+        settings: Settings::default(),
+    };
+    let collection = Collection {
+        name: String::from(title),
+        requests,
+    };
+    Ok((collection, Some(environment)))
+}
+
+/// Follows a local `$ref` such as `#/components/schemas/User`.
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+    for _ in 0..MAX_DEPTH {
+        match value
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|r| r.strip_prefix('#'))
+            .and_then(|pointer| spec.pointer(pointer))
+        {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+    value
+}
+
+/// The first server of an OpenAPI 3 document, or the host and base path of a
+/// Swagger 2 one.
+fn base_url(spec: &Value) -> String {
+    if let Some(server) = spec.pointer("/servers/0") {
+        let mut url = server
+            .get("url")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+        // Server URLs can have `{variables}` with defaults of their own
+        if let Some(vars) = server.get("variables").and_then(Value::as_object) {
+            for (name, var) in vars {
+                if let Some(default) = var.get("default").and_then(Value::as_str) {
+                    url = url.replace(&format!("{{{}}}", name), default);
+                }
+            }
+        }
+        return url.trim_end_matches('/').to_string();
+    }
+    let host = spec.get("host").and_then(Value::as_str).unwrap_or("");
+    let scheme = spec
+        .pointer("/schemes/0")
+        .and_then(Value::as_str)
+        .unwrap_or("https");
+    let base_path = spec.get("basePath").and_then(Value::as_str).unwrap_or("");
+    if host.is_empty() {
+        base_path.trim_end_matches('/').to_string()
+    } else {
+        format!("{}://{}{}", scheme, host, base_path.trim_end_matches('/'))
+    }
+}
+
+fn operation_request(
+    spec: &Value,
+    title: &str,
+    path: &str,
+    method: &str,
+    operation: &Value,
+    shared: Option<&Vec<Value>>,
+) -> SavedRequest {
+    let name = operation
+        .get("operationId")
+        .or_else(|| operation.get("summary"))
+        .and_then(Value::as_str)
+        .map_or_else(
+            || format!("{} {}", method.to_ascii_uppercase(), path),
+            String::from,
+        );
+
+    let mut url = format!("{{{{base_url}}}}{}", path);
+    let mut query = Vec::new();
+    let mut headers = Vec::new();
+    let mut body = String::new();
+
+    let own = operation.get("parameters").and_then(Value::as_array);
+    let parameters = shared.into_iter().chain(own).flatten();
+    for parameter in parameters {
+        let parameter = resolve(spec, parameter);
+        let name = parameter.get("name").and_then(Value::as_str).unwrap_or("");
+        let example = parameter_example(spec, parameter);
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => {
+                if let Some(example) = example {
+                    url = url.replace(&format!("{{{}}}", name), &example);
+                }
+            }
+            Some("query") => {
+                let required = parameter.get("required").and_then(Value::as_bool) == Some(true);
+                if required || example.is_some() {
+                    query.push(format!("{}={}", name, example.unwrap_or_default()));
+                }
+            }
+            Some("header") => headers.push(KeyValue::new(name, &example.unwrap_or_default())),
+            // Swagger 2 puts the body among the parameters
+            Some("body") => {
+                if let Some(schema) = parameter.get("schema") {
+                    body = pretty(&schema_example(spec, schema, 0));
+                    headers.push(KeyValue::new("Content-Type", "application/json"));
+                }
+            }
+            _ => {}
+        }
+    }
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+
+    if let Some(content) = operation
+        .get("requestBody")
+        .map(|b| resolve(spec, b))
+        .and_then(|b| b.get("content"))
+        .and_then(Value::as_object)
+    {
+        // Prefer JSON when the operation accepts several media types
+        let media = content
+            .iter()
+            .find(|(media_type, _)| media_type.contains("json"))
+            .or_else(|| content.iter().next());
+        if let Some((media_type, media)) = media {
+            headers.push(KeyValue::new("Content-Type", media_type));
+            body = pretty(&media_example(spec, media));
+        }
+    }
+
+    SavedRequest {
+        name,
+        method: method.to_ascii_uppercase(),
+        url,
+        headers,
+        body,
+        assertions: vec![format!(
+            "openapi {} {}",
+            json!(title),
+            json!(format!("{} {}", method.to_ascii_uppercase(), path))
+        )],
+        ..Default::default()
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => serde_json::to_string_pretty(value).unwrap_or_default(),
+    }
+}
+
+/// A parameter's example or default as text, if the spec gives one.
+fn parameter_example(spec: &Value, parameter: &Value) -> Option<String> {
+    let schema = parameter.get("schema").map(|s| resolve(spec, s));
+    let value = parameter
+        .get("example")
+        .or_else(|| schema.and_then(|s| s.get("example")))
+        .or_else(|| parameter.get("default"))
+        .or_else(|| schema.and_then(|s| s.get("default")))?;
+    Some(match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    })
+}
+
+/// An example body for a media type object: its own example, the first of
+/// its named examples, or one generated from the schema.
+fn media_example(spec: &Value, media: &Value) -> Value {
+    if let Some(example) = media.get("example") {
+        return example.clone();
+    }
+    if let Some(example) = media
+        .get("examples")
+        .and_then(Value::as_object)
+        .and_then(|examples| examples.values().next())
+        .map(|example| resolve(spec, example))
+        .and_then(|example| example.get("value"))
+    {
+        return example.clone();
+    }
+    match media.get("schema") {
+        Some(schema) => schema_example(spec, schema, 0),
+        None => Value::Null,
+    }
+}
+
+/// Builds a placeholder value that matches `schema`.
+fn schema_example(spec: &Value, schema: &Value, depth: usize) -> Value {
+    if depth > MAX_DEPTH {
+        return Value::Null;
+    }
+    let schema = resolve(spec, schema);
+    if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+        return example.clone();
+    }
+    if let Some(first) = schema.pointer("/enum/0") {
+        return first.clone();
+    }
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in parts {
+            if let Value::Object(fields) = schema_example(spec, part, depth + 1) {
+                merged.extend(fields);
+            }
+        }
+        return Value::Object(merged);
+    }
+    // For a choice of schemas the first one will do
+    if let Some(first) = schema
+        .pointer("/oneOf/0")
+        .or_else(|| schema.pointer("/anyOf/0"))
+    {
+        return schema_example(spec, first, depth + 1);
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("object") | None if schema.get("properties").is_some() => {
+            let mut fields = Map::new();
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, property) in properties {
+                    fields.insert(name.clone(), schema_example(spec, property, depth + 1));
+                }
+            }
+            Value::Object(fields)
+        }
+        Some("object") => json!({}),
+        Some("array") => match schema.get("items") {
+            Some(items) => json!([schema_example(spec, items, depth + 1)]),
+            None => json!([]),
+        },
+        Some("string") => json!(""),
+        Some("integer") | Some("number") => json!(0),
+        Some("boolean") => json!(false),
+        _ => Value::Null,
+    }
+}
+
+/// The API's title, which its spec is saved under.
+pub fn title(spec: &Value) -> &str {
+    spec.pointer("/info/title")
+        .and_then(Value::as_str)
+        .unwrap_or("OpenAPI")
+}
+
+fn spec_path(name: &str) -> PathBuf {
+    storage::data_dir()
+        .join("specs")
+        .join(format!("{}.json", storage::file_stem(name)))
+}
+
+/// Keeps `spec` in the data directory as the API `name`, for `validate`.
+pub fn save_spec(name: &str, spec: &Value) -> Result<(), AppError> {
+    storage::save_json(&spec_path(name), spec)?;
+    if let Ok(mut specs) = SPECS.lock() {
+        specs.insert(String::from(name), Arc::new(spec.clone()));
+    }
+    Ok(())
+}
+
+/// The spec kept as the API `name` by `save_spec`.
+pub fn load_spec(name: &str) -> Result<Arc<Value>, String> {
+    let mut specs = SPECS.lock().map_err(|e| e.to_string())?;
+    if let Some(spec) = specs.get(name) {
+        return Ok(spec.clone());
+    }
+    let spec: Value = storage::load_json(&spec_path(name)).map_err(|e| e.to_string())?;
+    if spec.is_null() {
+        return Err(format!("no OpenAPI spec imported as {}", name));
+    }
+    let spec = Arc::new(spec);
+    specs.insert(String::from(name), spec.clone());
+    Ok(spec)
+}
+
+/// Checks `response` against what the spec of the API `name` documents for
+/// `operation`, e.g. `GET /pets/{petId}`: that its status is one listed,
+/// and that a JSON body matches the schema given for it. Says what doesn't
+/// if anything.
+pub fn validate(name: &str, operation: &str, response: &ResponseData) -> Result<(), String> {
+    let failed = |reason: String| format!("openapi {:?} {:?}: {}", name, operation, reason);
+    let spec = load_spec(name).map_err(failed)?;
+    let found = violations(&spec, operation, response).map_err(failed)?;
+    if found.is_empty() {
+        return Ok(());
+    }
+    let mut reason = found[..found.len().min(SHOWN)].join("; ");
+    if found.len() > SHOWN {
+        reason += &format!("; and {} more", found.len() - SHOWN);
+    }
+    Err(failed(reason))
+}
+
+/// Each way `response` differs from the spec, or why it can't be checked.
+fn violations(
+    spec: &Value,
+    operation: &str,
+    response: &ResponseData,
+) -> Result<Vec<String>, String> {
+    let (method, path) = operation.split_once(' ').unwrap_or(("GET", operation));
+    let documented = spec
+        .get("paths")
+        .and_then(|paths| paths.get(path))
+        .map(|item| resolve(spec, item))
+        .and_then(|item| item.get(method.to_ascii_lowercase()))
+        .and_then(|operation| operation.get("responses"))
+        .ok_or_else(|| String::from("not in the spec"))?;
+    let documented = resolve(spec, documented);
+    let status = response.status.as_u16();
+    let range = format!("{}XX", status / 100);
+    let Some(documented) = documented
+        .get(status.to_string())
+        .or_else(|| documented.get(&range))
+        .or_else(|| documented.get(range.to_ascii_lowercase()))
+        .or_else(|| documented.get("default"))
+    else {
+        return Ok(vec![format!("status {} isn't documented", status)]);
+    };
+    let documented = resolve(spec, documented);
+    let content_type = response
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let schema = match documented.get("content").and_then(Value::as_object) {
+        Some(content) if !content.is_empty() => {
+            let (group, _) = content_type.split_once('/').unwrap_or(("", ""));
+            let media = content
+                .get(&content_type)
+                .or_else(|| content.get(&format!("{}/*", group)))
+                .or_else(|| content.get("*/*"));
+            match media {
+                Some(media) => media.get("schema"),
+                None => {
+                    return Ok(vec![format!(
+                        "Content-Type {} isn't documented for status {}",
+                        content_type, status
+                    )])
+                }
+            }
+        }
+        // Swagger 2 gives the schema on the response itself
+        _ => documented.get("schema"),
+    };
+    let mut found = Vec::new();
+    if let Some(schema) = schema {
+        if content_type.is_empty() || content_type.contains("json") {
+            match serde_json::from_str::<Value>(response.raw_body.as_str()) {
+                Ok(body) => check_schema(spec, schema, &body, "$", 0, &mut found),
+                Err(e) => found.push(format!("body isn't JSON: {}", e)),
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// The JSON type of `value`, as schemas name them.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, wanted: &str) -> bool {
+    match (wanted, value) {
+        ("number", Value::Number(_)) => true,
+        ("integer", Value::Number(n)) => n.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => type_name(value) == wanted,
+    }
+}
+
+/// Adds each way `value`, found at `path`, doesn't match `schema` to `found`.
+fn check_schema(
+    spec: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    depth: usize,
+    found: &mut Vec<String>,
+) {
+    if depth > MAX_CHECK_DEPTH {
+        return;
+    }
+    let schema = resolve(spec, schema);
+    let nullable = ["nullable", "x-nullable"]
+        .iter()
+        .any(|key| schema.get(key).and_then(Value::as_bool) == Some(true));
+    if value.is_null() && nullable {
+        return;
+    }
+    let matches = |part: &Value| {
+        let mut failed = Vec::new();
+        check_schema(spec, part, value, path, depth + 1, &mut failed);
+        failed.is_empty()
+    };
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        for part in parts {
+            check_schema(spec, part, value, path, depth + 1, found);
+        }
+    }
+    if let Some(parts) = schema.get("anyOf").and_then(Value::as_array) {
+        if !parts.iter().any(matches) {
+            found.push(format!("{} matches none of its anyOf schemas", path));
+        }
+    }
+    if let Some(parts) = schema.get("oneOf").and_then(Value::as_array) {
+        let matched = parts.iter().filter(|part| matches(part)).count();
+        if matched != 1 {
+            found.push(format!(
+                "{} matches {} of its oneOf schemas, not 1",
+                path, matched
+            ));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            found.push(format!(
+                "{} is {}, not one of the values allowed",
+                path, value
+            ));
+        }
+    }
+    // 3.1 lists types, e.g. ["string", "null"], where 3.0 has `nullable`
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(wanted)) => vec![wanted.as_str()],
+        Some(Value::Array(wanted)) => wanted.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|wanted| has_type(value, wanted)) {
+        found.push(format!(
+            "{} should be {}, not {}",
+            path,
+            types.join(" or "),
+            type_name(value)
+        ));
+        return;
+    }
+    match value {
+        Value::Object(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for required in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !fields.contains_key(required) {
+                    found.push(format!("{}.{} is missing", path, required));
+                }
+            }
+            for (key, field) in fields {
+                let child = format!("{}.{}", path, key);
+                match (
+                    properties.and_then(|properties| properties.get(key)),
+                    schema.get("additionalProperties"),
+                ) {
+                    (Some(property), _) => {
+                        check_schema(spec, property, field, &child, depth + 1, found)
+                    }
+                    (None, Some(Value::Bool(false))) => {
+                        found.push(format!("{} isn't in the schema", child))
+                    }
+                    (None, Some(extra @ Value::Object(_))) => {
+                        check_schema(spec, extra, field, &child, depth + 1, found)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if count < min {
+                    found.push(format!("{} has {} items, fewer than {}", path, count, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if count > max {
+                    found.push(format!("{} has {} items, more than {}", path, count, max));
+                }
+            }
+            if let Some(item) = schema.get("items") {
+                for (i, value) in items.iter().enumerate() {
+                    check_schema(
+                        spec,
+                        item,
+                        value,
+                        &format!("{}[{}]", path, i),
+                        depth + 1,
+                        found,
+                    );
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    found.push(format!("{} is shorter than {} characters", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    found.push(format!("{} is longer than {} characters", path, max));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                if Regex::new(pattern).is_ok_and(|pattern| !pattern.is_match(text)) {
+                    found.push(format!("{} doesn't match {}", path, pattern));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    found.push(format!(
+                        "{} is {}, under the minimum of {}",
+                        path, number, min
+                    ));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    found.push(format!(
+                        "{} is {}, over the maximum of {}",
+                        path, number, max
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Every operation in `spec`, e.g. `GET /pets/{petId}`, in the order listed.
+pub fn operations(spec: &Value) -> Vec<String> {
+    let mut operations = Vec::new();
+    for (path, item) in spec
+        .get("paths")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        let item = resolve(spec, item);
+        for method in METHODS {
+            if item.get(method).is_some() {
+                operations.push(format!("{} {}", method.to_ascii_uppercase(), path));
+            }
+        }
+    }
+    operations
+}
+
+/// A response `spec` documents for `operation`, the first successful one
+/// if there is one: its status, Content-Type and an example body.
+pub fn example(spec: &Value, operation: &str) -> Option<(u16, String, String)> {
+    let (method, path) = operation.split_once(' ')?;
+    let responses = spec
+        .get("paths")?
+        .get(path)
+        .map(|item| resolve(spec, item))?
+        .get(method.to_ascii_lowercase())?
+        .get("responses")
+        .map(|responses| resolve(spec, responses))?
+        .as_object()?;
+    let (status, documented) = responses
+        .iter()
+        .find(|(status, _)| status.starts_with('2'))
+        .or_else(|| responses.iter().next())?;
+    // `default` and ranges like `2XX` don't say which status to send
+    let status = status
+        .parse()
+        .unwrap_or(if status.starts_with('2') { 200 } else { 500 });
+    let documented = resolve(spec, documented);
+    let (content_type, body) = match documented.get("content").and_then(Value::as_object) {
+        Some(content) => match content
+            .iter()
+            .find(|(media_type, _)| media_type.contains("json"))
+            .or_else(|| content.iter().next())
+        {
+            Some((media_type, media)) => (media_type.clone(), pretty(&media_example(spec, media))),
+            None => (String::new(), String::new()),
+        },
+        // Swagger 2 gives the schema on the response itself
+        None => match documented.get("schema") {
+            Some(schema) => (
+                String::from("application/json"),
+                pretty(&schema_example(spec, schema, 0)),
+            ),
+            None => (String::new(), String::new()),
+        },
+    };
+    Some((status, content_type, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthKind;
+    use crate::body::BodyMode;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use reqwest::{StatusCode, Version};
+    use std::time::Duration;
+
+    fn spec() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "info": {"title": "Pets", "version": "1"},
+            "servers": [{"url": "https://{region}.pets.test/v1/", "variables": {"region": {"default": "eu"}}}],
+            "paths": {
+                "/pets/{petId}": {
+                    "parameters": [{"name": "petId", "in": "path", "required": true, "schema": {"type": "integer", "example": 7}}],
+                    "get": {
+                        "operationId": "showPet",
+                        "parameters": [
+                            {"name": "verbose", "in": "query", "schema": {"type": "boolean", "default": false}},
+                            {"name": "fields", "in": "query", "required": true},
+                            {"name": "limit", "in": "query"},
+                            {"$ref": "#/components/parameters/Trace"}
+                        ]
+                    },
+                    "delete": {"summary": "Remove a pet"}
+                },
+                "/pets": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "text/plain": {"example": "a pet"},
+                                "application/json": {"schema": {"$ref": "#/components/schemas/Pet"}}
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "parameters": {"Trace": {"name": "X-Trace", "in": "header", "example": "abc"}},
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string", "example": "Rex"},
+                            "kind": {"enum": ["dog", "cat"]},
+                            "tags": {"type": "array", "items": {"type": "string"}},
+                            "age": {"type": "integer"}
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn request<'a>(collection: &'a Collection, name: &str) -> &'a SavedRequest {
+        collection
+            .requests
+            .iter()
+            .find(|request| request.name == name)
+            .unwrap_or_else(|| panic!("no request {}", name))
+    }
+
+    #[test]
+    fn detects_openapi_and_swagger() {
+        assert!(detect(&spec()));
+        assert!(detect(&json!({"swagger": "2.0"})));
+        assert!(!detect(&json!({"info": {}})));
+    }
+
+    #[test]
+    fn imports_a_request_per_operation() {
+        let (collection, environment) = import(&spec()).unwrap();
+        assert_eq!(collection.name, "Pets");
+        assert_eq!(collection.requests.len(), 3);
+        let environment = environment.unwrap();
+        assert_eq!(environment.name, "Pets");
+        assert_eq!(environment.variables["base_url"], "https://eu.pets.test/v1");
+    }
+
+    #[test]
+    fn fills_in_path_query_and_header_parameters() {
+        let (collection, _) = import(&spec()).unwrap();
+        let show = request(&collection, "showPet");
+        assert_eq!(show.method, "GET");
+        // Optional parameters without an example are left out
+        assert_eq!(show.url, "{{base_url}}/pets/7?verbose=false&fields=");
+        assert_eq!(show.headers, [KeyValue::new("X-Trace", "abc")]);
+        assert!(show.body.is_empty());
+        assert_eq!(show.assertions, [r#"openapi "Pets" "GET /pets/{petId}""#]);
+    }
+
+    #[test]
+    fn names_operations_by_summary_then_method_and_path() {
+        let (collection, _) = import(&spec()).unwrap();
+        assert_eq!(request(&collection, "Remove a pet").method, "DELETE");
+        assert_eq!(request(&collection, "POST /pets").url, "{{base_url}}/pets");
+    }
+
+    #[test]
+    fn generates_a_json_body_from_the_schema() {
+        let (collection, _) = import(&spec()).unwrap();
+        let create = request(&collection, "POST /pets");
+        assert_eq!(
+            create.headers,
+            [KeyValue::new("Content-Type", "application/json")]
+        );
+        let body: Value = serde_json::from_str(&create.body).unwrap();
+        assert_eq!(
+            body,
+            json!({"name": "Rex", "kind": "dog", "tags": [""], "age": 0})
+        );
+    }
+
+    #[test]
+    fn leaves_what_the_spec_has_no_equivalent_for_unset() {
+        let (collection, _) = import(&spec()).unwrap();
+        for request in &collection.requests {
+            assert_eq!(request.body_mode, BodyMode::Raw);
+            assert!(request.fields.is_empty());
+            assert_eq!(request.auth.kind, AuthKind::None);
+            assert!(request.settings.values.is_empty());
+            assert!(request.pre_request.is_empty());
+            assert!(request.post_response.is_empty());
+            assert!(request.requires.is_empty());
+        }
+    }
+
+    #[test]
+    fn imports_swagger_2_hosts_and_body_parameters() {
+        let spec = json!({
+            "swagger": "2.0",
+            "info": {"title": "Old"},
+            "host": "old.test",
+            "basePath": "/api/",
+            "schemes": ["http"],
+            "paths": {
+                "/things": {
+                    "put": {
+                        "operationId": "putThing",
+                        "parameters": [{"name": "thing", "in": "body", "schema": {"type": "object", "properties": {"id": {"type": "integer"}}}}]
+                    }
+                }
+            }
+        });
+        let (collection, environment) = import(&spec).unwrap();
+        assert_eq!(
+            environment.unwrap().variables["base_url"],
+            "http://old.test/api"
+        );
+        let put = request(&collection, "putThing");
+        assert_eq!(
+            put.headers,
+            [KeyValue::new("Content-Type", "application/json")]
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&put.body).unwrap(),
+            json!({"id": 0})
+        );
+    }
+
+    #[test]
+    fn rejects_a_spec_without_paths() {
+        assert!(import(&json!({"openapi": "3.0.0"})).is_err());
+    }
+
+    fn response(status: u16, content_type: &str, body: &str) -> ResponseData {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_str(content_type).unwrap(),
+        );
+        ResponseData::new(
+            StatusCode::from_u16(status).unwrap(),
+            Version::HTTP_11,
+            headers,
+            Duration::from_millis(1),
+            body.as_bytes(),
+            true,
+        )
+    }
+
+    fn documented() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "paths": {
+                "/pets/{petId}": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": {"schema": {"$ref": "#/components/schemas/Pet"}}
+                                }
+                            },
+                            "4XX": {"description": "No such pet"}
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "required": ["id", "name"],
+                        "properties": {
+                            "id": {"type": "integer"},
+                            "name": {"type": "string"},
+                            "kind": {"enum": ["dog", "cat"]},
+                            "owner": {"$ref": "#/components/schemas/Owner"}
+                        }
+                    },
+                    "Owner": {
+                        "type": "object",
+                        "required": ["email"],
+                        "properties": {"email": {"type": "string"}}
+                    }
+                }
+            }
+        })
+    }
+
+    fn check(status: u16, body: &str) -> Vec<String> {
+        let response = response(status, "application/json; charset=utf-8", body);
+        violations(&documented(), "GET /pets/{petId}", &response).unwrap()
+    }
+
+    #[test]
+    fn passes_a_response_matching_the_schema() {
+        assert!(check(200, r#"{"id": 1, "name": "Rex", "kind": "dog"}"#).is_empty());
+        assert!(check(404, "").is_empty());
+    }
+
+    #[test]
+    fn reports_the_wrong_type() {
+        assert_eq!(
+            check(200, r#"{"id": "1", "name": "Rex"}"#),
+            ["$.id should be integer, not string"]
+        );
+        assert_eq!(check(200, "[]"), ["$ should be object, not array"]);
+    }
+
+    #[test]
+    fn reports_missing_required_fields() {
+        assert_eq!(check(200, r#"{"name": "Rex"}"#), ["$.id is missing"]);
+    }
+
+    #[test]
+    fn reports_values_outside_the_enum() {
+        assert_eq!(
+            check(200, r#"{"id": 1, "name": "Rex", "kind": "fish"}"#),
+            [r#"$.kind is "fish", not one of the values allowed"#]
+        );
+    }
+
+    #[test]
+    fn follows_refs_into_nested_schemas() {
+        assert_eq!(
+            check(200, r#"{"id": 1, "name": "Rex", "owner": {"email": 5}}"#),
+            ["$.owner.email should be string, not integer"]
+        );
+        assert_eq!(
+            check(200, r#"{"id": 1, "name": "Rex", "owner": {}}"#),
+            ["$.owner.email is missing"]
+        );
+    }
+
+    #[test]
+    fn reports_undocumented_statuses_and_content_types() {
+        assert_eq!(check(500, "{}"), ["status 500 isn't documented"]);
+        let html = response(200, "text/html", "<p>hi</p>");
+        assert_eq!(
+            violations(&documented(), "GET /pets/{petId}", &html).unwrap(),
+            ["Content-Type text/html isn't documented for status 200"]
+        );
+        assert_eq!(check(200, "not json").len(), 1);
+        assert!(check(200, "not json")[0].starts_with("body isn't JSON"));
+    }
+
+    #[test]
+    fn fails_for_operations_not_in_the_spec() {
+        let response = response(200, "application/json", "{}");
+        assert!(violations(&documented(), "POST /pets/{petId}", &response).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::fuzzy;
+use crate::keymap::{self, Action};
+
+/// The Ctrl+P popup for finding any action by name.
+#[derive(Debug, Default)]
+pub struct Palette {
+    pub query: String,
+    /// Index into `matches()` of the highlighted action.
+    pub selected: usize,
+}
+
+impl Palette {
+    /// Every action the keymap binds, with its keys, e.g. `Ctrl+S`.
+    pub fn entries() -> Vec<(Action, String)> {
+        let mut entries: Vec<(Action, String)> = Vec::new();
+        for binding in keymap::BINDINGS {
+            if binding.action == Action::Palette {
+                continue;
+            }
+            let keys = keymap::keys_label(binding);
+            match entries
+                .iter_mut()
+                .find(|(action, _)| *action == binding.action)
+            {
+                Some((_, labels)) => {
+                    labels.push_str(", ");
+                    labels.push_str(&keys);
+                }
+                None => entries.push((binding.action, keys)),
+            }
+        }
+        entries
+    }
+
+    /// The entries matching the query, best first.
+    pub fn matches(&self) -> Vec<(Action, String)> {
+        let entries = Palette::entries();
+        fuzzy::rank(
+            &self.query,
+            entries.iter().map(|(action, _)| action.description()),
+        )
+        .into_iter()
+        .map(|i| entries[i].clone())
+        .collect()
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn select(&mut self, step: isize) {
+        let last = self.matches().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(step).min(last);
+    }
+
+    pub fn selected_action(&self) -> Option<Action> {
+        self.matches().get(self.selected).map(|(action, _)| *action)
+    }
+}
@@ -0,0 +1,519 @@
+use crate::auth::{Auth, AuthKind};
+use crate::body::BodyMode;
+use crate::collections::{Collection, SavedRequest};
+use crate::environment::Environment;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::settings::Settings;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct PostmanCollection {
+    info: Info,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    variable: Vec<Pair>,
+    #[serde(default)]
+    auth: Option<PostmanAuth>,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    name: String,
+}
+
+/// A folder when it has `item`, otherwise a request.
+#[derive(Deserialize)]
+struct Item {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    item: Option<Vec<Item>>,
+    #[serde(default)]
+    request: Option<RequestOrUrl>,
+    /// A folder's, for the requests in it without their own.
+    #[serde(default)]
+    auth: Option<PostmanAuth>,
+}
+
+/// Postman allows a request to be written as just its URL.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RequestOrUrl {
+    Url(String),
+    Request(Box<PostmanRequest>),
+}
+
+#[derive(Deserialize)]
+struct PostmanRequest {
+    #[serde(default = "default_method")]
+    method: String,
+    #[serde(default)]
+    url: Option<Url>,
+    #[serde(default)]
+    header: Vec<Pair>,
+    #[serde(default)]
+    body: Option<Body>,
+    #[serde(default)]
+    auth: Option<PostmanAuth>,
+}
+
+fn default_method() -> String {
+    String::from("GET")
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Url {
+    Raw(String),
+    Parts {
+        #[serde(default)]
+        raw: String,
+    },
+}
+
+/// A header, query parameter, form field or variable.
+#[derive(Deserialize)]
+struct Pair {
+    key: String,
+    #[serde(default)]
+    value: serde_json::Value,
+    #[serde(default)]
+    disabled: bool,
+    /// `file` for form fields that upload `src`.
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    src: serde_json::Value,
+    #[serde(default, rename = "contentType")]
+    content_type: String,
+}
+
+impl Pair {
+    fn value(&self) -> String {
+        match &self.value {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Null => String::new(),
+            other => other.to_string(),
+        }
+    }
+
+    /// The value as a multipart field, `@path` for a file upload.
+    fn field_value(&self) -> String {
+        let value = match (self.kind.as_str(), &self.src) {
+            ("file", serde_json::Value::String(src)) => format!("@{}", src),
+            _ => return self.value(),
+        };
+        if self.content_type.is_empty() {
+            value
+        } else {
+            format!("{};type={}", value, self.content_type)
+        }
+    }
+}
+
+/// Credentials as a list of pairs under the type's own name, e.g.
+/// `"bearer": [{"key": "token", "value": "..."}]`.
+#[derive(Deserialize)]
+struct PostmanAuth {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    basic: Vec<Pair>,
+    #[serde(default)]
+    digest: Vec<Pair>,
+    #[serde(default)]
+    bearer: Vec<Pair>,
+    #[serde(default)]
+    apikey: Vec<Pair>,
+    #[serde(default)]
+    awsv4: Vec<Pair>,
+}
+
+impl PostmanAuth {
+    /// The Auth pane's equivalent, or none for `noauth` and the types it
+    /// doesn't have, such as Hawk or NTLM.
+    fn convert(&self) -> Auth {
+        let (kind, pairs, names): (_, _, &[(&str, &str)]) = match self.kind.as_str() {
+            "basic" => (
+                AuthKind::Basic,
+                &self.basic,
+                &[("username", "username"), ("password", "password")],
+            ),
+            "digest" => (
+                AuthKind::Digest,
+                &self.digest,
+                &[("username", "username"), ("password", "password")],
+            ),
+            "bearer" => (AuthKind::Bearer, &self.bearer, &[("token", "token")]),
+            "apikey" => (
+                AuthKind::ApiKey,
+                &self.apikey,
+                &[
+                    ("key", "key_name"),
+                    ("value", "key_value"),
+                    ("in", "key_in"),
+                ],
+            ),
+            "awsv4" => (
+                AuthKind::AwsSigV4,
+                &self.awsv4,
+                &[
+                    ("accessKey", "access_key"),
+                    ("secretKey", "secret_key"),
+                    ("sessionToken", "session_token"),
+                    ("region", "region"),
+                    ("service", "service"),
+                ],
+            ),
+            _ => return Auth::default(),
+        };
+        let mut auth = Auth {
+            kind,
+            ..Default::default()
+        };
+        for pair in pairs {
+            if let Some((_, name)) = names.iter().find(|(key, _)| *key == pair.key) {
+                auth.set(name, &pair.value());
+            }
+        }
+        auth
+    }
+}
+
+#[derive(Deserialize)]
+struct Body {
+    #[serde(default)]
+    mode: String,
+    #[serde(default)]
+    raw: String,
+    #[serde(default)]
+    urlencoded: Vec<Pair>,
+    #[serde(default)]
+    formdata: Vec<Pair>,
+    #[serde(default)]
+    graphql: Option<serde_json::Value>,
+}
+
+/// Whether `json` looks like a Postman collection.
+pub fn detect(json: &serde_json::Value) -> bool {
+    json.pointer("/info/schema")
+        .and_then(|schema| schema.as_str())
+        .is_some_and(|schema| schema.contains("getpostman.com"))
+}
+
+/// Converts a Postman v2.1 collection into a collection, flattening folders
+/// into request names like `Folder / Request`, and its variables into an
+/// environment named after the collection. Requests without auth of their
+/// own take their folder's or the collection's.
+pub fn import(json: serde_json::Value) -> Result<(Collection, Option<Environment>), AppError> {
+    let postman: PostmanCollection = serde_json::from_value(json)
+        .map_err(|e| AppError::Parse(format!("Postman collection: {}", e)))?;
+    let mut requests = Vec::new();
+    flatten(&postman.item, "", postman.auth.as_ref(), &mut requests);
+    let environment = if postman.variable.is_empty() {
+        None
+    } else {
+        Some(Environment {
+            name: postman.info.name.clone(),
+            variables: postman
+                .variable
+                .iter()
+                .map(|pair| (pair.key.clone(), pair.value()))
+                .collect(),
+            settings: Settings::default(),
+        })
+    };
+    let collection = Collection {
+        name: postman.info.name,
+        requests,
+    };
+    Ok((collection, environment))
+}
+
+fn flatten(
+    items: &[Item],
+    prefix: &str,
+    inherited: Option<&PostmanAuth>,
+    requests: &mut Vec<SavedRequest>,
+) {
+    for item in items {
+        let auth = item.auth.as_ref().or(inherited);
+        let name = if prefix.is_empty() {
+            item.name.clone()
+        } else {
+            format!("{} / {}", prefix, item.name)
+        };
+        if let Some(children) = &item.item {
+            flatten(children, &name, auth, requests);
+        } else if let Some(request) = &item.request {
+            requests.push(convert(name, request, auth));
+        }
+    }
+}
+
+/// Editor rows for `pairs`, with disabled ones kept but switched off.
+fn rows(pairs: &[Pair], value: fn(&Pair) -> String) -> Vec<KeyValue> {
+    pairs
+        .iter()
+        .map(|pair| KeyValue {
+            key: pair.key.clone(),
+            value: value(pair),
+            enabled: !pair.disabled,
+        })
+        .collect()
+}
+
+fn convert(name: String, request: &RequestOrUrl, inherited: Option<&PostmanAuth>) -> SavedRequest {
+    let request = match request {
+        RequestOrUrl::Url(url) => {
+            return SavedRequest {
+                name,
+                method: default_method(),
+                url: url.clone(),
+                auth: inherited.map(PostmanAuth::convert).unwrap_or_default(),
+                ..Default::default()
+            }
+        }
+        RequestOrUrl::Request(request) => request,
+    };
+    let auth = request.auth.as_ref().or(inherited);
+    let url = match &request.url {
+        Some(Url::Raw(raw)) | Some(Url::Parts { raw }) => raw.clone(),
+        None => String::new(),
+    };
+    let headers = rows(&request.header, Pair::value);
+    let mut body_mode = BodyMode::Raw;
+    let mut fields = Vec::new();
+    let mut variables = String::new();
+    let body = match &request.body {
+        Some(body) => match body.mode.as_str() {
+            "raw" => body.raw.clone(),
+            "urlencoded" => {
+                body_mode = BodyMode::Form;
+                fields = rows(&body.urlencoded, Pair::value);
+                String::new()
+            }
+            "formdata" => {
+                body_mode = BodyMode::Multipart;
+                fields = rows(&body.formdata, Pair::field_value);
+                String::new()
+            }
+            "graphql" => {
+                body_mode = BodyMode::GraphQl;
+                let graphql = body.graphql.clone().unwrap_or_default();
+                let text = |key| {
+                    graphql
+                        .get(key)
+                        .and_then(|v| v.as_str())
+                        .map(String::from)
+                        .unwrap_or_default()
+                };
+                variables = text("variables");
+                text("query")
+            }
+            _ => String::new(),
+        },
+        None => String::new(),
+    };
+    SavedRequest {
+        name,
+        method: request.method.to_ascii_uppercase(),
+        url,
+        headers,
+        body,
+        body_mode,
+        fields,
+        variables,
+        auth: auth.map(PostmanAuth::convert).unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn collection() -> serde_json::Value {
+        json!({
+            "info": {
+                "name": "Shop",
+                "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+            },
+            "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}"}]},
+            "variable": [{"key": "base", "value": "https://shop.test"}, {"key": "page", "value": 2}],
+            "item": [
+                {
+                    "name": "Users",
+                    "auth": {
+                        "type": "apikey",
+                        "apikey": [
+                            {"key": "key", "value": "X-Key"},
+                            {"key": "value", "value": "secret"},
+                            {"key": "in", "value": "query"}
+                        ]
+                    },
+                    "item": [
+                        {
+                            "name": "List",
+                            "request": {
+                                "method": "get",
+                                "url": {"raw": "{{base}}/users?page={{page}}", "host": ["{{base}}"]},
+                                "header": [
+                                    {"key": "Accept", "value": "application/json"},
+                                    {"key": "X-Debug", "value": "1", "disabled": true}
+                                ]
+                            }
+                        },
+                        {
+                            "name": "Create",
+                            "request": {
+                                "method": "POST",
+                                "url": "{{base}}/users",
+                                "auth": {
+                                    "type": "basic",
+                                    "basic": [
+                                        {"key": "username", "value": "ann"},
+                                        {"key": "password", "value": "pw"}
+                                    ]
+                                },
+                                "body": {
+                                    "mode": "urlencoded",
+                                    "urlencoded": [{"key": "name", "value": "Ann"}]
+                                }
+                            }
+                        }
+                    ]
+                },
+                {
+                    "name": "Upload",
+                    "request": {
+                        "method": "PUT",
+                        "url": "{{base}}/files",
+                        "auth": {"type": "noauth"},
+                        "body": {
+                            "mode": "formdata",
+                            "formdata": [
+                                {"key": "note", "value": "hi"},
+                                {"key": "file", "type": "file", "src": "/tmp/a.png", "contentType": "image/png"}
+                            ]
+                        }
+                    }
+                },
+                {"name": "Ping", "request": "{{base}}/ping"}
+            ]
+        })
+    }
+
+    fn row(key: &str, value: &str, enabled: bool) -> KeyValue {
+        KeyValue {
+            key: String::from(key),
+            value: String::from(value),
+            enabled,
+        }
+    }
+
+    #[test]
+    fn detects_postman_collections() {
+        assert!(detect(&collection()));
+        assert!(!detect(&json!({"openapi": "3.0.0"})));
+    }
+
+    #[test]
+    fn imports_requests_with_folders_flattened_into_names() {
+        let (collection, _) = import(collection()).unwrap();
+        assert_eq!(collection.name, "Shop");
+        let names: Vec<&str> = collection
+            .requests
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(names, ["Users / List", "Users / Create", "Upload", "Ping"]);
+    }
+
+    #[test]
+    fn imports_method_url_headers_and_body() {
+        let (collection, _) = import(collection()).unwrap();
+        let list = &collection.requests[0];
+        assert_eq!(list.method, "GET");
+        assert_eq!(list.url, "{{base}}/users?page={{page}}");
+        assert_eq!(
+            list.headers,
+            [
+                row("Accept", "application/json", true),
+                row("X-Debug", "1", false)
+            ]
+        );
+        assert_eq!(list.body_mode, BodyMode::Raw);
+        assert!(list.body.is_empty());
+
+        let create = &collection.requests[1];
+        assert_eq!(create.method, "POST");
+        assert_eq!(create.body_mode, BodyMode::Form);
+        assert_eq!(create.fields, [row("name", "Ann", true)]);
+
+        let upload = &collection.requests[2];
+        assert_eq!(upload.body_mode, BodyMode::Multipart);
+        assert_eq!(
+            upload.fields,
+            [
+                row("note", "hi", true),
+                row("file", "@/tmp/a.png;type=image/png", true)
+            ]
+        );
+
+        let ping = &collection.requests[3];
+        assert_eq!(ping.method, "GET");
+        assert_eq!(ping.url, "{{base}}/ping");
+    }
+
+    #[test]
+    fn imports_auth_inheriting_from_folders_and_the_collection() {
+        let (collection, _) = import(collection()).unwrap();
+        let list = &collection.requests[0].auth;
+        assert_eq!(list.kind, AuthKind::ApiKey);
+        assert_eq!(list.value("key_name"), "X-Key");
+        assert_eq!(list.value("key_value"), "secret");
+        assert_eq!(list.value("key_in"), "query");
+
+        let create = &collection.requests[1].auth;
+        assert_eq!(create.kind, AuthKind::Basic);
+        assert_eq!(create.value("username"), "ann");
+        assert_eq!(create.value("password"), "pw");
+
+        assert_eq!(collection.requests[2].auth.kind, AuthKind::None);
+
+        let ping = &collection.requests[3].auth;
+        assert_eq!(ping.kind, AuthKind::Bearer);
+        assert_eq!(ping.value("token"), "{{token}}");
+    }
+
+    #[test]
+    fn leaves_what_postman_has_no_equivalent_for_unset() {
+        let (collection, _) = import(collection()).unwrap();
+        for request in &collection.requests {
+            assert!(request.variables.is_empty());
+            assert!(request.settings.values.is_empty());
+            assert!(request.assertions.is_empty());
+            assert!(request.pre_request.is_empty());
+            assert!(request.post_response.is_empty());
+            assert!(request.requires.is_empty());
+        }
+    }
+
+    #[test]
+    fn imports_variables_as_an_environment() {
+        let (_, environment) = import(collection()).unwrap();
+        let environment = environment.unwrap();
+        assert_eq!(environment.name, "Shop");
+        assert_eq!(environment.variables["base"], "https://shop.test");
+        assert_eq!(environment.variables["page"], "2");
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_collection() {
+        assert!(import(json!({"item": []})).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+/// What to do with the text entered into a prompt once it is submitted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PromptAction {
+    /// Write the response body to the entered path.
+    SaveResponse,
+    /// Send the request, streaming the response body to the entered path.
+    Download,
+    /// Save the current request to a collection under the entered name.
+    SaveRequest,
+    /// Fill the editors from a curl command, or from a file given as `@path`.
+    ImportCurl,
+    /// Import collections from the file at the entered path.
+    ImportFile,
+    /// Write this session's requests and responses to the entered path as HAR.
+    ExportHar,
+    /// Write the Logs pane to the entered path.
+    ExportLogs,
+    /// Add the file at the entered path to the multipart body.
+    AttachFile,
+    /// Set the selected field of the Auth pane.
+    AuthField,
+    /// Change the value of the cookie selected in the cookie jar popup.
+    CookieValue,
+    /// Set the selected setting of the settings popup for this request.
+    SettingField,
+    /// Add the entered check to the request's assertions.
+    AddAssertion,
+    /// Replace the assertion selected in the assertions popup.
+    EditAssertion,
+    /// Replace the bearer token, in the environment if it refers to a variable.
+    BearerToken,
+    /// Run the entered `:` command, shown as a command line rather than a popup.
+    Command,
+    /// Send the request if the answer is `y`, for methods that ask first.
+    ConfirmSend,
+    /// Show only the part of the JSON or XML response the entered path
+    /// picks out.
+    Filter,
+    /// Write the JSON table, or the array selected in the JSON tree, to the
+    /// entered path as CSV.
+    ExportCsv,
+}
+
+/// A one-line text prompt shown in a popup over the panes.
+#[derive(Debug)]
+pub struct Prompt {
+    pub label: String,
+    pub input: String,
+    pub action: PromptAction,
+    /// Shows the input as `*`s, for passwords and tokens.
+    pub masked: bool,
+}
+
+impl Prompt {
+    pub fn new(action: PromptAction, label: &str, initial: &str) -> Prompt {
+        Prompt {
+            label: String::from(label),
+            input: String::from(initial),
+            action,
+            masked: false,
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::app::Message;
+use crate::collections::SavedRequest;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::logs::Level;
+use hyper::{
+    header::{self, HeaderMap, HeaderName},
+    server::conn::Http,
+    service::service_fn,
+    Body, Method, StatusCode,
+};
+use reqwest::{redirect, Client};
+use std::convert::Infallible;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Port the proxy listens on when none is given.
+pub const DEFAULT_PORT: u16 = 8888;
+
+/// Collection captured requests are saved into.
+pub const COLLECTION: &str = "Captured";
+
+/// Headers about one connection rather than the request, which a proxy
+/// doesn't pass on.
+const HOP_BY_HOP: [&str; 8] = [
+    "connection",
+    "proxy-connection",
+    "keep-alive",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+fn passed_on(name: &HeaderName) -> bool {
+    !HOP_BY_HOP.contains(&name.as_str())
+}
+
+/// The headers to save with a captured request: those the client sent,
+/// less those the request editor would fill in itself.
+fn saved_headers(headers: &HeaderMap) -> Vec<KeyValue> {
+    headers
+        .iter()
+        .filter(|(name, _)| passed_on(name))
+        .filter(|(name, _)| ![header::HOST, header::CONTENT_LENGTH].contains(name))
+        .map(|(name, value)| {
+            KeyValue::new(name.as_str(), &String::from_utf8_lossy(value.as_bytes()))
+        })
+        .collect()
+}
+
+/// Forwards `request` to where its absolute URL says, sending what was
+/// captured on `tx`.
+async fn forward(
+    client: &Client,
+    request: hyper::Request<Body>,
+    tx: &UnboundedSender<Message>,
+) -> hyper::Response<Body> {
+    let reply = |status: StatusCode, text: String| {
+        hyper::Response::builder()
+            .status(status)
+            .body(Body::from(text + "\n"))
+            .unwrap_or_default()
+    };
+    let url = request.uri().to_string();
+    if request.uri().scheme().is_none() {
+        return reply(
+            StatusCode::BAD_REQUEST,
+            format!("heade-rs is a proxy here, not a server for {}", url),
+        );
+    }
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => return reply(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    let mut outgoing = client.request(parts.method.clone(), &url);
+    for (name, value) in parts.headers.iter().filter(|(name, _)| passed_on(name)) {
+        if *name != header::HOST {
+            outgoing = outgoing.header(name, value);
+        }
+    }
+    let result = outgoing.body(body.to_vec()).send().await;
+    let log = |level, line: String| {
+        let _ = tx.send(Message::Log(
+            level,
+            format!("Proxy: {} {} {}", parts.method, url, line),
+        ));
+    };
+    let (response, status) = match result {
+        Ok(response) => {
+            let status = response.status();
+            let mut builder = hyper::Response::builder().status(status);
+            for (name, value) in response
+                .headers()
+                .iter()
+                .filter(|(name, _)| passed_on(name))
+            {
+                if *name != header::CONTENT_LENGTH {
+                    builder = builder.header(name, value);
+                }
+            }
+            match response.bytes().await {
+                Ok(bytes) => {
+                    log(Level::Info, format!("-> {}", status.as_u16()));
+                    let response = builder.body(Body::from(bytes)).unwrap_or_default();
+                    (response, Some(status.as_u16()))
+                }
+                Err(e) => {
+                    log(Level::Warning, format!("-> {}", e));
+                    (reply_error(&e.to_string()), None)
+                }
+            }
+        }
+        Err(e) => {
+            log(Level::Warning, format!("-> {}", e));
+            (reply_error(&e.to_string()), None)
+        }
+    };
+    let saved = SavedRequest {
+        name: format!("{} {}", parts.method, parts.uri.path()),
+        method: parts.method.to_string(),
+        url,
+        headers: saved_headers(&parts.headers),
+        body: String::from_utf8_lossy(&body).into_owned(),
+        ..Default::default()
+    };
+    let _ = tx.send(Message::Captured(Box::new(saved), status));
+    response
+}
+
+fn reply_error(error: &str) -> hyper::Response<Body> {
+    hyper::Response::builder()
+        .status(StatusCode::BAD_GATEWAY)
+        .body(Body::from(format!("{}\n", error)))
+        .unwrap_or_default()
+}
+
+/// Joins a `CONNECT` client to the host it names. What goes through is
+/// encrypted, so it can only be passed on, not captured.
+async fn tunnel(
+    request: hyper::Request<Body>,
+    tx: UnboundedSender<Message>,
+) -> hyper::Response<Body> {
+    let Some(authority) = request.uri().authority().map(|a| a.to_string()) else {
+        return hyper::Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::empty())
+            .unwrap_or_default();
+    };
+    let _ = tx.send(Message::Log(
+        Level::Info,
+        format!("Proxy: CONNECT {} passed through, not captured", authority),
+    ));
+    tokio::spawn(async move {
+        let upgraded = hyper::upgrade::on(request).await;
+        let server = TcpStream::connect(&authority).await;
+        match (upgraded, server) {
+            (Ok(mut client), Ok(mut server)) => {
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+            }
+            (_, Err(e)) => {
+                let _ = tx.send(Message::Log(
+                    Level::Warning,
+                    format!("Proxy: CONNECT {} failed: {}", authority, e),
+                ));
+            }
+            _ => {}
+        }
+    });
+    hyper::Response::new(Body::empty())
+}
+
+/// Runs a forward proxy on `port` of localhost until the task is dropped.
+/// Each plain HTTP exchange through it is sent on `tx` to be recorded;
+/// HTTPS is tunnelled unseen. Fails if the port can't be listened on.
+pub async fn serve(port: u16, tx: UnboundedSender<Message>) -> Result<(), AppError> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    // Redirects go back to the client, which decides whether to follow them
+    let client = Client::builder()
+        .no_proxy()
+        .redirect(redirect::Policy::none())
+        .build()?;
+    let _ = tx.send(Message::Log(
+        Level::Info,
+        format!(
+            "Proxying on http://127.0.0.1:{}, capturing into {}",
+            port, COLLECTION
+        ),
+    ));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (client, tx) = (client.clone(), tx.clone());
+        tokio::spawn(async move {
+            let service = service_fn(move |request: hyper::Request<Body>| {
+                let (client, tx) = (client.clone(), tx.clone());
+                async move {
+                    Ok::<_, Infallible>(match *request.method() {
+                        Method::CONNECT => tunnel(request, tx).await,
+                        _ => forward(&client, request, &tx).await,
+                    })
+                }
+            });
+            let _ = Http::new()
+                .http1_only(true)
+                .serve_connection(stream, service)
+                .with_upgrades()
+                .await;
+        });
+    }
+}
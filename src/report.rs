@@ -0,0 +1,127 @@
+use crate::error::AppError;
+use crate::storage;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How one request of a command-line run went, for its summary and reports.
+#[derive(Debug)]
+pub struct Case {
+    pub name: String,
+    pub elapsed: Duration,
+    /// Assertions that didn't hold, or captures that found nothing.
+    pub failures: Vec<String>,
+    /// Why no response came, if none did.
+    pub error: Option<String>,
+}
+
+impl Case {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty() && self.error.is_none()
+    }
+}
+
+/// Where `--report` writes how a run went.
+#[derive(Debug)]
+pub enum Report {
+    /// `junit:path.xml`, the JUnit XML that CI servers show test results from.
+    Junit(PathBuf),
+}
+
+impl Report {
+    /// Reads a `--report` value, `FORMAT:PATH`.
+    pub fn parse(value: &str) -> Result<Report, String> {
+        match value.split_once(':') {
+            Some(("junit", path)) if !path.is_empty() => {
+                Ok(Report::Junit(storage::expand_tilde(path)))
+            }
+            Some(("junit", _)) => Err(String::from("junit report needs a path, as junit:PATH")),
+            Some((format, _)) => Err(format!("unknown report format {}", format)),
+            None => Err(format!(
+                "report {} should be FORMAT:PATH, e.g. junit:report.xml",
+                value
+            )),
+        }
+    }
+
+    /// Writes the report of `cases`, run as `suite`.
+    pub fn write(&self, suite: &str, cases: &[Case]) -> Result<(), AppError> {
+        match self {
+            Report::Junit(path) => Ok(fs::write(path, junit(suite, cases))?),
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters aren't allowed in XML 1.0 at all
+            c if c.is_control() && !matches!(c, '\n' | '\t' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `cases` as a JUnit test suite. Requests that got no response are errors,
+/// and those whose assertions didn't hold are failures.
+fn junit(suite: &str, cases: &[Case]) -> String {
+    let failures = cases
+        .iter()
+        .filter(|case| case.error.is_none() && !case.failures.is_empty())
+        .count();
+    let errors = cases.iter().filter(|case| case.error.is_some()).count();
+    let time: f64 = cases.iter().map(|case| case.elapsed.as_secs_f64()).sum();
+    let suite = escape(suite);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml += &format!(
+        "<testsuites name=\"heade-rs\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        cases.len(),
+        failures,
+        errors,
+        time
+    );
+    xml += &format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        suite,
+        cases.len(),
+        failures,
+        errors,
+        time
+    );
+    for case in cases {
+        xml += &format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape(&case.name),
+            suite,
+            case.elapsed.as_secs_f64()
+        );
+        if case.passed() {
+            xml += "/>\n";
+            continue;
+        }
+        xml += ">\n";
+        if let Some(error) = &case.error {
+            xml += &format!(
+                "      <error message=\"{}\">{}</error>\n",
+                escape(error),
+                escape(error)
+            );
+        } else {
+            xml += &format!(
+                "      <failure message=\"{}\">{}</failure>\n",
+                escape(&case.failures[0]),
+                escape(&case.failures.join("\n"))
+            );
+        }
+        xml += "    </testcase>\n";
+    }
+    xml += "  </testsuite>\n</testsuites>\n";
+    xml
+}
@@ -0,0 +1,340 @@
+use crate::auth::{Auth, AuthKind};
+use crate::body::{self, BodyMode};
+use crate::cookies::CookieJar;
+use crate::download::Download;
+use crate::encoding;
+use crate::environment::Environments;
+use crate::error::AppError;
+use crate::kv::KeyValue;
+use crate::response::ResponseData;
+use crate::settings::Settings;
+use crate::sigv4;
+use crate::sse::{self, EventStream};
+use crate::unix;
+use reqwest::{header, Client, Method, RequestBuilder};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::form_urlencoded;
+
+/// Everything needed to send one request, detached from the editor state.
+#[derive(Clone, Debug, Default)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub params: Vec<KeyValue>,
+    pub headers: Vec<KeyValue>,
+    pub body: String,
+    pub body_mode: BodyMode,
+    /// The Body pane's fields table, sent instead of `body` in the modes that use it.
+    pub fields: Vec<KeyValue>,
+    /// JSON sent with the query in GraphQL mode.
+    pub variables: String,
+    pub auth: Auth,
+    pub settings: Settings,
+    /// Checks on the response, written as for `assertion::Assertion`.
+    pub assertions: Vec<String>,
+    /// Run with `script::run` before sending, to change the request.
+    pub pre_request: String,
+    /// Run on the response, e.g. to capture a token into a variable.
+    pub post_response: String,
+    /// Saved requests to send first, as for `Collections::prerequisites`.
+    pub requires: Vec<String>,
+    /// Where cookies are sent from and stored when the `cookies` setting is on.
+    pub cookie_jar: Option<Arc<CookieJar>>,
+    /// Streams the body to a file instead of keeping it, when set.
+    pub download: Option<Arc<Download>>,
+    /// Where events go as they arrive, if the response turns out to be an event stream.
+    pub events: Option<Arc<EventStream>>,
+}
+
+impl Request {
+    /// The URL with `https://` prepended if no scheme was typed, and its
+    /// query string rebuilt from `params` with every pair URL-encoded.
+    pub fn full_url(&self) -> String {
+        let (base, _) = split_url(&self.url);
+        let url = join_url(base, self.params.iter(), true);
+        if !url.starts_with("http") {
+            format!("https://{}", url)
+        } else {
+            url
+        }
+    }
+
+    /// A copy with `{{variables}}` in the URL, params, headers, body, auth
+    /// and settings replaced from the active environment.
+    pub fn interpolate(&self, environments: &Environments) -> Request {
+        let pairs = |rows: &[KeyValue]| -> Vec<KeyValue> {
+            rows.iter()
+                .map(|kv| KeyValue {
+                    key: environments.interpolate(&kv.key),
+                    value: environments.interpolate(&kv.value),
+                    enabled: kv.enabled,
+                })
+                .collect()
+        };
+        Request {
+            method: self.method.clone(),
+            url: environments.interpolate(&self.url),
+            params: pairs(&self.params),
+            headers: pairs(&self.headers),
+            body: environments.interpolate(&self.body),
+            body_mode: self.body_mode,
+            fields: pairs(&self.fields),
+            variables: environments.interpolate(&self.variables),
+            auth: self.auth.interpolate(environments),
+            settings: self.settings.interpolate(environments),
+            assertions: self
+                .assertions
+                .iter()
+                .map(|text| environments.interpolate(text))
+                .collect(),
+            // Scripts read variables with env() rather than having them filled in
+            pre_request: self.pre_request.clone(),
+            post_response: self.post_response.clone(),
+            requires: self.requires.clone(),
+            cookie_jar: self.cookie_jar.clone(),
+            download: self.download.clone(),
+            events: self.events.clone(),
+        }
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|kv| kv.key.eq_ignore_ascii_case(name))
+    }
+
+    /// Turns this request into a `reqwest` builder on `client`. Fails if a
+    /// file to upload can't be read.
+    pub fn build(&self, client: &Client) -> Result<RequestBuilder, AppError> {
+        let url = self.full_url();
+        // Sent as signed, or a space sent as `+` wouldn't match the signature
+        let url = match url::Url::parse(&url) {
+            Ok(parsed) if self.auth.kind == AuthKind::AwsSigV4 => {
+                sigv4::canonical_url(&parsed).to_string()
+            }
+            _ => url,
+        };
+        let mut builder = client.request(self.method.clone(), url.as_str());
+        for kv in &self.headers {
+            builder = builder.header(kv.key.as_str(), kv.value.as_str());
+        }
+        // A header typed by hand wins over the Auth pane
+        if self
+            .auth
+            .header_name()
+            .is_none_or(|name| !self.has_header(name))
+        {
+            builder = self.auth.apply(builder);
+            // The signature covers the final URL and body, so it's added here
+            if self.auth.kind == AuthKind::AwsSigV4 {
+                if let Ok(url) = url::Url::parse(&url) {
+                    for (name, value) in sigv4::sign(self, &url, chrono::Utc::now()) {
+                        builder = builder.header(name, value);
+                    }
+                }
+            }
+        }
+        // Decompressed by `ResponseData::new`, so the wire size can be shown
+        // too. Downloads are written as they arrive, so ask for them as is.
+        if !self.has_header(header::ACCEPT_ENCODING.as_str()) {
+            let accepted = match self.download {
+                Some(_) => "identity",
+                None => encoding::ACCEPTED,
+            };
+            builder = builder.header(header::ACCEPT_ENCODING, accepted);
+        }
+        if method_has_body(&self.method) {
+            match self.body_mode {
+                BodyMode::Raw if !self.body.is_empty() => {
+                    if !self.has_header(header::CONTENT_TYPE.as_str()) {
+                        builder =
+                            builder.header(header::CONTENT_TYPE, body_content_type(&self.body));
+                    }
+                    builder = builder.body(self.body.clone());
+                }
+                BodyMode::Raw => {}
+                BodyMode::Form => {
+                    if !self.has_header(header::CONTENT_TYPE.as_str()) {
+                        builder = builder
+                            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+                    }
+                    builder = builder.body(body::form_urlencoded(&self.fields));
+                }
+                BodyMode::Multipart => {
+                    builder = builder.multipart(body::multipart_form(&self.fields)?);
+                }
+                BodyMode::GraphQl => {
+                    if !self.has_header(header::CONTENT_TYPE.as_str()) {
+                        builder = builder.header(header::CONTENT_TYPE, "application/json");
+                    }
+                    builder = builder.body(body::graphql(&self.body, &self.variables)?);
+                }
+            }
+        }
+        Ok(builder)
+    }
+}
+
+/// Splits a URL into the part before `?` and its query string, dropping any fragment.
+pub fn split_url(url: &str) -> (&str, Option<&str>) {
+    let url = url.split('#').next().unwrap_or("");
+    match url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (url, None),
+    }
+}
+
+/// Parses a query string into decoded key/value rows.
+pub fn parse_query(query: &str) -> Vec<KeyValue> {
+    form_urlencoded::parse(query.as_bytes())
+        .map(|(key, value)| KeyValue::new(&key, &value))
+        .collect()
+}
+
+/// Appends `params` to `base` as a query string. Pairs are percent-encoded
+/// when `encode` is set, and left as typed for display in the URL field.
+pub fn join_url<'a>(
+    base: &str,
+    params: impl Iterator<Item = &'a KeyValue>,
+    encode: bool,
+) -> String {
+    let encoded = |s: &str| -> String {
+        if encode {
+            form_urlencoded::byte_serialize(s.as_bytes()).collect()
+        } else {
+            String::from(s)
+        }
+    };
+    let query: Vec<String> = params
+        .map(|kv| {
+            if kv.value.is_empty() {
+                encoded(&kv.key)
+            } else {
+                format!("{}={}", encoded(&kv.key), encoded(&kv.value))
+            }
+        })
+        .collect();
+    if query.is_empty() {
+        String::from(base)
+    } else {
+        format!("{}?{}", base, query.join("&"))
+    }
+}
+
+/// Only these methods carry the body pane's contents.
+pub fn method_has_body(method: &Method) -> bool {
+    matches!(*method, Method::POST | Method::PUT | Method::PATCH)
+}
+
+/// Picks a Content-Type for the body: JSON if it parses as JSON, plain text otherwise.
+fn body_content_type(body: &str) -> &'static str {
+    if serde_json::from_str::<serde_json::Value>(body).is_ok() {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    }
+}
+
+/// Awaits a step of receiving the response, giving up if no data arrives
+/// within the read timeout.
+async fn within<T>(
+    read_timeout: Option<Duration>,
+    request: &Request,
+    step: impl Future<Output = Result<T, reqwest::Error>>,
+) -> Result<T, AppError> {
+    let result = match read_timeout {
+        Some(limit) => tokio::time::timeout(limit, step)
+            .await
+            .map_err(|_| AppError::Timeout(format!("no data from the server for {:?}", limit)))?,
+        None => step.await,
+    };
+    result.map_err(|e| request.settings.timeout_error(e))
+}
+
+pub async fn send(request: &Request) -> Result<ResponseData, AppError> {
+    if !request.settings.value("unix_socket").is_empty() {
+        return unix::send(request).await;
+    }
+    let redirects = Arc::new(Mutex::new(Vec::new()));
+    let client = request
+        .settings
+        .client(&redirects, request.cookie_jar.as_ref())?;
+    let read_timeout = request.settings.seconds("read_timeout")?;
+    let start = Instant::now();
+    let mut res = within(read_timeout, request, request.build(&client)?.send()).await?;
+    let status = res.status();
+    let version = res.version();
+    let headers = res.headers().clone();
+    let mut response = match &request.download {
+        Some(download) => {
+            let mut file = download.begin(res.content_length()).await?;
+            while let Some(chunk) = within(read_timeout, request, res.chunk()).await? {
+                download.write(&mut file, &chunk).await?;
+            }
+            let size = download.finish(file).await?;
+            ResponseData::downloaded(
+                status,
+                version,
+                headers,
+                start.elapsed(),
+                &download.path,
+                size,
+            )
+        }
+        None => {
+            let events = request
+                .events
+                .as_ref()
+                .filter(|_| sse::is_event_stream(&headers));
+            let mut bytes = Vec::new();
+            match events {
+                // Read until the server ends the stream or it's disconnected
+                Some(events) => {
+                    events.open();
+                    let mut parser = sse::Parser::default();
+                    let result = loop {
+                        let chunk = tokio::select! {
+                            chunk = within(read_timeout, request, res.chunk()) => chunk,
+                            _ = events.disconnected() => break Ok(()),
+                        };
+                        match chunk {
+                            Ok(Some(chunk)) => {
+                                bytes.extend_from_slice(&chunk);
+                                events.push(parser.feed(&chunk));
+                            }
+                            Ok(None) => {
+                                events.push(parser.finish().into_iter().collect());
+                                break Ok(());
+                            }
+                            Err(e) => break Err(e),
+                        }
+                    };
+                    events.close();
+                    result?;
+                }
+                None => {
+                    while let Some(chunk) = within(read_timeout, request, res.chunk()).await? {
+                        bytes.extend_from_slice(&chunk);
+                    }
+                }
+            }
+            let decompress = request.settings.value("decompress") == "on";
+            let mut response = ResponseData::new(
+                status,
+                version,
+                headers,
+                start.elapsed(),
+                &bytes,
+                decompress,
+            );
+            if let Some(events) = events {
+                response.show_events(events.transcript());
+            }
+            response
+        }
+    };
+    response.redirects = redirects.lock().map(|r| r.clone()).unwrap_or_default();
+    Ok(response)
+}
@@ -0,0 +1,367 @@
+use crate::encoding;
+use crate::format::{self, BodyKind};
+use crate::jsonpath::JsonPath;
+use crate::xpath::{self, Found, XPath};
+use reqwest::{
+    header::{self, HeaderMap},
+    StatusCode, Version,
+};
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+
+/// Body text indexed by line, so the Response pane can draw just the lines
+/// in view without walking the whole body on every frame.
+#[derive(Debug)]
+pub struct BodyText {
+    text: String,
+    /// Byte offset where each line starts.
+    starts: Vec<usize>,
+}
+
+impl BodyText {
+    pub fn new(text: String) -> BodyText {
+        let mut starts = Vec::new();
+        if !text.is_empty() {
+            starts.push(0);
+        }
+        starts.extend(
+            memchr::memchr_iter(b'\n', text.as_bytes())
+                .map(|i| i + 1)
+                .filter(|&i| i < text.len()),
+        );
+        BodyText { text, starts }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Line `i` without its line ending, matching `str::lines`.
+    pub fn line(&self, i: usize) -> &str {
+        let start = self.starts[i];
+        let end = self.starts.get(i + 1).copied().unwrap_or(self.text.len());
+        let line = &self.text[start..end];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    /// Up to `count` lines starting at `first`, with their line numbers.
+    pub fn lines(&self, first: usize, count: usize) -> impl Iterator<Item = (usize, &str)> {
+        let end = (first + count).min(self.line_count());
+        (first.min(end)..end).map(move |i| (i, self.line(i)))
+    }
+}
+
+/// A redirect followed on the way to the final response.
+#[derive(Clone, Debug)]
+pub struct Redirect {
+    pub status: StatusCode,
+    /// Where it pointed, resolved against the URL that sent it.
+    pub location: String,
+}
+
+/// The `Content-Encoding` of a response, unless it's `identity`.
+fn content_encoding(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.trim().eq_ignore_ascii_case("identity"))
+        .map(String::from)
+}
+
+/// `bytes` as text, with those that aren't part of a UTF-8 character
+/// written as `\xNN` rather than replaced, so the raw view shows them.
+fn escape_invalid(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            text.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    text
+}
+
+/// A received response, with everything the Response pane displays.
+#[derive(Debug)]
+pub struct ResponseData {
+    pub status: StatusCode,
+    pub version: Version,
+    pub headers: HeaderMap,
+    /// Time from sending the request until the whole body was read.
+    pub elapsed: Duration,
+    /// What the body was detected as, which decides how it was formatted.
+    pub kind: BodyKind,
+    /// The body's bytes as received, decompressed if they were compressed
+    /// and decompression is on.
+    pub bytes: Vec<u8>,
+    /// `bytes` as text, unformatted, with any bytes that aren't UTF-8
+    /// written as `\xNN`.
+    pub raw_body: BodyText,
+    /// The body, formatted for display.
+    pub body: BodyText,
+    /// Redirects followed to get here, in order.
+    pub redirects: Vec<Redirect>,
+    /// Body bytes as they came over the wire, before any decompression.
+    pub wire_size: usize,
+    /// Body bytes after decompression, the same as `wire_size` if there was none.
+    pub size: usize,
+    /// The `Content-Encoding` the body arrived with, if any.
+    pub encoding: Option<String>,
+    /// Set if the body was compressed but is shown as it arrived, either by
+    /// choice or because it couldn't be decompressed.
+    pub undecoded: Option<String>,
+    /// Messages from the `errors` array of a GraphQL response.
+    pub graphql_errors: Vec<String>,
+    /// The query the JSON or XML body is filtered by, and what it picked
+    /// out, shown in place of the body until cleared.
+    pub filter: Option<(String, BodyText)>,
+}
+
+impl ResponseData {
+    /// A response with `wire` as its body, decompressed if it was compressed
+    /// and `decompress` is set, and formatted by its detected kind.
+    pub fn new(
+        status: StatusCode,
+        version: Version,
+        headers: HeaderMap,
+        elapsed: Duration,
+        wire: &[u8],
+        decompress: bool,
+    ) -> ResponseData {
+        let encoding = content_encoding(&headers);
+        let (bytes, undecoded) = match &encoding {
+            Some(_) if !decompress => (None, Some(String::from("decompression off"))),
+            Some(coding) => match encoding::decode(coding, wire) {
+                Ok(bytes) => (Some(bytes), None),
+                Err(e) => (None, Some(format!("could not decompress: {}", e))),
+            },
+            None => (None, None),
+        };
+        let bytes = bytes.as_deref().unwrap_or(wire);
+        let text = String::from_utf8_lossy(bytes).into_owned();
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        let kind = BodyKind::detect(content_type, &text);
+        let body = format::format_body(kind, &text);
+        ResponseData {
+            status,
+            version,
+            headers,
+            elapsed,
+            kind,
+            raw_body: BodyText::new(escape_invalid(bytes)),
+            bytes: bytes.to_vec(),
+            body: BodyText::new(body),
+            redirects: Vec::new(),
+            wire_size: wire.len(),
+            size: bytes.len(),
+            encoding,
+            undecoded,
+            graphql_errors: Vec::new(),
+            filter: None,
+        }
+    }
+
+    /// A response whose body was streamed to `path` by a download. Only a
+    /// note saying where it went is kept for display.
+    pub fn downloaded(
+        status: StatusCode,
+        version: Version,
+        headers: HeaderMap,
+        elapsed: Duration,
+        path: &Path,
+        size: u64,
+    ) -> ResponseData {
+        let size = size as usize;
+        let note = format!(
+            "Saved {} to {}",
+            encoding::format_size(size),
+            path.display()
+        );
+        let encoding = content_encoding(&headers);
+        let undecoded = encoding.as_ref().map(|_| String::from("saved as received"));
+        ResponseData {
+            status,
+            version,
+            headers,
+            elapsed,
+            kind: BodyKind::Text,
+            bytes: note.clone().into_bytes(),
+            raw_body: BodyText::new(note.clone()),
+            body: BodyText::new(note),
+            redirects: Vec::new(),
+            wire_size: size,
+            size,
+            encoding,
+            undecoded,
+            graphql_errors: Vec::new(),
+            filter: None,
+        }
+    }
+
+    /// Shows the events parsed from an event stream in place of the stream
+    /// itself, which stays available as the raw body.
+    pub fn show_events(&mut self, transcript: String) {
+        self.kind = BodyKind::Text;
+        self.body = BodyText::new(transcript);
+    }
+
+    /// e.g. `Body: 1.2 KB (gzip), 4.8 KB decompressed`, or just the size if
+    /// it wasn't compressed.
+    pub fn size_line(&self) -> String {
+        let wire = encoding::format_size(self.wire_size);
+        match (&self.encoding, &self.undecoded) {
+            (None, _) => format!("Body: {}", wire),
+            (Some(coding), Some(reason)) => format!("Body: {} ({}, {})", wire, coding, reason),
+            (Some(coding), None) => format!(
+                "Body: {} ({}), {} decompressed",
+                wire,
+                coding,
+                encoding::format_size(self.size)
+            ),
+        }
+    }
+
+    /// e.g. `HTTP/1.1 200 OK  (123 ms)`, or `(123 ms, 2 redirects)` after redirects.
+    pub fn status_line(&self) -> String {
+        let redirects = match self.redirects.len() {
+            0 => String::new(),
+            1 => String::from(", 1 redirect"),
+            n => format!(", {} redirects", n),
+        };
+        format!(
+            "{:?} {}  ({} ms{})",
+            self.version,
+            self.status,
+            self.elapsed.as_millis(),
+            redirects
+        )
+    }
+
+    /// Shows only what `query` picks out of the body: for XML an XPath such
+    /// as `//item/name`, for anything else a JSONPath such as
+    /// `$.items[0].name` or the same in jq's style, `.items[0].name`. The
+    /// error says why it couldn't, for the logs.
+    pub fn filter(&mut self, query: &str) -> Result<(), String> {
+        let query = query.trim();
+        let text = match self.kind {
+            BodyKind::Xml => self.filter_xml(query)?,
+            _ => self.filter_json(query)?,
+        };
+        self.filter = Some((String::from(query), BodyText::new(text)));
+        Ok(())
+    }
+
+    fn filter_json(&self, query: &str) -> Result<String, String> {
+        let path = match query {
+            "." => JsonPath::parse("$"),
+            _ if query.starts_with(['.', '[']) => JsonPath::parse(&format!("${}", query)),
+            _ => JsonPath::parse(query),
+        }?;
+        let json: Value = serde_json::from_str(self.raw_body.as_str())
+            .map_err(|e| format!("The body isn't JSON: {}", e))?;
+        let found = path.select(&json);
+        let value = match (path.is_single(), found.as_slice()) {
+            (_, []) => return Err(format!("Nothing in the body matches {}", query)),
+            (true, [value]) => (*value).clone(),
+            _ => Value::Array(found.into_iter().cloned().collect()),
+        };
+        Ok(format::format_body(BodyKind::Json, &value.to_string()))
+    }
+
+    /// Each element found as XML, and each text or attribute found as its
+    /// value, a line or more apiece.
+    fn filter_xml(&self, query: &str) -> Result<String, String> {
+        let path = XPath::parse(query)?;
+        let root = xpath::parse_document(self.raw_body.as_str())?;
+        let found = path.select(&root);
+        if found.is_empty() {
+            return Err(format!("Nothing in the body matches {}", query));
+        }
+        let parts: Vec<String> = found
+            .iter()
+            .map(|found| match found {
+                Found::Element(_) => format::format_body(BodyKind::Xml, &found.to_text()),
+                _ => found.to_text(),
+            })
+            .collect();
+        Ok(parts.join("\n"))
+    }
+
+    /// What the filter picked out while there is one, else the raw body
+    /// when `raw` is set and the formatted one otherwise.
+    pub fn text(&self, raw: bool) -> &BodyText {
+        if let Some((_, filtered)) = &self.filter {
+            filtered
+        } else if raw {
+            &self.raw_body
+        } else {
+            &self.body
+        }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.kind == BodyKind::Json
+    }
+
+    pub fn is_xml(&self) -> bool {
+        self.kind == BodyKind::Xml
+    }
+
+    /// One line per redirect followed, as `301 Moved Permanently -> location`.
+    pub fn redirect_lines(&self) -> Vec<String> {
+        self.redirects
+            .iter()
+            .map(|r| format!("{} -> {}", r.status, r.location))
+            .collect()
+    }
+
+    /// Header lines as `name: value`, in the order they were received.
+    pub fn header_lines(&self) -> Vec<String> {
+        self.headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(content_type: &str, body: &[u8]) -> ResponseData {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+        ResponseData::new(
+            StatusCode::OK,
+            Version::HTTP_11,
+            headers,
+            Duration::ZERO,
+            body,
+            true,
+        )
+    }
+
+    #[test]
+    fn keeps_binary_bodies_as_received() {
+        let png = b"\x89PNG\r\n\x1a\n\0\xff";
+        let response = response("image/png", png);
+        assert_eq!(response.bytes, png);
+        assert_eq!(response.raw_body.as_str(), "\\x89PNG\r\n\u{1a}\n\0\\xff");
+        assert_eq!(response.size, png.len());
+    }
+
+    #[test]
+    fn keeps_text_bodies_as_they_are() {
+        let response = response("application/json", "{\"a\":\"é\"}".as_bytes());
+        assert_eq!(response.raw_body.as_str(), "{\"a\":\"é\"}");
+        assert_eq!(response.bytes, "{\"a\":\"é\"}".as_bytes());
+    }
+}
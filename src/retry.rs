@@ -0,0 +1,87 @@
+use crate::error::AppError;
+use crate::response::ResponseData;
+use crate::settings::Settings;
+use std::time::Duration;
+
+/// What counts as retryable when `retry_on` isn't set.
+const DEFAULT_RETRY_ON: &str = "connect, timeout, 429, 502, 503, 504";
+
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// When to send a request again, from the `retry_*` settings.
+#[derive(Debug)]
+pub struct Retry {
+    /// Attempts in all, counting the first; 1 means no retries.
+    attempts: u32,
+    /// The wait before the first retry, doubled for each one after.
+    backoff: Duration,
+    statuses: Vec<u16>,
+    connect: bool,
+    timeout: bool,
+}
+
+impl Retry {
+    pub fn new(settings: &Settings) -> Result<Retry, AppError> {
+        let attempts = match settings.value("retry_attempts").trim() {
+            "" => 1,
+            n => n
+                .parse::<u32>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| AppError::Parse(format!("retry_attempts '{}' as a count", n)))?,
+        };
+        let backoff = settings
+            .seconds("retry_backoff")?
+            .unwrap_or(DEFAULT_BACKOFF);
+        let retry_on = match settings.value("retry_on") {
+            "" => DEFAULT_RETRY_ON,
+            retry_on => retry_on,
+        };
+        let mut retry = Retry {
+            attempts,
+            backoff,
+            statuses: Vec::new(),
+            connect: false,
+            timeout: false,
+        };
+        for condition in retry_on.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            match condition {
+                "connect" => retry.connect = true,
+                "timeout" => retry.timeout = true,
+                status => retry.statuses.push(status.parse().map_err(|_| {
+                    AppError::Parse(format!(
+                        "retry_on '{}': expected status codes, connect or timeout",
+                        status
+                    ))
+                })?),
+            }
+        }
+        Ok(retry)
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    fn retryable(&self, result: &Result<ResponseData, AppError>) -> bool {
+        match result {
+            Ok(response) => self.statuses.contains(&response.status.as_u16()),
+            Err(AppError::Timeout(_)) => self.timeout,
+            Err(AppError::Http(e)) if e.is_timeout() => self.timeout,
+            Err(AppError::Http(e)) if e.is_connect() => self.connect,
+            Err(_) => false,
+        }
+    }
+
+    /// How long to wait before trying again after `attempt` (counting from 1)
+    /// ended with `result`, or `None` if it shouldn't be retried.
+    pub fn delay(&self, attempt: u32, result: &Result<ResponseData, AppError>) -> Option<Duration> {
+        if attempt >= self.attempts || !self.retryable(result) {
+            return None;
+        }
+        Some(
+            self.backoff
+                .saturating_mul(2u32.saturating_pow(attempt - 1)),
+        )
+    }
+}
@@ -0,0 +1,158 @@
+use crate::assertion;
+use crate::error::AppError;
+use crate::response::ResponseData;
+use reqwest::StatusCode;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// How one request of a run went.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// Not sent yet, or being sent.
+    Pending,
+    Done {
+        status: StatusCode,
+        elapsed: Duration,
+        /// The request's assertions that didn't hold, and its script if it
+        /// failed.
+        failures: Vec<String>,
+    },
+    /// It couldn't be sent, or no response came.
+    Failed(String),
+}
+
+/// One request of a run, as listed in the runner popup.
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// Which of the collection's requests it is.
+    pub request: usize,
+    /// Which row of the dataset it was sent with, counting from 1, if the
+    /// run has one.
+    pub row: Option<usize>,
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    /// Whether the request has assertions to meet.
+    pub asserted: bool,
+    pub outcome: Outcome,
+}
+
+impl Outcome {
+    /// How a request with these `assertions` went, given its `result`.
+    pub fn new(result: Result<ResponseData, AppError>, assertions: &[String]) -> Outcome {
+        match result {
+            Ok(response) => Outcome::Done {
+                status: response.status,
+                elapsed: response.elapsed,
+                failures: assertion::check_each(assertions, &response)
+                    .into_iter()
+                    .filter_map(Result::err)
+                    .collect(),
+            },
+            Err(e) => Outcome::Failed(e.to_string()),
+        }
+    }
+
+    /// Whether it got a response that wasn't an error status and had no
+    /// failures. A request that was `asserted` is judged by its assertions
+    /// alone, so an expected error status can pass.
+    pub fn passed(&self, asserted: bool) -> bool {
+        match self {
+            Outcome::Done { failures, .. } if !failures.is_empty() => false,
+            Outcome::Done { .. } if asserted => true,
+            Outcome::Done { status, .. } => !status.is_client_error() && !status.is_server_error(),
+            _ => false,
+        }
+    }
+
+    /// Counts a response that came as failed for `reason`, e.g. a script
+    /// that didn't run.
+    pub fn fail(&mut self, reason: String) {
+        if let Outcome::Done { failures, .. } = self {
+            failures.push(reason);
+        }
+    }
+}
+
+impl Step {
+    pub fn passed(&self) -> bool {
+        self.outcome.passed(self.asserted)
+    }
+}
+
+/// Every request of a collection being sent one after another, as with
+/// Postman's collection runner.
+#[derive(Debug)]
+pub struct Run {
+    /// Told apart from results of a run that has since been replaced.
+    pub id: u64,
+    pub collection: String,
+    pub steps: Vec<Step>,
+    /// The step highlighted in the popup.
+    pub selected: usize,
+    pub started: Instant,
+    /// How long the whole run took, once it has finished.
+    pub took: Option<Duration>,
+    pub task: Option<JoinHandle<()>>,
+}
+
+impl Run {
+    pub fn is_running(&self) -> bool {
+        self.took.is_none()
+    }
+
+    pub fn passed(&self) -> usize {
+        self.steps.iter().filter(|step| step.passed()).count()
+    }
+
+    pub fn done(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| step.outcome != Outcome::Pending)
+            .count()
+    }
+
+    /// Records how step `i` went, finishing the run after the last.
+    pub fn record(&mut self, i: usize, outcome: Outcome) {
+        if let Some(step) = self.steps.get_mut(i) {
+            step.outcome = outcome;
+        }
+        if self.done() == self.steps.len() {
+            self.finish();
+        }
+    }
+
+    /// Stops sending, leaving the steps not yet sent pending.
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        self.task = None;
+        self.took.get_or_insert_with(|| self.started.elapsed());
+    }
+
+    pub fn select(&mut self, step: isize) {
+        let last = self.steps.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(step).min(last);
+    }
+
+    /// e.g. `Users: 5 of 5 sent, 4 passed, 1 failed in 1.2 s`
+    pub fn summary(&self) -> String {
+        let done = self.done();
+        let passed = self.passed();
+        let took = self.took.unwrap_or_else(|| self.started.elapsed());
+        format!(
+            "{}: {} of {} sent, {} passed, {} failed in {:.1} s",
+            self.collection,
+            done,
+            self.steps.len(),
+            passed,
+            done - passed,
+            took.as_secs_f64()
+        )
+    }
+}
@@ -0,0 +1,379 @@
+use crate::crypto::{hex, hmac_sha256};
+use crate::jsonpath::JsonPath;
+use crate::kv::KeyValue;
+use crate::request::{self, Request};
+use crate::response::ResponseData;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use md5::Md5;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Dynamic, EvalAltResult, Scope};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use url::form_urlencoded;
+
+/// What a script asked for, carried out by whoever ran it.
+#[derive(Debug, Default)]
+pub struct Effects {
+    /// Environment variables to set, in the order they were set.
+    pub variables: Vec<(String, String)>,
+    /// Headers to send, each replacing any of the same name.
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub url: Option<String>,
+    /// What it printed, for the Logs pane.
+    pub logs: Vec<String>,
+}
+
+impl Effects {
+    /// Makes the changes the script asked for to `request`.
+    pub fn apply(&self, request: &mut Request) {
+        for (name, value) in &self.headers {
+            match request
+                .headers
+                .iter_mut()
+                .find(|kv| kv.key.eq_ignore_ascii_case(name))
+            {
+                Some(kv) => kv.value = value.clone(),
+                None => request.headers.push(KeyValue {
+                    key: name.clone(),
+                    value: value.clone(),
+                    enabled: true,
+                }),
+            }
+        }
+        if let Some(body) = &self.body {
+            request.body = body.clone();
+        }
+        if let Some(url) = &self.url {
+            // The query is sent from the params, so they're what changes
+            let (_, query) = request::split_url(url);
+            request.params = request::parse_query(query.unwrap_or(""));
+            request.url = url.clone();
+        }
+    }
+}
+
+/// `request` as scripts see it: `request.method`, `request.url` with its
+/// query, `request.headers["Name"]` and `request.body`.
+pub fn request_value(request: &Request) -> Value {
+    let headers: Map<String, Value> = request
+        .headers
+        .iter()
+        .map(|kv| (kv.key.clone(), Value::from(kv.value.as_str())))
+        .collect();
+    json!({
+        "method": request.method.as_str(),
+        "url": request.full_url(),
+        "headers": headers,
+        "body": request.body,
+    })
+}
+
+/// `response` as scripts see it: `response.status`, `response.headers["name"]`
+/// in lowercase, `response.body` as text, `response.json`, the body parsed
+/// if it's JSON and `()` if not, and `response.elapsed_ms`.
+pub fn response_value(response: &ResponseData) -> Value {
+    let headers: Map<String, Value> = response
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str().to_string(), Value::from(value))
+        })
+        .collect();
+    let body = response.raw_body.as_str();
+    json!({
+        "status": response.status.as_u16(),
+        "headers": headers,
+        "body": body,
+        "json": serde_json::from_str::<Value>(body).unwrap_or_default(),
+        "elapsed_ms": response.elapsed.as_millis() as u64,
+    })
+}
+
+/// Operations a script may take before it's stopped, so a loop that never
+/// ends can't hang the request.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Runs `source`, a Rhai script, with `globals` bound as variables, e.g.
+/// `request` or `response`, and those `engine` adds to Rhai's own:
+/// `env("name")` reads `variables`, `set_env`, `header`, `set_body` and
+/// `set_url` make changes, and `print` and `log` write to the Logs pane.
+/// Hashes and HMACs are hex unless `_base64`.
+pub fn run(
+    source: &str,
+    globals: Vec<(&str, Value)>,
+    variables: &BTreeMap<String, String>,
+) -> Result<Effects, String> {
+    let effects = Rc::new(RefCell::new(Effects::default()));
+    let engine = engine(&effects, variables);
+    let mut scope = Scope::new();
+    for (name, value) in globals {
+        scope.push_dynamic(name, to_dynamic(value).map_err(|e| e.to_string())?);
+    }
+    engine
+        .run_with_scope(&mut scope, source)
+        .map_err(|e| e.to_string())?;
+    Ok(effects.take())
+}
+
+fn engine(effects: &Rc<RefCell<Effects>>, variables: &BTreeMap<String, String>) -> rhai::Engine {
+    let mut engine = rhai::Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let logs = Rc::clone(effects);
+    engine.on_print(move |text| logs.borrow_mut().logs.push(String::from(text)));
+    let logs = Rc::clone(effects);
+    engine.register_fn("log", move |value: Dynamic| {
+        logs.borrow_mut().logs.push(text(&value));
+    });
+
+    let set = Rc::clone(effects);
+    let variables = variables.clone();
+    engine.register_fn("env", move |key: &str| -> Dynamic {
+        // The latest value, including any this script set
+        let set = set.borrow();
+        let value = set.variables.iter().rev().find(|(k, _)| k == key);
+        value
+            .map(|(_, value)| value)
+            .or_else(|| variables.get(key))
+            .map_or(Dynamic::UNIT, |value| Dynamic::from(value.clone()))
+    });
+    let set = Rc::clone(effects);
+    engine.register_fn("set_env", move |key: &str, value: Dynamic| {
+        set.borrow_mut()
+            .variables
+            .push((String::from(key), text(&value)));
+    });
+    let set = Rc::clone(effects);
+    engine.register_fn("header", move |name: &str, value: Dynamic| {
+        set.borrow_mut()
+            .headers
+            .push((String::from(name), text(&value)));
+    });
+    let set = Rc::clone(effects);
+    engine.register_fn("set_body", move |body: Dynamic| {
+        set.borrow_mut().body = Some(text(&body));
+    });
+    let set = Rc::clone(effects);
+    engine.register_fn("set_url", move |url: &str| {
+        set.borrow_mut().url = Some(String::from(url));
+    });
+
+    engine
+        .register_fn("now", || Utc::now().timestamp())
+        .register_fn("now_ms", || Utc::now().timestamp_millis())
+        .register_fn("timestamp", || {
+            Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        })
+        .register_fn("uuid", uuid)
+        .register_fn("sha256", |data: &str| hex(&Sha256::digest(data.as_bytes())))
+        .register_fn("md5", |data: &str| hex(&Md5::digest(data.as_bytes())))
+        .register_fn("hmac_sha256", |key: &str, data: &str| {
+            hex(&hmac_sha256(key.as_bytes(), data))
+        })
+        .register_fn("hmac_sha256_base64", |key: &str, data: &str| {
+            STANDARD.encode(hmac_sha256(key.as_bytes(), data))
+        })
+        .register_fn("base64", |data: &str| STANDARD.encode(data))
+        .register_fn(
+            "base64_decode",
+            |data: &str| -> Result<String, Box<EvalAltResult>> {
+                let bytes = STANDARD
+                    .decode(data.trim())
+                    .map_err(|e| format!("base64_decode: {}", e))?;
+                Ok(String::from_utf8_lossy(&bytes).into_owned())
+            },
+        )
+        .register_fn("url_encode", |data: &str| {
+            form_urlencoded::byte_serialize(data.as_bytes()).collect::<String>()
+        })
+        .register_fn(
+            "parse_json",
+            |json: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+                let value: Value =
+                    serde_json::from_str(json).map_err(|e| format!("parse_json: {}", e))?;
+                to_dynamic(value)
+            },
+        )
+        .register_fn(
+            "to_json",
+            |value: Dynamic| -> Result<String, Box<EvalAltResult>> {
+                Ok(from_dynamic::<Value>(&value)?.to_string())
+            },
+        )
+        .register_fn("json_path", json_path);
+    engine
+}
+
+/// A value as text, for headers, bodies and variables: strings as they
+/// are, `()` as nothing, and everything else as JSON.
+fn text(value: &Dynamic) -> String {
+    if value.is_unit() {
+        return String::new();
+    }
+    match value.clone().into_immutable_string() {
+        Ok(text) => text.to_string(),
+        Err(_) => from_dynamic::<Value>(value)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|_| value.to_string()),
+    }
+}
+
+/// What `path` finds in `value`, e.g. `json_path(response.body, "$.id")`:
+/// `()` if nothing, the value for a path to one, or an array of them.
+fn json_path(value: Dynamic, path: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+    let path = JsonPath::parse(path)?;
+    // JSON text is parsed first
+    let root: Value = match value.clone().into_immutable_string() {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| format!("json_path: {}", e))?,
+        Err(_) => from_dynamic(&value)?,
+    };
+    let found = path.select(&root);
+    to_dynamic(match (path.is_single(), found.as_slice()) {
+        (_, []) => Value::Null,
+        (true, [value]) => (*value).clone(),
+        _ => Value::Array(found.into_iter().cloned().collect()),
+    })
+}
+
+/// A random version 4 UUID.
+fn uuid() -> String {
+    let mut bytes = rand::random::<[u8; 16]>();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_with(source: &str, globals: Vec<(&str, Value)>) -> Result<Effects, String> {
+        let variables = BTreeMap::from([(String::from("token"), String::from("abc"))]);
+        run(source, globals, &variables)
+    }
+
+    #[test]
+    fn sets_headers_body_url_and_variables() {
+        let effects = run_with(
+            r#"
+                header("X-Token", env("token"));
+                set_body(#{ id: 7 });
+                set_url("https://example.com/?a=1");
+                set_env("count", 1 + 2);
+            "#,
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            effects.headers,
+            [(String::from("X-Token"), String::from("abc"))]
+        );
+        assert_eq!(effects.body.as_deref(), Some(r#"{"id":7}"#));
+        assert_eq!(effects.url.as_deref(), Some("https://example.com/?a=1"));
+        assert_eq!(
+            effects.variables,
+            [(String::from("count"), String::from("3"))]
+        );
+    }
+
+    #[test]
+    fn env_reads_what_the_script_set() {
+        let effects = run_with(
+            r#"set_env("token", "new"); print(env("token")); log(env("missing"));"#,
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(effects.logs, ["new", ""]);
+    }
+
+    #[test]
+    fn binds_globals() {
+        let globals = vec![(
+            "response",
+            json!({ "status": 201, "headers": { "etag": "x" }, "json": { "items": [1, 2] } }),
+        )];
+        let effects = run_with(
+            r#"
+                if response.status == 201 && response.json.items.len() == 2 {
+                    set_env("etag", response.headers["etag"]);
+                }
+                for item in response.json.items { log(item); }
+            "#,
+            globals,
+        )
+        .unwrap();
+        assert_eq!(
+            effects.variables,
+            [(String::from("etag"), String::from("x"))]
+        );
+        assert_eq!(effects.logs, ["1", "2"]);
+    }
+
+    #[test]
+    fn hashes_and_encodes() {
+        let effects = run_with(
+            r#"
+                log(sha256("abc"));
+                log(md5(""));
+                log(hmac_sha256("key", "The quick brown fox jumps over the lazy dog"));
+                log(base64_decode(base64("hi")));
+                log(url_encode("a b&c"));
+            "#,
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            effects.logs,
+            [
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                "d41d8cd98f00b204e9800998ecf8427e",
+                "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8",
+                "hi",
+                "a+b%26c",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_and_queries_json() {
+        let effects = run_with(
+            r#"
+                let body = `{"items": [{"id": 1}, {"id": 2}]}`;
+                log(json_path(body, "$.items[1].id"));
+                log(json_path(parse_json(body), "$.items[*].id"));
+                log(to_json(parse_json("[1, null]")));
+                log(json_path(body, "$.missing"));
+            "#,
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(effects.logs, ["2", "[1,2]", "[1,null]", ""]);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(run_with("let x = ;", Vec::new()).is_err());
+        assert!(run_with("undefined_function()", Vec::new()).is_err());
+        assert!(run_with(r#"base64_decode("!")"#, Vec::new()).is_err());
+        assert!(run_with(r#"json_path("{}", "$[")"#, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn stops_scripts_that_never_end() {
+        assert!(run_with("loop {}", Vec::new()).is_err());
+    }
+}
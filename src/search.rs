@@ -0,0 +1,89 @@
+/// A match of the search query within one line of text, as byte offsets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Match {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// State of a `/` search over the response body.
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: String,
+    pub matches: Vec<Match>,
+    /// Index into `matches` of the match jumped to last.
+    pub current: usize,
+}
+
+impl Search {
+    /// Recomputes the matches of `query` in `text`. An all-lowercase query
+    /// matches case-insensitively, as with vim's `smartcase`.
+    pub fn update(&mut self, text: &str) {
+        self.matches.clear();
+        self.current = 0;
+        if self.query.is_empty() {
+            return;
+        }
+        let ignore_case = !self.query.chars().any(|c| c.is_uppercase());
+        let query = if ignore_case {
+            self.query.to_ascii_lowercase()
+        } else {
+            self.query.clone()
+        };
+        for (line, content) in text.lines().enumerate() {
+            let content = if ignore_case {
+                content.to_ascii_lowercase()
+            } else {
+                String::from(content)
+            };
+            self.matches
+                .extend(content.match_indices(&query).map(|(start, m)| Match {
+                    line,
+                    start,
+                    end: start + m.len(),
+                }));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.matches.clear();
+        self.current = 0;
+    }
+
+    pub fn next(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+        }
+    }
+
+    pub fn current_match(&self) -> Option<Match> {
+        self.matches.get(self.current).copied()
+    }
+
+    /// Matches on `line`, in order.
+    pub fn matches_on(&self, line: usize) -> impl Iterator<Item = (usize, &Match)> {
+        // Matches are sorted by line, so skip straight to the first one on `line`
+        let first = self.matches.partition_point(|m| m.line < line);
+        self.matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take_while(move |(_, m)| m.line == line)
+    }
+
+    /// e.g. `match 2 of 7`, or `no matches`.
+    pub fn status(&self) -> String {
+        if self.matches.is_empty() {
+            String::from("no matches")
+        } else {
+            format!("match {} of {}", self.current + 1, self.matches.len())
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use crate::auth::Field;
+use crate::cookies::CookieJar;
+use crate::environment::Environments;
+use crate::error::AppError;
+use crate::response::Redirect;
+use crate::storage;
+use reqwest::{redirect, Certificate, Client, Identity, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Redirects followed before giving up when `max_redirects` isn't set.
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// The settings shown in the settings popup, in order.
+pub const FIELDS: [Field; 23] = [
+    Field {
+        name: "client_cert",
+        label: "Client certificate (PEM or .p12)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "client_key",
+        label: "Client key (PEM, if not in the certificate)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "cert_password",
+        label: "Certificate password (.p12)",
+        secret: true,
+        choices: &[],
+    },
+    Field {
+        name: "ca_bundle",
+        label: "Extra CA certificates (PEM)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "insecure",
+        label: "Accept invalid certificates (dev only!)",
+        secret: false,
+        choices: &["off", "on"],
+    },
+    Field {
+        name: "proxy",
+        label: "Proxy (http://, https:// or socks5://; none to bypass)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "proxy_user",
+        label: "Proxy username",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "proxy_password",
+        label: "Proxy password",
+        secret: true,
+        choices: &[],
+    },
+    Field {
+        name: "no_proxy",
+        label: "Hosts to reach without the proxy",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "follow_redirects",
+        label: "Follow redirects",
+        secret: false,
+        choices: &["on", "off"],
+    },
+    Field {
+        name: "max_redirects",
+        label: "Most redirects to follow (default 10)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "connect_timeout",
+        label: "Connect timeout (seconds)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "read_timeout",
+        label: "Read timeout, the longest wait for data (seconds)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "timeout",
+        label: "Total timeout (seconds)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "retry_attempts",
+        label: "Attempts, counting the first (default 1: no retries)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "retry_backoff",
+        label: "Wait before retrying, doubled each time (seconds, default 1)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "retry_on",
+        label: "Retry on (default connect, timeout, 429, 502, 503, 504)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "rate_limit",
+        label: "Most requests a second in runs and benchmarks (0.5: one every 2 s)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "cookies",
+        label: "Send and store cookies",
+        secret: false,
+        choices: &["on", "off"],
+    },
+    Field {
+        name: "http_version",
+        label: "HTTP version (auto negotiates HTTP/2 over TLS)",
+        secret: false,
+        choices: &["auto", "HTTP/1.1", "HTTP/2"],
+    },
+    Field {
+        name: "resolve",
+        label: "Resolve hosts to addresses (host:port:address or host=address, ...)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "unix_socket",
+        label: "Unix socket to send to (the URL then gives the host and path)",
+        secret: false,
+        choices: &[],
+    },
+    Field {
+        name: "decompress",
+        label: "Decompress gzip, deflate and brotli bodies",
+        secret: false,
+        choices: &["on", "off"],
+    },
+];
+
+/// How the client connects, as opposed to what it sends. Settings can be
+/// given in `config.json`, per environment and per request, each overriding
+/// the one before. Values are kept as typed, so `{{variables}}` work in them.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Settings {
+    pub values: BTreeMap<String, String>,
+}
+
+impl Settings {
+    /// The value of setting `name`, or its default choice if unset.
+    pub fn value(&self, name: &str) -> &str {
+        match self.values.get(name) {
+            Some(value) => value,
+            None => FIELDS
+                .iter()
+                .find(|field| field.name == name)
+                .and_then(|field| field.choices.first())
+                .copied()
+                .unwrap_or(""),
+        }
+    }
+
+    /// Sets `name`, or unsets it when `value` is empty so it's inherited again.
+    pub fn set(&mut self, name: &str, value: &str) {
+        if value.is_empty() {
+            self.values.remove(name);
+        } else {
+            self.values.insert(String::from(name), String::from(value));
+        }
+    }
+
+    pub fn is_set(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// These settings with any left unset taken from `base`.
+    pub fn over(&self, base: &Settings) -> Settings {
+        let mut values = base.values.clone();
+        values.extend(self.values.clone());
+        Settings { values }
+    }
+
+    /// A copy with `{{variables}}` in every value filled in.
+    pub fn interpolate(&self, environments: &Environments) -> Settings {
+        Settings {
+            values: self
+                .values
+                .iter()
+                .map(|(name, value)| (name.clone(), environments.interpolate(value)))
+                .collect(),
+        }
+    }
+
+    /// A client configured by these settings. Each redirect it follows is
+    /// added to `redirects`, and cookies come from and go to `cookie_jar`.
+    pub fn client(
+        &self,
+        redirects: &Arc<Mutex<Vec<Redirect>>>,
+        cookie_jar: Option<&Arc<CookieJar>>,
+    ) -> Result<Client, AppError> {
+        let mut builder = Client::builder().redirect(self.redirect_policy(redirects)?);
+        if let Some(jar) = cookie_jar.filter(|_| self.sends_cookies()) {
+            builder = builder.cookie_provider(Arc::clone(jar));
+        }
+        for (host, addr) in self.resolve_overrides()? {
+            builder = builder.resolve(&host, addr);
+        }
+        match self.value("http_version") {
+            "HTTP/1.1" => builder = builder.http1_only(),
+            // HTTP/2 from the first byte, so plain http:// servers need h2c
+            "HTTP/2" => builder = builder.http2_prior_knowledge(),
+            _ => {}
+        }
+        if let Some(timeout) = self.seconds("connect_timeout")? {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.seconds("timeout")? {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(identity) = self.identity()? {
+            builder = builder.identity(identity);
+        }
+        for cert in self.root_certificates()? {
+            builder = builder.add_root_certificate(cert);
+        }
+        if self.insecure() {
+            builder = builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        match self.value("proxy") {
+            // reqwest honors HTTP_PROXY, HTTPS_PROXY and NO_PROXY by itself
+            "" => {}
+            "none" => builder = builder.no_proxy(),
+            url => builder = builder.proxy(self.proxy(url)?),
+        }
+        Ok(builder.build()?)
+    }
+
+    /// The proxy at `url`, used for every scheme. Credentials can be in the
+    /// URL or in their own settings; hosts in `no_proxy`, or else in the
+    /// `NO_PROXY` variable, bypass it.
+    fn proxy(&self, url: &str) -> Result<Proxy, AppError> {
+        let mut proxy =
+            Proxy::all(url).map_err(|e| AppError::Parse(format!("proxy URL {}: {}", url, e)))?;
+        if !self.value("proxy_user").is_empty() {
+            proxy = proxy.basic_auth(self.value("proxy_user"), self.value("proxy_password"));
+        }
+        let no_proxy = match self.value("no_proxy") {
+            "" => NoProxy::from_env(),
+            hosts => NoProxy::from_string(hosts),
+        };
+        Ok(proxy.no_proxy(no_proxy))
+    }
+
+    /// The duration setting `name`, given in seconds, if set.
+    pub fn seconds(&self, name: &str) -> Result<Option<Duration>, AppError> {
+        match self.value(name).trim() {
+            "" => Ok(None),
+            value => value
+                .parse::<f64>()
+                .ok()
+                .and_then(|s| Duration::try_from_secs_f64(s).ok())
+                .map(Some)
+                .ok_or_else(|| AppError::Parse(format!("{} '{}' as seconds", name, value))),
+        }
+    }
+
+    /// Turns a timeout from reqwest into one saying which limit ran out.
+    pub fn timeout_error(&self, e: reqwest::Error) -> AppError {
+        if !e.is_timeout() {
+            return AppError::Http(e);
+        }
+        let (name, what) = if e.is_connect() {
+            ("connect_timeout", "connecting took longer than")
+        } else {
+            ("timeout", "the request took longer than")
+        };
+        match self.seconds(name) {
+            Ok(Some(limit)) => AppError::Timeout(format!("{} {:?}", what, limit)),
+            _ => AppError::Http(e),
+        }
+    }
+
+    /// The `resolve` entries, separated by commas or spaces. Each is either
+    /// curl's `host:port:address` or `host=address`. reqwest always connects
+    /// on the URL's port, so the port in curl's form is only checked to be a number.
+    fn resolve_overrides(&self) -> Result<Vec<(String, SocketAddr)>, AppError> {
+        self.value("resolve")
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let invalid = || AppError::Parse(format!("resolve entry '{}'", entry));
+                let (host, port, addr) = match entry.split_once('=') {
+                    Some((host, addr)) => (host, "0", addr),
+                    None => {
+                        let mut parts = entry.splitn(3, ':');
+                        match (parts.next(), parts.next(), parts.next()) {
+                            (Some(host), Some(port), Some(addr)) => (host, port, addr),
+                            _ => return Err(invalid()),
+                        }
+                    }
+                };
+                let port: u16 = port.parse().map_err(|_| invalid())?;
+                let ip: IpAddr = addr
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse()
+                    .map_err(|_| invalid())?;
+                Ok((host.to_ascii_lowercase(), SocketAddr::new(ip, port)))
+            })
+            .collect()
+    }
+
+    /// Follows redirects up to the limit, recording each one. Once the limit
+    /// is reached the last redirect is shown as the response.
+    fn redirect_policy(
+        &self,
+        redirects: &Arc<Mutex<Vec<Redirect>>>,
+    ) -> Result<redirect::Policy, AppError> {
+        if self.value("follow_redirects") == "off" {
+            return Ok(redirect::Policy::none());
+        }
+        let max = match self.value("max_redirects") {
+            "" => DEFAULT_MAX_REDIRECTS,
+            max => max
+                .trim()
+                .parse()
+                .map_err(|_| AppError::Parse(format!("max_redirects '{}' as a number", max)))?,
+        };
+        let redirects = Arc::clone(redirects);
+        Ok(redirect::Policy::custom(move |attempt| {
+            // `previous` starts with the original URL
+            if attempt.previous().len() > max {
+                return attempt.stop();
+            }
+            if let Ok(mut redirects) = redirects.lock() {
+                redirects.push(Redirect {
+                    status: attempt.status(),
+                    location: attempt.url().to_string(),
+                });
+            }
+            attempt.follow()
+        }))
+    }
+
+    pub fn sends_cookies(&self) -> bool {
+        self.value("cookies") == "on"
+    }
+
+    /// Whether certificate checks are turned off.
+    pub fn insecure(&self) -> bool {
+        self.value("insecure") == "on"
+    }
+
+    /// The CAs trusted on top of the system's, from a PEM file that may hold several.
+    fn root_certificates(&self) -> Result<Vec<Certificate>, AppError> {
+        let path = match self.value("ca_bundle") {
+            "" => return Ok(Vec::new()),
+            path => storage::expand_tilde(path),
+        };
+        let pem =
+            fs::read(&path).map_err(|e| AppError::Tls(format!("{}: {}", path.display(), e)))?;
+        Certificate::from_pem_bundle(&pem)
+            .map_err(|e| AppError::Tls(format!("CA bundle {}: {}", path.display(), e)))
+    }
+
+    /// The client certificate for mutual TLS. A `.p12`/`.pfx` file is read as
+    /// PKCS#12; anything else as PEM, with the PKCS#8 key either in its own
+    /// file or after the certificate.
+    fn identity(&self) -> Result<Option<Identity>, AppError> {
+        let cert_path = match self.value("client_cert") {
+            "" => return Ok(None),
+            path => storage::expand_tilde(path),
+        };
+        let read = |path: &std::path::Path| {
+            fs::read(path).map_err(|e| AppError::Tls(format!("{}: {}", path.display(), e)))
+        };
+        let cert = read(&cert_path)?;
+        let extension = cert_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let identity = if matches!(extension.as_deref(), Some("p12" | "pfx")) {
+            Identity::from_pkcs12_der(&cert, self.value("cert_password"))
+        } else {
+            let key = match self.value("client_key") {
+                "" => cert.clone(),
+                path => read(&storage::expand_tilde(path))?,
+            };
+            Identity::from_pkcs8_pem(&cert, &key)
+        };
+        identity.map(Some).map_err(|e| {
+            AppError::Tls(format!("client certificate {}: {}", cert_path.display(), e))
+        })
+    }
+}
@@ -0,0 +1,245 @@
+use crate::body::{self, BodyMode};
+use crate::crypto::{hex, hmac_sha256};
+use crate::request::Request;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// Percent-encodes everything but the RFC 3986 unreserved characters, as
+/// SigV4 requires for query strings and path segments.
+fn aws_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                String::from(b as char)
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The path of `url` as signed. It's already percent-encoded, and every
+/// service but S3 wants each segment encoded again, so `/a%20b` is signed
+/// as `/a%2520b`.
+fn canonical_uri(url: &url::Url, service: &str) -> String {
+    if service == "s3" {
+        return String::from(url.path());
+    }
+    let segments: Vec<String> = url.path().split('/').map(aws_encode).collect();
+    segments.join("/")
+}
+
+/// `url` with its query encoded as it's signed, spaces as `%20` and `+` as
+/// `%2B`. AWS reads the query it receives this way, so sent with `+` for a
+/// space it wouldn't match the signature.
+pub fn canonical_url(url: &url::Url) -> url::Url {
+    let query: Vec<String> = url
+        .query_pairs()
+        .map(|(k, v)| format!("{}={}", aws_encode(&k), aws_encode(&v)))
+        .collect();
+    let mut url = url.clone();
+    if !query.is_empty() {
+        url.set_query(Some(&query.join("&")));
+    }
+    url
+}
+
+/// The headers that sign `request` with AWS Signature Version 4, using the
+/// access key, secret, region and service from its auth. Only `host` and the
+/// `x-amz-*` headers are signed.
+pub fn sign(request: &Request, url: &url::Url, now: DateTime<Utc>) -> Vec<(String, String)> {
+    let auth = &request.auth;
+    let (region, service) = (auth.value("region"), auth.value("service"));
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = match request.body_mode {
+        _ if !crate::request::method_has_body(&request.method) => hex(&Sha256::digest(b"")),
+        BodyMode::Raw => hex(&Sha256::digest(request.body.as_bytes())),
+        BodyMode::Form => hex(&Sha256::digest(
+            body::form_urlencoded(&request.fields).as_bytes(),
+        )),
+        BodyMode::GraphQl => hex(&Sha256::digest(
+            body::graphql(&request.body, &request.variables)
+                .unwrap_or_default()
+                .as_bytes(),
+        )),
+        // The multipart boundary isn't known until reqwest encodes the body
+        BodyMode::Multipart => String::from("UNSIGNED-PAYLOAD"),
+    };
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or(""), port),
+        None => String::from(url.host_str().unwrap_or("")),
+    };
+    let mut headers = vec![
+        (String::from("host"), host),
+        (String::from("x-amz-date"), amz_date.clone()),
+    ];
+    // S3 insists on the payload hash header; other services don't need it
+    if service == "s3" {
+        headers.push((String::from("x-amz-content-sha256"), payload_hash.clone()));
+    }
+    if !auth.value("session_token").is_empty() {
+        headers.push((
+            String::from("x-amz-security-token"),
+            String::from(auth.value("session_token")),
+        ));
+    }
+    headers.sort();
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (aws_encode(&k), aws_encode(&v)))
+        .collect();
+    query.sort();
+    let query: Vec<String> = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers: Vec<&str> = headers.iter().map(|(name, _)| name.as_str()).collect();
+    let signed_headers = signed_headers.join(";");
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        canonical_uri(url, service),
+        query.join("&"),
+        canonical_headers,
+        signed_headers,
+        payload_hash
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = format!("AWS4{}", auth.value("secret_key"));
+    let key = hmac_sha256(key.as_bytes(), &date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    let key = hmac_sha256(&key, "aws4_request");
+    let signature = hex(&hmac_sha256(&key, &string_to_sign));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        auth.value("access_key"),
+        scope,
+        signed_headers,
+        signature
+    );
+    // reqwest sets Host itself
+    headers.retain(|(name, _)| name != "host");
+    headers.push((String::from("authorization"), authorization));
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{Auth, AuthKind};
+    use chrono::TimeZone;
+    use reqwest::Method;
+    use std::collections::BTreeMap;
+
+    /// The credentials and time of the AWS SigV4 test suite.
+    fn request(method: Method, service: &str) -> Request {
+        let values = [
+            ("access_key", "AKIDEXAMPLE"),
+            ("secret_key", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"),
+            ("region", "us-east-1"),
+            ("service", service),
+        ];
+        Request {
+            method,
+            auth: Auth {
+                kind: AuthKind::AwsSigV4,
+                values: values
+                    .iter()
+                    .map(|(k, v)| (String::from(*k), String::from(*v)))
+                    .collect::<BTreeMap<_, _>>(),
+                selected: 0,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn authorization(request: &Request, url: &str) -> String {
+        let url = url::Url::parse(url).unwrap();
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        let headers = sign(request, &url, now);
+        let (_, authorization) = headers
+            .into_iter()
+            .find(|(name, _)| name == "authorization")
+            .unwrap();
+        authorization
+    }
+
+    #[test]
+    fn signs_get_vanilla() {
+        assert_eq!(
+            authorization(
+                &request(Method::GET, "service"),
+                "https://example.amazonaws.com/"
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn signs_get_vanilla_query_order_key_case() {
+        assert_eq!(
+            authorization(
+                &request(Method::GET, "service"),
+                "https://example.amazonaws.com/?Param2=value2&Param1=value1"
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    #[test]
+    fn signs_get_vanilla_utf8_query() {
+        assert_eq!(
+            authorization(
+                &request(Method::GET, "service"),
+                "https://example.amazonaws.com/?\u{1234}=bar"
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=2cdec8eed098649ff3a119c94853b13c643bcf08f8b0a1d91e12c9027818dd04"
+        );
+    }
+
+    #[test]
+    fn sends_a_space_in_the_query_as_signed() {
+        let sent = url::Url::parse("https://example.amazonaws.com/?q=a+b&r=c%2Bd").unwrap();
+        let sent = canonical_url(&sent);
+        assert_eq!(sent.query(), Some("q=a%20b&r=c%2Bd"));
+        let request = request(Method::GET, "service");
+        assert_eq!(
+            authorization(&request, sent.as_str()),
+            authorization(&request, "https://example.amazonaws.com/?r=c%2Bd&q=a%20b")
+        );
+    }
+
+    #[test]
+    fn encodes_path_segments_again_except_for_s3() {
+        let url = url::Url::parse("https://example.com/documents and settings/a%2Fb").unwrap();
+        assert_eq!(
+            canonical_uri(&url, "execute-api"),
+            "/documents%2520and%2520settings/a%252Fb"
+        );
+        assert_eq!(
+            canonical_uri(&url, "s3"),
+            "/documents%20and%20settings/a%2Fb"
+        );
+        let root = url::Url::parse("https://example.com").unwrap();
+        assert_eq!(canonical_uri(&root, "lambda"), "/");
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::AppError;
+use crate::jsonpath::JsonPath;
+use crate::response::ResponseData;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// What ignored fields are stored as, so they still have to be there.
+const IGNORED: &str = "<ignored>";
+
+/// How many differences a failed check lists before counting the rest.
+const SHOWN: usize = 3;
+
+/// The parts of a response compared with a stored copy: its status, and
+/// its body as JSON if it reads as JSON, or as text.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Snapshot {
+    /// `response`, with the fields at each of `ignored` in its JSON body
+    /// left out of the comparison.
+    pub fn new(response: &ResponseData, ignored: &[JsonPath]) -> Snapshot {
+        let text = response.raw_body.as_str();
+        let body = match serde_json::from_str::<Value>(text) {
+            Ok(mut json) => {
+                for path in ignored {
+                    path.replace(&mut json, &Value::from(IGNORED));
+                }
+                json
+            }
+            Err(_) => Value::from(text),
+        };
+        Snapshot {
+            status: response.status.as_u16(),
+            body,
+        }
+    }
+}
+
+/// Where the snapshot `name` is kept, in the data directory.
+pub fn path(name: &str) -> PathBuf {
+    storage::data_dir()
+        .join("snapshots")
+        .join(format!("{}.json", storage::file_stem(name)))
+}
+
+/// The snapshot `name`, if one has been stored.
+pub fn load(name: &str) -> Option<Snapshot> {
+    storage::load_json(&path(name)).ok().flatten()
+}
+
+/// Stores `response` as the snapshot `name`, replacing any kept before.
+pub fn record(name: &str, ignored: &[JsonPath], response: &ResponseData) -> Result<(), AppError> {
+    storage::save_json(&path(name), &Snapshot::new(response, ignored))
+}
+
+/// Compares `response` with the snapshot `name`, saying what differs if
+/// it doesn't match. The first response checked is stored as the snapshot.
+pub fn check(name: &str, ignored: &[JsonPath], response: &ResponseData) -> Result<(), String> {
+    let failed = |reason: String| format!("snapshot {:?}: {}", name, reason);
+    let stored: Option<Snapshot> =
+        storage::load_json(&path(name)).map_err(|e| failed(e.to_string()))?;
+    let Some(stored) = stored else {
+        return record(name, ignored, response).map_err(|e| failed(e.to_string()));
+    };
+    let snapshot = Snapshot::new(response, ignored);
+    let mut found = Vec::new();
+    if stored.status != snapshot.status {
+        found.push(format!(
+            "status was {}, now {}",
+            stored.status, snapshot.status
+        ));
+    }
+    differences("$", &stored.body, &snapshot.body, &mut found);
+    if found.is_empty() {
+        return Ok(());
+    }
+    let mut reason = found[..found.len().min(SHOWN)].join("; ");
+    if found.len() > SHOWN {
+        reason += &format!("; and {} more", found.len() - SHOWN);
+    }
+    Err(failed(reason))
+}
+
+/// `value` as JSON, cut short if it's long, e.g. a whole text body.
+fn short(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(40) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// Adds where `new` differs from `old`, both found at `path`, to `found`.
+fn differences(path: &str, old: &Value, new: &Value, found: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, value) in old {
+                let child = format!("{}.{}", path, key);
+                match new.get(key) {
+                    Some(other) => differences(&child, value, other, found),
+                    None => found.push(format!("{} is gone", child)),
+                }
+            }
+            for key in new.keys().filter(|key| !old.contains_key(*key)) {
+                found.push(format!("{}.{} is new", path, key));
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (i, (value, other)) in old.iter().zip(new).enumerate() {
+                differences(&format!("{}[{}]", path, i), value, other, found);
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => found.push(format!(
+            "{} had {} items, now {}",
+            path,
+            old.len(),
+            new.len()
+        )),
+        (old, new) if old != new => {
+            found.push(format!("{} was {}, now {}", path, short(old), short(new)))
+        }
+        _ => {}
+    }
+}
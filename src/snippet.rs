@@ -0,0 +1,443 @@
+use crate::body::{BodyMode, FieldValue};
+use crate::error::AppError;
+use crate::request::{self, Request};
+use reqwest::{header, Client, Method};
+use std::path::Path;
+
+/// A language requests can be written out in, for handing to someone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    Curl,
+    Python,
+    JavaScript,
+    Go,
+    Rust,
+}
+
+impl Language {
+    pub const ALL: [Language; 5] = [
+        Language::Curl,
+        Language::Python,
+        Language::JavaScript,
+        Language::Go,
+        Language::Rust,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Curl => "curl",
+            Language::Python => "Python requests",
+            Language::JavaScript => "JavaScript fetch",
+            Language::Go => "Go net/http",
+            Language::Rust => "Rust reqwest",
+        }
+    }
+}
+
+/// A multipart field, as the snippets upload it.
+enum Field {
+    Text(String),
+    File {
+        path: String,
+        content_type: Option<String>,
+    },
+}
+
+enum Body {
+    None,
+    Text(String),
+    Multipart(Vec<(String, Field)>),
+}
+
+/// A request as it would go out, in the pieces snippets are written from.
+struct Parts {
+    method: Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Body,
+}
+
+impl Parts {
+    /// `request`, already prepared, as `reqwest` would send it: with its
+    /// auth, signature and Content-Type in the headers. Multipart bodies
+    /// are kept as fields, since their files are read by whoever runs the
+    /// snippet.
+    fn new(request: &Request) -> Result<Parts, AppError> {
+        let multipart =
+            request.body_mode == BodyMode::Multipart && request::method_has_body(&request.method);
+        let mut copy = request.clone();
+        if multipart {
+            copy.body_mode = BodyMode::Raw;
+            copy.body.clear();
+        }
+        let built = copy.build(&Client::new())?.build()?;
+        let typed = |name: &header::HeaderName| {
+            request
+                .headers
+                .iter()
+                .any(|kv| kv.key.eq_ignore_ascii_case(name.as_str()))
+        };
+        let headers = built
+            .headers()
+            .iter()
+            // Asked for by this app to show sizes, and up to the snippet's client
+            .filter(|(name, _)| **name != header::ACCEPT_ENCODING || typed(name))
+            // Set with the boundary by the snippet's multipart encoder
+            .filter(|(name, _)| !multipart || **name != header::CONTENT_TYPE)
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+        let body = if multipart {
+            let fields = request
+                .fields
+                .iter()
+                .map(|kv| {
+                    let field = match FieldValue::parse(&kv.value) {
+                        FieldValue::Text(text) => Field::Text(String::from(text)),
+                        FieldValue::File { path, content_type } => Field::File {
+                            path: String::from(path),
+                            content_type: content_type.map(String::from),
+                        },
+                    };
+                    (kv.key.clone(), field)
+                })
+                .collect();
+            Body::Multipart(fields)
+        } else {
+            match built.body().and_then(|body| body.as_bytes()) {
+                Some(bytes) if !bytes.is_empty() => {
+                    Body::Text(String::from_utf8_lossy(bytes).into_owned())
+                }
+                _ => Body::None,
+            }
+        };
+        Ok(Parts {
+            method: built.method().clone(),
+            url: built.url().to_string(),
+            headers,
+            body,
+        })
+    }
+
+    fn has_files(&self) -> bool {
+        match &self.body {
+            Body::Multipart(fields) => fields
+                .iter()
+                .any(|(_, field)| matches!(field, Field::File { .. })),
+            _ => false,
+        }
+    }
+}
+
+/// `request`, already prepared, as code in `language` that sends it the
+/// same way and prints the status and body.
+pub fn generate(request: &Request, language: Language) -> Result<String, AppError> {
+    let parts = Parts::new(request)?;
+    Ok(match language {
+        Language::Curl => curl(&parts),
+        Language::Python => python(&parts),
+        Language::JavaScript => javascript(&parts),
+        Language::Go => go(&parts),
+        Language::Rust => rust(&parts),
+    })
+}
+
+/// Single-quoted for a POSIX shell.
+fn shell(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// A double-quoted string literal, as JSON writes them, which Python,
+/// JavaScript and Go all read the same way. Rust doesn't: its `\u{1b}`
+/// escapes aren't JSON's `\u001b`, and it has no `\b` or `\f`, so `rust`
+/// writes its literals with `{:?}` instead.
+fn quoted(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
+
+fn curl(parts: &Parts) -> String {
+    let mut words = vec![match parts.method {
+        Method::GET => format!("curl {}", shell(&parts.url)),
+        Method::HEAD => format!("curl --head {}", shell(&parts.url)),
+        _ => format!("curl -X {} {}", parts.method, shell(&parts.url)),
+    }];
+    for (name, value) in &parts.headers {
+        words.push(format!("-H {}", shell(&format!("{}: {}", name, value))));
+    }
+    match &parts.body {
+        Body::None => {}
+        Body::Text(text) => words.push(format!("--data-raw {}", shell(text))),
+        Body::Multipart(fields) => {
+            for (name, field) in fields {
+                words.push(match field {
+                    Field::Text(text) => {
+                        format!("--form-string {}", shell(&format!("{}={}", name, text)))
+                    }
+                    Field::File { path, content_type } => {
+                        let mut value = format!("{}=@{}", name, path);
+                        if let Some(content_type) = content_type {
+                            value += &format!(";type={}", content_type);
+                        }
+                        format!("-F {}", shell(&value))
+                    }
+                });
+            }
+        }
+    }
+    words.join(" \\\n  ") + "\n"
+}
+
+fn python(parts: &Parts) -> String {
+    let mut code = String::from("import requests\n\n");
+    code += &format!("url = {}\n", quoted(&parts.url));
+    let mut arguments = vec![quoted(parts.method.as_str()), String::from("url")];
+    if !parts.headers.is_empty() {
+        code += "headers = {\n";
+        for (name, value) in &parts.headers {
+            code += &format!("    {}: {},\n", quoted(name), quoted(value));
+        }
+        code += "}\n";
+        arguments.push(String::from("headers=headers"));
+    }
+    match &parts.body {
+        Body::None => {}
+        Body::Text(text) => {
+            code += &format!("data = {}\n", quoted(text));
+            arguments.push(String::from("data=data"));
+        }
+        Body::Multipart(fields) => {
+            // Text fields as (None, value) keep them in order with the files
+            code += "files = [\n";
+            for (name, field) in fields {
+                let value = match field {
+                    Field::Text(text) => format!("(None, {})", quoted(text)),
+                    Field::File { path, content_type } => {
+                        let mut value = format!(
+                            "({}, open({}, \"rb\")",
+                            quoted(file_name(path)),
+                            quoted(path)
+                        );
+                        if let Some(content_type) = content_type {
+                            value += &format!(", {}", quoted(content_type));
+                        }
+                        value + ")"
+                    }
+                };
+                code += &format!("    ({}, {}),\n", quoted(name), value);
+            }
+            code += "]\n";
+            arguments.push(String::from("files=files"));
+        }
+    }
+    code += &format!("\nresponse = requests.request({})\n", arguments.join(", "));
+    code += "print(response.status_code)\nprint(response.text)\n";
+    code
+}
+
+fn javascript(parts: &Parts) -> String {
+    let mut code = String::new();
+    if parts.has_files() {
+        code += "import { openAsBlob } from \"node:fs\";\n\n";
+    }
+    if let Body::Multipart(fields) = &parts.body {
+        code += "const form = new FormData();\n";
+        for (name, field) in fields {
+            code += &match field {
+                Field::Text(text) => format!("form.append({}, {});\n", quoted(name), quoted(text)),
+                Field::File { path, content_type } => {
+                    let options = match content_type {
+                        Some(content_type) => format!(", {{ type: {} }}", quoted(content_type)),
+                        None => String::new(),
+                    };
+                    format!(
+                        "form.append({}, await openAsBlob({}{}), {});\n",
+                        quoted(name),
+                        quoted(path),
+                        options,
+                        quoted(file_name(path))
+                    )
+                }
+            };
+        }
+        code.push('\n');
+    }
+    code += &format!("const response = await fetch({}, {{\n", quoted(&parts.url));
+    code += &format!("  method: {},\n", quoted(parts.method.as_str()));
+    if !parts.headers.is_empty() {
+        code += "  headers: {\n";
+        for (name, value) in &parts.headers {
+            code += &format!("    {}: {},\n", quoted(name), quoted(value));
+        }
+        code += "  },\n";
+    }
+    match &parts.body {
+        Body::None => {}
+        Body::Text(text) => code += &format!("  body: {},\n", quoted(text)),
+        Body::Multipart(_) => code += "  body: form,\n",
+    }
+    code += "});\n\nconsole.log(response.status);\nconsole.log(await response.text());\n";
+    code
+}
+
+fn go(parts: &Parts) -> String {
+    let mut imports = vec!["fmt", "io", "net/http"];
+    match &parts.body {
+        Body::None => {}
+        Body::Text(_) => imports.push("strings"),
+        Body::Multipart(_) => imports.extend(["bytes", "mime/multipart"]),
+    }
+    if parts.has_files() {
+        imports.extend(["os", "path/filepath"]);
+    }
+    imports.sort_unstable();
+    let mut code = String::from("package main\n\nimport (\n");
+    for import in imports {
+        code += &format!("\t{}\n", quoted(import));
+    }
+    code += ")\n\nfunc main() {\n";
+    let body = match &parts.body {
+        Body::None => "nil",
+        Body::Text(text) => {
+            code += &format!("\tbody := strings.NewReader({})\n", quoted(text));
+            "body"
+        }
+        Body::Multipart(fields) => {
+            code += "\tvar body bytes.Buffer\n\tform := multipart.NewWriter(&body)\n";
+            for (name, field) in fields {
+                code += &match field {
+                    Field::Text(text) => {
+                        format!("\tform.WriteField({}, {})\n", quoted(name), quoted(text))
+                    }
+                    Field::File { path, .. } => format!(
+                        "\tif err := addFile(form, {}, {}); err != nil {{\n\t\tpanic(err)\n\t}}\n",
+                        quoted(name),
+                        quoted(path)
+                    ),
+                };
+            }
+            code += "\tform.Close()\n";
+            "&body"
+        }
+    };
+    code += &format!(
+        "\treq, err := http.NewRequest({}, {}, {})\n\tif err != nil {{\n\t\tpanic(err)\n\t}}\n",
+        quoted(parts.method.as_str()),
+        quoted(&parts.url),
+        body
+    );
+    for (name, value) in &parts.headers {
+        code += &format!("\treq.Header.Add({}, {})\n", quoted(name), quoted(value));
+    }
+    if let Body::Multipart(_) = parts.body {
+        code += "\treq.Header.Set(\"Content-Type\", form.FormDataContentType())\n";
+    }
+    code += "\tresp, err := http.DefaultClient.Do(req)\n\tif err != nil {\n\t\tpanic(err)\n\t}\n";
+    code += "\tdefer resp.Body.Close()\n\tdata, err := io.ReadAll(resp.Body)\n\tif err != nil {\n\t\tpanic(err)\n\t}\n";
+    code += "\tfmt.Println(resp.Status)\n\tfmt.Println(string(data))\n}\n";
+    if parts.has_files() {
+        code += "\nfunc addFile(form *multipart.Writer, field, path string) error {\n";
+        code += "\tfile, err := os.Open(path)\n\tif err != nil {\n\t\treturn err\n\t}\n\tdefer file.Close()\n";
+        code += "\tpart, err := form.CreateFormFile(field, filepath.Base(path))\n\tif err != nil {\n\t\treturn err\n\t}\n";
+        code += "\t_, err = io.Copy(part, file)\n\treturn err\n}\n";
+    }
+    code
+}
+
+fn rust(parts: &Parts) -> String {
+    let method = match parts.method {
+        Method::GET
+        | Method::POST
+        | Method::PUT
+        | Method::PATCH
+        | Method::DELETE
+        | Method::HEAD
+        | Method::OPTIONS
+        | Method::TRACE
+        | Method::CONNECT => format!("reqwest::Method::{}", parts.method),
+        _ => format!("reqwest::Method::from_bytes(b{:?})?", parts.method.as_str()),
+    };
+    let mut code = String::new();
+    if let Body::Multipart(_) = parts.body {
+        code += "// reqwest needs its \"multipart\" feature for this\n";
+    }
+    code += "#[tokio::main]\nasync fn main() -> Result<(), Box<dyn std::error::Error>> {\n";
+    if let Body::Multipart(fields) = &parts.body {
+        code += "    let form = reqwest::multipart::Form::new()";
+        for (name, field) in fields {
+            code += &match field {
+                Field::Text(text) => format!("\n        .text({:?}, {:?})", name, text),
+                Field::File { path, content_type } => {
+                    let mut part = format!(
+                        "reqwest::multipart::Part::bytes(std::fs::read({:?})?).file_name({:?})",
+                        path,
+                        file_name(path)
+                    );
+                    if let Some(content_type) = content_type {
+                        part += &format!(".mime_str({:?})?", content_type);
+                    }
+                    format!("\n        .part({:?}, {})", name, part)
+                }
+            };
+        }
+        code += ";\n";
+    }
+    code += "    let response = reqwest::Client::new()\n";
+    code += &format!("        .request({}, {:?})\n", method, parts.url);
+    for (name, value) in &parts.headers {
+        code += &format!("        .header({:?}, {:?})\n", name, value);
+    }
+    match &parts.body {
+        Body::None => {}
+        Body::Text(text) => code += &format!("        .body({:?})\n", text),
+        Body::Multipart(_) => code += "        .multipart(form)\n",
+    }
+    code += "        .send()\n        .await?;\n";
+    code += "    println!(\"{}\", response.status());\n    println!(\"{}\", response.text().await?);\n    Ok(())\n}\n";
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: &str) -> Request {
+        Request {
+            method: Method::POST,
+            url: String::from("https://example.com/"),
+            body: String::from(body),
+            body_mode: BodyMode::Raw,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn escapes_control_characters_the_way_rust_reads_them() {
+        let code = generate(&request("\x1b[1m\x08\x0c"), Language::Rust).unwrap();
+        assert!(code.contains(r#".body("\u{1b}[1m\u{8}\u{c}")"#), "{}", code);
+        assert!(!code.contains(r"\u001b"), "{}", code);
+    }
+
+    #[test]
+    fn escapes_control_characters_as_json_for_the_others() {
+        for language in [Language::Python, Language::JavaScript, Language::Go] {
+            let code = generate(&request("\x1b[1m\x08\x0c"), language).unwrap();
+            assert!(code.contains(r#""\u001b[1m\b\f""#), "{}", code);
+        }
+    }
+
+    #[test]
+    fn writes_a_rust_request() {
+        let code = generate(&request("{\"a\": 1}"), Language::Rust).unwrap();
+        assert!(code.contains(r#"        .request(reqwest::Method::POST, "https://example.com/")"#));
+        assert!(code.contains(r#"        .body("{\"a\": 1}")"#), "{}", code);
+    }
+}
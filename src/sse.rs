@@ -0,0 +1,322 @@
+use chrono::{DateTime, Local};
+use reqwest::header::{self, HeaderMap};
+use std::sync::{Mutex, MutexGuard};
+use tokio::sync::Notify;
+
+/// Whether a response is an event stream that can be parsed as it arrives,
+/// which it can't be if it was compressed.
+pub fn is_event_stream(headers: &HeaderMap) -> bool {
+    let value = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .unwrap_or("")
+    };
+    value(header::CONTENT_TYPE)
+        .to_ascii_lowercase()
+        .starts_with("text/event-stream")
+        && matches!(value(header::CONTENT_ENCODING), "" | "identity")
+}
+
+/// A server-sent event, as dispatched by a blank line.
+#[derive(Clone, Debug)]
+pub struct Event {
+    pub received: DateTime<Local>,
+    /// From the `event` field, `message` if there was none.
+    pub name: String,
+    /// The last `id` the stream set, which carries over to later events.
+    pub id: String,
+    pub data: String,
+}
+
+impl Event {
+    /// e.g. `13:37:00.123  message #5  {"count": 1}`, with newlines in the
+    /// data shown as `\n` so each event stays on one line.
+    pub fn summary(&self) -> String {
+        let id = if self.id.is_empty() {
+            String::new()
+        } else {
+            format!(" #{}", self.id)
+        };
+        format!(
+            "{}  {}{}  {}",
+            self.received.format("%H:%M:%S%.3f"),
+            self.name,
+            id,
+            self.data.replace('\n', "\\n")
+        )
+    }
+}
+
+/// Turns a `text/event-stream` body into events as chunks of it arrive.
+#[derive(Debug, Default)]
+pub struct Parser {
+    /// The start of a line whose end hasn't arrived yet.
+    partial: Vec<u8>,
+    /// Set when the last chunk ended with a CR, so an LF starting the next
+    /// is the rest of that line ending rather than a blank line.
+    after_cr: bool,
+    name: String,
+    id: String,
+    data: Vec<String>,
+}
+
+impl Parser {
+    /// The events completed by `chunk`. Lines may end with CRLF, LF or CR.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        let mut chunk = chunk;
+        if self.after_cr && !chunk.is_empty() {
+            self.after_cr = false;
+            chunk = chunk.strip_prefix(b"\n").unwrap_or(chunk);
+        }
+        self.partial.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.partial.iter().position(|b| *b == b'\n' || *b == b'\r') {
+            let ending = match (self.partial[end], self.partial.get(end + 1)) {
+                (b'\r', Some(b'\n')) => 2,
+                (b'\r', None) => {
+                    self.after_cr = true;
+                    1
+                }
+                _ => 1,
+            };
+            let line: Vec<u8> = self.partial.drain(..end + ending).take(end).collect();
+            if let Some(event) = self.line(&String::from_utf8_lossy(&line)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// The event the stream was in the middle of when it ended, without the
+    /// blank line that would have sent it. Browsers drop it, but it's what
+    /// the server sent, so it's shown.
+    pub fn finish(&mut self) -> Option<Event> {
+        let line = std::mem::take(&mut self.partial);
+        if !line.is_empty() {
+            self.line(&String::from_utf8_lossy(&line));
+        }
+        self.line("")
+    }
+
+    fn line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            let name = std::mem::take(&mut self.name);
+            if self.data.is_empty() {
+                return None;
+            }
+            return Some(Event {
+                received: Local::now(),
+                name: if name.is_empty() {
+                    String::from("message")
+                } else {
+                    name
+                },
+                id: self.id.clone(),
+                data: std::mem::take(&mut self.data).join("\n"),
+            });
+        }
+        // Lines starting with a colon are comments, often sent as keep-alives
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.name = String::from(value),
+            "data" => self.data.push(String::from(value)),
+            "id" if !value.contains('\0') => self.id = String::from(value),
+            _ => {}
+        }
+        None
+    }
+}
+
+/// The events received so far on a request's response, shared with the UI
+/// while the stream is open. The UI can also ask for it to be closed.
+#[derive(Debug, Default)]
+pub struct EventStream {
+    state: Mutex<State>,
+    disconnect: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    open: bool,
+    events: Vec<Event>,
+}
+
+impl EventStream {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Marks the stream as being received, dropping events from an earlier attempt.
+    pub fn open(&self) {
+        let mut state = self.lock();
+        state.open = true;
+        state.events.clear();
+    }
+
+    pub fn close(&self) {
+        self.lock().open = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.lock().open
+    }
+
+    pub fn push(&self, events: Vec<Event>) {
+        self.lock().events.extend(events);
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().events.len()
+    }
+
+    /// Summaries of up to `count` events starting at `first`.
+    pub fn summaries(&self, first: usize, count: usize) -> Vec<String> {
+        let state = self.lock();
+        let end = (first + count).min(state.events.len());
+        state.events[first.min(end)..end]
+            .iter()
+            .map(Event::summary)
+            .collect()
+    }
+
+    /// Every event's summary, one per line.
+    pub fn transcript(&self) -> String {
+        let state = self.lock();
+        let lines: Vec<String> = state.events.iter().map(Event::summary).collect();
+        lines.join("\n")
+    }
+
+    /// Asks the task receiving the stream to stop and finish the response.
+    pub fn disconnect(&self) {
+        self.disconnect.notify_one();
+    }
+
+    /// Completes once `disconnect` has been called.
+    pub async fn disconnected(&self) {
+        self.disconnect.notified().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each event's name, id and data.
+    fn fields(events: Vec<Event>) -> Vec<(String, String, String)> {
+        events
+            .into_iter()
+            .map(|event| (event.name, event.id, event.data))
+            .collect()
+    }
+
+    fn event(name: &str, id: &str, data: &str) -> (String, String, String) {
+        (String::from(name), String::from(id), String::from(data))
+    }
+
+    /// The events of `chunks` fed in turn, and of finishing the stream.
+    fn parse(chunks: &[&[u8]]) -> Vec<(String, String, String)> {
+        let mut parser = Parser::default();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(parser.feed(chunk));
+        }
+        events.extend(parser.finish());
+        fields(events)
+    }
+
+    #[test]
+    fn reads_events() {
+        assert_eq!(
+            parse(&[b"event: update\ndata: {\"count\": 1}\n\ndata:plain\n\n"]),
+            [
+                event("update", "", "{\"count\": 1}"),
+                event("message", "", "plain")
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_lines_split_across_chunks() {
+        assert_eq!(
+            parse(&[b"da", b"ta: hel", b"lo\n", b"\n"]),
+            [event("message", "", "hello")]
+        );
+        // "é" is two bytes, split between the chunks
+        assert_eq!(
+            parse(&[b"data: caf\xc3", b"\xa9\n\n"]),
+            [event("message", "", "café")]
+        );
+    }
+
+    #[test]
+    fn reads_each_line_ending() {
+        let expected = [event("message", "", "a"), event("message", "", "b")];
+        assert_eq!(parse(&[b"data: a\r\n\r\ndata: b\r\n\r\n"]), expected);
+        assert_eq!(parse(&[b"data: a\r\rdata: b\r\r"]), expected);
+        // A CRLF split between chunks is one line ending, not two
+        assert_eq!(
+            parse(&[b"data: a\r", b"\ndata: b\r", b"\n\r\n"]),
+            [event("message", "", "a\nb")]
+        );
+        assert_eq!(
+            parse(&[b"data: a\r", b"", b"\n\n"]),
+            [event("message", "", "a")]
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_unknown_fields() {
+        assert_eq!(
+            parse(&[b": keep-alive\n\nretry: 3000\nfoo: bar\ndata: x\n\n"]),
+            [event("message", "", "x")]
+        );
+    }
+
+    #[test]
+    fn joins_multi_line_data() {
+        assert_eq!(
+            parse(&[b"data: one\ndata:\ndata:  three\n\n"]),
+            [event("message", "", "one\n\n three")]
+        );
+    }
+
+    #[test]
+    fn carries_ids_over_to_later_events() {
+        assert_eq!(
+            parse(&[
+                b"id: 7\ndata: a\n\nevent: ping\ndata: b\n\nid\ndata: c\n\nid: a\0b\ndata: d\n\n"
+            ]),
+            [
+                event("message", "7", "a"),
+                event("ping", "7", "b"),
+                event("message", "", "c"),
+                event("message", "", "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sends_no_event_without_data() {
+        assert!(parse(&[b"event: empty\n\n\n\nid: 1\n\n"]).is_empty());
+        // The name is cleared by the blank line, not kept for the next event
+        assert_eq!(
+            parse(&[b"event: empty\n\ndata: x\n\n"]),
+            [event("message", "", "x")]
+        );
+    }
+
+    #[test]
+    fn finishes_an_event_left_open() {
+        assert_eq!(
+            parse(&[b"data: a\n\ndata: b\ndata: c"]),
+            [event("message", "", "a"), event("message", "", "b\nc")]
+        );
+        assert_eq!(parse(&[b"data: a\n\n: comment"]).len(), 1);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::collections::SavedRequest;
+use crate::error::AppError;
+use crate::layout::Preset;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// What was open when the app last quit, kept in `state.json` so the next
+/// session picks up from there.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// The request in the editors of the tab that was open.
+    pub request: Option<SavedRequest>,
+    /// Name of the active environment.
+    pub environment: Option<String>,
+    /// The layout preset, which overrides `layout` in `config.json`.
+    pub layout: Option<Preset>,
+}
+
+impl State {
+    fn path() -> PathBuf {
+        storage::data_dir().join("state.json")
+    }
+
+    pub fn load() -> Result<State, AppError> {
+        storage::load_json(&State::path())
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        storage::save_json(&State::path(), self)
+    }
+}
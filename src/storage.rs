@@ -0,0 +1,65 @@
+use crate::error::AppError;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+fn home_dir() -> PathBuf {
+    env::var_os("HOME").map_or_else(|| PathBuf::from("."), PathBuf::from)
+}
+
+/// Expands a leading `~/` in a path typed by the user.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/httrs`, defaulting to `~/.config/httrs`.
+pub fn config_dir() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map_or_else(|| home_dir().join(".config"), PathBuf::from)
+        .join("httrs")
+}
+
+/// `$XDG_DATA_HOME/httrs`, defaulting to `~/.local/share/httrs`.
+pub fn data_dir() -> PathBuf {
+    env::var_os("XDG_DATA_HOME")
+        .map_or_else(|| home_dir().join(".local").join("share"), PathBuf::from)
+        .join("httrs")
+}
+
+/// `name` with anything but letters, digits, `-` and `_` replaced by `_`,
+/// to name a file after it.
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Reads a JSON file, or returns the default value if it doesn't exist yet.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, AppError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| AppError::Parse(format!("{}: {}", path.display(), e))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes `value` as pretty-printed JSON, creating parent directories as needed.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::Parse(format!("{}: {}", path.display(), e)))?;
+    fs::write(path, json)?;
+    Ok(())
+}
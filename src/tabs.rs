@@ -0,0 +1,70 @@
+use crate::auth::Auth;
+use crate::body::BodyMode;
+use crate::download::Download;
+use crate::editor::Editor;
+use crate::error::AppError;
+use crate::kv::KeyValueTable;
+use crate::request::Request;
+use crate::response::ResponseData;
+use crate::search::Search;
+use crate::settings::Settings;
+use crate::sse::EventStream;
+use crate::undo::Undo;
+use reqwest::Method;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::task::JoinHandle;
+
+/// Most characters of the URL shown in a tab's label.
+const MAX_LABEL_URL: usize = 24;
+
+/// A request tab's editors and response. The tab being shown keeps these on
+/// `App` itself, and swaps them with its `Tab` when another is switched to,
+/// so a tab's request can finish in the background.
+#[derive(Default)]
+pub struct Tab {
+    pub method: Method,
+    pub url: String,
+    pub url_undo: Undo<(String, usize)>,
+    pub params: KeyValueTable,
+    pub headers: KeyValueTable,
+    pub auth: Auth,
+    pub settings: Settings,
+    pub body: String,
+    pub body_editor: Editor,
+    pub body_mode: BodyMode,
+    pub fields: KeyValueTable,
+    pub variables: String,
+    pub variables_editor: Editor,
+    pub assertions: Vec<String>,
+    pub pre_request: String,
+    pub post_response: String,
+    pub requires: Vec<String>,
+    pub response: Option<ResponseData>,
+    pub error: Option<AppError>,
+    pub show_raw: bool,
+    pub response_scroll: usize,
+    pub follow_events: bool,
+    pub search: Search,
+    pub in_flight: Option<JoinHandle<()>>,
+    pub request_started: Option<Instant>,
+    pub download: Option<Arc<Download>>,
+    pub events: Option<Arc<EventStream>>,
+    pub request_id: u64,
+    pub sent_request: Option<(Request, Request)>,
+}
+
+/// e.g. `GET example.com/users`, for the tab bar.
+pub fn label(method: &Method, url: &str) -> String {
+    let url = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    if url.is_empty() {
+        return format!("{} (new)", method);
+    }
+    let mut short: String = url.chars().take(MAX_LABEL_URL).collect();
+    if url.chars().count() > MAX_LABEL_URL {
+        short.push('…');
+    }
+    format!("{} {}", method, short)
+}
@@ -0,0 +1,123 @@
+use serde::Deserialize;
+use tui::style::Color;
+
+/// The colors the panes are drawn with.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// The focused pane while browsing.
+    pub focused: Color,
+    /// The focused pane while typing into it, and prompts.
+    pub editing: Color,
+    /// Every other pane.
+    pub unfocused: Color,
+    pub success: Color,
+    pub redirect: Color,
+    pub error: Color,
+    /// Warnings in the Logs pane.
+    pub warning: Color,
+    /// Statuses that are none of the above, and progress bars.
+    pub info: Color,
+    pub key: Color,
+    pub string: Color,
+    pub number: Color,
+    /// `true`, `false` and `null`.
+    pub literal: Color,
+    pub punctuation: Color,
+    pub search_match: Color,
+    pub current_match: Color,
+    /// Text drawn over a search match.
+    pub match_text: Color,
+    /// Lines picked in visual mode.
+    pub selection: Color,
+}
+
+/// The built-in themes, picked with `theme` in `config.json` and cycled with `T`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    Solarized,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 3] = [ThemeName::Dark, ThemeName::Light, ThemeName::Solarized];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThemeName::Dark => "Dark",
+            ThemeName::Light => "Light",
+            ThemeName::Solarized => "Solarized",
+        }
+    }
+
+    pub fn next(self) -> ThemeName {
+        let i = ThemeName::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        ThemeName::ALL[(i + 1) % ThemeName::ALL.len()]
+    }
+
+    pub fn theme(&self) -> Theme {
+        match self {
+            ThemeName::Dark => Theme {
+                focused: Color::Cyan,
+                editing: Color::Yellow,
+                unfocused: Color::Reset,
+                success: Color::Green,
+                redirect: Color::Yellow,
+                error: Color::Red,
+                warning: Color::Yellow,
+                info: Color::Blue,
+                key: Color::Cyan,
+                string: Color::Green,
+                number: Color::Magenta,
+                literal: Color::Yellow,
+                punctuation: Color::Gray,
+                search_match: Color::Yellow,
+                current_match: Color::LightRed,
+                match_text: Color::Black,
+                selection: Color::DarkGray,
+            },
+            // Darker colors that stay readable on a white background
+            ThemeName::Light => Theme {
+                focused: Color::Blue,
+                editing: Color::Magenta,
+                unfocused: Color::Reset,
+                success: Color::Green,
+                redirect: Color::Rgb(175, 95, 0),
+                error: Color::Red,
+                warning: Color::Rgb(175, 95, 0),
+                info: Color::Blue,
+                key: Color::Blue,
+                string: Color::Green,
+                number: Color::Magenta,
+                literal: Color::Rgb(175, 95, 0),
+                punctuation: Color::DarkGray,
+                search_match: Color::LightYellow,
+                current_match: Color::LightRed,
+                match_text: Color::Black,
+                selection: Color::Gray,
+            },
+            // From Ethan Schoonover's Solarized palette
+            ThemeName::Solarized => Theme {
+                focused: Color::Rgb(38, 139, 210),
+                editing: Color::Rgb(181, 137, 0),
+                unfocused: Color::Rgb(131, 148, 150),
+                success: Color::Rgb(133, 153, 0),
+                redirect: Color::Rgb(181, 137, 0),
+                error: Color::Rgb(220, 50, 47),
+                warning: Color::Rgb(181, 137, 0),
+                info: Color::Rgb(108, 113, 196),
+                key: Color::Rgb(38, 139, 210),
+                string: Color::Rgb(42, 161, 152),
+                number: Color::Rgb(211, 54, 130),
+                literal: Color::Rgb(203, 75, 22),
+                punctuation: Color::Rgb(88, 110, 117),
+                search_match: Color::Rgb(181, 137, 0),
+                current_match: Color::Rgb(203, 75, 22),
+                match_text: Color::Rgb(0, 43, 54),
+                selection: Color::Rgb(7, 54, 66),
+            },
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::error::AppError;
+use crate::settings::Settings;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Spaces out requests sent together, e.g. those of a run or benchmark, so
+/// none goes sooner after the one before than its `rate_limit` allows.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    /// When the next request may go, once one has.
+    next: Mutex<Option<Instant>>,
+}
+
+/// The least time between requests with `settings`, from `rate_limit`
+/// given in requests a second.
+pub fn interval(settings: &Settings) -> Result<Option<Duration>, AppError> {
+    match settings.value("rate_limit").trim() {
+        "" => Ok(None),
+        rate => rate
+            .parse::<f64>()
+            .ok()
+            .filter(|rate| *rate > 0.0)
+            .and_then(|rate| Duration::try_from_secs_f64(1.0 / rate).ok())
+            .map(Some)
+            .ok_or_else(|| AppError::Parse(format!("rate_limit '{}' as requests a second", rate))),
+    }
+}
+
+impl Throttle {
+    /// Waits until a request with `settings` may go, keeping its place
+    /// ahead of those that wait after it.
+    pub async fn wait(&self, settings: &Settings) -> Result<(), AppError> {
+        let Some(interval) = interval(settings)? else {
+            return Ok(());
+        };
+        let at = {
+            let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            let at = next.map_or_else(Instant::now, |next| next.max(Instant::now()));
+            *next = Some(at + interval);
+            at
+        };
+        tokio::time::sleep_until(at).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,1772 @@
+use crate::app::{App, InputMode};
+use crate::auth::Auth;
+use crate::bench::{self, Bench};
+use crate::body::BodyMode;
+use crate::collections::{Collections, Row};
+use crate::editor::Editor;
+use crate::focus::Focus;
+use crate::highlight;
+use crate::keymap::{self, Action, Scope};
+use crate::kv::{self, KeyValueTable};
+use crate::layout::Preset;
+use crate::monitor::Monitor;
+use crate::prompt::{Prompt, PromptAction};
+use crate::response::ResponseData;
+use crate::runner::{Outcome, Run};
+use crate::search::Search;
+use crate::settings;
+use crate::sse::EventStream;
+use crate::theme::Theme;
+use chrono::{DateTime, Local, Utc};
+use reqwest::StatusCode;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    text::{Span, Spans},
+    widgets::{
+        Axis, BarChart, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType,
+        List, ListItem, ListState, Paragraph, Row as TableRow, Table, Tabs, Wrap,
+    },
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Most header lines shown before the metadata section stops growing.
+const MAX_METADATA_LINES: usize = 12;
+
+/// Widest a column of the JSON table gets; longer values are cut off.
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Frames of the spinner shown while a request runs, a frame every
+/// `SPINNER_INTERVAL` milliseconds.
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_INTERVAL: u128 = 100;
+
+pub fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Percentage(5),
+                Constraint::Percentage(90),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let titles: Vec<Spans> = app
+        .tab_labels()
+        .into_iter()
+        .enumerate()
+        .map(|(i, label)| Spans::from(format!("{}: {}", i + 1, label)))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(app.current_tab)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_widget(tabs, chunks[0]);
+
+    // Top two inner blocks
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(10), Constraint::Percentage(90)].as_ref())
+        .split(chunks[1]);
+
+    // Top left inner block with green background
+    let input = Paragraph::new(app.method.as_str())
+        .style(get_style(app, Focus::Method))
+        .block(pane_block(app, Focus::Method).title("Method"));
+    f.render_widget(input, top_chunks[0]);
+
+    // Top right inner block with styled title aligned to the right
+    let mut url_title = match &app.environments.active {
+        Some(name) => format!("URL (env: {}, Ctrl+E to switch)", name),
+        None => String::from("URL"),
+    };
+    if app.settings.over(&app.inherited_settings()).insecure() {
+        url_title += " [INSECURE: accepting invalid certificates]";
+    }
+    let scripts: Vec<&str> = [
+        (&app.pre_request, "pre-request (P)"),
+        (&app.post_response, "post-response (O)"),
+    ]
+    .into_iter()
+    .filter(|(script, _)| !script.trim().is_empty())
+    .map(|(_, name)| name)
+    .collect();
+    if !scripts.is_empty() {
+        url_title += &format!(" [scripts: {}]", scripts.join(", "));
+    }
+    if !app.requires.is_empty() {
+        url_title += &format!(" [after: {}]", app.requires.join(", "));
+    }
+    // While editing, the URL scrolls sideways to keep the cursor in the box
+    let cursor_column = app.url.get(..app.url_cursor).unwrap_or(&app.url).width() as u16;
+    let url_scroll = match app.input_mode {
+        InputMode::Editing => cursor_column.saturating_sub(top_chunks[1].width.saturating_sub(3)),
+        _ => 0,
+    };
+    let input = Paragraph::new(app.url.as_ref())
+        .style(get_style(app, Focus::Url))
+        .scroll((0, url_scroll))
+        .block(pane_block(app, Focus::Url).title(url_title));
+    f.render_widget(input, top_chunks[1]);
+    match app.input_mode {
+        InputMode::Normal
+        | InputMode::EditingBody
+        | InputMode::EditingVariables
+        | InputMode::EditingTable
+        | InputMode::Search
+        | InputMode::Prompt
+        | InputMode::History
+        | InputMode::Settings
+        | InputMode::Cookies
+        | InputMode::Assertions
+        | InputMode::Runner
+        | InputMode::Snippets
+        | InputMode::Monitor
+        | InputMode::Bench
+        | InputMode::Trend
+        | InputMode::Visual
+        | InputMode::Tree
+        | InputMode::JsonTable
+        | InputMode::Help
+        | InputMode::Palette
+        | InputMode::Finder =>
+            // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
+            {}
+
+        InputMode::Editing => {
+            // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
+            f.set_cursor(
+                // Put the cursor at its column of the text, inside the border
+                top_chunks[1].x + cursor_column - url_scroll + 1,
+                // Move one line down, from the border to the input line
+                top_chunks[1].y + 1,
+            )
+        }
+    }
+
+    let panes = panes(app.config.layout, chunks[2]);
+    let mut state = ListState::default();
+    state.select(Some(app.collections.selected));
+    let sidebar = List::new(render_collections(&app.collections))
+        .style(get_style(app, Focus::Collections))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(pane_block(app, Focus::Collections).title("Collections"));
+    f.render_stateful_widget(sidebar, panes.sidebar, &mut state);
+
+    // Request editors stacked down the side, or in a row above the response
+    let editor_direction = match app.config.layout {
+        Preset::Stacked => Direction::Horizontal,
+        _ => Direction::Vertical,
+    };
+    let editor_chunks = Layout::default()
+        .direction(editor_direction)
+        .constraints(
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(40),
+            ]
+            .as_ref(),
+        )
+        .split(panes.editors);
+
+    let body_title = format!("Body: {} (t to change)", app.body_mode.name());
+    let mut tables = vec![
+        (Focus::Params, "Params", &app.params, editor_chunks[0]),
+        (Focus::Headers, "Headers", &app.headers, editor_chunks[1]),
+    ];
+    if app.body_mode.uses_fields() {
+        tables.push((
+            Focus::Body,
+            body_title.as_str(),
+            &app.fields,
+            editor_chunks[3],
+        ));
+    }
+    for (window, title, table, area) in tables {
+        let rows = List::new(render_key_values(table))
+            .style(get_style(app, window))
+            .block(pane_block(app, window).title(title));
+        f.render_widget(rows, area);
+        if let InputMode::EditingTable = app.input_mode {
+            if app.focus == window {
+                let offset = key_value_cursor_offset(table);
+                f.set_cursor(
+                    area.x + offset as u16 + 1,
+                    area.y + table.selected as u16 + 1,
+                )
+            }
+        }
+    }
+
+    let response_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(90), Constraint::Percentage(10)].as_ref())
+        .split(panes.response);
+
+    // Bottom left block with all default borders, above the variables in GraphQL mode
+    let (body_area, variables_area) = if app.body_mode == BodyMode::GraphQl {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(editor_chunks[3]);
+        (chunks[0], chunks[1])
+    } else {
+        (editor_chunks[3], Rect::default())
+    };
+    if !app.body_mode.uses_fields() {
+        let block = pane_block(app, Focus::Body)
+            .style(get_style(app, Focus::Body))
+            .title(body_title.as_str());
+        let editing = matches!(app.input_mode, InputMode::EditingBody);
+        render_editor(
+            f,
+            &app.body,
+            &mut app.body_editor,
+            block,
+            body_area,
+            editing,
+        );
+    }
+    if app.body_mode == BodyMode::GraphQl {
+        let block = pane_block(app, Focus::Body)
+            .style(get_style(app, Focus::Body))
+            .title("Variables (v to edit)");
+        let editing = matches!(app.input_mode, InputMode::EditingVariables);
+        render_editor(
+            f,
+            &app.variables,
+            &mut app.variables_editor,
+            block,
+            variables_area,
+            editing,
+        );
+    }
+
+    let mut state = ListState::default();
+    state.select(Some(app.auth.selected));
+    let auth = List::new(render_auth(&app.auth))
+        .style(get_style(app, Focus::Auth))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            pane_block(app, Focus::Auth)
+                .title(format!("Auth: {} (t to change)", app.auth.kind.name())),
+        );
+    f.render_stateful_widget(auth, editor_chunks[2], &mut state);
+
+    match app.download.clone() {
+        Some(download) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+                .split(response_chunks[0]);
+            render_response(f, app, chunks[0]);
+            let progress = download.progress();
+            let gauge = Gauge::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!("Downloading to {}", download.path.display())),
+                )
+                .gauge_style(Style::default().fg(app.theme().success))
+                .ratio(progress.ratio())
+                .label(progress.label());
+            f.render_widget(gauge, chunks[1]);
+        }
+        None => render_response(f, app, response_chunks[0]),
+    }
+
+    app.logs_height = response_chunks[1].height.saturating_sub(2) as usize;
+    app.scroll_logs(0);
+    let theme = app.theme();
+    let logs: Vec<ListItem> = app
+        .logs
+        .iter()
+        .skip(app.logs_scroll)
+        .take(app.logs_height)
+        .map(|entry| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    entry.timestamp.format("%H:%M:%S ").to_string(),
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
+                Span::styled(
+                    format!("{:<5} ", entry.level.name()),
+                    Style::default().fg(entry.level.color(&theme)),
+                ),
+                Span::raw(entry.message.as_str()),
+            ]))
+        })
+        .collect();
+
+    let logs = List::new(logs).style(get_style(app, Focus::Logs)).block(
+        pane_block(app, Focus::Logs).title(if app.in_flight.is_some() {
+            "Logs (request in progress...)"
+        } else {
+            "Logs"
+        }),
+    );
+    f.render_widget(logs, response_chunks[1]);
+
+    app.pane_areas = vec![
+        (Focus::Method, top_chunks[0]),
+        (Focus::Url, top_chunks[1]),
+        (Focus::Params, editor_chunks[0]),
+        (Focus::Headers, editor_chunks[1]),
+        (Focus::Auth, editor_chunks[2]),
+        (Focus::Body, editor_chunks[3]),
+        (Focus::Response, response_chunks[0]),
+        (Focus::Logs, response_chunks[1]),
+        (Focus::Collections, panes.sidebar),
+    ];
+
+    render_status_bar(f, app, chunks[3]);
+
+    if matches!(app.input_mode, InputMode::Editing) && !app.url_completions.is_empty() {
+        render_url_completions(f, app, top_chunks[1]);
+    }
+
+    if let InputMode::History = app.input_mode {
+        render_history(f, app);
+    }
+
+    // Popups stay drawn under the prompts opened from them
+    if matches!(app.input_mode, InputMode::Cookies)
+        || app
+            .prompt
+            .as_ref()
+            .is_some_and(|p| p.action == PromptAction::CookieValue)
+    {
+        render_cookies(f, app);
+    }
+
+    if matches!(app.input_mode, InputMode::Assertions)
+        || app.prompt.as_ref().is_some_and(|p| {
+            matches!(
+                p.action,
+                PromptAction::AddAssertion | PromptAction::EditAssertion
+            )
+        })
+    {
+        render_assertions(f, app);
+    }
+
+    if let (InputMode::Runner, Some(run)) = (&app.input_mode, &app.run) {
+        render_runner(f, app, run);
+    }
+
+    if let InputMode::Snippets = app.input_mode {
+        render_snippets(f, app);
+    }
+
+    if let (InputMode::Monitor, Some(monitor)) = (&app.input_mode, &app.monitor) {
+        render_monitor(f, app, monitor);
+    }
+
+    if let (InputMode::Bench, Some(bench)) = (&app.input_mode, &app.bench) {
+        render_bench(f, app, bench);
+    }
+
+    if let InputMode::Trend = app.input_mode {
+        render_trend(f, app);
+    }
+
+    if matches!(app.input_mode, InputMode::Settings)
+        || app
+            .prompt
+            .as_ref()
+            .is_some_and(|p| p.action == PromptAction::SettingField)
+    {
+        render_settings(f, app);
+    }
+
+    if let InputMode::Help = app.input_mode {
+        render_help(f, app);
+    }
+
+    if let InputMode::Palette = app.input_mode {
+        render_palette(f, app);
+    }
+
+    if let InputMode::Finder = app.input_mode {
+        render_finder(f, app);
+    }
+
+    if let Some(prompt) = &app.prompt {
+        render_prompt(f, prompt, &app.theme());
+    }
+}
+
+/// Where the main panes go for a layout preset. Those it hides are empty.
+struct Panes {
+    sidebar: Rect,
+    editors: Rect,
+    /// The response, with the logs beneath it.
+    response: Rect,
+}
+
+fn panes(preset: Preset, area: Rect) -> Panes {
+    let split = |direction, constraints: &[Constraint], area| {
+        Layout::default()
+            .direction(direction)
+            .constraints(constraints)
+            .split(area)
+    };
+    match preset {
+        Preset::SidebarLeft => {
+            let chunks = split(
+                Direction::Horizontal,
+                &[
+                    Constraint::Percentage(15),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(55),
+                ],
+                area,
+            );
+            Panes {
+                sidebar: chunks[0],
+                editors: chunks[1],
+                response: chunks[2],
+            }
+        }
+        Preset::SidebarRight => {
+            let chunks = split(
+                Direction::Horizontal,
+                &[
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(55),
+                    Constraint::Percentage(15),
+                ],
+                area,
+            );
+            Panes {
+                sidebar: chunks[2],
+                editors: chunks[0],
+                response: chunks[1],
+            }
+        }
+        Preset::Stacked => {
+            let chunks = split(
+                Direction::Horizontal,
+                &[Constraint::Percentage(15), Constraint::Percentage(85)],
+                area,
+            );
+            let main = split(
+                Direction::Vertical,
+                &[Constraint::Percentage(40), Constraint::Percentage(60)],
+                chunks[1],
+            );
+            Panes {
+                sidebar: chunks[0],
+                editors: main[0],
+                response: main[1],
+            }
+        }
+        Preset::ResponseMaximized => Panes {
+            sidebar: Rect::default(),
+            editors: Rect::default(),
+            response: area,
+        },
+    }
+}
+
+/// A `width` percent wide, `height` rows tall rectangle in the middle of `area`.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width * width / 100;
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// One line per field of the selected auth type, as `Label: value`.
+fn render_auth(auth: &Auth) -> Vec<ListItem<'_>> {
+    auth.kind
+        .fields()
+        .iter()
+        .map(|field| ListItem::new(format!("{}: {}", field.label, auth.display(field))))
+        .collect()
+}
+
+/// Sidebar lines: each collection name followed by its requests, indented.
+fn render_collections(collections: &Collections) -> Vec<ListItem<'_>> {
+    collections
+        .rows()
+        .into_iter()
+        .map(|row| match row {
+            Row::Collection(c) => ListItem::new(Span::styled(
+                collections.collections[c].name.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Row::Request(c, r) => {
+                let saved = &collections.collections[c].requests[r];
+                let mark = if collections.is_marked(row) { '*' } else { ' ' };
+                ListItem::new(format!("{} {} {}", mark, saved.method, saved.name))
+            }
+        })
+        .collect()
+}
+
+/// History URLs matching the one being typed, dropped down under `url_area`.
+fn render_url_completions<B: Backend>(f: &mut Frame<B>, app: &App, url_area: Rect) {
+    let size = f.size();
+    let y = url_area.y + url_area.height;
+    let height = (app.url_completions.len() as u16 + 2).min(size.height.saturating_sub(y));
+    let area = Rect::new(url_area.x, y, url_area.width, height);
+    let items: Vec<ListItem> = app
+        .url_completions
+        .iter()
+        .map(|url| ListItem::new(url.as_str()))
+        .collect();
+    let title = if app.showing_recent_urls {
+        "Recent URLs (Enter to use, Esc to close)"
+    } else {
+        "History (Up/Down, Tab to complete)"
+    };
+    let list = List::new(items)
+        .style(Style::default().fg(app.theme().editing))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    let mut state = ListState::default();
+    state.select(app.completion_selected);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// A multi-line text soft wrapped to fit inside `block`, with the cursor
+/// shown while `editing`.
+fn render_editor<B: Backend>(
+    f: &mut Frame<B>,
+    text: &str,
+    editor: &mut Editor,
+    block: Block,
+    area: Rect,
+    editing: bool,
+) {
+    let inner = block.inner(area);
+    let (rows, (row, column)) = editor.layout(text, inner.width as usize, inner.height as usize);
+    let lines: Vec<Spans> = rows
+        .iter()
+        .map(|row| Spans::from(&text[row.start..row.end]))
+        .collect();
+    f.render_widget(Paragraph::new(lines).block(block), area);
+    if editing {
+        f.set_cursor(inner.x + column as u16, inner.y + row as u16);
+    }
+}
+
+/// The mode, focused pane, environment and last status along the bottom,
+/// then the keys most likely to be wanted next. The `:` command line
+/// replaces it while open, as in vim.
+fn render_status_bar<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    if let Some(prompt) = app
+        .prompt
+        .as_ref()
+        .filter(|p| p.action == PromptAction::Command)
+    {
+        let line = format!(":{}", prompt.input);
+        f.render_widget(Paragraph::new(line.as_str()), area);
+        f.set_cursor(area.x + line.width() as u16, area.y);
+        return;
+    }
+    let theme = app.theme();
+    let mode = app.input_mode.name();
+    let mode_style = Style::default()
+        .fg(match app.input_mode {
+            InputMode::Normal => theme.focused,
+            _ => theme.editing,
+        })
+        .add_modifier(Modifier::REVERSED | Modifier::BOLD);
+    let mut spans = vec![
+        Span::styled(format!(" {} ", mode.to_uppercase()), mode_style),
+        Span::raw(format!(" {}", app.focus.name())),
+    ];
+    if let Some(name) = &app.environments.active {
+        spans.push(Span::raw(format!(" | env: {}", name)));
+    }
+    if let Some(response) = &app.response {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            response.status.to_string(),
+            status_style(response.status, &theme),
+        ));
+    }
+    let hints = match app.input_mode {
+        // The ways to find every key, then the focused pane's own
+        InputMode::Normal => {
+            let mut hints = vec![String::from("? help"), String::from("Ctrl+P commands")];
+            for binding in keymap::BINDINGS {
+                if binding.scope != Scope::Anywhere && hints.len() < 5 && binding.scope.applies(app)
+                {
+                    hints.push(format!(
+                        "{} {}",
+                        keymap::keys_label(binding),
+                        binding.action.description()
+                    ));
+                }
+            }
+            hints.join("  ")
+        }
+        _ => app.input_mode.keys().map_or(String::new(), |keys| {
+            let hints: Vec<String> = keys
+                .bindings
+                .iter()
+                .map(|binding| {
+                    format!(
+                        "{} {}",
+                        keymap::mode_keys_label(binding),
+                        binding.description
+                    )
+                })
+                .collect();
+            hints.join("  ")
+        }),
+    };
+    spans.push(Span::styled(
+        format!("  {}", hints),
+        Style::default().add_modifier(Modifier::DIM),
+    ));
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
+}
+
+/// The command palette: the query above the actions matching it.
+fn render_palette<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    let area = centered_rect(60, size.height * 6 / 10, size);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(area);
+    let input = Paragraph::new(app.palette.query.as_str())
+        .style(Style::default().fg(app.theme().editing))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Commands (Enter to run, Esc to close)"),
+        );
+    let width = chunks[1].width.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = app
+        .palette
+        .matches()
+        .into_iter()
+        .map(|(action, keys)| {
+            let description = action.description();
+            let gap = width.saturating_sub(description.width() + keys.width());
+            ListItem::new(Spans::from(vec![
+                Span::raw(description),
+                Span::raw(" ".repeat(gap.max(1))),
+                Span::styled(keys, Style::default().add_modifier(Modifier::DIM)),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL));
+    let mut state = ListState::default();
+    state.select(Some(app.palette.selected));
+    f.render_widget(Clear, area);
+    f.render_widget(input, chunks[0]);
+    f.render_stateful_widget(list, chunks[1], &mut state);
+    f.set_cursor(
+        chunks[0].x + app.palette.query.width() as u16 + 1,
+        chunks[0].y + 1,
+    );
+}
+
+fn render_finder<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    let area = centered_rect(70, size.height * 6 / 10, size);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(area);
+    let input = Paragraph::new(app.finder.query.as_str())
+        .style(Style::default().fg(app.theme().editing))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Find a request (Enter to load, Esc to close)"),
+        );
+    let matches = app.finder_matches();
+    let mut state = ListState::default();
+    if !matches.is_empty() {
+        state.select(Some(app.finder.selected));
+    }
+    let items: Vec<ListItem> = if matches.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "No saved or past requests match",
+            Style::default().add_modifier(Modifier::DIM),
+        ))]
+    } else {
+        matches
+            .into_iter()
+            .map(|(_, label)| ListItem::new(label))
+            .collect()
+    };
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(Clear, area);
+    f.render_widget(input, chunks[0]);
+    f.render_stateful_widget(list, chunks[1], &mut state);
+    f.set_cursor(
+        chunks[0].x + app.finder.query.width() as u16 + 1,
+        chunks[0].y + 1,
+    );
+}
+
+/// Popup listing Normal mode's bindings by where they apply, then the other modes.
+fn render_help<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let size = f.size();
+    let area = centered_rect(70, size.height * 8 / 10, size);
+    let heading = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = Vec::new();
+    for scope in Scope::ALL {
+        // Keys for the same action are listed together
+        let mut actions: Vec<(Action, Vec<String>)> = Vec::new();
+        for binding in keymap::BINDINGS.iter().filter(|b| b.scope == scope) {
+            let keys = keymap::keys_label(binding);
+            match actions
+                .iter_mut()
+                .find(|(action, _)| *action == binding.action)
+            {
+                Some((_, labels)) => labels.push(keys),
+                None => actions.push((binding.action, vec![keys])),
+            }
+        }
+        lines.push(Spans::from(Span::styled(scope.name(), heading)));
+        for (action, keys) in actions {
+            lines.push(Spans::from(format!(
+                "  {:<18}{}",
+                keys.join(", "),
+                action.description()
+            )));
+        }
+        lines.push(Spans::default());
+    }
+    for mode in keymap::MODES {
+        // Ctrl+N and Down both picking the next row share a line
+        let mut actions: Vec<(&str, Vec<String>)> = Vec::new();
+        for binding in mode.bindings {
+            let keys = keymap::mode_keys_label(binding);
+            match actions
+                .iter_mut()
+                .find(|(description, _)| *description == binding.description)
+            {
+                Some((_, labels)) => labels.push(keys),
+                None => actions.push((binding.description, vec![keys])),
+            }
+        }
+        lines.push(Spans::from(Span::styled(mode.name, heading)));
+        for (description, keys) in actions {
+            lines.push(Spans::from(format!(
+                "  {:<18}{}",
+                keys.join(", "),
+                description
+            )));
+        }
+        if let Some(other_keys) = mode.other_keys {
+            lines.push(Spans::from(format!("  {:<18}{}", "Other keys", other_keys)));
+        }
+        lines.push(Spans::default());
+    }
+    let max = lines
+        .len()
+        .saturating_sub(area.height.saturating_sub(2) as usize);
+    app.help_scroll = app.help_scroll.min(max);
+    let help = Paragraph::new(lines)
+        .scroll((app.help_scroll as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Keys (j/k to scroll, Esc to close)"),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
+
+/// Popup listing past requests, newest first.
+fn render_history<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let theme = app.theme();
+    let size = f.size();
+    let area = centered_rect(80, size.height * 6 / 10, size);
+    let items: Vec<ListItem> = app
+        .history
+        .entries
+        .iter()
+        .rev()
+        .map(|entry| {
+            let style = entry
+                .status
+                .and_then(|s| StatusCode::from_u16(s).ok())
+                .map_or(Style::default().fg(theme.error), |status| {
+                    status_style(status, &theme)
+                });
+            ListItem::new(Spans::from(Span::styled(entry.summary(), style)))
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("History (Enter to load, Esc to close)"),
+        );
+    let mut state = ListState::default();
+    state.select(Some(app.history_selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Popup listing the cookie jar, sorted by domain.
+fn render_cookies<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    let area = centered_rect(80, size.height * 6 / 10, size);
+    let items: Vec<ListItem> = app
+        .cookies
+        .all()
+        .iter()
+        .map(|cookie| ListItem::new(cookie.summary()))
+        .collect();
+    let sending = if app.settings.over(&app.inherited_settings()).sends_cookies() {
+        "on"
+    } else {
+        "off"
+    };
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Cookies, sending {} (i to edit, d to delete, t to toggle sending, Esc to close)",
+            sending
+        )));
+    let mut state = ListState::default();
+    state.select(Some(app.cookies_selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Popup showing the request as code, with a tab per language.
+fn render_snippets<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    let area = centered_rect(80, size.height * 8 / 10, size);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Export as code (h/l to switch language, j/k to scroll, y to copy, Esc to close)");
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+        .split(inner);
+    let titles = app
+        .snippets
+        .iter()
+        .map(|(language, _)| Spans::from(language.name()))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(app.snippet_selected)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_widget(tabs, chunks[0]);
+    if let Some((_, code)) = app.snippets.get(app.snippet_selected) {
+        let code = Paragraph::new(code.as_str()).scroll((app.snippet_scroll, 0));
+        f.render_widget(code, chunks[1]);
+    }
+}
+
+/// Popup following a collection run, a line per request, with the summary
+/// in its title.
+fn render_runner<B: Backend>(f: &mut Frame<B>, app: &App, run: &Run) {
+    let theme = app.theme();
+    let size = f.size();
+    let area = centered_rect(80, size.height * 6 / 10, size);
+    let items: Vec<ListItem> = run
+        .steps
+        .iter()
+        .map(|step| {
+            let (label, result, style) = match &step.outcome {
+                Outcome::Pending => (
+                    "    ",
+                    String::from("..."),
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
+                Outcome::Done {
+                    status, elapsed, ..
+                } => (
+                    if step.passed() { "PASS" } else { "FAIL" },
+                    format!("{} {:>6} ms", status.as_u16(), elapsed.as_millis()),
+                    if step.passed() {
+                        status_style(*status, &theme)
+                    } else {
+                        Style::default().fg(theme.error)
+                    },
+                ),
+                Outcome::Failed(e) => ("FAIL", e.clone(), Style::default().fg(theme.error)),
+            };
+            let mut lines = vec![Spans::from(vec![
+                Span::styled(format!("{} {:<13}", label, result), style),
+                Span::raw(match step.row {
+                    Some(row) => {
+                        format!(" {} {}  {} (row {})", step.method, step.url, step.name, row)
+                    }
+                    None => format!(" {} {}  {}", step.method, step.url, step.name),
+                }),
+            ])];
+            // The assertions that didn't hold go under their request
+            if let Outcome::Done { failures, .. } = &step.outcome {
+                lines.extend(failures.iter().map(|failure| {
+                    Spans::from(Span::styled(
+                        format!("     {}", failure),
+                        Style::default().fg(theme.error),
+                    ))
+                }));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+    let keys = if run.is_running() {
+        "x to stop, Enter to load, Esc to close"
+    } else {
+        "Enter to load, Esc to close"
+    };
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "{} ({})",
+            run.summary(),
+            keys
+        )));
+    let mut state = ListState::default();
+    state.select(Some(run.selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Popup following a monitored request, a line per send with the newest
+/// on top, and the summary in its title.
+fn render_monitor<B: Backend>(f: &mut Frame<B>, app: &App, monitor: &Monitor) {
+    let theme = app.theme();
+    let size = f.size();
+    let area = centered_rect(80, size.height * 6 / 10, size);
+    let items: Vec<ListItem> = monitor
+        .samples
+        .iter()
+        .map(|sample| {
+            let passed = sample.outcome.passed(monitor.asserted);
+            let (result, style) = match &sample.outcome {
+                Outcome::Done {
+                    status, elapsed, ..
+                } => (
+                    format!("{} {:>6} ms", status.as_u16(), elapsed.as_millis()),
+                    if passed {
+                        status_style(*status, &theme)
+                    } else {
+                        Style::default().fg(theme.error)
+                    },
+                ),
+                Outcome::Failed(e) => (e.clone(), Style::default().fg(theme.error)),
+                Outcome::Pending => (String::from("..."), Style::default()),
+            };
+            let mut lines = vec![Spans::from(vec![
+                Span::raw(format!("{} ", sample.at.format("%H:%M:%S"))),
+                Span::styled(
+                    format!("{} {}", if passed { "PASS" } else { "FAIL" }, result),
+                    style,
+                ),
+            ])];
+            if let Outcome::Done { failures, .. } = &sample.outcome {
+                lines.extend(failures.iter().map(|failure| {
+                    Spans::from(Span::styled(
+                        format!("              {}", failure),
+                        Style::default().fg(theme.error),
+                    ))
+                }));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+    let keys = if monitor.is_running() {
+        "x to stop, Esc to close"
+    } else {
+        "stopped, Esc to close"
+    };
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "{} ({})",
+            monitor.summary(),
+            keys
+        )));
+    let mut state = ListState::default();
+    state.select(Some(monitor.selected));
+    f.render_widget(Clear, area);
+    let latencies = monitor.latencies();
+    if latencies.is_empty() {
+        f.render_stateful_widget(list, area, &mut state);
+        return;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(9)].as_ref())
+        .split(area);
+    f.render_stateful_widget(list, chunks[0], &mut state);
+    render_histogram(f, app, chunks[1], &latencies);
+}
+
+/// Bar chart of how many of `latencies` fell in each range, as wide as
+/// `area` allows.
+fn render_histogram<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, latencies: &[Duration]) {
+    const BAR_WIDTH: u16 = 5;
+    let buckets = area.width.saturating_sub(2) / (BAR_WIDTH + 1);
+    let histogram = bench::histogram(latencies, usize::from(buckets));
+    let bars: Vec<(&str, u64)> = histogram
+        .iter()
+        .map(|(label, count)| (label.as_str(), *count))
+        .collect();
+    let chart = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title("Latency (ms)"))
+        .data(&bars)
+        .bar_width(BAR_WIDTH)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(app.theme().info));
+    f.render_widget(chart, area);
+}
+
+/// Popup following a benchmark, with a gauge of how many requests are
+/// done while it runs and what it found once it's finished.
+fn render_bench<B: Backend>(f: &mut Frame<B>, app: &App, bench: &Bench) {
+    let theme = app.theme();
+    let size = f.size();
+    let title = if bench.is_running() {
+        format!("{} (x to stop, Esc to close)", bench.summary())
+    } else {
+        format!("{} (Esc to close)", bench.label)
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let (stats, text) = match &bench.result {
+        Some(Ok(stats)) => (
+            Some(stats),
+            stats.lines().into_iter().map(Spans::from).collect(),
+        ),
+        Some(Err(e)) => (
+            None,
+            vec![Spans::from(Span::styled(
+                e.clone(),
+                Style::default().fg(theme.error),
+            ))],
+        ),
+        None if bench.is_running() => {
+            let area = centered_rect(80, 3, size);
+            let done = bench.done.load(Ordering::Relaxed);
+            let gauge = Gauge::default()
+                .block(block)
+                .gauge_style(Style::default().fg(theme.success))
+                .ratio(done as f64 / bench.requests.max(1) as f64)
+                .label(format!("{} of {}", done, bench.requests));
+            f.render_widget(Clear, area);
+            f.render_widget(gauge, area);
+            return;
+        }
+        None => (None, vec![Spans::from("Stopped")]),
+    };
+    let width = (size.width * 80 / 100).saturating_sub(2).max(1) as usize;
+    let lines = text
+        .iter()
+        .map(|line| line.width().max(1).div_ceil(width) as u16)
+        .sum::<u16>()
+        + 2;
+    // The latencies are charted under what was found, if any came back
+    let latencies = stats.map_or(&[][..], |stats| stats.latencies.as_slice());
+    let height = if latencies.is_empty() {
+        lines
+    } else {
+        size.height * 6 / 10
+    };
+    let area = centered_rect(80, height.max(lines), size);
+    f.render_widget(Clear, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(lines), Constraint::Min(0)].as_ref())
+        .split(area);
+    f.render_widget(
+        Paragraph::new(text).block(block).wrap(Wrap { trim: false }),
+        chunks[0],
+    );
+    if !latencies.is_empty() {
+        render_histogram(f, app, chunks[1], latencies);
+    }
+}
+
+/// Popup charting how long the request took each time it was sent, from
+/// the history, oldest on the left.
+fn render_trend<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let theme = app.theme();
+    let size = f.size();
+    let area = centered_rect(80, size.height * 6 / 10, size);
+    let times = app.trend();
+    let (Some((first, _)), Some((last, _))) = (times.first(), times.last()) else {
+        return;
+    };
+    let points: Vec<(f64, f64)> = times
+        .iter()
+        .map(|(at, ms)| ((*at - *first).num_seconds() as f64, *ms as f64))
+        .collect();
+    let slowest = times.iter().map(|(_, ms)| *ms).max().unwrap_or(0).max(1) as f64;
+    let span = points.last().map_or(0.0, |(x, _)| *x).max(1.0);
+    let date = |at: &DateTime<Utc>| {
+        at.with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    };
+    let datasets = vec![Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(theme.info))
+        .data(&points)];
+    let chart = Chart::new(datasets)
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Response times of {} {}, {} sends (Esc to close)",
+            app.method,
+            app.url,
+            times.len()
+        )))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, span])
+                .labels(vec![Span::raw(date(first)), Span::raw(date(last))]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("ms")
+                .bounds([0.0, slowest])
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.0}", slowest / 2.0)),
+                    Span::raw(format!("{:.0}", slowest)),
+                ]),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(chart, area);
+}
+
+/// Popup listing the request's assertions, each marked with whether it
+/// holds for the response shown.
+fn render_assertions<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let theme = app.theme();
+    let size = f.size();
+    let area = centered_rect(80, size.height * 6 / 10, size);
+    let results = app.assertion_results();
+    let items: Vec<ListItem> = app
+        .assertions
+        .iter()
+        .enumerate()
+        .map(
+            |(i, text)| match results.as_ref().map(|results| &results[i]) {
+                None => ListItem::new(format!("     {}", text)),
+                Some(Ok(())) => ListItem::new(Spans::from(vec![
+                    Span::styled("PASS ", Style::default().fg(theme.success)),
+                    Span::raw(text.clone()),
+                ])),
+                Some(Err(e)) => ListItem::new(vec![
+                    Spans::from(vec![
+                        Span::styled("FAIL ", Style::default().fg(theme.error)),
+                        Span::raw(text.clone()),
+                    ]),
+                    Spans::from(Span::styled(
+                        format!("     {}", e),
+                        Style::default().fg(theme.error),
+                    )),
+                ]),
+            },
+        )
+        .collect();
+    let passing = match &results {
+        Some(results) => format!(
+            ", {} of {} holding",
+            results.iter().filter(|result| result.is_ok()).count(),
+            results.len()
+        ),
+        None => String::new(),
+    };
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Assertions{} (a to add, i to edit, d to delete, u to update snapshots, Esc to close)",
+            passing
+        )));
+    let mut state = ListState::default();
+    state.select(Some(app.assertions_selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Popup listing the connection settings. Values the request doesn't set
+/// itself are dimmed and show what it inherits.
+fn render_settings<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let area = centered_rect(70, settings::FIELDS.len() as u16 + 2, f.size());
+    let inherited = app.inherited_settings();
+    let items: Vec<ListItem> = settings::FIELDS
+        .iter()
+        .map(|field| {
+            let (source, style) = if app.settings.is_set(field.name) {
+                (&app.settings, Style::default())
+            } else {
+                (&inherited, Style::default().add_modifier(Modifier::DIM))
+            };
+            let mut value = String::from(source.value(field.name));
+            if field.secret {
+                value = "*".repeat(value.chars().count());
+            }
+            ListItem::new(Spans::from(Span::styled(
+                format!("{}: {}", field.label, value),
+                style,
+            )))
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Settings (i to edit, d to inherit, Esc to close)"),
+        );
+    let mut state = ListState::default();
+    state.select(Some(app.settings_selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_prompt<B: Backend>(f: &mut Frame<B>, prompt: &Prompt, theme: &Theme) {
+    // The command line is drawn in place of the status bar
+    if prompt.action == PromptAction::Command {
+        return;
+    }
+    let area = centered_rect(60, 3, f.size());
+    let text = if prompt.masked {
+        "*".repeat(prompt.input.chars().count())
+    } else {
+        prompt.input.clone()
+    };
+    let input = Paragraph::new(text.as_str())
+        .style(Style::default().fg(theme.editing))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(prompt.label.as_str()),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(input, area);
+    f.set_cursor(area.x + prompt.input.width() as u16 + 1, area.y + 1);
+}
+
+/// The Response pane: an error, or the status/headers section above the body.
+fn render_response<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let style = get_style(app, Focus::Response);
+
+    if let Some(events) = app.events.clone().filter(|events| events.is_open()) {
+        render_events(f, app, area, &events);
+        return;
+    }
+
+    if let Some(e) = &app.error {
+        let error = Paragraph::new(e.to_string())
+            .style(Style::default().fg(app.theme().error))
+            .wrap(Wrap { trim: false })
+            .block(pane_block(app, Focus::Response).title("Response (error)"));
+        f.render_widget(error, area);
+        return;
+    }
+
+    let metadata = match &app.response {
+        Some(response) => render_metadata(response, app.show_metadata, &app.theme()),
+        None => {
+            let title = match in_flight_label(app) {
+                Some(label) => format!("Response {}", label),
+                None => String::from("Response"),
+            };
+            let empty = pane_block(app, Focus::Response).style(style).title(title);
+            f.render_widget(empty, area);
+            return;
+        }
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(metadata.len() as u16 + 2),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let hint = if app.show_metadata {
+        "Status (H to collapse)"
+    } else {
+        "Status (H to expand)"
+    };
+    let metadata = Paragraph::new(metadata).block(pane_block(app, Focus::Response).title(hint));
+    f.render_widget(metadata, chunks[0]);
+
+    // Remember the viewport so paging and scroll clamping match what is on screen
+    app.response_height = chunks[1].height.saturating_sub(2) as usize;
+    // Still shown under a prompt opened from them
+    if app.table.is_some() {
+        return render_table(f, app, chunks[1]);
+    }
+    if app.tree.is_some() {
+        return render_tree(f, app, chunks[1]);
+    }
+    app.scroll_response(0);
+    let mut title = format!("Response [{}]", app.response_position());
+    if let Some((query, _)) = app.response.as_ref().and_then(|r| r.filter.as_ref()) {
+        title += &format!(" (filtered by {}, F for all)", query);
+    } else if app.show_raw {
+        title += " (raw)";
+    }
+    if let Some(results) = app
+        .assertion_results()
+        .filter(|results| !results.is_empty())
+    {
+        let passed = results.iter().filter(|result| result.is_ok()).count();
+        let verdict = if passed == results.len() {
+            "PASS"
+        } else {
+            "FAIL"
+        };
+        title += &format!(" {} {}/{} asserts (A)", verdict, passed, results.len());
+    }
+    if let Some((first, last)) = app.visual_range() {
+        title += &format!(" -- VISUAL {} lines (y to copy) --", last - first + 1);
+    }
+    if !app.search.query.is_empty() || matches!(app.input_mode, InputMode::Search) {
+        title += &format!(" /{} ({})", app.search.query, app.search.status());
+    }
+    let syntax =
+        app.config.syntax_highlighting && app.response.as_ref().is_some_and(ResponseData::is_json);
+    // Only the lines in view are turned into spans, so huge bodies stay cheap to draw
+    let lines = match app.response_text() {
+        Some(text) => render_body(
+            text.lines(app.response_scroll, app.response_height),
+            &app.search,
+            syntax,
+            app.visual_range(),
+            &app.theme(),
+        ),
+        None => Vec::new(),
+    };
+    let mut title = vec![Span::raw(title)];
+    if let Some(response) = &app.response {
+        title.insert(0, status_badge(response.status, &app.theme()));
+        title.insert(1, Span::raw(" "));
+    }
+    // The last response stays shown while the next one is on its way
+    if let Some(label) = in_flight_label(app) {
+        title.push(Span::raw(format!(" {}", label)));
+    }
+    let body = Paragraph::new(lines)
+        .style(style)
+        .block(pane_block(app, Focus::Response).title(Spans::from(title)));
+    f.render_widget(body, chunks[1]);
+}
+
+/// The JSON response as a tree, a node per line, with the selected one's
+/// path in the title.
+fn render_tree<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let theme = app.theme();
+    let height = app.response_height;
+    let Some(tree) = app.tree.as_mut() else {
+        return;
+    };
+    tree.scroll_into_view(height);
+    let lines: Vec<Spans> = tree
+        .nodes()
+        .iter()
+        .enumerate()
+        .skip(tree.scroll)
+        .take(height)
+        .map(|(i, node)| {
+            let marker = match node.expanded {
+                Some(true) => "▾ ",
+                Some(false) => "▸ ",
+                None => "  ",
+            };
+            let color = match node.value.chars().next() {
+                _ if node.expanded.is_some() => theme.punctuation,
+                Some('{' | '[') => theme.punctuation,
+                Some('"') => theme.string,
+                Some('-' | '0'..='9') => theme.number,
+                _ => theme.literal,
+            };
+            let mut spans = vec![
+                Span::raw(format!("{}{}", "  ".repeat(node.depth), marker)),
+                Span::styled(node.label.clone(), Style::default().fg(theme.key)),
+                Span::styled(node.value.clone(), Style::default().fg(color)),
+            ];
+            if i == tree.selected {
+                for span in &mut spans {
+                    span.style = span.style.bg(theme.selection);
+                }
+            }
+            Spans::from(spans)
+        })
+        .collect();
+    let title = format!(
+        "JSON tree {} [{}/{}] (y to copy the path)",
+        tree.path(),
+        tree.selected + 1,
+        tree.nodes().len()
+    );
+    let body = Paragraph::new(lines)
+        .style(get_style(app, Focus::Response))
+        .block(pane_block(app, Focus::Response).title(title));
+    f.render_widget(body, area);
+}
+
+/// A JSON array of objects as a table, scrolled across to keep the
+/// selected column in view.
+fn render_table<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let theme = app.theme();
+    // Less the header row
+    let height = app.response_height.saturating_sub(1);
+    let Some(table) = app.table.as_mut() else {
+        return;
+    };
+    table.scroll_into_view(height);
+    let headers: Vec<String> = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| match table.sorted {
+            Some((sorted, false)) if sorted == i => format!("{} ▲", column),
+            Some((sorted, true)) if sorted == i => format!("{} ▼", column),
+            _ => column.clone(),
+        })
+        .collect();
+    let widths: Vec<u16> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            let widest = (0..table.len())
+                .map(|row| table.cell(row, i).width())
+                .fold(header.width(), usize::max);
+            widest.clamp(1, MAX_COLUMN_WIDTH) as u16
+        })
+        .collect();
+    // The first column shown, so the selected one fits
+    let room = area.width.saturating_sub(2);
+    let mut first = 0;
+    while first < table.column
+        && widths[first..=table.column]
+            .iter()
+            .map(|w| w + 1)
+            .sum::<u16>()
+            > room
+    {
+        first += 1;
+    }
+    let header_style = Style::default().fg(theme.key).add_modifier(Modifier::BOLD);
+    let header = TableRow::new(
+        headers
+            .into_iter()
+            .enumerate()
+            .skip(first)
+            .map(|(i, header)| {
+                let style = if i == table.column {
+                    header_style.add_modifier(Modifier::UNDERLINED)
+                } else {
+                    header_style
+                };
+                Cell::from(header).style(style)
+            }),
+    );
+    let rows: Vec<TableRow> = (table.scroll..table.len())
+        .take(height)
+        .map(|row| {
+            let cells = (first..table.columns.len()).map(|i| Cell::from(table.cell(row, i)));
+            let style = if row == table.selected {
+                Style::default().bg(theme.selection)
+            } else {
+                Style::default()
+            };
+            TableRow::new(cells).style(style)
+        })
+        .collect();
+    let widths: Vec<Constraint> = widths[first..]
+        .iter()
+        .map(|width| Constraint::Length(*width))
+        .collect();
+    let sorted = match table.sorted {
+        Some((column, _)) => format!(" sorted by {}", table.columns[column]),
+        None => String::new(),
+    };
+    let title = format!(
+        "JSON table [{}/{}]{} (s to sort by the column, e to export CSV)",
+        table.selected + 1,
+        table.len(),
+        sorted
+    );
+    let widget = Table::new(rows)
+        .header(header)
+        .widths(&widths)
+        .column_spacing(1)
+        .style(get_style(app, Focus::Response))
+        .block(pane_block(app, Focus::Response).title(title));
+    f.render_widget(widget, area);
+}
+
+/// The events received so far on an open event stream, one per line.
+fn render_events<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, events: &EventStream) {
+    app.response_height = area.height.saturating_sub(2) as usize;
+    if app.follow_events {
+        app.scroll_response_to_bottom();
+    } else {
+        app.scroll_response(0);
+    }
+    let lines: Vec<Spans> = events
+        .summaries(app.response_scroll, app.response_height)
+        .into_iter()
+        .map(|summary| Spans::from(Span::raw(summary)))
+        .collect();
+    let title = format!("Events [{}] (x to disconnect)", app.response_position());
+    let list = Paragraph::new(lines)
+        .style(get_style(app, Focus::Response))
+        .block(pane_block(app, Focus::Response).title(title));
+    f.render_widget(list, area);
+}
+
+/// Numbered body lines, syntax highlighted when `syntax` is set, with search
+/// matches drawn over the top and the current match standing out from the rest.
+/// Lines in the visual `selection` get a background behind all of that.
+fn render_body<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+    search: &Search,
+    syntax: bool,
+    selection: Option<(usize, usize)>,
+    theme: &Theme,
+) -> Vec<Spans<'a>> {
+    let match_style = Style::default().bg(theme.search_match).fg(theme.match_text);
+    let current_style = Style::default()
+        .bg(theme.current_match)
+        .fg(theme.match_text);
+    let selected_style = Style::default().bg(theme.selection);
+    lines
+        .map(|(i, line)| {
+            let base: Vec<(usize, usize, Style)> = if syntax {
+                highlight::json_line_tokens(line)
+                    .into_iter()
+                    .map(|t| (t.start, t.end, t.kind.style(theme)))
+                    .collect()
+            } else {
+                vec![(0, line.len(), Style::default())]
+            };
+            let overlays: Vec<(usize, usize, Style)> = search
+                .matches_on(i)
+                .map(|(index, m)| {
+                    let style = if index == search.current {
+                        current_style
+                    } else {
+                        match_style
+                    };
+                    (m.start, m.end, style)
+                })
+                .collect();
+            let mut spans = merge_styles(line, &base, &overlays);
+            if selection.is_some_and(|(first, last)| (first..=last).contains(&i)) {
+                for span in &mut spans {
+                    span.style = selected_style.patch(span.style);
+                }
+            }
+            Spans::from(spans)
+        })
+        .collect()
+}
+
+/// Cuts `line` into spans wherever a base or overlay range starts or ends,
+/// styling each piece with the overlay covering it, else the base style.
+fn merge_styles<'a>(
+    line: &'a str,
+    base: &[(usize, usize, Style)],
+    overlays: &[(usize, usize, Style)],
+) -> Vec<Span<'a>> {
+    if overlays.is_empty() {
+        return base
+            .iter()
+            .map(|&(start, end, style)| Span::styled(&line[start..end], style))
+            .collect();
+    }
+    let mut cuts: Vec<usize> = base
+        .iter()
+        .chain(overlays)
+        .flat_map(|&(start, end, _)| [start, end])
+        .collect();
+    cuts.push(0);
+    cuts.push(line.len());
+    cuts.sort_unstable();
+    cuts.dedup();
+    let covering = |ranges: &[(usize, usize, Style)], at: usize| {
+        ranges
+            .iter()
+            .find(|&&(start, end, _)| start <= at && at < end)
+            .map(|&(_, _, style)| style)
+    };
+    cuts.windows(2)
+        .map(|w| {
+            let style = covering(overlays, w[0])
+                .or_else(|| covering(base, w[0]))
+                .unwrap_or_default();
+            Span::styled(&line[w[0]..w[1]], style)
+        })
+        .collect()
+}
+
+/// The status line, followed by the headers when `expanded`.
+fn render_metadata(response: &ResponseData, expanded: bool, theme: &Theme) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from(Span::styled(
+        response.status_line(),
+        status_style(response.status, theme),
+    ))];
+    lines.extend(response.graphql_errors.iter().map(|error| {
+        Spans::from(Span::styled(
+            format!("GraphQL error: {}", error),
+            Style::default().fg(theme.error),
+        ))
+    }));
+    if expanded {
+        lines.push(Spans::from(Span::styled(
+            response.size_line(),
+            Style::default().add_modifier(Modifier::DIM),
+        )));
+        lines.extend(
+            response
+                .redirect_lines()
+                .into_iter()
+                .map(|line| Spans::from(Span::styled(line, Style::default().fg(theme.redirect)))),
+        );
+        let headers = response.header_lines();
+        let hidden = headers.len().saturating_sub(MAX_METADATA_LINES);
+        lines.extend(
+            headers
+                .into_iter()
+                .take(MAX_METADATA_LINES)
+                .map(|line| Spans::from(Span::raw(line))),
+        );
+        if hidden > 0 {
+            lines.push(Spans::from(Span::styled(
+                format!("... {} more", hidden),
+                Style::default().add_modifier(Modifier::DIM),
+            )));
+        }
+    }
+    lines
+}
+
+/// The theme's color for successes, redirects, or client and server errors.
+pub fn status_style(status: StatusCode, theme: &Theme) -> Style {
+    let color = if status.is_success() {
+        theme.success
+    } else if status.is_redirection() {
+        theme.redirect
+    } else if status.is_client_error() || status.is_server_error() {
+        theme.error
+    } else {
+        theme.info
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+/// A spinner and how long the request in flight has taken so far, e.g.
+/// `⠹ 1.3s`.
+fn in_flight_label(app: &App) -> Option<String> {
+    app.in_flight.as_ref()?;
+    let elapsed = app.request_started?.elapsed();
+    let frame = (elapsed.as_millis() / SPINNER_INTERVAL) as usize % SPINNER.len();
+    Some(format!("{} {:.1}s", SPINNER[frame], elapsed.as_secs_f64()))
+}
+
+/// The status code and reason, e.g. ` 404 Not Found `, on its status color.
+fn status_badge(status: StatusCode, theme: &Theme) -> Span<'static> {
+    let text = match status.canonical_reason() {
+        Some(reason) => format!(" {} {} ", status.as_u16(), reason),
+        None => format!(" {} ", status.as_u16()),
+    };
+    let color = status_style(status, theme).fg.unwrap_or(theme.info);
+    Span::styled(
+        text,
+        Style::default()
+            .fg(Color::Black)
+            .bg(color)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+/// The border of a pane, drawn thick and in the focus colour when it has focus
+/// so it's clear where keys will go.
+fn pane_block(app: &App, pane: Focus) -> Block<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(get_style(app, pane));
+    if app.focus == pane {
+        block.border_type(BorderType::Thick)
+    } else {
+        block
+    }
+}
+
+fn get_style(app: &App, this_window: Focus) -> Style {
+    let theme = app.theme();
+    if app.focus == this_window {
+        match app.input_mode {
+            InputMode::Normal => Style::default().fg(theme.focused),
+            _ => Style::default().fg(theme.editing),
+        }
+    } else {
+        Style::default().fg(theme.unfocused)
+    }
+}
+
+/// One list line per row, rendered as `[x] key: value`.
+fn render_key_values(table: &KeyValueTable) -> Vec<ListItem<'_>> {
+    table
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, kv)| {
+            let check = if kv.enabled { "[x] " } else { "[ ] " };
+            let style = if i == table.selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Spans::from(vec![
+                Span::raw(check),
+                Span::styled(format!("{}: {}", kv.key, kv.value), style),
+            ]))
+        })
+        .collect()
+}
+
+/// Column of the editing cursor within the selected row of `render_key_values`.
+fn key_value_cursor_offset(table: &KeyValueTable) -> usize {
+    let prefix = "[x] ".width();
+    match table.rows.get(table.selected) {
+        Some(kv) => match table.column {
+            kv::Column::Key => prefix + kv.key.width(),
+            kv::Column::Value => prefix + kv.key.width() + ": ".width() + kv.value.width(),
+        },
+        None => prefix,
+    }
+}
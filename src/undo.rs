@@ -0,0 +1,150 @@
+use crate::input::Edit;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// How many changes each field remembers.
+const LIMIT: usize = 100;
+
+/// A kind of change that carries on the one before, so a word typed or
+/// deleted a key at a time is undone in one go.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Run {
+    Typing,
+    Deleting,
+}
+
+impl Run {
+    /// The run an editing key is part of, if any.
+    pub fn of(key: &KeyEvent) -> Option<Run> {
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char(c) if !c.is_whitespace() => Some(Run::Typing),
+            KeyCode::Backspace | KeyCode::Delete => Some(Run::Deleting),
+            _ => None,
+        }
+    }
+}
+
+/// Earlier and undone states of one field, e.g. the URL.
+#[derive(Clone, Debug)]
+pub struct Undo<T> {
+    undone: Vec<T>,
+    redone: Vec<T>,
+    run: Option<Run>,
+}
+
+impl<T> Default for Undo<T> {
+    fn default() -> Undo<T> {
+        Undo {
+            undone: Vec::new(),
+            redone: Vec::new(),
+            run: None,
+        }
+    }
+}
+
+impl<T> Undo<T> {
+    /// Remembers `before`, the state ahead of a change, unless the change
+    /// carries on the same `run` as the last one.
+    pub fn record(&mut self, before: T, run: Option<Run>) {
+        self.redone.clear();
+        if run.is_some() && run == self.run {
+            return;
+        }
+        self.run = run;
+        self.undone.push(before);
+        if self.undone.len() > LIMIT {
+            self.undone.remove(0);
+        }
+    }
+
+    /// Ends the current run, e.g. when the cursor moves away.
+    pub fn seal(&mut self) {
+        self.run = None;
+    }
+
+    /// Goes back to the state before the last change, returning whether
+    /// there was one.
+    pub fn undo(&mut self, current: &mut T) -> bool {
+        self.run = None;
+        match self.undone.pop() {
+            Some(before) => {
+                self.redone.push(std::mem::replace(current, before));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Puts back the last change undone, returning whether there was one.
+    pub fn redo(&mut self, current: &mut T) -> bool {
+        self.run = None;
+        match self.redone.pop() {
+            Some(after) => {
+                self.undone.push(std::mem::replace(current, after));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn apply(&mut self, step: Step, current: &mut T) -> bool {
+        match step {
+            Step::Undo => self.undo(current),
+            Step::Redo => self.redo(current),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Undo,
+    Redo,
+}
+
+impl Step {
+    /// Ctrl+Z undoes and Ctrl+R redoes.
+    pub fn of(key: &KeyEvent) -> Option<Step> {
+        if !key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char('z') => Some(Step::Undo),
+            KeyCode::Char('r') => Some(Step::Redo),
+            _ => None,
+        }
+    }
+}
+
+/// Applies `edit` to a text and its cursor, saving them in `undo` first if
+/// they change. Ctrl+Z and Ctrl+R undo and redo instead.
+pub fn edit_text(
+    key: &KeyEvent,
+    text: &mut String,
+    cursor: &mut usize,
+    undo: &mut Undo<(String, usize)>,
+    edit: impl FnOnce(&mut String, &mut usize) -> Edit,
+) -> Edit {
+    if let Some(step) = Step::of(key) {
+        let mut state = (std::mem::take(text), *cursor);
+        let stepped = undo.apply(step, &mut state);
+        (*text, *cursor) = state;
+        return if stepped {
+            Edit::Changed
+        } else {
+            Edit::Ignored
+        };
+    }
+    let before = (text.clone(), *cursor);
+    let result = edit(text, cursor);
+    match result {
+        Edit::Changed => undo.record(before, Run::of(key)),
+        Edit::Moved => undo.seal(),
+        Edit::Ignored => {}
+    }
+    result
+}
@@ -0,0 +1,103 @@
+use crate::error::AppError;
+use crate::request::Request;
+use crate::response::ResponseData;
+use std::io;
+
+#[cfg(unix)]
+fn io_error(e: impl std::error::Error + Send + Sync + 'static) -> AppError {
+    AppError::Io(io::Error::other(e))
+}
+
+/// Sends `request` over the Unix socket in its `unix_socket` setting, for
+/// daemons such as Docker's that only listen on one. The URL still supplies
+/// the path, query and `Host` header. Only HTTP/1.1 is spoken, and redirects
+/// aren't followed.
+#[cfg(unix)]
+pub async fn send(request: &Request) -> Result<ResponseData, AppError> {
+    use crate::storage;
+    use hyper::{body, body::HttpBody, client::conn, Body};
+    use reqwest::{header, Client};
+    use std::time::Instant;
+    use tokio::net::UnixStream;
+
+    // Built as usual so auth, headers and the body come out the same
+    let built = request.build(&Client::new())?.build()?;
+    let url = built.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => String::from(url.path()),
+    };
+    let mut outgoing = hyper::Request::builder()
+        .method(built.method().clone())
+        .uri(target);
+    if let Some(headers) = outgoing.headers_mut() {
+        headers.extend(built.headers().clone());
+        if let Ok(host) = header::HeaderValue::from_str(url.host_str().unwrap_or("localhost")) {
+            headers.entry(header::HOST).or_insert(host);
+        }
+    }
+    let bytes = match built.body() {
+        Some(body) => body.as_bytes().map(<[u8]>::to_vec).ok_or_else(|| {
+            AppError::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "multipart bodies can't be sent over a Unix socket",
+            ))
+        })?,
+        None => Vec::new(),
+    };
+    let outgoing = outgoing.body(Body::from(bytes)).map_err(io_error)?;
+
+    let path = storage::expand_tilde(request.settings.value("unix_socket"));
+    let exchange = async {
+        let start = Instant::now();
+        let stream = UnixStream::connect(&path).await.map_err(|e| {
+            AppError::Io(io::Error::new(
+                e.kind(),
+                format!("{}: {}", path.display(), e),
+            ))
+        })?;
+        let (mut sender, connection) = conn::handshake(stream).await.map_err(io_error)?;
+        tokio::spawn(connection);
+        let response = sender.send_request(outgoing).await.map_err(io_error)?;
+        let (parts, mut body) = response.into_parts();
+        if let Some(download) = &request.download {
+            let total = body.size_hint().exact();
+            let mut file = download.begin(total).await?;
+            while let Some(chunk) = body.data().await {
+                download.write(&mut file, &chunk.map_err(io_error)?).await?;
+            }
+            let size = download.finish(file).await?;
+            return Ok(ResponseData::downloaded(
+                parts.status,
+                parts.version,
+                parts.headers,
+                start.elapsed(),
+                &download.path,
+                size,
+            ));
+        }
+        let bytes = body::to_bytes(body).await.map_err(io_error)?;
+        Ok(ResponseData::new(
+            parts.status,
+            parts.version,
+            parts.headers,
+            start.elapsed(),
+            &bytes,
+            request.settings.value("decompress") == "on",
+        ))
+    };
+    match request.settings.seconds("timeout")? {
+        Some(limit) => tokio::time::timeout(limit, exchange)
+            .await
+            .map_err(|_| AppError::Timeout(format!("the request took longer than {:?}", limit)))?,
+        None => exchange.await,
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn send(_request: &Request) -> Result<ResponseData, AppError> {
+    Err(AppError::Io(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix sockets aren't available on this platform",
+    )))
+}
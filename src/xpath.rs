@@ -0,0 +1,655 @@
+/// An element of a parsed XML document, with what's inside it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Element {
+    /// With its namespace prefix, if any, e.g. `soap:Body`.
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Element(Element),
+    /// With entities and CDATA decoded. Whitespace between elements is left out.
+    Text(String),
+}
+
+impl Element {
+    /// All the text inside the element, at any depth.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            match child {
+                Node::Element(element) => text += &element.text(),
+                Node::Text(part) => text += part,
+            }
+        }
+        text
+    }
+
+    /// The element as XML again, on one line.
+    fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+        for (name, value) in &self.attributes {
+            out.push_str(&format!(" {}=\"{}\"", name, escape(value, true)));
+        }
+        if self.children.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+        out.push('>');
+        for child in &self.children {
+            match child {
+                Node::Element(element) => element.write(out),
+                Node::Text(text) => out.push_str(&escape(text, false)),
+            }
+        }
+        out.push_str(&format!("</{}>", self.name));
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    if attribute {
+        text.replace('"', "&quot;")
+    } else {
+        text
+    }
+}
+
+/// `text` with the predefined and numeric character entities decoded.
+/// Others are left as they are.
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out + rest
+}
+
+/// Where the tag starting `rest` ends, the index of its `>`, skipping any
+/// inside quoted attribute values.
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads `<name attr="value" ...`, without its `<` and `>` or `/>`.
+fn start_tag(tag: &str) -> Result<Element, String> {
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = &tag[..name_end];
+    if name.is_empty() {
+        return Err(String::from("XML: a tag has no name"));
+    }
+    let mut attributes = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let error = || format!("XML: bad attribute in <{}>", name);
+        let (attribute, after) = rest.split_once('=').ok_or_else(error)?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let quote = quote.ok_or_else(error)?;
+        let (value, after) = after[1..].split_once(quote).ok_or_else(error)?;
+        attributes.push((String::from(attribute.trim()), unescape(value)));
+        rest = after.trim_start();
+    }
+    Ok(Element {
+        name: String::from(name),
+        attributes,
+        children: Vec::new(),
+    })
+}
+
+/// Parses an XML document, returning its root element. Covers what API
+/// responses use: elements, attributes, text, CDATA and entities, with
+/// comments, processing instructions and doctypes skipped.
+pub fn parse_document(text: &str) -> Result<Element, String> {
+    let mut open: Vec<Element> = Vec::new();
+    let mut root = None;
+    let mut rest = text;
+    let mut close = |element: Element, open: &mut Vec<Element>| match open.last_mut() {
+        Some(parent) => {
+            parent.children.push(Node::Element(element));
+            Ok(())
+        }
+        None if root.is_none() => {
+            root = Some(element);
+            Ok(())
+        }
+        None => Err(String::from("XML: more than one root element")),
+    };
+    while !rest.is_empty() {
+        // Past `terminator`, or an error naming `what` it should have ended
+        let skip = |rest: &str, terminator: &str, what: &str| {
+            rest.find(terminator)
+                .map(|i| i + terminator.len())
+                .ok_or_else(|| format!("XML: no {} to end {}", terminator, what))
+        };
+        if rest.starts_with("<!--") {
+            rest = &rest[skip(rest, "-->", "a comment")?..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = skip(after, "]]>", "a CDATA section")?;
+            if let Some(element) = open.last_mut() {
+                let text = &after[..end - 3];
+                element.children.push(Node::Text(String::from(text)));
+            }
+            rest = &after[end..];
+        } else if rest.starts_with("<?") {
+            rest = &rest[skip(rest, "?>", "a processing instruction")?..];
+        } else if rest.starts_with("<!") {
+            rest = &rest[skip(rest, ">", "a declaration")?..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = skip(after, ">", "an end tag")?;
+            let name = after[..end - 1].trim();
+            match open.pop() {
+                Some(element) if element.name == name => close(element, &mut open)?,
+                Some(element) => return Err(format!("XML: </{}> closes <{}>", name, element.name)),
+                None => return Err(format!("XML: </{}> closes nothing", name)),
+            }
+            rest = &after[end..];
+        } else if rest.starts_with('<') {
+            let end = tag_end(rest).ok_or("XML: a tag isn't closed with >")?;
+            let tag = &rest[1..end];
+            match tag.strip_suffix('/') {
+                Some(tag) => close(start_tag(tag.trim_end())?, &mut open)?,
+                None => open.push(start_tag(tag)?),
+            }
+            rest = &rest[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            match open.last_mut() {
+                Some(element) if !text.trim().is_empty() => {
+                    element.children.push(Node::Text(unescape(text)));
+                }
+                None if !text.trim().is_empty() => {
+                    return Err(String::from("XML: text outside the root element"));
+                }
+                _ => {}
+            }
+            rest = &rest[end..];
+        }
+    }
+    if let Some(element) = open.last() {
+        return Err(format!("XML: <{}> isn't closed", element.name));
+    }
+    root.ok_or_else(|| String::from("XML: no elements"))
+}
+
+/// What a step looks for.
+#[derive(Clone, Debug, PartialEq)]
+enum Test {
+    /// `name` or `prefix:name`
+    Name(String),
+    /// `*`
+    AnyElement,
+    /// `text()`
+    Text,
+    /// `@name`
+    Attribute(String),
+}
+
+/// A condition in `[...]` on what a step found.
+#[derive(Clone, Debug, PartialEq)]
+enum Predicate {
+    /// `[2]`, counting from 1.
+    Position(usize),
+    /// `[last()]`
+    Last,
+    /// `[@id]`
+    HasAttribute(String),
+    /// `[@id='3']`
+    Attribute(String, String),
+    /// `[name]`
+    HasChild(String),
+    /// `[name='Ada']`, comparing the child's text.
+    Child(String, String),
+}
+
+/// One step of a path.
+#[derive(Clone, Debug, PartialEq)]
+struct Step {
+    /// Set for `//`, looking at any depth below rather than just children.
+    descendant: bool,
+    test: Test,
+    predicates: Vec<Predicate>,
+}
+
+/// What a path leads to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Found<'a> {
+    Element(&'a Element),
+    Text(&'a str),
+    /// An attribute's value.
+    Attribute(&'a str),
+}
+
+impl Found<'_> {
+    /// Elements as XML, text and attributes as their values.
+    pub fn to_text(&self) -> String {
+        match self {
+            Found::Element(element) => {
+                let mut out = String::new();
+                element.write(&mut out);
+                out
+            }
+            Found::Text(text) => String::from(text.trim()),
+            Found::Attribute(value) => String::from(*value),
+        }
+    }
+}
+
+/// A parsed XPath such as `//item[@id='3']/name`. Covers child and `//`
+/// steps, `*`, `@attribute` and `text()`, with predicates by position,
+/// attribute or child text, but not functions or axes beyond those. A name
+/// without a prefix matches any prefix, so `//Body` finds `soap:Body`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XPath {
+    steps: Vec<Step>,
+}
+
+/// Whether an element or attribute called `name` matches `wanted`.
+fn name_matches(name: &str, wanted: &str) -> bool {
+    name == wanted
+        || (!wanted.contains(':')
+            && name
+                .rsplit_once(':')
+                .is_some_and(|(_, local)| local == wanted))
+}
+
+impl XPath {
+    pub fn parse(path: &str) -> Result<XPath, String> {
+        let error = |reason: &str| format!("XPath {}: {}", path, reason);
+        let mut rest = path.trim();
+        if !rest.starts_with('/') {
+            return Err(error("should start with /"));
+        }
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            let descendant = rest.starts_with("//");
+            rest = rest
+                .strip_prefix(if descendant { "//" } else { "/" })
+                .ok_or_else(|| error("steps should be separated by /"))?;
+            // The step runs to the next / outside brackets and quotes
+            let mut depth = 0;
+            let mut quote = None;
+            let end = rest
+                .char_indices()
+                .find(|&(_, c)| {
+                    match (quote, c) {
+                        (None, '\'' | '"') => quote = Some(c),
+                        (Some(q), c) if q == c => quote = None,
+                        (None, '[') => depth += 1,
+                        (None, ']') => depth -= 1,
+                        (None, '/') if depth == 0 => return true,
+                        _ => {}
+                    }
+                    false
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            let (step, after) = rest.split_at(end);
+            steps.push(parse_step(step, descendant).map_err(|reason| error(&reason))?);
+            rest = after;
+        }
+        if steps[..steps.len() - 1]
+            .iter()
+            .any(|step| matches!(step.test, Test::Text | Test::Attribute(_)))
+        {
+            return Err(error("text() and @ can only be the last step"));
+        }
+        Ok(XPath { steps })
+    }
+
+    /// Everything the path leads to in the document `root` is the root
+    /// element of.
+    pub fn select<'a>(&self, root: &'a Element) -> Vec<Found<'a>> {
+        let Some((first, rest)) = self.steps.split_first() else {
+            return Vec::new();
+        };
+        // The document above the root holds nothing else, nor any attributes
+        let top = if matches_element(&first.test, root) {
+            vec![Found::Element(root)]
+        } else {
+            Vec::new()
+        };
+        let mut current = filter(first, top);
+        if first.descendant {
+            take_step(first, root, &mut current);
+        }
+        for step in rest {
+            let mut next = Vec::new();
+            for found in &current {
+                if let Found::Element(element) = found {
+                    take_step(step, element, &mut next);
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+fn parse_step(step: &str, descendant: bool) -> Result<Step, String> {
+    let (test, mut rest) = step.split_at(step.find('[').unwrap_or(step.len()));
+    let test = match test.trim() {
+        "" => return Err(String::from("a step is empty")),
+        "*" => Test::AnyElement,
+        "text()" => Test::Text,
+        test => match test.strip_prefix('@') {
+            Some(name) => Test::Attribute(String::from(name)),
+            None if test.contains(['(', ')', '@', '=']) => {
+                return Err(format!("unsupported step {}", test))
+            }
+            None => Test::Name(String::from(test)),
+        },
+    };
+    let mut predicates = Vec::new();
+    while let Some(after) = rest.strip_prefix('[') {
+        let end = after.find(']').ok_or_else(|| String::from("unclosed ["))?;
+        predicates.push(parse_predicate(after[..end].trim())?);
+        rest = &after[end + 1..];
+    }
+    if !rest.is_empty() {
+        return Err(format!("unexpected {}", rest));
+    }
+    Ok(Step {
+        descendant,
+        test,
+        predicates,
+    })
+}
+
+fn parse_predicate(predicate: &str) -> Result<Predicate, String> {
+    if predicate == "last()" {
+        return Ok(Predicate::Last);
+    }
+    if let Ok(position) = predicate.parse::<usize>() {
+        return match position {
+            0 => Err(String::from("positions count from [1]")),
+            position => Ok(Predicate::Position(position)),
+        };
+    }
+    let (name, value) = match predicate.split_once('=') {
+        Some((name, value)) => {
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('\'')
+                .and_then(|v| v.strip_suffix('\''))
+                .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+                .ok_or_else(|| format!("the value in [{}] should be quoted", predicate))?;
+            (name.trim(), Some(String::from(unquoted)))
+        }
+        None => (predicate, None),
+    };
+    if name.is_empty() || name.contains(['(', ')', '[', '/']) {
+        return Err(format!("unsupported [{}]", predicate));
+    }
+    Ok(match (name.strip_prefix('@'), value) {
+        (Some(attribute), Some(value)) => Predicate::Attribute(String::from(attribute), value),
+        (Some(attribute), None) => Predicate::HasAttribute(String::from(attribute)),
+        (None, Some(value)) => Predicate::Child(String::from(name), value),
+        (None, None) => Predicate::HasChild(String::from(name)),
+    })
+}
+
+fn matches_element(test: &Test, element: &Element) -> bool {
+    match test {
+        Test::Name(name) => name_matches(&element.name, name),
+        Test::AnyElement => true,
+        Test::Text | Test::Attribute(_) => false,
+    }
+}
+
+/// Adds what `step` finds from `element` to `found`: its attributes, or
+/// its children, less those failing the predicates. For `//` the same is
+/// done from every element inside it in turn.
+fn take_step<'a>(step: &Step, element: &'a Element, found: &mut Vec<Found<'a>>) {
+    let direct: Vec<Found> = match &step.test {
+        Test::Attribute(name) => attributes(element, name),
+        test => element
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                Node::Element(child) if matches_element(test, child) => Some(Found::Element(child)),
+                Node::Text(text) if *test == Test::Text => Some(Found::Text(text)),
+                _ => None,
+            })
+            .collect(),
+    };
+    found.extend(filter(step, direct));
+    if step.descendant {
+        for child in &element.children {
+            if let Node::Element(child) = child {
+                take_step(step, child, found);
+            }
+        }
+    }
+}
+
+/// The values of `element`'s attributes called `name`, or of all of them for `*`.
+fn attributes<'a>(element: &'a Element, name: &str) -> Vec<Found<'a>> {
+    element
+        .attributes
+        .iter()
+        .filter(|(attribute, _)| name == "*" || name_matches(attribute, name))
+        .map(|(_, value)| Found::Attribute(value))
+        .collect()
+}
+
+/// `found` less what fails `step`'s predicates, each applied to what's
+/// left by the one before.
+fn filter<'a>(step: &Step, mut found: Vec<Found<'a>>) -> Vec<Found<'a>> {
+    for predicate in &step.predicates {
+        found = match predicate {
+            Predicate::Position(position) => found.into_iter().skip(position - 1).take(1).collect(),
+            Predicate::Last => found.pop().into_iter().collect(),
+            _ => found
+                .into_iter()
+                .filter(|found| match found {
+                    Found::Element(element) => holds(predicate, element),
+                    _ => false,
+                })
+                .collect(),
+        };
+    }
+    found
+}
+
+fn holds(predicate: &Predicate, element: &Element) -> bool {
+    let attribute = |name: &str| {
+        element
+            .attributes
+            .iter()
+            .find(|(attribute, _)| name_matches(attribute, name))
+            .map(|(_, value)| value.as_str())
+    };
+    let mut children = element.children.iter().filter_map(|child| match child {
+        Node::Element(child) => Some(child),
+        Node::Text(_) => None,
+    });
+    match predicate {
+        Predicate::HasAttribute(name) => attribute(name).is_some(),
+        Predicate::Attribute(name, value) => attribute(name) == Some(value.as_str()),
+        Predicate::HasChild(name) => children.any(|child| name_matches(&child.name, name)),
+        Predicate::Child(name, value) => {
+            children.any(|child| name_matches(&child.name, name) && child.text().trim() == value)
+        }
+        Predicate::Position(_) | Predicate::Last => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE catalog>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <!-- a comment -->
+  <soap:Body>
+    <item id="1" kind="book"><name>Dune</name><price>9</price></item>
+    <item id="2"><name>Emma &amp; Co</name><note><![CDATA[<b>sold</b>]]></note></item>
+    <item id="3" kind="film"><name>Alien</name></item>
+  </soap:Body>
+</soap:Envelope>"#;
+
+    fn select(path: &str) -> Vec<String> {
+        let root = parse_document(DOCUMENT).unwrap();
+        let path = XPath::parse(path).unwrap();
+        path.select(&root).iter().map(Found::to_text).collect()
+    }
+
+    #[test]
+    fn parses_elements_attributes_and_text() {
+        let root = parse_document(DOCUMENT).unwrap();
+        assert_eq!(root.name, "soap:Envelope");
+        assert_eq!(root.attributes.len(), 1);
+        assert_eq!(root.text().trim(), "Dune9Emma & Co<b>sold</b>Alien");
+    }
+
+    #[test]
+    fn decodes_entities() {
+        let root = parse_document("<a b='&lt;&#65;&#x42;&unknown;'>&quot;x&apos;</a>").unwrap();
+        assert_eq!(
+            root.attributes,
+            [(String::from("b"), String::from("<AB&unknown;"))]
+        );
+        assert_eq!(root.text(), "\"x'");
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for document in [
+            "",
+            "text",
+            "<a>",
+            "<a></b>",
+            "</a>",
+            "<a/><b/>",
+            "<a/>text",
+            "<a",
+            "<>",
+            "<a b></a>",
+            "<a b=c></a>",
+            "<a b='c></a>",
+            "<a>x</",
+            "<a>x</a",
+            "<a><!-- x",
+            "<a><![CDATA[x",
+            "<a><![CDATA[€",
+            "<a><![CDATA[",
+            "<?xml",
+            "<!DOCTYPE",
+        ] {
+            assert!(parse_document(document).is_err(), "{:?}", document);
+        }
+    }
+
+    #[test]
+    fn selects_children_and_descendants() {
+        assert_eq!(
+            select("/Envelope/Body/item/name/text()"),
+            ["Dune", "Emma & Co", "Alien"]
+        );
+        assert_eq!(select("//name/text()"), ["Dune", "Emma & Co", "Alien"]);
+        assert_eq!(select("//note"), ["<note>&lt;b&gt;sold&lt;/b&gt;</note>"]);
+        assert_eq!(select("/Envelope/*").len(), 1);
+        assert_eq!(select("//item/*").len(), 5);
+        assert_eq!(select("//Envelope").len(), 1);
+        assert!(select("/Body").is_empty());
+    }
+
+    #[test]
+    fn selects_attributes() {
+        assert_eq!(select("//item/@id"), ["1", "2", "3"]);
+        assert_eq!(select("//item[1]/@*"), ["1", "book"]);
+        assert_eq!(
+            select("/Envelope/@xmlns:soap"),
+            ["http://schemas.xmlsoap.org/soap/envelope/"]
+        );
+    }
+
+    #[test]
+    fn matches_names_with_and_without_prefixes() {
+        assert_eq!(select("/soap:Envelope/soap:Body").len(), 1);
+        assert_eq!(select("/Envelope/Body").len(), 1);
+        assert!(select("/other:Envelope").is_empty());
+    }
+
+    #[test]
+    fn filters_by_predicates() {
+        assert_eq!(select("//item[2]/name/text()"), ["Emma & Co"]);
+        assert_eq!(select("//item[last()]/name/text()"), ["Alien"]);
+        assert!(select("//item[4]").is_empty());
+        assert_eq!(select("//item[@kind]/@id"), ["1", "3"]);
+        assert_eq!(select("//item[@kind='film']/@id"), ["3"]);
+        assert_eq!(select("//item[@kind=\"book\"]/@id"), ["1"]);
+        assert_eq!(select("//item[note]/@id"), ["2"]);
+        assert_eq!(select("//item[name='Dune']/price/text()"), ["9"]);
+        assert_eq!(select("//item[@kind][2]/@id"), ["3"]);
+    }
+
+    #[test]
+    fn rejects_unsupported_paths() {
+        for path in [
+            "",
+            "item",
+            "/",
+            "//",
+            "/a//",
+            "/a/",
+            "/a[",
+            "/a[0]",
+            "/a[x]y",
+            "/a[b=c]",
+            "/a[count(b)]",
+            "/a[]",
+            "/text()/a",
+            "/@id/a",
+            "/a/b()",
+        ] {
+            assert!(XPath::parse(path).is_err(), "{:?}", path);
+        }
+    }
+}